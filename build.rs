@@ -0,0 +1,32 @@
+#[cfg(feature = "ffi")]
+extern crate cbindgen;
+
+/// Generate the C header of the ffi module into `OUT_DIR`, since build
+/// scripts mustn't write to the source tree. `include/simulation.h` is a copy
+/// of it checked in so that C users don't need to build with the feature to
+/// get it.
+#[cfg(feature = "ffi")]
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    let out = std::path::Path::new(&std::env::var_os("OUT_DIR").expect("cargo sets OUT_DIR"))
+        .join("simulation.h");
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("SIMULATION_H".to_string()),
+        autogen_warning: Some("/* Generated by build.rs from src/ffi.rs, don't edit */"
+                              .to_string()),
+        usize_is_size_t: true,
+        ..Default::default()
+    };
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/ffi.rs")
+        .generate()
+        .expect("couldn't generate the C header")
+        .write_to_file(out);
+}
+
+#[cfg(not(feature = "ffi"))]
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+}
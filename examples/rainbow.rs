@@ -38,8 +38,8 @@ fn main() {
         }
 
         // save the image
-        let ref name = format!("files/{:03}.png", n);
-        let ref mut fout = File::create(&Path::new(name)).unwrap();
+        let name = format!("files/{:03}.png", n);
+        let fout = &mut File::create(Path::new(&name)).unwrap();
         let _ = image::ImageRgb8(buf).blur(10.).save(fout, image::PNG);
 
         // advance to the next frame
@@ -48,7 +48,7 @@ fn main() {
 }
 
 /// Fill a frame
-fn random_init_frame(mut frame: &mut simulation::Frame<State>) {
+fn random_init_frame(frame: &mut simulation::Frame<State>) {
     for x in 0..frame.width() {
         for y in 0..frame.height() {
             *frame.get_mut(x, y) = match rand::thread_rng().gen_range(0, 4) {
@@ -64,7 +64,7 @@ fn random_init_frame(mut frame: &mut simulation::Frame<State>) {
 
 /// Fill a 20 by 20 region of a frame
 #[allow(dead_code)]
-fn fill_rect(mut frame: &mut simulation::Frame<State>, x: usize, y: usize) {
+fn fill_rect(frame: &mut simulation::Frame<State>, x: usize, y: usize) {
     let W(fill) = rand::thread_rng().gen();
     for i in 0..20 {
         for j in 0..20 {
@@ -103,9 +103,9 @@ impl Rand for W<State> {
     }
 }
 
-impl Into<Color> for W<State> {
-    fn into(self) -> Color {
-        match self {
+impl From<W<State>> for Color {
+    fn from(state: W<State>) -> Color {
+        match state {
             W(State::Red)   => image::Rgb([255, 0, 0]),
             W(State::Blue)  => image::Rgb([0, 255, 0]),
             W(State::Green) => image::Rgb([0, 0, 255]),
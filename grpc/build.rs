@@ -0,0 +1,10 @@
+/// Generate the service from its proto. protox compiles it, so building
+/// doesn't need protoc installed.
+fn main() {
+    println!("cargo:rerun-if-changed=proto/simulation.proto");
+    let descriptors = protox::compile(["simulation.proto"], ["proto"])
+        .expect("couldn't compile proto/simulation.proto");
+    tonic_build::configure()
+        .compile_fds(descriptors)
+        .expect("couldn't generate the service");
+}
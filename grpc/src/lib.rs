@@ -0,0 +1,336 @@
+//! A gRPC service running boards of the built in rules, for embedding the
+//! simulations in larger systems. The service is defined in
+//! `proto/simulation.proto` and serves `board::Board`s, with cells read and
+//! written as numbers.
+
+// tonic decides the error type of the service, and its Status is large
+#![allow(clippy::result_large_err)]
+
+use simulation::board::{Board, RULES};
+
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The messages and service generated from the proto
+pub mod proto {
+    tonic::include_proto!("simulation");
+}
+
+pub use proto::simulation_server::SimulationServer;
+
+/// The most steps a subscriber can fall behind before it misses some
+const SUBSCRIBER_BACKLOG: usize = 16;
+
+/// The most cells a board can have
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// The most generations a single step request can ask for
+pub const MAX_GENERATIONS: u64 = 1000;
+
+/// The cells of a board as they were after some step
+struct Snapshot {
+    generation: u64,
+    width: usize,
+    values: Vec<f32>,
+}
+
+/// A board along with the channel sending subscribers every generation
+struct Entry {
+    board: Arc<Mutex<Board>>,
+    steps: broadcast::Sender<Arc<Snapshot>>,
+}
+
+/// Runs any number of boards, each known by the id it was created with
+#[derive(Default)]
+pub struct Service {
+    boards: Mutex<HashMap<u64, Entry>>,
+    next: Mutex<u64>,
+}
+
+impl Service {
+    pub fn new() -> Service {
+        Service::default()
+    }
+
+    /// The service ready to add to a tonic server
+    pub fn into_server(self) -> SimulationServer<Service> {
+        SimulationServer::new(self)
+    }
+
+    fn entry<T, F>(&self, id: u64, f: F) -> Result<T, Status>
+    where F: FnOnce(&Entry) -> Result<T, Status> {
+        let boards = lock(&self.boards)?;
+        let entry = boards.get(&id).ok_or_else(|| Status::not_found(format!("no board {}", id)))?;
+        f(entry)
+    }
+}
+
+/// Lock mutex, failing the request rather than panicking if something
+/// panicked while holding it
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, Status> {
+    mutex.lock().map_err(|_| Status::internal("the service was left broken by an earlier request"))
+}
+
+fn describe(id: u64, board: &Board) -> proto::Board {
+    proto::Board {
+        id,
+        rule: board.rule().to_string(),
+        width: board.width() as u32,
+        height: board.height() as u32,
+        generation: board.generation(),
+    }
+}
+
+/// The region of a w by h board at generation that request asks for,
+/// checking that it fits. get reads the cells of the board.
+fn region<G>(generation: u64, (w, h): (usize, usize), get: G, request: &proto::RegionRequest)
+             -> Result<proto::Region, Status>
+where G: Fn(usize, usize) -> f32 {
+    let (x, y) = (request.x as usize, request.y as usize);
+    let (rw, rh) = match (request.width as usize, request.height as usize) {
+        (0, 0) => (w, h),
+        size => size,
+    };
+    if x >= w || y >= h || rw == 0 || rh == 0 || rw > w || rh > h {
+        return Err(Status::invalid_argument(format!(
+            "region {}x{} at {},{} doesn't fit on the {}x{} board", rw, rh, x, y, w, h)));
+    }
+    let values = (0..rh)
+        .flat_map(|j| (0..rw).map(move |i| ((x + i) % w, (y + j) % h)))
+        .map(|(i, j)| get(i, j))
+        .collect();
+    Ok(proto::Region {
+        generation,
+        x: x as u32,
+        y: y as u32,
+        width: rw as u32,
+        height: rh as u32,
+        values,
+    })
+}
+
+fn board_region(board: &Board, request: &proto::RegionRequest) -> Result<proto::Region, Status> {
+    region(board.generation(), (board.width(), board.height()), |x, y| board.get(x, y), request)
+}
+
+fn snapshot_region(snapshot: &Snapshot, request: &proto::RegionRequest)
+                   -> Result<proto::Region, Status> {
+    let (w, values) = (snapshot.width, &snapshot.values);
+    region(snapshot.generation, (w, values.len() / w), |x, y| values[y * w + x], request)
+}
+
+type RegionStream = Pin<Box<dyn Stream<Item = Result<proto::Region, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl proto::simulation_server::Simulation for Service {
+    async fn create(&self, request: Request<proto::CreateRequest>)
+                    -> Result<Response<proto::Board>, Status> {
+        let request = request.into_inner();
+        let (w, h) = (request.width as usize, request.height as usize);
+        if w == 0 || h == 0 {
+            return Err(Status::invalid_argument("boards have to be at least 1x1"));
+        }
+        if w.checked_mul(h).is_none_or(|cells| cells > MAX_CELLS) {
+            return Err(Status::invalid_argument(format!(
+                "a {}x{} board is too large, the most cells a board can have is {}",
+                w, h, MAX_CELLS)));
+        }
+        let board = Board::new(&request.rule, w, h)
+            .ok_or_else(|| Status::invalid_argument(format!(
+                "unknown rule {}, expected one of {}", request.rule, RULES.join(", "))))?;
+        // stepping a board no larger than the reach of its rule would panic
+        if board.reach() >= w || board.reach() >= h {
+            return Err(Status::invalid_argument(format!(
+                "a {}x{} board is too small for {}, which looks {} cells away",
+                w, h, request.rule, board.reach())));
+        }
+        let id = {
+            let mut next = lock(&self.next)?;
+            *next += 1;
+            *next
+        };
+        let reply = describe(id, &board);
+        let (steps, _) = broadcast::channel(SUBSCRIBER_BACKLOG);
+        let entry = Entry { board: Arc::new(Mutex::new(board)), steps };
+        lock(&self.boards)?.insert(id, entry);
+        Ok(Response::new(reply))
+    }
+
+    async fn set_cells(&self, request: Request<proto::SetCellsRequest>)
+                       -> Result<Response<proto::Board>, Status> {
+        let request = request.into_inner();
+        self.entry(request.id, |entry| {
+            let mut board = lock(&entry.board)?;
+            let (w, h) = (board.width(), board.height());
+            // check every cell first, so a bad request changes nothing
+            if let Some(c) = request.cells.iter().find(|c| c.x as usize >= w || c.y as usize >= h) {
+                return Err(Status::invalid_argument(format!(
+                    "{},{} is outside the {}x{} board", c.x, c.y, w, h)));
+            }
+            for c in &request.cells {
+                board.set(c.x as usize, c.y as usize, c.value);
+            }
+            Ok(Response::new(describe(request.id, &board)))
+        })
+    }
+
+    async fn step(&self, request: Request<proto::StepRequest>)
+                  -> Result<Response<proto::Board>, Status> {
+        let request = request.into_inner();
+        if request.generations == 0 || request.generations > MAX_GENERATIONS {
+            return Err(Status::invalid_argument(format!(
+                "generations has to be from 1 to {}", MAX_GENERATIONS)));
+        }
+        let entry = self.entry(request.id, |entry| {
+            Ok((entry.board.clone(), entry.steps.clone()))
+        });
+        let (shared, steps) = entry?;
+        // stepping can take a while, so it happens off the async threads
+        let reply = tokio::task::spawn_blocking(move || {
+            for _ in 0..request.generations {
+                // the board is unlocked between generations, so reading it
+                // doesn't wait for the whole step
+                let mut board = lock(&shared)?;
+                board.step();
+                if steps.receiver_count() > 0 {
+                    let snapshot = Snapshot {
+                        generation: board.generation(),
+                        width: board.width(),
+                        values: board.values(),
+                    };
+                    // subscribers leaving in between isn't an error
+                    let _ = steps.send(Arc::new(snapshot));
+                }
+            }
+            Ok(describe(request.id, &*lock(&shared)?))
+        }).await.map_err(|e| Status::internal(e.to_string()))??;
+        Ok(Response::new(reply))
+    }
+
+    async fn get_region(&self, request: Request<proto::RegionRequest>)
+                        -> Result<Response<proto::Region>, Status> {
+        let request = request.into_inner();
+        self.entry(request.id, |entry| {
+            board_region(&*lock(&entry.board)?, &request).map(Response::new)
+        })
+    }
+
+    type SubscribeStream = RegionStream;
+
+    async fn subscribe(&self, request: Request<proto::RegionRequest>)
+                       -> Result<Response<RegionStream>, Status> {
+        let request = request.into_inner();
+        let (steps, first) = self.entry(request.id, |entry| {
+            // subscribing under the lock means no step is missed in between
+            let board = lock(&entry.board)?;
+            let first = board_region(&board, &request)?;
+            Ok((entry.steps.subscribe(), first))
+        })?;
+        // subscribers that fall behind miss the generations they fell behind by
+        let updates = BroadcastStream::new(steps)
+            .filter_map(|step| step.ok())
+            .map(move |snapshot| snapshot_region(&snapshot, &request));
+        let stream = tokio_stream::once(Ok(first)).chain(updates);
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn delete(&self, request: Request<proto::BoardId>)
+                    -> Result<Response<proto::Board>, Status> {
+        let id = request.into_inner().id;
+        let entry = lock(&self.boards)?.remove(&id)
+            .ok_or_else(|| Status::not_found(format!("no board {}", id)))?;
+        let board = lock(&entry.board)?;
+        Ok(Response::new(describe(id, &board)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::simulation_server::Simulation;
+    use super::proto::{BoardId, Cell, CreateRequest, RegionRequest, SetCellsRequest,
+                       StepRequest};
+    use super::{Service, MAX_GENERATIONS};
+
+    use tokio_stream::StreamExt;
+    use tonic::{Code, Request};
+
+    fn blinker() -> SetCellsRequest {
+        let cells = (1..4).map(|y| Cell { x: 2, y, value: 1.0 }).collect();
+        SetCellsRequest { id: 1, cells }
+    }
+
+    fn rows(values: &[f32], width: usize) -> Vec<String> {
+        values.chunks(width)
+            .map(|row| row.iter().map(|&v| if v > 0.0 { 'O' } else { '.' }).collect())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn step_and_subscribe() {
+        let service = Service::new();
+        let create = CreateRequest { rule: "life".into(), width: 5, height: 5 };
+        let board = service.create(Request::new(create)).await.unwrap().into_inner();
+        assert_eq!((board.id, &board.rule[..], board.width, board.generation),
+                   (1, "life", 5, 0));
+        service.set_cells(Request::new(blinker())).await.unwrap();
+
+        let whole = RegionRequest { id: 1, ..Default::default() };
+        let mut updates = service.subscribe(Request::new(whole)).await.unwrap().into_inner();
+        let step = StepRequest { id: 1, generations: 2 };
+        let board = service.step(Request::new(step)).await.unwrap().into_inner();
+        assert_eq!(board.generation, 2);
+
+        let first = updates.next().await.unwrap().unwrap();
+        assert_eq!(rows(&first.values, 5), [".....", "..O..", "..O..", "..O..", "....."]);
+        let second = updates.next().await.unwrap().unwrap();
+        assert_eq!(second.generation, 1);
+        assert_eq!(rows(&second.values, 5), [".....", ".....", ".OOO.", ".....", "....."]);
+
+        // a region wrapping around the right edge
+        let corner = RegionRequest { id: 1, x: 4, y: 1, width: 4, height: 1 };
+        let region = service.get_region(Request::new(corner)).await.unwrap().into_inner();
+        assert_eq!(rows(&region.values, 4), ["...O"]);
+
+        service.delete(Request::new(BoardId { id: 1 })).await.unwrap();
+        let _ = updates.next().await;
+        assert!(updates.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        let service = Service::new();
+        let create = |rule: &str, width| {
+            Request::new(CreateRequest { rule: rule.into(), width, height: 3 })
+        };
+        let status = service.create(create("walk", 3)).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert!(status.message().starts_with("unknown rule walk, expected one of life"));
+        assert_eq!(service.create(create("life", 0)).await.unwrap_err().code(),
+                   Code::InvalidArgument);
+        let huge = CreateRequest { rule: "life".into(), width: u32::MAX, height: u32::MAX };
+        let status = service.create(Request::new(huge)).await.unwrap_err();
+        assert!(status.message().ends_with("the most cells a board can have is 16777216"));
+        let status = service.create(create("lenia", 3)).await.unwrap_err();
+        assert_eq!(status.message(),
+                   "a 3x3 board is too small for lenia, which looks 13 cells away");
+
+        service.create(create("life", 3)).await.unwrap();
+        let status = service.set_cells(Request::new(blinker())).await.unwrap_err();
+        assert_eq!(status.message(), "2,3 is outside the 3x3 board");
+        let missing = StepRequest { id: 7, generations: 1 };
+        assert_eq!(service.step(Request::new(missing)).await.unwrap_err().code(),
+                   Code::NotFound);
+        let too_long = StepRequest { id: 1, generations: MAX_GENERATIONS + 1 };
+        assert_eq!(service.step(Request::new(too_long)).await.unwrap_err().code(),
+                   Code::InvalidArgument);
+        let too_wide = RegionRequest { id: 1, x: 0, y: 0, width: 4, height: 1 };
+        assert_eq!(service.get_region(Request::new(too_wide)).await.unwrap_err().code(),
+                   Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,24 @@
+use simulation_grpc::Service;
+
+use tonic::transport::Server;
+
+use std::env;
+use std::process;
+
+/// Serve boards over gRPC on the address given, 127.0.0.1:50051 by default
+#[tokio::main]
+async fn main() {
+    let addr = env::args().nth(1).unwrap_or_else(|| "127.0.0.1:50051".to_string());
+    let addr = match addr.parse() {
+        Ok(addr) => addr,
+        Err(_) => {
+            eprintln!("simulation-grpc: invalid address {}", addr);
+            process::exit(2);
+        }
+    };
+    eprintln!("listening on {}", addr);
+    if let Err(e) = Server::builder().add_service(Service::new().into_server()).serve(addr).await {
+        eprintln!("simulation-grpc: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,124 @@
+#ifndef SIMULATION_H
+#define SIMULATION_H
+
+/* Generated by build.rs from src/ffi.rs, don't edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * `simulation_step` succeeded
+ */
+#define SIMULATION_OK 0
+
+/**
+ * The frame or rule name was null, or the rule name wasn't valid UTF-8
+ */
+#define SIMULATION_INVALID_ARGUMENT -1
+
+/**
+ * The rule isn't one of the built in rules
+ */
+#define SIMULATION_UNKNOWN_RULE -2
+
+/**
+ * The frame isn't wider and taller than the neighborhood of the rule, so it
+ * can't be stepped. The frame is left as it was.
+ */
+#define SIMULATION_STEP_FAILED -3
+
+/**
+ * A frame of numbers, which is how C sees the cells of every rule: the state
+ * index for discrete rules, 0 being dead, and the value for continuous ones
+ */
+typedef struct SimulationFrame SimulationFrame;
+
+/**
+ * Create an empty width by height frame, or return null if it would be too
+ * large to allocate
+ */
+struct SimulationFrame *simulation_frame_new(size_t width, size_t height);
+
+/**
+ * Free a frame created by `simulation_frame_new`
+ *
+ * # Safety
+ *
+ * frame has to be null or a frame that hasn't been freed yet.
+ */
+void simulation_frame_free(struct SimulationFrame *frame);
+
+/**
+ * The width of frame, 0 if it is null
+ *
+ * # Safety
+ *
+ * frame has to be null or a live frame.
+ */
+size_t simulation_frame_width(const struct SimulationFrame *frame);
+
+/**
+ * The height of frame, 0 if it is null
+ *
+ * # Safety
+ *
+ * frame has to be null or a live frame.
+ */
+size_t simulation_frame_height(const struct SimulationFrame *frame);
+
+/**
+ * The cell at (x, y), or NaN if it is outside the frame
+ *
+ * # Safety
+ *
+ * frame has to be null or a live frame.
+ */
+float simulation_frame_get(const struct SimulationFrame *frame, size_t x, size_t y);
+
+/**
+ * Set the cell at (x, y), returning false if it is outside the frame
+ *
+ * # Safety
+ *
+ * frame has to be null or a live frame.
+ */
+bool simulation_frame_set(struct SimulationFrame *frame, size_t x, size_t y, float value);
+
+/**
+ * The cells of frame row by row, valid until the frame is freed and updated
+ * in place by stepping, or null if frame is null
+ *
+ * # Safety
+ *
+ * frame has to be null or a live frame.
+ */
+const float *simulation_frame_data(const struct SimulationFrame *frame);
+
+/**
+ * Copy the cells of frame row by row into out, which holds len floats.
+ * Returns the number of cells copied, which is 0 if out is too small for the
+ * whole frame.
+ *
+ * # Safety
+ *
+ * frame has to be null or a live frame and out has to point to len floats.
+ */
+size_t simulation_frame_copy(const struct SimulationFrame *frame, float *out, size_t len);
+
+/**
+ * Advance frame steps generations of the built in rule called rule, one of
+ * life, rainbow, cyclic, smooth-life or lenia. Cells are rounded or clamped
+ * to what the rule allows first. Returns `SIMULATION_OK` or one of the error
+ * codes.
+ *
+ * # Safety
+ *
+ * frame has to be null or a live frame and rule null or a NUL terminated
+ * string.
+ */
+int simulation_step(struct SimulationFrame *frame, const char *rule, uint64_t steps);
+
+#endif  /* SIMULATION_H */
@@ -0,0 +1,202 @@
+use super::Frame;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Something that moves around a frame rather than living in a cell, like an
+/// ant, a random walker or an animal in Wa-Tor
+pub trait Agent: Sized {
+    /// The cells of the frames the agent lives on
+    type Cell;
+
+    /// Where the agent is
+    fn position(&self) -> (usize, usize);
+
+    /// Take a turn, reading and writing frame and moving by changing its own
+    /// position. turn gives access to the other agents. Returns false if the
+    /// agent dies.
+    fn act(&mut self, frame: &mut Frame<Self::Cell>, turn: &mut Turn<Self>) -> bool;
+}
+
+/// What an agent can do to the others while taking its turn
+pub struct Turn<'a, A>
+where A: 'a {
+    /// every agent, with None for the one taking its turn and the dead
+    agents: &'a mut [Option<A>],
+    born: &'a mut Vec<A>,
+}
+
+impl<'a, A: Agent> Turn<'a, A> {
+    /// Add an agent, which takes its first turn in the next tick
+    pub fn spawn(&mut self, agent: A) {
+        self.born.push(agent);
+    }
+
+    /// The other agents that are alive and were around when the tick began
+    pub fn others(&self) -> impl Iterator<Item = &A> {
+        self.agents.iter().filter_map(|a| a.as_ref())
+    }
+
+    /// Whether another agent is at (x, y)
+    pub fn occupied(&self, x: usize, y: usize) -> bool {
+        self.others().any(|a| a.position() == (x, y))
+    }
+
+    /// Remove an agent at (x, y), which then doesn't get its turn if it
+    /// hasn't had it yet
+    pub fn remove_at(&mut self, x: usize, y: usize) -> Option<A> {
+        self.agents.iter_mut()
+            .find(|a| a.as_ref().is_some_and(|a| a.position() == (x, y)))
+            .and_then(|a| a.take())
+    }
+}
+
+/// A population of agents that take their turns in the order they were added
+#[derive(Clone, Debug, PartialEq)]
+pub struct Agents<A> {
+    agents: Vec<A>,
+}
+
+impl<A> Default for Agents<A> {
+    fn default() -> Agents<A> {
+        Agents { agents: vec![] }
+    }
+}
+
+impl<A: Agent> Agents<A> {
+    pub fn new(agents: Vec<A>) -> Agents<A> {
+        Agents { agents }
+    }
+
+    pub fn push(&mut self, agent: A) {
+        self.agents.push(agent);
+    }
+
+    /// The agents that are alive
+    pub fn as_slice(&self) -> &[A] {
+        &self.agents
+    }
+
+    pub fn len(&self) -> usize {
+        self.agents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty()
+    }
+
+    /// Let every agent take a turn on frame. Agents spawned during the tick
+    /// are added at the end, after those that survived.
+    pub fn tick(&mut self, frame: &mut Frame<A::Cell>) {
+        let mut slots = self.agents.drain(..).map(Some).collect::<Vec<_>>();
+        let mut born = vec![];
+        for i in 0..slots.len() {
+            if let Some(mut agent) = slots[i].take() {
+                let alive = agent.act(frame, &mut Turn { agents: &mut slots, born: &mut born });
+                if alive {
+                    slots[i] = Some(agent);
+                }
+            }
+        }
+        self.agents = slots.into_iter().flatten().chain(born).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Simulation};
+    use super::{Agent, Agents, Turn};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    /// Langton's ant, which turns right on 0, left on 1, flips the cell and
+    /// moves forward
+    struct Ant {
+        position: (usize, usize),
+        /// 0 is up, then clockwise
+        heading: usize,
+    }
+
+    impl Agent for Ant {
+        type Cell = u8;
+
+        fn position(&self) -> (usize, usize) {
+            self.position
+        }
+
+        fn act(&mut self, frame: &mut Frame<u8>, _: &mut Turn<Ant>) -> bool {
+            let (x, y) = self.position;
+            let (w, h) = (frame.width(), frame.height());
+            let cell = frame.get_mut(x, y);
+            self.heading = (self.heading + if *cell == 0 { 1 } else { 3 }) % 4;
+            *cell = 1 - *cell;
+            self.position = match self.heading {
+                0 => (x, (y + h - 1) % h),
+                1 => ((x + 1) % w, y),
+                2 => (x, (y + 1) % h),
+                _ => ((x + w - 1) % w, y),
+            };
+            true
+        }
+    }
+
+    #[test]
+    fn langtons_ant() {
+        let mut sim = Simulation::new(Frame::new(10, 10), |sq| *sq.get(0, 0));
+        let mut ants = Agents::new(vec![Ant { position: (5, 5), heading: 0 }]);
+        for _ in 0..5 {
+            sim.step_with(&mut ants);
+        }
+        assert_eq!(sim.generation(), 5);
+        assert_eq!(ants.as_slice()[0].position, (4, 5));
+        let black = sim.frame().enumerate_squares()
+            .filter(|&(_, _, &c)| c == 1)
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<_>>();
+        assert_eq!(black, vec![(6, 5), (5, 6), (6, 6)]);
+    }
+
+    /// Fish breed every turn, sharks eat a fish where they stand and die if
+    /// there is none
+    #[derive(Debug, PartialEq)]
+    enum Animal {
+        Fish(usize, usize),
+        Shark(usize, usize),
+    }
+
+    impl Agent for Animal {
+        type Cell = ();
+
+        fn position(&self) -> (usize, usize) {
+            match *self {
+                Animal::Fish(x, y) | Animal::Shark(x, y) => (x, y),
+            }
+        }
+
+        fn act(&mut self, _: &mut Frame<()>, turn: &mut Turn<Animal>) -> bool {
+            match *self {
+                Animal::Fish(x, y) => {
+                    if !turn.occupied(x + 1, y) {
+                        turn.spawn(Animal::Fish(x + 1, y));
+                    }
+                    true
+                }
+                Animal::Shark(x, y) => turn.remove_at(x, y).is_some(),
+            }
+        }
+    }
+
+    #[test]
+    fn predators() {
+        use self::Animal::*;
+
+        let mut frame = Frame::new(1, 1);
+        let mut animals = Agents::new(vec![Shark(0, 0), Fish(0, 0), Fish(5, 0), Shark(9, 9)]);
+        animals.tick(&mut frame);
+        assert_eq!(animals.as_slice(), &[Shark(0, 0), Fish(5, 0), Fish(6, 0)]);
+        animals.tick(&mut frame);
+        assert_eq!(animals.len(), 3);
+        assert!(!animals.is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use super::{Frame, Square};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// How a frame settles into a cycle
+#[derive(Clone, Debug, PartialEq)]
+pub struct Oscillation<T> {
+    /// the first generation of the cycle, 0 if the frame was already in it
+    pub start: u64,
+    /// how many generations the cycle takes
+    pub period: u64,
+    /// the frames of the cycle in order, starting with generation start
+    pub phases: Vec<Frame<T>>,
+}
+
+impl<T> Oscillation<T> {
+    /// Whether the cycle is a single frame
+    pub fn is_still_life(&self) -> bool {
+        self.period == 1
+    }
+}
+
+fn hash<T: Hash>(frame: &Frame<T>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    frame.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Step frame with rule until a frame repeats or max_generations have
+/// passed, giving every frame of the run in order, the first and the last
+/// included, along with the generation of the frame the last one repeats,
+/// or None if it doesn't. Frames are looked up by their hash but compared
+/// exactly, so a collision can't end a run early.
+pub fn run_until_repeat<T, F>(frame: Frame<T>, rule: F, max_generations: u64)
+                              -> (Vec<Frame<T>>, Option<u64>)
+where T: Eq + Hash, F: Fn(Square<T>) -> T {
+    let mut seen = HashMap::<u64, Vec<u64>>::new();
+    seen.entry(hash(&frame)).or_default().push(0);
+    let mut frames = vec![frame];
+    for generation in 1..max_generations + 1 {
+        let next = frames[frames.len() - 1].next_frame(&rule);
+        let same = seen.entry(hash(&next)).or_default();
+        let start = same.iter().copied().find(|&g| frames[g as usize] == next);
+        same.push(generation);
+        frames.push(next);
+        if start.is_some() {
+            return (frames, start);
+        }
+    }
+    (frames, None)
+}
+
+/// Step frame with rule until a frame repeats, giving up after
+/// max_generations. Frames are compared exactly, so on a small frame a
+/// spaceship that comes back around counts as oscillating.
+pub fn oscillation<T, F>(frame: &Frame<T>, rule: F, max_generations: u64)
+                         -> Option<Oscillation<T>>
+where T: Clone + Eq + Hash, F: Fn(Square<T>) -> T {
+    let (mut frames, start) = run_until_repeat(frame.clone(), rule, max_generations);
+    let start = start?;
+    // the last frame is the first of the cycle again
+    frames.pop();
+    Some(Oscillation {
+        start,
+        period: frames.len() as u64 - start,
+        phases: frames.split_off(start as usize),
+    })
+}
+
+/// How a pattern repeats up to moving, as a spaceship does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Motion {
+    /// the first generation of the cycle
+    pub start: u64,
+    /// how many generations the cycle takes
+    pub period: u64,
+    /// how far the pattern moves right and down each period, (0, 0) for
+    /// oscillators and still lifes
+    pub displacement: (isize, isize),
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl Motion {
+    /// Whether the pattern moves
+    pub fn is_spaceship(&self) -> bool {
+        self.displacement != (0, 0)
+    }
+
+    /// The speed in the usual notation, the cells moved along the longer
+    /// axis per generation as a fraction of c, e.g. `c/4` for the glider
+    /// and `c/2` for the lightweight spaceship
+    pub fn speed(&self) -> String {
+        let (dx, dy) = self.displacement;
+        let cells = dx.unsigned_abs().max(dy.unsigned_abs()) as u64;
+        if cells == 0 {
+            return "0".to_string();
+        }
+        let d = gcd(cells, self.period);
+        match (cells / d, self.period / d) {
+            (1, 1) => "c".to_string(),
+            (1, p) => format!("c/{}", p),
+            (n, 1) => format!("{}c", n),
+            (n, p) => format!("{}c/{}", n, p),
+        }
+    }
+}
+
+/// Step frame with rule until the live cells, those that aren't
+/// `T::default()`, repeat up to where they are, giving up after
+/// max_generations. Patterns are found by their bounding box, which doesn't
+/// wrap, so the frame should be large enough for the pattern not to cross
+/// an edge before it repeats.
+pub fn motion<T, F>(frame: &Frame<T>, rule: F, max_generations: u64) -> Option<Motion>
+where T: Clone + Default + Eq + Hash, F: Fn(Square<T>) -> T {
+    let dead = T::default();
+    let shape = |frame: &Frame<T>| {
+        let position = frame.bounding_box(|c| *c != dead).map_or((0, 0), |r| (r.x, r.y));
+        (frame.crop_to_content(0), position)
+    };
+    let (w, h) = (frame.width() as isize, frame.height() as isize);
+    // the shortest way around the frame from a to b
+    let towards = |a: usize, b: usize, size: isize| {
+        let d = (b as isize - a as isize).rem_euclid(size.max(1));
+        if d > size / 2 { d - size } else { d }
+    };
+
+    let mut seen = HashMap::new();
+    let mut frame = frame.clone();
+    let (pattern, position) = shape(&frame);
+    seen.insert(pattern, (0, position));
+    for generation in 1..max_generations + 1 {
+        frame = frame.next_frame(&rule);
+        let (pattern, (x, y)) = shape(&frame);
+        if let Some(&(start, (x0, y0))) = seen.get(&pattern) {
+            return Some(Motion {
+                start,
+                period: generation - start,
+                displacement: (towards(x0, x, w), towards(y0, y, h)),
+            });
+        }
+        seen.insert(pattern, (generation, (x, y)));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::super::patterns::{Orientation, Pattern};
+    use super::{motion, oscillation, run_until_repeat, Motion, Oscillation};
+
+    fn run(pattern: Pattern, size: usize, max: u64) -> Option<Oscillation<game_of_life::State>> {
+        let mut frame = Frame::new(size, size);
+        pattern.place(&mut frame, 2, 2, Orientation::Identity, Alive);
+        oscillation(&frame, game_of_life::rule, max)
+    }
+
+    #[test]
+    fn oscillators() {
+        let block = run(Pattern::block(), 6, 10).unwrap();
+        assert!(block.is_still_life());
+        assert_eq!((block.start, block.phases.len()), (0, 1));
+
+        let blinker = run(Pattern::blinker(), 7, 10).unwrap();
+        assert_eq!((blinker.start, blinker.period), (0, 2));
+        assert!(!blinker.is_still_life());
+        assert_ne!(blinker.phases[0], blinker.phases[1]);
+        assert_eq!(blinker.phases[1].next_frame(game_of_life::rule), blinker.phases[0]);
+
+        assert_eq!(run(Pattern::pulsar(), 17, 10).unwrap().period, 3);
+    }
+
+    #[test]
+    fn settling() {
+        // the diehard dies out completely after 130 generations
+        let diehard = run(Pattern::diehard(), 40, 200).unwrap();
+        assert_eq!((diehard.start, diehard.period), (130, 1));
+        assert!(diehard.phases[0].as_slice().iter().all(|&c| c == Dead));
+        // a glider on a torus comes back after crossing it
+        assert_eq!(run(Pattern::glider(), 8, 40).unwrap().period, 32);
+        assert_eq!(run(Pattern::glider(), 8, 20), None);
+        // runs that don't repeat keep every frame they got to
+        let mut frame = Frame::new(8, 8);
+        Pattern::glider().place(&mut frame, 2, 2, Orientation::Identity, Alive);
+        let (frames, start) = run_until_repeat(frame.clone(), game_of_life::rule, 20);
+        assert_eq!((frames.len(), start), (21, None));
+        assert_eq!(frames[0], frame);
+    }
+
+    #[test]
+    fn spaceships() {
+        let moving = |pattern: Pattern, orientation| {
+            let mut frame = Frame::new(30, 30);
+            pattern.place(&mut frame, 12, 12, orientation, Alive);
+            motion(&frame, game_of_life::rule, 20).unwrap()
+        };
+        let glider = moving(Pattern::glider(), Orientation::Identity);
+        assert_eq!(glider, Motion { start: 0, period: 4, displacement: (1, 1) });
+        assert!(glider.is_spaceship());
+        assert_eq!(glider.speed(), "c/4");
+        let glider = moving(Pattern::glider(), Orientation::Rotate180);
+        assert_eq!(glider.displacement, (-1, -1));
+
+        let lwss = moving(Pattern::lwss(), Orientation::Identity);
+        assert_eq!((lwss.period, lwss.displacement.0.abs(), lwss.displacement.1), (4, 2, 0));
+        assert_eq!(lwss.speed(), "c/2");
+
+        let blinker = moving(Pattern::blinker(), Orientation::Identity);
+        assert_eq!((blinker.period, blinker.displacement), (2, (0, 0)));
+        assert_eq!(blinker.speed(), "0");
+        assert!(!blinker.is_spaceship());
+        let speed = |displacement, period| Motion { start: 0, period, displacement }.speed();
+        assert_eq!(speed((2, 0), 3), "2c/3");
+        assert_eq!(speed((0, -1), 1), "c");
+        assert_eq!(speed((3, 3), 1), "3c");
+    }
+}
@@ -0,0 +1,95 @@
+use super::Frame;
+
+#[cfg(feature = "approx")]
+use approx::{AbsDiffEq, RelativeEq, UlpsEq};
+
+macro_rules! approx_eq {
+    ($($t:ty),*) => {$(
+        impl Frame<$t> {
+            /// Whether other is the same size as this frame and every cell is
+            /// within tolerance of the cell in the same place, which is how
+            /// results of continuous rules have to be compared since rounding
+            /// differs between ways of computing them. NaN is never equal.
+            pub fn approx_eq(&self, other: &Frame<$t>, tolerance: $t) -> bool {
+                (self.width(), self.height()) == (other.width(), other.height())
+                    && self.as_slice().iter().zip(other.as_slice())
+                           .all(|(a, b)| (a - b).abs() <= tolerance)
+            }
+        }
+    )*}
+}
+
+approx_eq!(f32, f64);
+
+#[cfg(feature = "approx")]
+impl<T> AbsDiffEq for Frame<T>
+where T: AbsDiffEq, T::Epsilon: Clone {
+    type Epsilon = T::Epsilon;
+
+    fn default_epsilon() -> T::Epsilon {
+        T::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Frame<T>, epsilon: T::Epsilon) -> bool {
+        (self.width(), self.height()) == (other.width(), other.height())
+            && self.as_slice().iter().zip(other.as_slice())
+                   .all(|(a, b)| a.abs_diff_eq(b, epsilon.clone()))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<T> RelativeEq for Frame<T>
+where T: RelativeEq, T::Epsilon: Clone {
+    fn default_max_relative() -> T::Epsilon {
+        T::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Frame<T>, epsilon: T::Epsilon, max_relative: T::Epsilon)
+                   -> bool {
+        (self.width(), self.height()) == (other.width(), other.height())
+            && self.as_slice().iter().zip(other.as_slice())
+                   .all(|(a, b)| a.relative_eq(b, epsilon.clone(), max_relative.clone()))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<T> UlpsEq for Frame<T>
+where T: UlpsEq, T::Epsilon: Clone {
+    fn default_max_ulps() -> u32 {
+        T::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Frame<T>, epsilon: T::Epsilon, max_ulps: u32) -> bool {
+        (self.width(), self.height()) == (other.width(), other.height())
+            && self.as_slice().iter().zip(other.as_slice())
+                   .all(|(a, b)| a.ulps_eq(b, epsilon.clone(), max_ulps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+
+    #[test]
+    fn tolerance() {
+        let a = Frame::from_vec(2, 1, vec![0.1f32 + 0.2, 1.]).unwrap();
+        let b = Frame::from_vec(2, 1, vec![0.3f32, 1.001]).unwrap();
+        assert!(a.approx_eq(&b, 0.01));
+        assert!(!a.approx_eq(&b, 1e-4));
+        assert!(!a.approx_eq(&Frame::from_vec(1, 2, vec![0.3, 1.001]).unwrap(), 0.01));
+        let nan = Frame::from_vec(1, 1, vec![f64::NAN]).unwrap();
+        assert!(!nan.approx_eq(&nan, 1.));
+    }
+
+    #[cfg(feature = "approx")]
+    #[test]
+    fn approx() {
+        let a = Frame::from_vec(2, 1, vec![0.1f64 + 0.2, 1e6]).unwrap();
+        let b = Frame::from_vec(2, 1, vec![0.3f64, 1e6 + 1e-4]).unwrap();
+        assert_ne!(a, b);
+        assert_abs_diff_eq!(a, b, epsilon = 1e-3);
+        assert_relative_eq!(a, b, max_relative = 1e-9);
+        assert_ulps_ne!(a, b);
+        assert_abs_diff_ne!(a, Frame::new(2, 1));
+    }
+}
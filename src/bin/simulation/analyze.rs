@@ -0,0 +1,244 @@
+use args::Until;
+use cell::Cell;
+
+use simulation::{Frame, Simulation, Square};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::hash::Hasher;
+
+/// The smallest rectangle holding every live cell, as (x, y, width, height).
+/// The board wraps, but the box doesn't, so a pattern straddling an edge gets
+/// a box spanning the board.
+pub type BoundingBox = (usize, usize, usize, usize);
+
+/// Statistics of a headless run
+#[derive(Clone, Debug, PartialEq)]
+pub struct Analysis {
+    /// how many generations were run, fewer than asked for if the board
+    /// started repeating
+    pub generations: u64,
+    /// the population of every generation, starting with the initial board
+    pub population: Vec<usize>,
+    /// the number of cells that changed in each step
+    pub activity: Vec<usize>,
+    /// the generation from which the board repeats, if it does
+    pub stabilized: Option<u64>,
+    /// how many generations it takes to repeat once stable
+    pub period: Option<u64>,
+    pub initial_box: Option<BoundingBox>,
+    pub final_box: Option<BoundingBox>,
+    /// the largest area the bounding box had during the run
+    pub max_box_area: usize,
+}
+
+fn hash<T: Cell>(frame: &Frame<T>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (_, _, c) in frame.enumerate_squares() {
+        hasher.write_u64(c.to_bits());
+    }
+    hasher.finish()
+}
+
+/// The number of cells that aren't dead
+pub fn population<T: Cell>(frame: &Frame<T>) -> usize {
+    frame.enumerate_squares().filter(|&(_, _, c)| *c != T::default()).count()
+}
+
+fn bounding_box<T: Cell>(frame: &Frame<T>) -> Option<BoundingBox> {
+    frame.bounding_box(|c| *c != T::default()).map(|r| (r.x, r.y, r.width, r.height))
+}
+
+/// Run sim for up to steps generations, stopping early once the board repeats.
+/// Repeats are found by hashing every generation, so a hash collision could in
+/// principle report a period that isn't there.
+pub fn analyze<T, F>(sim: &mut Simulation<T, F>, steps: u64) -> Analysis
+where T: Cell, F: Fn(Square<T>) -> T {
+    let start = sim.generation();
+    let mut seen = HashMap::new();
+    seen.insert(hash(sim.frame()), start);
+    let initial_box = bounding_box(sim.frame());
+    let mut analysis = Analysis {
+        generations: 0,
+        population: vec![population(sim.frame())],
+        activity: vec![],
+        stabilized: None,
+        period: None,
+        initial_box,
+        final_box: initial_box,
+        max_box_area: initial_box.map_or(0, |(_, _, w, h)| w * h),
+    };
+
+    for _ in 0..steps {
+        let previous = sim.frame().clone();
+        sim.step();
+        let frame = sim.frame();
+        let changed = previous.enumerate_squares()
+            .filter(|&(x, y, c)| c != frame.get(x, y))
+            .count();
+        analysis.activity.push(changed);
+        analysis.population.push(population(frame));
+        analysis.final_box = bounding_box(frame);
+        let area = analysis.final_box.map_or(0, |(_, _, w, h)| w * h);
+        analysis.max_box_area = analysis.max_box_area.max(area);
+        analysis.generations = sim.generation() - start;
+
+        if let Some(&earlier) = seen.get(&hash(frame)) {
+            analysis.stabilized = Some(earlier);
+            analysis.period = Some(sim.generation() - earlier);
+            break;
+        }
+        seen.insert(hash(frame), sim.generation());
+    }
+    analysis
+}
+
+/// Tells when a run should stop early
+pub struct Stop {
+    until: Until,
+    seen: HashSet<u64>,
+}
+
+impl Stop {
+    pub fn new(until: Until) -> Stop {
+        Stop { until, seen: HashSet::new() }
+    }
+
+    /// Whether the run should stop at frame, which has to be passed every
+    /// generation in order for repeats to be noticed
+    pub fn check<T: Cell>(&mut self, frame: &Frame<T>) -> bool {
+        match self.until {
+            Until::Stable => !self.seen.insert(hash(frame)),
+            Until::Empty => population(frame) == 0,
+        }
+    }
+}
+
+/// Summary statistics of counts as `{"min":..,"max":..,"mean":..}`
+fn stats(counts: &[usize]) -> String {
+    let min = counts.iter().min().cloned().unwrap_or(0);
+    let max = counts.iter().max().cloned().unwrap_or(0);
+    let mean = if counts.is_empty() {
+        0.0
+    } else {
+        counts.iter().sum::<usize>() as f64 / counts.len() as f64
+    };
+    format!("{{\"min\":{},\"max\":{},\"mean\":{}}}", min, max, mean)
+}
+
+fn optional<T: ToString>(v: Option<T>) -> String {
+    v.map_or("null".to_string(), |v| v.to_string())
+}
+
+fn bounding_box_json(b: Option<BoundingBox>) -> String {
+    optional(b.map(|(x, y, w, h)| {
+        format!("{{\"x\":{},\"y\":{},\"width\":{},\"height\":{}}}", x, y, w, h)
+    }))
+}
+
+impl Analysis {
+    /// The analysis as a single line json object. fields are written first,
+    /// they describe the run, e.g. the rule and seed, and have to be valid
+    /// json values.
+    pub fn to_json(&self, fields: &[(&str, String)]) -> String {
+        let mut json = String::from("{");
+        for &(name, ref value) in fields {
+            write!(json, "\"{}\":{},", name, value).unwrap();
+        }
+        let area = |b: Option<BoundingBox>| b.map_or(0, |(_, _, w, h)| w * h);
+        let growth = match (area(self.initial_box), area(self.final_box)) {
+            (0, _) => "null".to_string(),
+            (a, b) => (b as f64 / a as f64).to_string(),
+        };
+        write!(json, "\"generations\":{},\"population\":{{\"initial\":{},\"final\":{},\
+                      \"stats\":{}}},\"stabilized\":{},\"period\":{},\
+                      \"bounding_box\":{{\"initial\":{},\"final\":{},\"max_area\":{},\
+                      \"growth\":{}}},\"activity\":{{\"final\":{},\"stats\":{}}}}}",
+               self.generations, self.population[0], self.population[self.population.len() - 1],
+               stats(&self.population), optional(self.stabilized), optional(self.period),
+               bounding_box_json(self.initial_box), bounding_box_json(self.final_box),
+               self.max_box_area, growth, optional(self.activity.last()),
+               stats(&self.activity))
+            .unwrap();
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use simulation::{Frame, Simulation};
+    use simulation::game_of_life;
+    use simulation::game_of_life::State::Alive;
+    use simulation::patterns::Pattern;
+
+    use args::Until;
+
+    use super::{analyze, Stop};
+
+    #[test]
+    fn blinker() {
+        let mut frame = Frame::new(7, 7);
+        Pattern::blinker().place(&mut frame, 2, 3, Default::default(), Alive);
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+        let analysis = analyze(&mut sim, 100);
+        assert_eq!(analysis.generations, 2);
+        assert_eq!(analysis.stabilized, Some(0));
+        assert_eq!(analysis.period, Some(2));
+        assert_eq!(analysis.population, vec![3, 3, 3]);
+        assert_eq!(analysis.activity, vec![4, 4]);
+        assert_eq!(analysis.initial_box, Some((2, 3, 3, 1)));
+        assert_eq!(analysis.max_box_area, 3);
+        assert_eq!(analysis.to_json(&[("rule", "\"life\"".to_string())]),
+                   "{\"rule\":\"life\",\"generations\":2,\
+                    \"population\":{\"initial\":3,\"final\":3,\
+                    \"stats\":{\"min\":3,\"max\":3,\"mean\":3}},\
+                    \"stabilized\":0,\"period\":2,\
+                    \"bounding_box\":{\"initial\":{\"x\":2,\"y\":3,\"width\":3,\"height\":1},\
+                    \"final\":{\"x\":2,\"y\":3,\"width\":3,\"height\":1},\"max_area\":3,\
+                    \"growth\":1},\"activity\":{\"final\":4,\"stats\":{\"min\":4,\"max\":4,\
+                    \"mean\":4}}}");
+    }
+
+    #[test]
+    fn dies_out() {
+        let mut frame = Frame::new(6, 6);
+        *frame.get_mut(1, 1) = Alive;
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+        let analysis = analyze(&mut sim, 100);
+        assert_eq!(analysis.stabilized, Some(1));
+        assert_eq!(analysis.period, Some(1));
+        assert_eq!(analysis.final_box, None);
+        assert!(analysis.to_json(&[]).contains("\"final\":null"));
+    }
+
+    #[test]
+    fn unstable() {
+        let mut frame = Frame::new(20, 20);
+        Pattern::r_pentomino().place(&mut frame, 8, 8, Default::default(), Alive);
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+        let analysis = analyze(&mut sim, 10);
+        assert_eq!(analysis.generations, 10);
+        assert_eq!(analysis.period, None);
+        assert!(analysis.max_box_area > 9);
+        assert!(analysis.to_json(&[]).contains("\"period\":null"));
+    }
+
+    #[test]
+    fn stop() {
+        let mut frame = Frame::new(6, 6);
+        Pattern::blinker().place(&mut frame, 1, 1, Default::default(), Alive);
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+        let mut stable = Stop::new(Until::Stable);
+        let mut empty = Stop::new(Until::Empty);
+        let stops = (0..3)
+            .map(|_| {
+                let stop = (stable.check(sim.frame()), empty.check(sim.frame()));
+                sim.step();
+                stop
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(stops, vec![(false, false), (false, false), (true, false)]);
+        assert!(Stop::new(Until::Empty).check(&Frame::<game_of_life::State>::new(3, 3)));
+    }
+}
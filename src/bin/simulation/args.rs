@@ -0,0 +1,390 @@
+use bench::BACKENDS;
+use config;
+use output::Output;
+use pattern::Placement;
+
+use std::str::FromStr;
+use std::time::Duration;
+
+/// What the command line asked for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// run a simulation
+    Run,
+    /// time how fast a rule steps
+    Bench,
+    /// run without drawing anything and report statistics as json
+    Analyze,
+    /// stream a run to WebSocket clients
+    Serve,
+    /// read commands against a live simulation from stdin
+    Repl,
+    /// print the usage
+    Help,
+}
+
+/// How the board starts out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fill {
+    /// a random soup
+    Random,
+    /// every cell in the default state
+    Empty,
+}
+
+/// A condition that ends a run before all of its steps have passed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Until {
+    /// the board repeats an earlier generation, so it is still or oscillating
+    Stable,
+    /// every cell is dead
+    Empty,
+}
+
+impl FromStr for Until {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Until, String> {
+        match s {
+            "stable" => Ok(Until::Stable),
+            "empty" => Ok(Until::Empty),
+            _ => Err(format!("unknown stop condition {}, expected stable or empty", s)),
+        }
+    }
+}
+
+/// The parsed command line
+#[derive(Clone, Debug, PartialEq)]
+pub struct Args {
+    pub command: Command,
+    /// the name of the built in rule, or the path of a rule script
+    pub rule: String,
+    pub width: usize,
+    pub height: usize,
+    /// overrides for the parameters of the rule, later ones taking
+    /// precedence
+    pub params: Vec<(String, Vec<f64>)>,
+    /// the number of generations to run
+    pub steps: u64,
+    /// stop the run early once this holds
+    pub until: Option<Until>,
+    pub fill: Fill,
+    /// the seed for random soups, picked at random if not given
+    pub seed: Option<u64>,
+    /// the chance of a cell being alive, either overall or for each live
+    /// state
+    pub density: Vec<f64>,
+    /// pattern files placed on the board before running
+    pub patterns: Vec<Placement>,
+    /// where the run is shown or recorded
+    pub outputs: Vec<Output>,
+    /// write numbered pngs every this many generations
+    pub every: u64,
+    /// the side length of the block of pixels drawn for each cell
+    pub scale: usize,
+    /// the pause between generations of the terminal view
+    pub delay: Duration,
+    /// how frames are stepped when benchmarking
+    pub backend: String,
+    /// the address the server listens on
+    pub listen: String,
+}
+
+impl Default for Args {
+    fn default() -> Args {
+        Args {
+            command: Command::Help,
+            rule: "life".to_string(),
+            width: 100,
+            height: 100,
+            params: vec![],
+            steps: 100,
+            until: None,
+            fill: Fill::Random,
+            seed: None,
+            density: vec![0.5],
+            patterns: vec![],
+            outputs: vec![],
+            every: 1,
+            scale: 1,
+            delay: Duration::from_millis(50),
+            backend: "naive".to_string(),
+            listen: "127.0.0.1:8080".to_string(),
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+usage: simulation <command> [options]
+
+commands:
+    run         run a rule for a number of generations
+    bench       time how fast a rule steps a board
+    analyze     run until the board repeats or --steps generations have
+                passed and print statistics about the run as json
+    serve       run a rule and stream it to WebSocket clients, which can
+                send play, pause and step. Needs the server feature.
+    repl        read commands like step 10, set 3 4 alive, load gun.rle 20 20,
+                stats and save out.rle from stdin, printing the board after
+                each one. help lists them all.
+    help        show this message
+
+options:
+    --config <path>     read options from a toml scenario file, with sections
+                        [board] for width, height, fill, seed, density and
+                        boundary, [rule] for name and parameters, [[pattern]]
+                        for path, x, y and orientation, [stop] for steps and
+                        until and [output] for targets, every, scale and
+                        delay. Options after it override the file.
+    --rule <name>       life, two-color, rainbow, cyclic, smooth-life or lenia
+                        (default life), the path of a .rhai rule script,
+                        which needs the script feature, a rule in the rule
+                        language like 'next = if alive { n in {2, 3} } else
+                        { n == 3 }', where states other than 0 are alive, or
+                        the path of an .rle file whose rule header is in the
+                        rule language
+    --size <w>x<h>      the size of the board (default 100x100)
+    --param <p>=<v>     set a parameter of the rule to a number or a comma
+                        separated list of numbers. cyclic takes threshold and
+                        range, smooth-life radius, birth, death, alpha_n and
+                        alpha_m, lenia radius, peaks, mu, sigma and dt. Can be
+                        repeated.
+    --steps <n>         the number of generations to run (default 100)
+    --until <c>         stop the run early once the board is stable, that is
+                        it repeats an earlier generation, or empty
+    --fill <fill>       random or empty (default random)
+    --seed <n>          the seed for random soups, printed if not given
+    --density <d>       the chance of a cell being alive (default 0.5), or a
+                        comma separated chance for each live state
+    --pattern <p>       place a .rle, .cells, .lif or .mc pattern on the board,
+                        as path[@x,y][:orientation] where orientation is one
+                        of rot0, rot90, rot180, rot270, flip-h, flip-v,
+                        transpose or anti-transpose. Can be repeated.
+    --output <o>        terminal to watch the run, a .rle or .cells path for
+                        the final board, a .png path for the final board or,
+                        if it contains {}, for every --every generations with
+                        {} replaced by the generation, or a .gif path for an
+                        animation of the whole run. Can be repeated.
+    --every <n>         how often numbered pngs are written (default 1)
+    --scale <n>         the pixels per cell of images (default 1)
+    --delay <ms>        the pause between generations in the terminal or
+                        when serving (default 50)
+    --listen <addr>     the address serve listens on (default 127.0.0.1:8080)
+    --backend <b>       how bench steps the board: naive, or lookup for
+                        life and rainbow (default naive)
+";
+
+/// Parse value as the argument to flag
+fn value<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+/// Parse a size like `200x100`
+fn size(flag: &str, s: &str) -> Result<(usize, usize), String> {
+    let mut parts = s.splitn(2, 'x');
+    match (parts.next(), parts.next()) {
+        (Some(w), Some(h)) => {
+            let (w, h) = (value::<usize>(flag, w)?, value::<usize>(flag, h)?);
+            if w == 0 || h == 0 {
+                return Err(format!("{} has to be at least 1x1", flag));
+            }
+            Ok((w, h))
+        }
+        _ => Err(format!("invalid value for {}: {}, expected <width>x<height>", flag, s)),
+    }
+}
+
+/// Parse a rule parameter like `birth=0.25,0.35`
+fn param(flag: &str, s: &str) -> Result<(String, Vec<f64>), String> {
+    match s.find('=') {
+        Some(i) if i > 0 => {
+            let values = s[i + 1..].split(',')
+                .map(|v| value(flag, v))
+                .collect::<Result<_, _>>()?;
+            Ok((s[..i].to_string(), values))
+        }
+        _ => Err(format!("invalid value for {}: {}, expected <name>=<value>", flag, s)),
+    }
+}
+
+/// Parse the arguments after the program name. Flags take their value either
+/// as the next argument or after an `=`.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    parsed.command = match args.next().as_ref().map(|s| &s[..]) {
+        Some("run") => Command::Run,
+        Some("bench") => Command::Bench,
+        Some("analyze") => Command::Analyze,
+        Some("serve") => Command::Serve,
+        Some("repl") => Command::Repl,
+        Some("help") | Some("--help") | Some("-h") | None => Command::Help,
+        Some(command) => return Err(format!("unknown command {}", command)),
+    };
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(format!("unexpected argument {}", arg));
+        }
+        let (flag, inline) = match arg.find('=') {
+            Some(i) => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+            None => (arg.clone(), None),
+        };
+        if flag == "--help" {
+            parsed.command = Command::Help;
+            continue;
+        }
+        let v = match inline.or_else(|| args.next()) {
+            Some(v) => v,
+            None => return Err(format!("missing value for {}", flag)),
+        };
+        match &flag[..] {
+            "--rule" => parsed.rule = v,
+            "--size" => {
+                let (w, h) = size(&flag, &v)?;
+                parsed.width = w;
+                parsed.height = h;
+            }
+            "--config" => config::load(&v, &mut parsed)?,
+            "--param" => parsed.params.push(param(&flag, &v)?),
+            "--steps" => parsed.steps = value(&flag, &v)?,
+            "--until" => parsed.until = Some(v.parse()?),
+            "--fill" => {
+                parsed.fill = match &v[..] {
+                    "random" => Fill::Random,
+                    "empty" => Fill::Empty,
+                    _ => return Err(format!("invalid value for --fill: {}", v)),
+                }
+            }
+            "--seed" => parsed.seed = Some(value(&flag, &v)?),
+            "--density" => {
+                parsed.density = v.split(',')
+                    .map(|d| value(&flag, d))
+                    .collect::<Result<_, _>>()?;
+            }
+            "--pattern" => parsed.patterns.push(Placement::parse(&v)?),
+            "--output" => parsed.outputs.push(Output::parse(&v)?),
+            "--every" | "--scale" => {
+                let n = value(&flag, &v)?;
+                if n == 0 {
+                    return Err(format!("{} has to be at least 1", flag));
+                }
+                if flag == "--every" { parsed.every = n as u64 } else { parsed.scale = n }
+            }
+            "--listen" => parsed.listen = v,
+            "--delay" => parsed.delay = Duration::from_millis(value(&flag, &v)?),
+            "--backend" if BACKENDS.contains(&&v[..]) => parsed.backend = v,
+            "--backend" => {
+                return Err(format!("unknown backend {}, expected one of {}", v,
+                                   BACKENDS.join(", ")));
+            }
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use output::Output;
+
+    use super::{parse, Args, Command, Fill, Until};
+
+    use std::env;
+    use std::fs;
+
+    fn args(s: &str) -> Result<Args, String> {
+        parse(s.split_whitespace().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn run() {
+        let parsed = args("run --rule lenia --size 200x150 --steps=1000").unwrap();
+        assert_eq!(parsed.command, Command::Run);
+        assert_eq!(parsed.rule, "lenia");
+        assert_eq!((parsed.width, parsed.height), (200, 150));
+        assert_eq!(parsed.steps, 1000);
+        assert_eq!(args("").unwrap().command, Command::Help);
+        assert_eq!(args("run --help").unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn soup() {
+        let parsed = args("run --seed 42 --density 0.35 --fill random").unwrap();
+        assert_eq!(parsed.seed, Some(42));
+        assert_eq!(parsed.density, vec![0.35]);
+        assert_eq!(parsed.fill, Fill::Random);
+        let parsed = args("run --density 0.1,0.2 --fill empty").unwrap();
+        assert_eq!(parsed.density, vec![0.1, 0.2]);
+        assert_eq!(parsed.fill, Fill::Empty);
+        assert_eq!(args("run --density 0.1,x").unwrap_err(), "invalid value for --density: x");
+    }
+
+    #[test]
+    fn patterns() {
+        let parsed = args("run --pattern glider.rle@10,10 --pattern gun.rle@50,5:rot90").unwrap();
+        let paths = parsed.patterns.iter().map(|p| &p.path[..]).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["glider.rle", "gun.rle"]);
+        assert_eq!((parsed.patterns[1].x, parsed.patterns[1].y), (50, 5));
+    }
+
+    #[test]
+    fn outputs() {
+        let parsed = args("run --output terminal --output snap-{}.png --every 10 --scale 4")
+            .unwrap();
+        assert_eq!(parsed.outputs, vec![Output::Terminal, Output::Png("snap-{}.png".into())]);
+        assert_eq!((parsed.every, parsed.scale), (10, 4));
+        assert_eq!(args("run --every 0").unwrap_err(), "--every has to be at least 1");
+    }
+
+    #[test]
+    fn bench() {
+        let parsed = args("bench --rule life --size 1024x1024 --steps 500 --backend lookup")
+            .unwrap();
+        assert_eq!(parsed.command, Command::Bench);
+        assert_eq!(args("analyze --steps 10").unwrap().command, Command::Analyze);
+        assert_eq!(parsed.backend, "lookup");
+        assert_eq!(args("bench --backend hashlife").unwrap_err(),
+                   "unknown backend hashlife, expected one of naive, lookup");
+        let parsed = args("serve --listen 0.0.0.0:9000").unwrap();
+        assert_eq!((parsed.command, &parsed.listen[..]), (Command::Serve, "0.0.0.0:9000"));
+        assert_eq!(args("repl --fill empty").unwrap().command, Command::Repl);
+    }
+
+    #[test]
+    fn params() {
+        let parsed = args("run --rule cyclic --param threshold=3 --param birth=0.2,0.3 \
+                           --until stable").unwrap();
+        assert_eq!(parsed.params, vec![("threshold".to_string(), vec![3.0]),
+                                       ("birth".to_string(), vec![0.2, 0.3])]);
+        assert_eq!(parsed.until, Some(Until::Stable));
+        assert_eq!(args("run --param 3").unwrap_err(),
+                   "invalid value for --param: 3, expected <name>=<value>");
+        assert_eq!(args("run --until never").unwrap_err(),
+                   "unknown stop condition never, expected stable or empty");
+    }
+
+    #[test]
+    fn config() {
+        let path = env::temp_dir().join("simulation-cli-scenario.toml");
+        fs::write(&path, "[board]\nwidth = 30\nheight = 20\n[stop]\nsteps = 9\n").unwrap();
+        let parsed = args(&format!("run --config {} --steps 4", path.display()));
+        fs::remove_file(&path).unwrap();
+        let parsed = parsed.unwrap();
+        assert_eq!((parsed.width, parsed.height, parsed.steps), (30, 20, 4));
+        assert!(args("run --config does-not-exist.toml").unwrap_err()
+                .starts_with("does-not-exist.toml: "));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(args("walk").unwrap_err(), "unknown command walk");
+        assert_eq!(args("run --size 20").unwrap_err(),
+                   "invalid value for --size: 20, expected <width>x<height>");
+        assert_eq!(args("run --size 0x5").unwrap_err(), "--size has to be at least 1x1");
+        assert_eq!(args("run --steps").unwrap_err(), "missing value for --steps");
+        assert_eq!(args("run --steps x").unwrap_err(), "invalid value for --steps: x");
+        assert_eq!(args("run --speed 3").unwrap_err(), "unknown option --speed");
+        assert_eq!(args("run life").unwrap_err(), "unexpected argument life");
+    }
+}
@@ -0,0 +1,113 @@
+use simulation::Frame;
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The ways of stepping a frame that can be benchmarked
+pub const BACKENDS: [&str; 2] = ["naive", "lookup"];
+
+/// Timings of a benchmark run
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    /// the number of cells in the frame
+    pub cells: usize,
+    /// how long each step took, in order
+    pub latencies: Vec<Duration>,
+}
+
+impl Report {
+    pub fn total(&self) -> Duration {
+        self.latencies.iter().sum()
+    }
+
+    /// Cells updated per second over the whole run
+    pub fn cells_per_second(&self) -> f64 {
+        let total = self.total().as_secs_f64();
+        if total == 0.0 {
+            return 0.0;
+        }
+        (self.cells * self.latencies.len()) as f64 / total
+    }
+
+    /// The step latency that p percent of steps were at least as fast as,
+    /// using the nearest rank
+    pub fn percentile(&self, p: f64) -> Duration {
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+        if sorted.is_empty() {
+            return Duration::from_secs(0);
+        }
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+}
+
+/// Format a duration in the largest unit that keeps it above 1
+fn duration(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    if secs >= 1.0 {
+        format!("{:.2}s", secs)
+    } else if secs >= 1e-3 {
+        format!("{:.2}ms", secs * 1e3)
+    } else {
+        format!("{:.2}µs", secs * 1e6)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "total {}  {:.2}M cells/s", duration(self.total()),
+                 self.cells_per_second() / 1e6)?;
+        write!(f, "step latency  p50 {}  p90 {}  p99 {}  max {}",
+               duration(self.percentile(50.0)), duration(self.percentile(90.0)),
+               duration(self.percentile(99.0)), duration(self.percentile(100.0)))
+    }
+}
+
+/// Time steps generations of stepping frame with step
+pub fn bench<T, S>(mut frame: Frame<T>, steps: u64, step: S) -> Report
+where S: Fn(&Frame<T>) -> Frame<T> {
+    let cells = frame.width() * frame.height();
+    let mut latencies = Vec::with_capacity(steps as usize);
+    for _ in 0..steps {
+        let start = Instant::now();
+        frame = step(&frame);
+        latencies.push(start.elapsed());
+    }
+    Report { cells, latencies }
+}
+
+#[cfg(test)]
+mod tests {
+    use simulation::Frame;
+    use simulation::game_of_life;
+
+    use super::{bench, Report};
+
+    use std::time::Duration;
+
+    #[test]
+    fn percentiles() {
+        let report = Report {
+            cells: 100,
+            latencies: (1..11).rev().map(Duration::from_millis).collect(),
+        };
+        assert_eq!(report.total(), Duration::from_millis(55));
+        assert_eq!(report.percentile(50.0), Duration::from_millis(5));
+        assert_eq!(report.percentile(90.0), Duration::from_millis(9));
+        assert_eq!(report.percentile(99.0), Duration::from_millis(10));
+        assert_eq!(report.percentile(0.0), Duration::from_millis(1));
+        assert!((report.cells_per_second() - 1000.0 / 0.055).abs() < 1e-6);
+        assert_eq!(report.to_string(),
+                   "total 55.00ms  0.02M cells/s\n\
+                    step latency  p50 5.00ms  p90 9.00ms  p99 10.00ms  max 10.00ms");
+    }
+
+    #[test]
+    fn steps() {
+        let frame = Frame::<game_of_life::State>::new(8, 8);
+        let report = bench(frame, 5, |f| f.next_frame(game_of_life::rule));
+        assert_eq!(report.cells, 64);
+        assert_eq!(report.latencies.len(), 5);
+    }
+}
@@ -0,0 +1,116 @@
+use simulation::{game_of_life, rainbow_life, two_color_life, CellState};
+use simulation::cyclic::Cyclic;
+use simulation::render::Color;
+use simulation::render::colormap::Gradient;
+
+use rand::Rng;
+
+thread_local! {
+    /// the gradient for rules with many states, built once rather than per cell
+    static VIRIDIS: Gradient = Gradient::viridis();
+}
+
+/// What the command line needs to know about the states of the built in
+/// rules on top of `CellState` to build boards
+pub trait Cell: CellState + Clone + Default + PartialEq {
+    /// The number of live states
+    fn states() -> usize;
+
+    /// The nth live state, for random soups
+    fn live<R: Rng>(rng: &mut R, n: usize) -> Self;
+
+    /// How this cell is drawn in images and the terminal, its own color
+    /// unless the rule only uses a few of its numbers
+    fn shade(&self) -> Color {
+        self.color()
+    }
+
+    /// The cell as a number for clients of the server, the state unless
+    /// the rule is continuous
+    #[cfg(feature = "server")]
+    fn value(&self) -> f64 {
+        self.index() as f64
+    }
+
+    /// Every state the rule can produce, if there are few enough of them to
+    /// compile the rule into a lookup table
+    fn table_states() -> Option<Vec<Self>> {
+        None
+    }
+}
+
+impl Cell for game_of_life::State {
+    fn states() -> usize {
+        1
+    }
+
+    fn live<R: Rng>(_: &mut R, _: usize) -> Self {
+        game_of_life::State::Alive
+    }
+
+    fn table_states() -> Option<Vec<Self>> {
+        Some(vec![game_of_life::State::Dead, game_of_life::State::Alive])
+    }
+}
+
+impl Cell for two_color_life::State {
+    fn states() -> usize {
+        2
+    }
+
+    fn live<R: Rng>(_: &mut R, n: usize) -> Self {
+        two_color_life::State::Alive([0, 255][n])
+    }
+}
+
+impl Cell for rainbow_life::State {
+    fn states() -> usize {
+        3
+    }
+
+    fn live<R: Rng>(_: &mut R, n: usize) -> Self {
+        use simulation::rainbow_life::State::{Blue, Green, Red};
+        [Red, Green, Blue][n]
+    }
+
+    fn table_states() -> Option<Vec<Self>> {
+        use simulation::rainbow_life::State::{Blue, Dead, Green, Red};
+        Some(vec![Dead, Red, Green, Blue])
+    }
+}
+
+/// The states of the cyclic automaton with its default number of states
+impl Cell for u8 {
+    fn states() -> usize {
+        Cyclic::default().states() as usize - 1
+    }
+
+    fn live<R: Rng>(_: &mut R, n: usize) -> Self {
+        n as u8 + 1
+    }
+
+    /// The states go around the viridis gradient
+    fn shade(&self) -> Color {
+        VIRIDIS.with(|g| g.at(*self as f64 / Self::states() as f64))
+    }
+}
+
+/// Continuous rules, where soups are random values
+impl Cell for f32 {
+    fn states() -> usize {
+        1
+    }
+
+    fn live<R: Rng>(rng: &mut R, _: usize) -> Self {
+        rng.gen()
+    }
+
+    fn shade(&self) -> Color {
+        VIRIDIS.with(|g| g.at(*self as f64))
+    }
+
+    #[cfg(feature = "server")]
+    fn value(&self) -> f64 {
+        *self as f64
+    }
+}
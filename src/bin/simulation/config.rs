@@ -0,0 +1,261 @@
+use args::{Args, Fill};
+use output::Output;
+use pattern::{self, Placement};
+
+use toml::{Table, Value};
+
+use std::fs;
+use std::time::Duration;
+
+/// The keys each section of a scenario file may have, anything else is
+/// reported rather than ignored so typos don't go unnoticed
+const SECTIONS: [(&str, &[&str]); 4] = [
+    ("board", &["width", "height", "fill", "seed", "density", "boundary"]),
+    ("stop", &["steps", "until"]),
+    ("output", &["targets", "every", "scale", "delay"]),
+    ("pattern", &["path", "x", "y", "orientation"]),
+];
+
+/// Read the scenario file at path into args
+pub fn load(path: &str, args: &mut Args) -> Result<(), String> {
+    let s = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    apply(&s, args).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Set everything the scenario s declares in args, leaving the rest alone
+pub fn apply(s: &str, args: &mut Args) -> Result<(), String> {
+    let table = s.parse::<Table>().map_err(|e| e.message().to_string())?;
+    for (name, value) in &table {
+        match &name[..] {
+            "board" => board(section(name, value)?, args)?,
+            "rule" => rule(section(name, value)?, args)?,
+            "stop" => {
+                let stop = section(name, value)?;
+                if let Some(v) = stop.get("steps") {
+                    args.steps = integer("stop.steps", v)?;
+                }
+                if let Some(v) = stop.get("until") {
+                    args.until = Some(string("stop.until", v)?.parse()?);
+                }
+            }
+            "output" => output(section(name, value)?, args)?,
+            "pattern" => {
+                let patterns = value.as_array().ok_or("pattern has to be an array of tables")?;
+                for p in patterns {
+                    args.patterns.push(placement(section(name, p)?)?);
+                }
+            }
+            _ => return Err(format!("unknown section {}", name)),
+        }
+    }
+    Ok(())
+}
+
+/// The table of section name, checked for unknown keys
+fn section<'a>(name: &str, value: &'a Value) -> Result<&'a Table, String> {
+    let table = value.as_table().ok_or_else(|| format!("{} has to be a table", name))?;
+    if let Some(&(_, keys)) = SECTIONS.iter().find(|&&(n, _)| n == name) {
+        if let Some(key) = table.keys().find(|k| !keys.contains(&&k[..])) {
+            return Err(format!("unknown key {}.{}", name, key));
+        }
+    }
+    Ok(table)
+}
+
+fn integer(key: &str, value: &Value) -> Result<u64, String> {
+    match value.as_integer() {
+        Some(n) if n >= 0 => Ok(n as u64),
+        _ => Err(format!("{} has to be a non-negative integer", key)),
+    }
+}
+
+/// An integer that has to be at least 1
+fn positive(key: &str, value: &Value) -> Result<u64, String> {
+    match integer(key, value)? {
+        0 => Err(format!("{} has to be at least 1", key)),
+        n => Ok(n),
+    }
+}
+
+fn number(key: &str, value: &Value) -> Result<f64, String> {
+    value.as_float()
+        .or_else(|| value.as_integer().map(|n| n as f64))
+        .ok_or_else(|| format!("{} has to be a number", key))
+}
+
+/// A number or an array of numbers
+fn numbers(key: &str, value: &Value) -> Result<Vec<f64>, String> {
+    match value.as_array() {
+        Some(values) => values.iter().map(|v| number(key, v)).collect(),
+        None => number(key, value).map(|n| vec![n]),
+    }
+}
+
+fn string<'a>(key: &str, value: &'a Value) -> Result<&'a str, String> {
+    value.as_str().ok_or_else(|| format!("{} has to be a string", key))
+}
+
+fn board(board: &Table, args: &mut Args) -> Result<(), String> {
+    if let Some(v) = board.get("width") {
+        args.width = positive("board.width", v)? as usize;
+    }
+    if let Some(v) = board.get("height") {
+        args.height = positive("board.height", v)? as usize;
+    }
+    if let Some(v) = board.get("fill") {
+        args.fill = match string("board.fill", v)? {
+            "random" => Fill::Random,
+            "empty" => Fill::Empty,
+            fill => return Err(format!("unknown fill {}, expected random or empty", fill)),
+        };
+    }
+    if let Some(v) = board.get("seed") {
+        args.seed = Some(integer("board.seed", v)?);
+    }
+    if let Some(v) = board.get("density") {
+        args.density = numbers("board.density", v)?;
+    }
+    // frames always wrap, the key is there so scenarios can say so
+    if let Some(v) = board.get("boundary") {
+        match string("board.boundary", v)? {
+            "wrap" => {}
+            boundary => return Err(format!("unsupported boundary {}, only wrap is", boundary)),
+        }
+    }
+    Ok(())
+}
+
+/// The rule's name, with every other key taken as one of its parameters
+fn rule(rule: &Table, args: &mut Args) -> Result<(), String> {
+    for (key, value) in rule {
+        if key == "name" {
+            args.rule = string("rule.name", value)?.to_string();
+        } else {
+            let values = numbers(&format!("rule.{}", key), value)?;
+            args.params.push((key.clone(), values));
+        }
+    }
+    Ok(())
+}
+
+fn output(output: &Table, args: &mut Args) -> Result<(), String> {
+    if let Some(v) = output.get("targets") {
+        let targets = v.as_array().ok_or("output.targets has to be an array")?;
+        for target in targets {
+            args.outputs.push(Output::parse(string("output.targets", target)?)?);
+        }
+    }
+    if let Some(v) = output.get("every") {
+        args.every = positive("output.every", v)?;
+    }
+    if let Some(v) = output.get("scale") {
+        args.scale = positive("output.scale", v)? as usize;
+    }
+    if let Some(v) = output.get("delay") {
+        args.delay = Duration::from_millis(integer("output.delay", v)?);
+    }
+    Ok(())
+}
+
+fn placement(p: &Table) -> Result<Placement, String> {
+    let path = p.get("path").ok_or("pattern.path is missing")?;
+    let coordinate = |key| p.get(key).map_or(Ok(0), |v| integer(&format!("pattern.{}", key), v));
+    Ok(Placement {
+        path: string("pattern.path", path)?.to_string(),
+        x: coordinate("x")? as usize,
+        y: coordinate("y")? as usize,
+        orientation: match p.get("orientation") {
+            Some(v) => pattern::orientation(string("pattern.orientation", v)?)?,
+            None => Default::default(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use args::{Args, Fill, Until};
+    use output::Output;
+
+    use simulation::patterns::Orientation;
+
+    use super::apply;
+
+    use std::time::Duration;
+
+    fn scenario(s: &str) -> Result<Args, String> {
+        let mut args = Args::default();
+        apply(s, &mut args).map(|_| args)
+    }
+
+    #[test]
+    fn full() {
+        let args = scenario(r#"
+            [board]
+            width = 200
+            height = 120
+            fill = "random"
+            seed = 7
+            density = [0.1, 0.2]
+            boundary = "wrap"
+
+            [rule]
+            name = "smooth-life"
+            radius = 8
+            birth = [0.25, 0.35]
+
+            [[pattern]]
+            path = "glider.rle"
+            x = 10
+            y = 20
+            orientation = "rot90"
+
+            [[pattern]]
+            path = "block.cells"
+
+            [stop]
+            steps = 500
+            until = "stable"
+
+            [output]
+            targets = ["terminal", "run.gif"]
+            every = 5
+            scale = 2
+            delay = 10
+        "#).unwrap();
+        assert_eq!((args.width, args.height), (200, 120));
+        assert_eq!((args.fill, args.seed), (Fill::Random, Some(7)));
+        assert_eq!(args.density, vec![0.1, 0.2]);
+        assert_eq!(args.rule, "smooth-life");
+        assert_eq!(args.params, vec![("birth".to_string(), vec![0.25, 0.35]),
+                                     ("radius".to_string(), vec![8.0])]);
+        assert_eq!(args.patterns.len(), 2);
+        assert_eq!((args.patterns[0].x, args.patterns[0].y), (10, 20));
+        assert_eq!(args.patterns[0].orientation, Orientation::Rotate90);
+        assert_eq!(args.patterns[1].orientation, Orientation::Identity);
+        assert_eq!((args.steps, args.until), (500, Some(Until::Stable)));
+        assert_eq!(args.outputs, vec![Output::Terminal, Output::Gif("run.gif".into())]);
+        assert_eq!((args.every, args.scale), (5, 2));
+        assert_eq!(args.delay, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn partial() {
+        let args = scenario("[stop]\nuntil = \"empty\"\n").unwrap();
+        assert_eq!(args.until, Some(Until::Empty));
+        assert_eq!(args.rule, Args::default().rule);
+        assert_eq!(args.steps, Args::default().steps);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(scenario("[board]\nwidht = 3").unwrap_err(), "unknown key board.widht");
+        assert_eq!(scenario("[boards]").unwrap_err(), "unknown section boards");
+        assert_eq!(scenario("[board]\nwidth = 0").unwrap_err(), "board.width has to be at least 1");
+        assert_eq!(scenario("[board]\nboundary = \"clamp\"").unwrap_err(),
+                   "unsupported boundary clamp, only wrap is");
+        assert_eq!(scenario("[rule]\nradius = \"big\"").unwrap_err(),
+                   "rule.radius has to be a number");
+        assert_eq!(scenario("[[pattern]]\nx = 1").unwrap_err(), "pattern.path is missing");
+        assert!(scenario("[board\n").is_err());
+    }
+}
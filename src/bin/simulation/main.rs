@@ -0,0 +1,304 @@
+extern crate rand;
+extern crate simulation;
+extern crate toml;
+
+mod analyze;
+mod args;
+mod bench;
+mod cell;
+mod config;
+mod output;
+mod pattern;
+mod repl;
+mod soup;
+
+use args::{Args, Command, Fill};
+use cell::Cell;
+
+use simulation::{Frame, Simulation, Square};
+use simulation::{game_of_life, rainbow_life, two_color_life};
+use simulation::cyclic::Cyclic;
+use simulation::dsl;
+use simulation::lenia::Lenia;
+use simulation::lookup::LookupTable;
+#[cfg(feature = "server")]
+use simulation::server::Server;
+#[cfg(feature = "script")]
+use simulation::rules::scripted;
+use simulation::smooth_life::SmoothLife;
+
+use std::env;
+use std::io;
+use std::process;
+use std::time::Instant;
+
+/// The board the command line asks for, with any patterns placed on it, and
+/// the seed of its soup if it has one
+fn seeded_board<T: Cell>(args: &Args) -> Result<(Frame<T>, Option<u64>), String> {
+    let empty = Frame::try_new(args.width, args.height).map_err(|e| e.to_string())?;
+    let (mut frame, seed) = match args.fill {
+        Fill::Empty => (empty, None),
+        Fill::Random => {
+            let seed = match args.seed {
+                Some(seed) => seed,
+                None => {
+                    let seed = rand::random();
+                    eprintln!("seed {}", seed);
+                    seed
+                }
+            };
+            let frame = soup::soup(&mut soup::rng(seed), args.width, args.height, &args.density,
+                                   T::states(), T::live)?;
+            (frame, Some(seed))
+        }
+    };
+    for placement in &args.patterns {
+        placement.place(&mut frame)?;
+    }
+    Ok((frame, seed))
+}
+
+/// The board the command line asks for, with any patterns placed on it
+fn board<T: Cell>(args: &Args) -> Result<Frame<T>, String> {
+    seeded_board(args).map(|(frame, _)| frame)
+}
+
+/// Run the command with rule
+fn execute<T, F>(args: &Args, rule: F) -> Result<(), String>
+where T: Cell, F: Fn(Square<T>) -> T {
+    match args.command {
+        Command::Run => {
+            let initial = board(args)?;
+            let options = output::Options {
+                every: args.every, scale: args.scale, delay: args.delay,
+            };
+            let mut sim = Simulation::new(initial.clone(), &rule);
+            let mut stop = args.until.map(analyze::Stop::new);
+            let mut stopped = |sim: &Simulation<T, &F>| {
+                sim.generation() == args.steps
+                    || stop.as_mut().is_some_and(|s| s.check(sim.frame()))
+            };
+            let start = Instant::now();
+            let mut last = stopped(&sim);
+            output::generation(&args.outputs, &options, &sim, last)?;
+            while !last {
+                sim.step();
+                last = stopped(&sim);
+                output::generation(&args.outputs, &options, &sim, last)?;
+            }
+            output::finish(&args.outputs, &options, initial, &rule, sim.generation())?;
+            let population = sim.frame().enumerate_squares()
+                .filter(|&(_, _, c)| *c != T::default())
+                .count();
+            println!("generation {}  population {}  {:.2}s", sim.generation(), population,
+                     start.elapsed().as_secs_f64());
+            Ok(())
+        }
+        Command::Bench => {
+            let frame = board(args)?;
+            let report = match &args.backend[..] {
+                "lookup" => {
+                    let states = T::table_states().ok_or_else(|| {
+                        format!("the lookup backend doesn't support {}, only life and rainbow",
+                                args.rule)
+                    })?;
+                    let table = LookupTable::compile(&states, &rule).map_err(|e| e.to_string())?;
+                    bench::bench(frame, args.steps, |f| table.next_frame(f))
+                }
+                _ => bench::bench(frame, args.steps, |f| f.next_frame(&rule)),
+            };
+            println!("rule {}  backend {}  size {}x{}  steps {}", args.rule, args.backend,
+                     args.width, args.height, args.steps);
+            println!("{}", report);
+            Ok(())
+        }
+        Command::Analyze => {
+            let (frame, seed) = seeded_board(args)?;
+            let mut sim = Simulation::new(frame, rule);
+            let analysis = analyze::analyze(&mut sim, args.steps);
+            let fields = [
+                ("rule", format!("\"{}\"", args.rule)),
+                ("width", args.width.to_string()),
+                ("height", args.height.to_string()),
+                ("seed", seed.map_or("null".to_string(), |s| s.to_string())),
+            ];
+            println!("{}", analysis.to_json(&fields));
+            Ok(())
+        }
+        Command::Serve => serve(args, rule),
+        Command::Repl => {
+            let mut sim = Simulation::new(board(args)?, rule);
+            let stdin = io::stdin();
+            repl::repl(&mut sim, stdin.lock(), io::stdout())
+        }
+        Command::Help => {
+            print!("{}", args::USAGE);
+            Ok(())
+        }
+    }
+}
+
+/// Stream the run to WebSocket clients until interrupted
+#[cfg(feature = "server")]
+fn serve<T, F>(args: &Args, rule: F) -> Result<(), String>
+where T: Cell, F: Fn(Square<T>) -> T {
+    let sim = Simulation::new(board(args)?, rule);
+    let mut server = Server::bind(&args.listen[..], sim, T::value)
+        .map_err(|e| format!("{}: {}", args.listen, e))?;
+    server.set_delay(args.delay);
+    eprintln!("listening on ws://{}", args.listen);
+    server.run().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "server"))]
+fn serve<T, F>(_: &Args, _: F) -> Result<(), String>
+where T: Cell, F: Fn(Square<T>) -> T {
+    Err("serve needs building with --features server".to_string())
+}
+
+/// Run the rule script at the path given as the rule, on cyclic's states
+#[cfg(feature = "script")]
+fn script(args: &Args) -> Result<(), String> {
+    if let Some((name, _)) = args.params.first() {
+        return Err(format!("unknown parameter {} for {}", name, args.rule));
+    }
+    let script = scripted::load(&args.rule).map_err(|e| format!("{}: {}", args.rule, e))?;
+    execute(args, |sq| script.try_rule(sq).unwrap_or_else(|e| {
+        // the rest of the run would be made of states the script never gave
+        eprintln!("simulation: {}: {}", args.rule, e);
+        process::exit(1);
+    }))
+}
+
+#[cfg(not(feature = "script"))]
+fn script(_: &Args) -> Result<(), String> {
+    Err("rule scripts need building with --features script".to_string())
+}
+
+/// The parameters each rule takes on the command line
+const PARAMS: [(&str, &[&str]); 6] = [
+    ("life", &[]),
+    ("two-color", &[]),
+    ("rainbow", &[]),
+    ("cyclic", &["threshold", "range"]),
+    ("smooth-life", &["radius", "birth", "death", "alpha_n", "alpha_m"]),
+    ("lenia", &["radius", "peaks", "mu", "sigma", "dt"]),
+];
+
+/// The values of parameter name, the last given taking precedence
+fn param<'a>(args: &'a Args, name: &str) -> Option<&'a [f64]> {
+    args.params.iter().rev().find(|(n, _)| n == name).map(|(_, v)| &v[..])
+}
+
+/// The single value of parameter name, or default if it wasn't given
+fn scalar(args: &Args, name: &str, default: f64) -> Result<f64, String> {
+    match param(args, name) {
+        Some(&[v]) => Ok(v),
+        Some(_) => Err(format!("{} takes a single value", name)),
+        None => Ok(default),
+    }
+}
+
+/// The interval given as parameter name, or default if it wasn't given
+fn interval(args: &Args, name: &str, default: (f32, f32)) -> Result<(f32, f32), String> {
+    match param(args, name) {
+        Some(&[a, b]) => Ok((a as f32, b as f32)),
+        Some(_) => Err(format!("{} takes two values", name)),
+        None => Ok(default),
+    }
+}
+
+/// A parameter that has to be a whole number of at least 1
+fn count(args: &Args, name: &str, default: usize) -> Result<usize, String> {
+    let v = scalar(args, name, default as f64)?;
+    if v < 1.0 || v.fract() != 0.0 {
+        return Err(format!("{} has to be a whole number of at least 1", name));
+    }
+    Ok(v as usize)
+}
+
+/// Run a rule in the rule language, which takes no parameters
+fn execute_dsl(args: &Args, rule: &dsl::Rule) -> Result<(), String> {
+    if let Some((name, _)) = args.params.first() {
+        return Err(format!("unknown parameter {} for rules in the rule language", name));
+    }
+    execute(args, |sq| rule.rule(sq))
+}
+
+/// Pick the rule named on the command line, with its parameters
+fn dispatch(args: &Args) -> Result<(), String> {
+    if args.rule.ends_with(".rhai") {
+        return script(args);
+    }
+    if args.rule.ends_with(".rle") {
+        let rule = pattern::rle_rule(&args.rule)?;
+        return execute_dsl(args, &rule);
+    }
+    // names of built in rules aren't valid in the rule language
+    let not_dsl = match args.rule.parse::<dsl::Rule>() {
+        Ok(rule) => return execute_dsl(args, &rule),
+        Err(e) => e,
+    };
+    let known = PARAMS.iter()
+        .find(|&&(rule, _)| rule == args.rule)
+        .map(|&(_, known)| known)
+        .ok_or_else(|| format!("unknown rule {}, or a rule in the rule language with an \
+                                error at {}", args.rule, not_dsl))?;
+    if let Some((name, _)) = args.params.iter().find(|(n, _)| !known.contains(&&n[..])) {
+        return Err(format!("unknown parameter {} for {}", name, args.rule));
+    }
+
+    match &args.rule[..] {
+        "life" => execute(args, game_of_life::rule),
+        "two-color" => execute(args, two_color_life::rule),
+        "rainbow" => execute(args, rainbow_life::rule),
+        "cyclic" => {
+            let default = Cyclic::default();
+            let cyclic = Cyclic::new(default.states(),
+                                     count(args, "threshold", default.threshold())?,
+                                     count(args, "range", default.range() as usize)? as isize);
+            execute(args, |sq| cyclic.rule(sq))
+        }
+        "smooth-life" => {
+            let radius = scalar(args, "radius", 6.0)?;
+            if radius <= 0.0 {
+                return Err("radius has to be positive".to_string());
+            }
+            let mut smooth_life = SmoothLife::new(radius as f32);
+            smooth_life.birth = interval(args, "birth", smooth_life.birth)?;
+            smooth_life.death = interval(args, "death", smooth_life.death)?;
+            smooth_life.alpha_n = scalar(args, "alpha_n", smooth_life.alpha_n as f64)? as f32;
+            smooth_life.alpha_m = scalar(args, "alpha_m", smooth_life.alpha_m as f64)? as f32;
+            execute(args, |sq| smooth_life.rule(sq))
+        }
+        "lenia" => {
+            let orbium = Lenia::orbium();
+            let peaks = param(args, "peaks")
+                .map_or(vec![1.0], |p| p.iter().map(|&p| p as f32).collect());
+            if peaks.is_empty() {
+                return Err("peaks needs at least one value".to_string());
+            }
+            let lenia = Lenia::new(count(args, "radius", orbium.radius())?, &peaks,
+                                   scalar(args, "mu", orbium.mu as f64)? as f32,
+                                   scalar(args, "sigma", orbium.sigma as f64)? as f32,
+                                   scalar(args, "dt", orbium.dt as f64)? as f32);
+            execute(args, |sq| lenia.rule(sq))
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    let args = match args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("simulation: {}", e);
+            eprint!("{}", args::USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = dispatch(&args) {
+        eprintln!("simulation: {}", e);
+        process::exit(1);
+    }
+}
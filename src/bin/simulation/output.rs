@@ -0,0 +1,200 @@
+use cell::Cell;
+
+use simulation::{Frame, Simulation, Square};
+use simulation::io;
+use simulation::render::{gif, png, terminal, Color};
+use simulation::render::gif::GifOptions;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// Somewhere the command line sends a run
+#[derive(Clone, Debug, PartialEq)]
+pub enum Output {
+    /// redraw the board in the terminal every generation
+    Terminal,
+    /// the final board as an RLE pattern
+    Rle(String),
+    /// the final board as a plaintext pattern
+    Cells(String),
+    /// a png of the final board, or every few generations if the path
+    /// contains `{}`, which is replaced by the generation
+    Png(String),
+    /// an animated gif of the whole run
+    Gif(String),
+}
+
+impl Output {
+    /// Parse `terminal` or a path whose extension picks the format
+    pub fn parse(s: &str) -> Result<Output, String> {
+        if s == "terminal" {
+            return Ok(Output::Terminal);
+        }
+        let extension = Path::new(s).extension().and_then(|e| e.to_str()).unwrap_or("");
+        match &extension.to_lowercase()[..] {
+            "rle" => Ok(Output::Rle(s.to_string())),
+            "cells" => Ok(Output::Cells(s.to_string())),
+            "png" => Ok(Output::Png(s.to_string())),
+            "gif" => Ok(Output::Gif(s.to_string())),
+            _ => Err(format!("unknown output {}, expected terminal or a .rle, .cells, .png or \
+                              .gif path", s)),
+        }
+    }
+}
+
+/// How outputs are drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Options {
+    /// write numbered pngs every this many generations
+    pub every: u64,
+    /// the side length of the block of pixels drawn for each cell
+    pub scale: usize,
+    /// the pause between generations of the terminal view
+    pub delay: Duration,
+}
+
+fn create(path: &str) -> Result<BufWriter<File>, String> {
+    File::create(path).map(BufWriter::new).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn color<T: Cell>(cell: &T) -> Color {
+    cell.shade()
+}
+
+/// Write everything that happens at generation of a run. The last
+/// generation is where the final board outputs are written.
+pub fn generation<T, F>(outputs: &[Output], options: &Options, sim: &Simulation<T, F>,
+                        last: bool) -> Result<(), String>
+where T: Cell, F: Fn(Square<T>) -> T {
+    let generation = sim.generation();
+    let frame = sim.frame();
+    for output in outputs {
+        match *output {
+            Output::Terminal => {
+                let stdout = ::std::io::stdout();
+                let mut stdout = stdout.lock();
+                let clear = if generation == 0 { "\x1b[2J" } else { "" };
+                write!(stdout, "{}\x1b[H", clear)
+                    .and_then(|_| terminal::print_half_blocks(&mut stdout, frame, &color))
+                    .and_then(|_| writeln!(stdout, "generation {}", generation))
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| e.to_string())?;
+                if !last {
+                    thread::sleep(options.delay);
+                }
+            }
+            Output::Png(ref path)
+            if path.contains("{}") && (generation.is_multiple_of(options.every) || last) => {
+                let path = path.replace("{}", &format!("{:06}", generation));
+                png::save(frame, &path, &color, options.scale)
+                    .map_err(|e| format!("{}: {}", path, e))?;
+            }
+            Output::Png(ref path) if path.contains("{}") => {}
+            Output::Png(ref path) if last => {
+                png::save(frame, path, &color, options.scale)
+                    .map_err(|e| format!("{}: {}", path, e))?;
+            }
+            Output::Rle(ref path) if last => write_pattern(path, frame, true)?,
+            Output::Cells(ref path) if last => write_pattern(path, frame, false)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write frame as a .rle or .cells pattern, picking the format from the
+/// extension of path
+pub fn save<T: Cell>(path: &str, frame: &Frame<T>) -> Result<(), String> {
+    match Output::parse(path) {
+        Ok(Output::Rle(_)) => write_pattern(path, frame, true),
+        Ok(Output::Cells(_)) => write_pattern(path, frame, false),
+        _ => Err(format!("{}: can only save .rle or .cells patterns", path)),
+    }
+}
+
+fn write_pattern<T: Cell>(path: &str, frame: &Frame<T>, rle: bool) -> Result<(), String> {
+    let file = create(path)?;
+    let written = if rle {
+        io::rle::write(file, frame, None)
+    } else {
+        io::cells::write(file, frame, None)
+    };
+    written.map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Write the outputs that cover the whole run once it is over. The rules are
+/// deterministic, so the run is replayed from initial rather than kept in
+/// memory.
+pub fn finish<T, F>(outputs: &[Output], options: &Options, initial: Frame<T>, rule: F,
+                    steps: u64) -> Result<(), String>
+where T: Cell, F: Fn(Square<T>) -> T {
+    for output in outputs {
+        if let Output::Gif(ref path) = *output {
+            let mut sim = Simulation::new(initial.clone(), &rule);
+            let options = GifOptions { scale: options.scale, ..GifOptions::default() };
+            gif::record(create(path)?, &mut sim, steps as usize + 1, &color, &options)
+                .map_err(|e| format!("{}: {}", path, e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use simulation::{Frame, Simulation};
+    use simulation::game_of_life;
+    use simulation::game_of_life::State;
+
+    use super::{finish, generation, Options, Output};
+
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Output::parse("terminal").unwrap(), Output::Terminal);
+        assert_eq!(Output::parse("out/final.RLE").unwrap(), Output::Rle("out/final.RLE".into()));
+        assert_eq!(Output::parse("gen-{}.png").unwrap(), Output::Png("gen-{}.png".into()));
+        assert_eq!(Output::parse("run.gif").unwrap(), Output::Gif("run.gif".into()));
+        assert!(Output::parse("run.mp4").is_err());
+    }
+
+    #[test]
+    fn files() {
+        let dir = env::temp_dir().join("simulation-cli-outputs");
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let outputs = vec![Output::Rle(path("final.rle")), Output::Cells(path("final.cells")),
+                           Output::Png(path("{}.png")), Output::Gif(path("run.gif"))];
+        let options = Options { every: 2, scale: 1, delay: Duration::from_millis(0) };
+
+        let mut frame = Frame::new(5, 5);
+        for y in 1..4 {
+            *frame.get_mut(2, y) = State::Alive;
+        }
+        let mut sim = Simulation::new(frame.clone(), game_of_life::rule);
+        for g in 0..4 {
+            generation(&outputs, &options, &sim, g == 3).unwrap();
+            if g < 3 {
+                sim.step();
+            }
+        }
+        finish(&outputs, &options, frame, game_of_life::rule, 3).unwrap();
+
+        // the blinker ends up horizontal after an odd number of generations
+        assert_eq!(fs::read_to_string(path("final.cells")).unwrap(),
+                   ".....\n.....\n.OOO.\n.....\n.....\n");
+        assert!(fs::read_to_string(path("final.rle")).unwrap().starts_with("x = 5, y = 5"));
+        let pngs = ["000000.png", "000002.png", "000003.png"];
+        for png in &pngs {
+            assert!(dir.join(png).exists(), "{} is missing", png);
+        }
+        assert!(!dir.join("000001.png").exists());
+        assert!(fs::read(path("run.gif")).unwrap().starts_with(b"GIF89a"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+use simulation::{CellState, Frame};
+use simulation::dsl;
+use simulation::io;
+use simulation::patterns::Orientation;
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// A pattern file to place on the board, given as `path[@x,y][:orientation]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub path: String,
+    /// where the top left corner of the pattern goes
+    pub x: usize,
+    pub y: usize,
+    pub orientation: Orientation,
+}
+
+/// The names orientations go by on the command line
+const ORIENTATIONS: [(&str, Orientation); 8] = [
+    ("rot0", Orientation::Identity),
+    ("rot90", Orientation::Rotate90),
+    ("rot180", Orientation::Rotate180),
+    ("rot270", Orientation::Rotate270),
+    ("flip-h", Orientation::FlipHorizontal),
+    ("flip-v", Orientation::FlipVertical),
+    ("transpose", Orientation::Transpose),
+    ("anti-transpose", Orientation::AntiTranspose),
+];
+
+/// The orientation called name on the command line
+pub fn orientation(name: &str) -> Result<Orientation, String> {
+    ORIENTATIONS.iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, o)| o)
+        .ok_or_else(|| format!("unknown orientation {}, expected one of {}", name,
+                               ORIENTATIONS.iter().map(|&(n, _)| n)
+                                   .collect::<Vec<_>>().join(", ")))
+}
+
+impl Placement {
+    /// Parse `path[@x,y][:orientation]`
+    pub fn parse(s: &str) -> Result<Placement, String> {
+        let (rest, orientation) = match s.rfind(':') {
+            Some(i) => (&s[..i], orientation(&s[i + 1..])?),
+            None => (s, Orientation::Identity),
+        };
+        let (path, x, y) = match rest.rfind('@') {
+            Some(i) => {
+                let position = &rest[i + 1..];
+                let invalid = || format!("invalid pattern position {}, expected x,y", position);
+                let mut parts = position.splitn(2, ',');
+                let mut next = || {
+                    parts.next().and_then(|p| p.trim().parse::<usize>().ok()).ok_or_else(invalid)
+                };
+                let (x, y) = (next()?, next()?);
+                (&rest[..i], x, y)
+            }
+            None => (rest, 0, 0),
+        };
+        if path.is_empty() {
+            return Err(format!("missing path in pattern {}", s));
+        }
+        Ok(Placement { path: path.to_string(), x, y, orientation })
+    }
+
+    /// Load the pattern and copy it onto frame. Cells within the pattern's
+    /// bounding box are overwritten, dead ones included, and the pattern
+    /// wraps around the edges.
+    pub fn place<T: CellState>(&self, frame: &mut Frame<T>) -> Result<(), String> {
+        let pattern = load(&self.path)?;
+        let (w, h) = (pattern.width(), pattern.height());
+        let (fw, fh) = (frame.width(), frame.height());
+        for j in 0..h {
+            for i in 0..w {
+                let (i2, j2) = self.orientation.apply(i, j, w, h);
+                *frame.get_mut((self.x + i2) % fw, (self.y + j2) % fh) = io::state(*pattern.get(i, j));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The rule in the header of the RLE file at path, which has to be in the
+/// rule language
+pub fn rle_rule(path: &str) -> Result<dsl::Rule, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let rle = io::rle::read(BufReader::new(file)).map_err(|e| format!("{}: {}", path, e))?;
+    match rle.dsl_rule() {
+        Some(rule) => rule.map_err(|e| format!("{}: rule {}", path, e)),
+        None => Err(format!("{}: the header has no rule", path)),
+    }
+}
+
+/// Read the pattern at path as state indices, picking the format from the
+/// extension. Formats with their own origin are placed by their top left
+/// live cell.
+pub fn load(path: &str) -> Result<Frame<u8>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let reader = BufReader::new(file);
+    let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+    let pattern: io::Result<Frame<u8>> = match &extension.to_lowercase()[..] {
+        "rle" => io::rle::read(reader).map(|p| p.pattern),
+        "cells" => io::cells::read(reader).map(|p| p.pattern),
+        "lif" | "life" => io::life::read(reader).map(|p| p.pattern),
+        "mc" => io::macrocell::read(reader).map(|p| p.pattern),
+        _ => return Err(format!("{}: unknown pattern format, expected .rle, .cells, .lif or .mc",
+                                path)),
+    };
+    pattern.map_err(|e| format!("{}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use simulation::Frame;
+    use simulation::patterns::Orientation;
+
+    use super::Placement;
+
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Placement::parse("glider.rle@10,10").unwrap(),
+                   Placement { path: "glider.rle".to_string(), x: 10, y: 10,
+                               orientation: Orientation::Identity });
+        assert_eq!(Placement::parse("gun.rle@50,5:rot90").unwrap(),
+                   Placement { path: "gun.rle".to_string(), x: 50, y: 5,
+                               orientation: Orientation::Rotate90 });
+        assert_eq!(Placement::parse("a.cells:flip-v").unwrap(),
+                   Placement { path: "a.cells".to_string(), x: 0, y: 0,
+                               orientation: Orientation::FlipVertical });
+        assert!(Placement::parse("a.rle@1").is_err());
+        assert!(Placement::parse("a.rle:rot45").unwrap_err().starts_with("unknown orientation"));
+        assert!(Placement::parse("@1,2").is_err());
+    }
+
+    #[test]
+    fn place() {
+        let path = env::temp_dir().join("simulation-cli-glider.rle");
+        fs::write(&path, "x = 3, y = 3\nbo$2bo$3o!\n").unwrap();
+        let placement = Placement::parse(&format!("{}@4,0:rot90", path.display())).unwrap();
+        let mut frame = Frame::<u8>::new(6, 3);
+        placement.place(&mut frame).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // the glider rotated a quarter turn, wrapping around the right edge
+        let rows = (0..3)
+            .map(|y| (0..6).map(|x| if *frame.get(x, y) == 1 { 'O' } else { '.' })
+                 .collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec!["....O.", "O...O.", "....OO"]);
+
+        let missing = Placement::parse("does-not-exist.rle").unwrap();
+        assert!(missing.place(&mut frame).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use analyze;
+use cell::Cell;
+use output;
+use pattern::{self, Placement};
+
+use simulation::{Frame, Simulation, Square};
+use simulation::io;
+use simulation::patterns::Orientation;
+
+use std::io::{BufRead, Write};
+
+pub const HELP: &str = "\
+commands:
+    step [n]                    step n generations (default 1)
+    set <x> <y> <state>         set a cell to dead, alive or a state number
+    load <path> <x> <y> [o]     place a pattern with its top left corner at
+                                x, y, in orientation o (default rot0)
+    save <path>                 write the board as .rle or .cells
+    clear                       kill every cell
+    stats                       print the generation and population
+    help                        show this message
+    quit                        leave the repl
+";
+
+/// What a command asks of the loop once it has run
+enum Next {
+    /// print the board and read the next command
+    Show,
+    /// read the next command without printing the board
+    Quiet,
+    Quit,
+}
+
+/// Parse the argument called name
+fn number<T: ::std::str::FromStr>(name: &str, arg: Option<&str>) -> Result<T, String> {
+    let arg = arg.ok_or_else(|| format!("missing {}", name))?;
+    arg.parse().map_err(|_| format!("invalid {}: {}", name, arg))
+}
+
+/// Write the board as one line of characters per row
+fn show<T: Cell, W: Write>(out: &mut W, frame: &Frame<T>) -> Result<(), String> {
+    for y in 0..frame.height() {
+        let row = (0..frame.width()).map(|x| frame.get(x, y).to_char())
+            .collect::<String>();
+        writeln!(out, "{}", row).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Run a single command line against sim
+fn command<T, F, W>(sim: &mut Simulation<T, F>, line: &str, out: &mut W) -> Result<Next, String>
+where T: Cell, F: Fn(Square<T>) -> T, W: Write {
+    let mut words = line.split_whitespace();
+    let name = match words.next() {
+        Some(name) => name,
+        None => return Ok(Next::Quiet),
+    };
+    // check for extra arguments before running anything
+    let most = match name {
+        "step" | "save" => 1,
+        "set" => 3,
+        "load" => 4,
+        _ => 0,
+    };
+    if let Some(extra) = words.clone().nth(most) {
+        return Err(format!("unexpected argument {}", extra));
+    }
+    let next = match name {
+        "step" => {
+            let n = match words.next() {
+                Some(n) => number("generation count", Some(n))?,
+                None => 1,
+            };
+            sim.step_n(n);
+            Next::Show
+        }
+        "set" => {
+            let x: usize = number("x", words.next())?;
+            let y: usize = number("y", words.next())?;
+            let state = match words.next() {
+                Some("dead") => 0,
+                Some("alive") => 1,
+                state => number("state", state)?,
+            };
+            let (w, h) = (sim.frame().width(), sim.frame().height());
+            if x >= w || y >= h {
+                return Err(format!("{},{} is outside the {}x{} board", x, y, w, h));
+            }
+            *sim.frame_mut().get_mut(x, y) = io::state(state);
+            Next::Show
+        }
+        "load" => {
+            let path = words.next().ok_or("missing path")?.to_string();
+            let x = number("x", words.next())?;
+            let y = number("y", words.next())?;
+            let orientation = match words.next() {
+                Some(o) => pattern::orientation(o)?,
+                None => Orientation::Identity,
+            };
+            Placement { path, x, y, orientation }.place(sim.frame_mut())?;
+            Next::Show
+        }
+        "save" => {
+            let path = words.next().ok_or("missing path")?;
+            output::save(path, sim.frame())?;
+            writeln!(out, "saved {}", path).map_err(|e| e.to_string())?;
+            Next::Quiet
+        }
+        "clear" => {
+            let (w, h) = (sim.frame().width(), sim.frame().height());
+            *sim.frame_mut() = Frame::new(w, h);
+            Next::Show
+        }
+        "stats" => {
+            writeln!(out, "generation {}  population {}", sim.generation(),
+                     analyze::population(sim.frame())).map_err(|e| e.to_string())?;
+            Next::Quiet
+        }
+        "help" => {
+            write!(out, "{}", HELP).map_err(|e| e.to_string())?;
+            Next::Quiet
+        }
+        "quit" | "exit" => Next::Quit,
+        _ => return Err(format!("unknown command {}, try help", name)),
+    };
+    Ok(next)
+}
+
+/// Read commands from input until it ends or says quit, printing the board
+/// after every command that changes it. A command that fails prints its
+/// error and leaves the board as it was, so piped scripts keep going.
+pub fn repl<T, F, R, W>(sim: &mut Simulation<T, F>, input: R, mut out: W) -> Result<(), String>
+where T: Cell, F: Fn(Square<T>) -> T, R: BufRead, W: Write {
+    show(&mut out, sim.frame())?;
+    for line in input.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        match command(sim, &line, &mut out) {
+            Ok(Next::Show) => {
+                writeln!(out, "generation {}", sim.generation()).map_err(|e| e.to_string())?;
+                show(&mut out, sim.frame())?;
+            }
+            Ok(Next::Quiet) => {}
+            Ok(Next::Quit) => break,
+            Err(e) => writeln!(out, "error: {}", e).map_err(|e| e.to_string())?,
+        }
+        out.flush().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use simulation::{game_of_life, Frame, Simulation};
+    use simulation::game_of_life::State;
+
+    use super::repl;
+
+    use std::env;
+    use std::fs;
+
+    fn run(script: &str) -> String {
+        let mut sim = Simulation::new(Frame::<State>::new(5, 5), game_of_life::rule);
+        let mut out = vec![];
+        repl(&mut sim, script.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn blinker() {
+        let out = run("set 1 2 alive\nset 2 2 alive\nset 3 2 1\nstep\nstats\n");
+        let last = out.rsplit("generation 1\n").next().unwrap();
+        assert_eq!(last, ".....\n..O..\n..O..\n..O..\n.....\ngeneration 1  population 3\n");
+    }
+
+    #[test]
+    fn errors() {
+        let out = run("set 9 9 alive\nwalk\nstep x\nstep 1 2\nquit\nstep\n");
+        let errors = out.lines().filter(|l| l.starts_with("error: ")).collect::<Vec<_>>();
+        assert_eq!(errors, vec!["error: 9,9 is outside the 5x5 board",
+                                "error: unknown command walk, try help",
+                                "error: invalid generation count: x",
+                                "error: unexpected argument 2"]);
+        // failed commands leave the board alone and nothing runs after quit
+        assert!(!out.contains("generation 1"));
+    }
+
+    #[test]
+    fn load_and_save() {
+        let dir = env::temp_dir();
+        let glider = dir.join("simulation-repl-glider.rle");
+        let saved = dir.join("simulation-repl-saved.cells");
+        fs::write(&glider, "x = 3, y = 3\nbo$2bo$3o!\n").unwrap();
+        let out = run(&format!("load {} 1 1\nsave {}\n", glider.display(), saved.display()));
+        let written = fs::read_to_string(&saved);
+        fs::remove_file(&glider).unwrap();
+        fs::remove_file(&saved).unwrap();
+        let board = ".....\n..O..\n...O.\n.OOO.\n.....\n";
+        assert!(out.ends_with(&format!("{}saved {}\n", board, saved.display())));
+        assert!(written.unwrap().ends_with(board));
+    }
+}
@@ -0,0 +1,86 @@
+use simulation::Frame;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+/// A generator seeded from seed. The same seed always gives the same soup.
+pub fn rng(seed: u64) -> XorShiftRng {
+    // xorshift can't be seeded with all zeros, so fix half of the state
+    XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32, 0x9e37_79b9, 0x243f_6a88])
+}
+
+/// Fill a width by height frame at random. With a single density each cell
+/// is alive with that probability and picks one of the `states` live states
+/// uniformly; otherwise there has to be a density for each live state. live
+/// makes the nth live state.
+pub fn soup<T, R, L>(rng: &mut R, width: usize, height: usize, density: &[f64], states: usize,
+                     live: L) -> Result<Frame<T>, String>
+where T: Clone + Default, R: Rng, L: Fn(&mut R, usize) -> T {
+    let density = match density.len() {
+        1 => vec![density[0] / states as f64; states],
+        n if n == states => density.to_vec(),
+        n => return Err(format!("{} densities given for a rule with {} live states", n, states)),
+    };
+    let total = density.iter().sum::<f64>();
+    if density.iter().any(|&d| d < 0.0) || total > 1.0 + 1e-9 {
+        return Err(format!("densities have to be positive and add up to at most 1, not {}",
+                           total));
+    }
+
+    let mut frame = Frame::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let mut r = rng.next_f64();
+            for (i, &d) in density.iter().enumerate() {
+                if r < d {
+                    *frame.get_mut(x, y) = live(rng, i);
+                    break;
+                }
+                r -= d;
+            }
+        }
+    }
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rng, soup};
+
+    fn count(frame: &::simulation::Frame<u8>, state: u8) -> usize {
+        frame.enumerate_squares().filter(|&(_, _, &c)| c == state).count()
+    }
+
+    #[test]
+    fn reproducible() {
+        let a = soup(&mut rng(7), 30, 30, &[0.35], 1, |_, _| 1u8).unwrap();
+        let b = soup(&mut rng(7), 30, 30, &[0.35], 1, |_, _| 1u8).unwrap();
+        let c = soup(&mut rng(8), 30, 30, &[0.35], 1, |_, _| 1u8).unwrap();
+        assert!(a == b);
+        assert!(a != c);
+    }
+
+    #[test]
+    fn density() {
+        let frame = soup(&mut rng(1), 100, 100, &[0.35], 1, |_, _| 1u8).unwrap();
+        let alive = count(&frame, 1);
+        assert!(alive > 3200 && alive < 3800, "{} alive", alive);
+
+        let frame = soup(&mut rng(1), 100, 100, &[0.1, 0.5], 2, |_, i| i as u8 + 1).unwrap();
+        let (ones, twos) = (count(&frame, 1), count(&frame, 2));
+        assert!(ones > 800 && ones < 1200, "{} ones", ones);
+        assert!(twos > 4700 && twos < 5300, "{} twos", twos);
+
+        let frame = soup(&mut rng(1), 100, 100, &[0.6], 3, |_, i| i as u8 + 1).unwrap();
+        for state in 1..4 {
+            let n = count(&frame, state);
+            assert!(n > 1800 && n < 2200, "{} of {}", n, state);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(soup(&mut rng(1), 2, 2, &[0.1, 0.2], 3, |_, _| 1u8).unwrap_err(),
+                   "2 densities given for a rule with 3 live states");
+        assert!(soup(&mut rng(1), 2, 2, &[0.7, 0.5], 2, |_, _| 1u8).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use super::{game_of_life, rainbow_life, CellState, Frame};
+use super::cyclic::Cyclic;
+use super::lenia::Lenia;
+use super::render::{Color, ColorMap};
+use super::render::colormap::{Binary, Gradient, Palette};
+use super::smooth_life::SmoothLife;
+
+/// The names of the rules a board can run
+pub const RULES: [&str; 5] = ["life", "rainbow", "cyclic", "smooth-life", "lenia"];
+
+/// The frame of a board along with the rule stepping it
+#[derive(Clone, Debug)]
+enum Cells {
+    Life(Frame<game_of_life::State>),
+    Rainbow(Frame<rainbow_life::State>),
+    Cyclic(Cyclic, Frame<u8>),
+    SmoothLife(SmoothLife, Frame<f32>),
+    Lenia(Lenia, Frame<f32>),
+}
+
+/// A built in rule picked by name at runtime, with cells read and written as
+/// numbers. This is what bindings to other languages are built on, as they
+/// can't name the generic types a `Simulation` is made of.
+///
+/// Cells of discrete rules are their state index, 0 being dead, and cells of
+/// continuous rules are their value between 0 and 1.
+#[derive(Clone, Debug)]
+pub struct Board {
+    rule: &'static str,
+    cells: Cells,
+    generation: u64,
+    /// built once rather than for every cell drawn
+    gradient: Gradient,
+}
+
+impl Board {
+    /// An empty width by height board running the rule called rule, or None
+    /// if it isn't one of `RULES`. Rules with parameters use the same defaults
+    /// as the command line.
+    pub fn new(rule: &str, width: usize, height: usize) -> Option<Board> {
+        let cells = match rule {
+            "life" => Cells::Life(Frame::new(width, height)),
+            "rainbow" => Cells::Rainbow(Frame::new(width, height)),
+            "cyclic" => Cells::Cyclic(Cyclic::default(), Frame::new(width, height)),
+            "smooth-life" => Cells::SmoothLife(SmoothLife::new(6.), Frame::new(width, height)),
+            "lenia" => Cells::Lenia(Lenia::orbium(), Frame::new(width, height)),
+            _ => return None,
+        };
+        let rule = RULES.iter().find(|&&r| r == rule).unwrap();
+        Some(Board { rule, cells, generation: 0, gradient: Gradient::viridis() })
+    }
+
+    /// The name of the rule
+    pub fn rule(&self) -> &str {
+        self.rule
+    }
+
+    pub fn width(&self) -> usize {
+        match self.cells {
+            Cells::Life(ref f) => f.width(),
+            Cells::Rainbow(ref f) => f.width(),
+            Cells::Cyclic(_, ref f) => f.width(),
+            Cells::SmoothLife(_, ref f) | Cells::Lenia(_, ref f) => f.width(),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self.cells {
+            Cells::Life(ref f) => f.height(),
+            Cells::Rainbow(ref f) => f.height(),
+            Cells::Cyclic(_, ref f) => f.height(),
+            Cells::SmoothLife(_, ref f) | Cells::Lenia(_, ref f) => f.height(),
+        }
+    }
+
+    /// The number of steps taken so far
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The cell at (x, y) as a number
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        match self.cells {
+            Cells::Life(ref f) => f.get(x, y).index() as f32,
+            Cells::Rainbow(ref f) => f.get(x, y).index() as f32,
+            Cells::Cyclic(_, ref f) => *f.get(x, y) as f32,
+            Cells::SmoothLife(_, ref f) | Cells::Lenia(_, ref f) => *f.get(x, y),
+        }
+    }
+
+    /// Set the cell at (x, y) from a number. Discrete states are rounded, with
+    /// states past the last live one wrapping around the live states, and
+    /// continuous values are clamped to between 0 and 1.
+    pub fn set(&mut self, x: usize, y: usize, value: f32) {
+        let state = value.round().max(0.) as usize;
+        match self.cells {
+            Cells::Life(ref mut f) => {
+                *f.get_mut(x, y) = if state == 0 {
+                    game_of_life::State::Dead
+                } else {
+                    game_of_life::State::Alive
+                };
+            }
+            Cells::Rainbow(ref mut f) => {
+                use super::rainbow_life::State::{Blue, Dead, Green, Red};
+                *f.get_mut(x, y) = match state {
+                    0 => Dead,
+                    s => [Red, Green, Blue][(s - 1) % 3],
+                };
+            }
+            Cells::Cyclic(ref cyclic, ref mut f) => {
+                *f.get_mut(x, y) = (state % cyclic.states() as usize) as u8;
+            }
+            Cells::SmoothLife(_, ref mut f) | Cells::Lenia(_, ref mut f) => {
+                *f.get_mut(x, y) = if value.is_nan() { 0. } else { value.clamp(0., 1.) };
+            }
+        }
+    }
+
+    /// Every cell as a number, row by row
+    pub fn values(&self) -> Vec<f32> {
+        let (w, h) = (self.width(), self.height());
+        (0..h).flat_map(|y| (0..w).map(move |x| (x, y))).map(|(x, y)| self.get(x, y)).collect()
+    }
+
+    /// How many cells away the rule looks, which the board has to be wider
+    /// and taller than, e.g. 13 for lenia
+    pub fn reach(&self) -> usize {
+        match self.cells {
+            Cells::Life(_) | Cells::Rainbow(_) => 1,
+            Cells::Cyclic(ref cyclic, _) => cyclic.range().unsigned_abs(),
+            Cells::SmoothLife(ref smooth_life, _) => smooth_life.radius(),
+            Cells::Lenia(ref lenia, _) => lenia.radius(),
+        }
+    }
+
+    /// Advance the board a generation. Like `Frame::next_frame`, this panics
+    /// if the board isn't wider and taller than the reach of the rule, even
+    /// for the rules that are stepped by convolving, so every rule sees each
+    /// neighbor once.
+    pub fn step(&mut self) {
+        let reach = self.reach();
+        assert!(reach < self.width() && reach < self.height(),
+                "a {}x{} board is too small for {}, which looks {} cells away",
+                self.width(), self.height(), self.rule, reach);
+        match self.cells {
+            Cells::Life(ref mut f) => *f = f.next_frame(game_of_life::rule),
+            Cells::Rainbow(ref mut f) => *f = f.next_frame(rainbow_life::rule),
+            Cells::Cyclic(ref cyclic, ref mut f) => *f = cyclic.step(f),
+            Cells::SmoothLife(ref smooth_life, ref mut f) => *f = smooth_life.step(f),
+            Cells::Lenia(ref lenia, ref mut f) => *f = lenia.step(f),
+        }
+        self.generation += 1;
+    }
+
+    /// Advance the board n generations
+    pub fn step_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// How the cell at (x, y) is drawn: white on black for life, a color per
+    /// state for rainbow and viridis for the rest
+    pub fn color(&self, x: usize, y: usize) -> Color {
+        match self.cells {
+            Cells::Life(ref f) => Binary::default().color(f.get(x, y)),
+            Cells::Rainbow(ref f) => Palette::default().color(f.get(x, y)),
+            Cells::Cyclic(ref cyclic, ref f) => {
+                self.gradient.at(*f.get(x, y) as f64 / (cyclic.states() - 1) as f64)
+            }
+            Cells::SmoothLife(_, ref f) | Cells::Lenia(_, ref f) => {
+                self.gradient.color(f.get(x, y))
+            }
+        }
+    }
+
+    /// Draw the board into buf as opaque RGBA pixels, row by row, as a canvas
+    /// wants them.
+    ///
+    /// # Panics
+    ///
+    /// If buf doesn't hold exactly 4 bytes per cell.
+    pub fn write_rgba(&self, buf: &mut [u8]) {
+        let (w, h) = (self.width(), self.height());
+        assert_eq!(buf.len(), w * h * 4, "a {}x{} board needs {} bytes", w, h, w * h * 4);
+        for (i, pixel) in buf.chunks_mut(4).enumerate() {
+            let [r, g, b] = self.color(i % w, i / w);
+            pixel.copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Board, RULES};
+
+    #[test]
+    fn rules() {
+        for &rule in &RULES {
+            let mut board = Board::new(rule, 30, 28).unwrap();
+            assert_eq!((board.rule(), board.width(), board.height()), (rule, 30, 28));
+            board.set(1, 2, 1.);
+            assert_eq!(board.get(1, 2), 1.);
+            board.step_n(2);
+            assert_eq!(board.generation(), 2);
+        }
+        assert!(Board::new("hashlife", 4, 4).is_none());
+    }
+
+    #[test]
+    fn blinker() {
+        let mut board = Board::new("life", 5, 5).unwrap();
+        for y in 1..4 {
+            board.set(2, y, 1.);
+        }
+        board.step();
+        let row = |y| (0..5).map(|x| board.get(x, y)).collect::<Vec<_>>();
+        assert_eq!(row(2), vec![0., 1., 1., 1., 0.]);
+        assert_eq!(board.values().iter().sum::<f32>(), 3.);
+    }
+
+    #[test]
+    fn values() {
+        let mut board = Board::new("rainbow", 2, 1).unwrap();
+        board.set(0, 0, 5.);
+        assert_eq!(board.get(0, 0), 2.);
+        let mut board = Board::new("cyclic", 2, 1).unwrap();
+        board.set(0, 0, 15.);
+        assert_eq!(board.get(0, 0), 1.);
+        let mut board = Board::new("lenia", 2, 1).unwrap();
+        board.set(0, 0, 1.5);
+        board.set(1, 0, -0.5);
+        assert_eq!(board.values(), vec![1., 0.]);
+    }
+
+    #[test]
+    fn rgba() {
+        let mut board = Board::new("life", 2, 1).unwrap();
+        board.set(1, 0, 1.);
+        let mut buf = [0; 8];
+        board.write_rgba(&mut buf);
+        assert_eq!(buf, [0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rgba_size() {
+        Board::new("life", 2, 2).unwrap().write_rgba(&mut [0; 8]);
+    }
+}
@@ -0,0 +1,118 @@
+use super::Frame;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// A rectangle of cells with its top left corner at (x, y)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    /// How many cells the rectangle covers
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Whether (x, y) lies within the rectangle
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+impl<T> Frame<T> {
+    /// The smallest rectangle holding every cell alive holds for, or None if
+    /// there are none. The frame wraps, but the rectangle doesn't, so a
+    /// pattern straddling an edge gets a rectangle spanning the frame.
+    pub fn bounding_box<F>(&self, alive: F) -> Option<Rect>
+    where F: Fn(&T) -> bool {
+        self.enumerate_squares()
+            .filter(|&(_, _, c)| alive(c))
+            .fold(None, |b, (x, y, _)| {
+                let (l, t, r, b) = b.unwrap_or((x, y, x, y));
+                Some((l.min(x), t.min(y), r.max(x), b.max(y)))
+            })
+            .map(|(l, t, r, b)| Rect { x: l, y: t, width: r - l + 1, height: b - t + 1 })
+    }
+}
+
+impl<T> Frame<T>
+where T: Clone {
+    /// A copy of the cells in rect
+    ///
+    /// # Panics
+    ///
+    /// if rect doesn't fit in the frame
+    pub fn crop(&self, rect: Rect) -> Frame<T> {
+        assert!(rect.x + rect.width <= self.width() && rect.y + rect.height <= self.height(),
+                "{:?} doesn't fit in a {}x{} frame", rect, self.width(), self.height());
+        let data = (0..rect.area())
+            .map(|i| self.get(rect.x + i % rect.width, rect.y + i / rect.width).clone())
+            .collect::<Vec<_>>();
+        Frame::from_vec(rect.width, rect.height, data).unwrap()
+    }
+}
+
+impl<T> Frame<T>
+where T: Clone + Default + PartialEq {
+    /// The live cells, those that aren't `T::default()`, cropped to their
+    /// bounding box with margin empty cells on every side, or None if every
+    /// cell is dead
+    pub fn crop_to_content(&self, margin: usize) -> Option<Frame<T>> {
+        let dead = T::default();
+        let rect = self.bounding_box(|c| *c != dead)?;
+        let cropped = self.crop(rect);
+        let mut padded = Frame::new(rect.width + 2 * margin, rect.height + 2 * margin);
+        for (x, y, c) in cropped.enumerate_squares() {
+            *padded.get_mut(x + margin, y + margin) = c.clone();
+        }
+        Some(padded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::Rect;
+
+    #[test]
+    fn bounding_box() {
+        let mut frame = Frame::<u8>::new(6, 5);
+        assert_eq!(frame.bounding_box(|&c| c != 0), None);
+        *frame.get_mut(1, 3) = 1;
+        *frame.get_mut(4, 2) = 2;
+        let rect = frame.bounding_box(|&c| c != 0).unwrap();
+        assert_eq!(rect, Rect { x: 1, y: 2, width: 4, height: 2 });
+        assert_eq!(rect.area(), 8);
+        assert!(rect.contains(4, 3) && !rect.contains(5, 3) && !rect.contains(1, 1));
+        assert_eq!(frame.bounding_box(|&c| c == 2), Some(Rect { x: 4, y: 2, width: 1, height: 1 }));
+    }
+
+    #[test]
+    fn crop() {
+        let frame = Frame::from_vec(4, 3, (0..12).collect()).unwrap();
+        let cropped = frame.crop(Rect { x: 1, y: 1, width: 3, height: 2 });
+        assert_eq!(cropped.as_slice(), &[5, 6, 7, 9, 10, 11]);
+
+        let mut frame = Frame::<u8>::new(8, 8);
+        assert_eq!(frame.crop_to_content(1), None);
+        *frame.get_mut(3, 4) = 1;
+        *frame.get_mut(4, 5) = 1;
+        let cropped = frame.crop_to_content(0).unwrap();
+        assert_eq!(cropped.as_slice(), &[1, 0, 0, 1]);
+        let padded = frame.crop_to_content(2).unwrap();
+        assert_eq!((padded.width(), padded.height()), (6, 6));
+        assert_eq!((*padded.get(2, 2), *padded.get(3, 3), *padded.get(3, 2)), (1, 1, 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn crop_outside() {
+        Frame::<u8>::new(4, 4).crop(Rect { x: 2, y: 0, width: 3, height: 1 });
+    }
+}
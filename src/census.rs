@@ -0,0 +1,304 @@
+use super::{Frame, Square};
+use super::analysis::{self, Motion};
+use super::patterns::{Orientation, Pattern};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// The groups of live cells of frame that touch, each as a pattern cropped to
+/// its cells. Cells touch when they are next to each other orthogonally or
+/// diagonally, across the edges of the frame too, so objects closer than
+/// that, like the halves of a pseudo still life, count as one.
+pub fn objects<T, F>(frame: &Frame<T>, alive: F) -> Vec<Pattern>
+where F: Fn(&T) -> bool {
+    let (w, h) = (frame.width(), frame.height());
+    let mut seen = frame.map(|c| !alive(c));
+    let mut objects = vec![];
+    let mut stack = vec![];
+    for start in 0..w * h {
+        if seen.as_slice()[start] {
+            continue;
+        }
+        *seen.get_mut(start % w, start / w) = true;
+        // positions unwrapped from the start, so objects crossing an edge
+        // stay in one piece
+        let mut cells = vec![(0isize, 0isize)];
+        stack.push((0isize, 0isize));
+        while let Some((dx, dy)) = stack.pop() {
+            for (i, j) in (-1..2).flat_map(|j| (-1..2).map(move |i| (i, j))) {
+                let (nx, ny) = (dx + i, dy + j);
+                let x = ((start % w) as isize + nx).rem_euclid(w as isize) as usize;
+                let y = ((start / w) as isize + ny).rem_euclid(h as isize) as usize;
+                if !*seen.get(x, y) {
+                    *seen.get_mut(x, y) = true;
+                    cells.push((nx, ny));
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        let x0 = cells.iter().map(|&(x, _)| x).min().unwrap();
+        let y0 = cells.iter().map(|&(_, y)| y).min().unwrap();
+        let width = cells.iter().map(|&(x, _)| (x - x0) as usize + 1).max().unwrap();
+        let height = cells.iter().map(|&(_, y)| (y - y0) as usize + 1).max().unwrap();
+        let cells = cells.iter().map(|&(x, y)| ((x - x0) as usize, (y - y0) as usize)).collect();
+        objects.push(Pattern::from_cells(width, height, cells));
+    }
+    objects
+}
+
+/// How many of each object were seen, with objects that only differ in
+/// position or orientation counted together under their canonical pattern
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Census {
+    counts: HashMap<Pattern, u64>,
+}
+
+impl Census {
+    pub fn new() -> Census {
+        Census::default()
+    }
+
+    /// Count the objects of frame
+    pub fn take<T, F>(frame: &Frame<T>, alive: F) -> Census
+    where F: Fn(&T) -> bool {
+        let mut census = Census::new();
+        for object in objects(frame, alive) {
+            census.add(&object);
+        }
+        census
+    }
+
+    /// Count object once more
+    pub fn add(&mut self, object: &Pattern) {
+        *self.counts.entry(object.canonical()).or_insert(0) += 1;
+    }
+
+    /// Add the counts of other to these
+    pub fn merge(&mut self, other: &Census) {
+        for (object, &n) in &other.counts {
+            *self.counts.entry(object.clone()).or_insert(0) += n;
+        }
+    }
+
+    /// How many times object, in any position or orientation, was seen
+    pub fn count(&self, object: &Pattern) -> u64 {
+        self.counts.get(&object.canonical()).cloned().unwrap_or(0)
+    }
+
+    /// The number of objects seen
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Every object seen with its count, the most common first and ties
+    /// ordered by the smallest population
+    pub fn sorted(&self) -> Vec<(&Pattern, u64)> {
+        let mut sorted = self.counts.iter().map(|(p, &n)| (p, n)).collect::<Vec<_>>();
+        sorted.sort_by(|a, b| {
+            b.1.cmp(&a.1).then(a.0.population().cmp(&b.0.population())).then(a.0.cmp(b.0))
+        });
+        sorted
+    }
+
+    /// The objects seen classified by running each on its own, with the
+    /// counts of phases of the same object added together, the most common
+    /// first. The arguments are those of `classify`.
+    pub fn classify<T, R>(&self, alive: T, rule: R, max_generations: u64)
+                          -> Vec<(Classification, u64)>
+    where T: Clone + Default + Eq + Hash, R: Fn(Square<T>) -> T {
+        let mut counts = HashMap::new();
+        for (object, &n) in &self.counts {
+            let classification = classify(object, alive.clone(), &rule, max_generations);
+            *counts.entry(classification).or_insert(0) += n;
+        }
+        let mut sorted = counts.into_iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.representative.cmp(&b.0.representative)));
+        sorted
+    }
+}
+
+/// What an object does when left on its own
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    StillLife,
+    Oscillator { period: u64 },
+    /// a pattern that moves by displacement every period, with the longer
+    /// axis first and both positive whichever way it is heading
+    Spaceship { period: u64, displacement: (usize, usize) },
+    /// anything that doesn't repeat from the start, dies or grows past the
+    /// limit, like objects that were only stable next to others
+    Unknown,
+}
+
+/// An object identified by what it does
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Classification {
+    pub kind: Kind,
+    /// the usual name, for the most common objects
+    pub name: Option<&'static str>,
+    /// the same pattern for every phase, position and orientation of the
+    /// object: the first of the canonical patterns of its phases
+    pub representative: Pattern,
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(name) = self.name {
+            return write!(f, "{}", name);
+        }
+        let cells = self.representative.population();
+        match self.kind {
+            Kind::StillLife => write!(f, "{} cell still life", cells),
+            Kind::Oscillator { period } => write!(f, "{} cell p{} oscillator", cells, period),
+            Kind::Spaceship { period, displacement: (dx, dy) } => {
+                let motion = Motion { start: 0, period, displacement: (dx as isize, dy as isize) };
+                write!(f, "{} cell {} spaceship", cells, motion.speed())
+            }
+            Kind::Unknown => write!(f, "{} cell unknown object", cells),
+        }
+    }
+}
+
+/// The objects with names, in the orientation they are usually drawn
+fn known() -> Vec<(&'static str, Pattern)> {
+    vec![
+        ("block", Pattern::block()),
+        ("beehive", Pattern::beehive()),
+        ("loaf", Pattern::from_rows(&[".OO.", "O..O", ".O.O", "..O."])),
+        ("boat", Pattern::from_rows(&["OO.", "O.O", ".O."])),
+        ("ship", Pattern::from_rows(&["OO.", "O.O", ".OO"])),
+        ("tub", Pattern::from_rows(&[".O.", "O.O", ".O."])),
+        ("pond", Pattern::from_rows(&[".OO.", "O..O", "O..O", ".OO."])),
+        ("blinker", Pattern::blinker()),
+        ("toad", Pattern::toad()),
+        ("beacon", Pattern::beacon()),
+        ("glider", Pattern::glider()),
+        ("lwss", Pattern::lwss()),
+    ]
+}
+
+/// Classify object by running it on its own with rule, with its live cells
+/// set to alive, for up to max_generations. The object is put in the middle
+/// of an empty frame with room for it to move or change over a period
+/// without meeting itself across the edges. The names are those of Conway's
+/// Life, so they only mean something under that rule.
+pub fn classify<T, R>(object: &Pattern, alive: T, rule: R, max_generations: u64) -> Classification
+where T: Clone + Default + Eq + Hash, R: Fn(Square<T>) -> T {
+    let object = object.cropped();
+    let margin = object.width().max(object.height()) + 4;
+    let size = object.width().max(object.height()) + 2 * margin;
+    let mut frame = Frame::new(size, size);
+    object.place(&mut frame, margin, margin, Orientation::Identity, alive);
+
+    let dead = T::default();
+    let unknown = Classification {
+        kind: Kind::Unknown,
+        name: None,
+        representative: object.canonical(),
+    };
+    let motion = match analysis::motion(&frame, &rule, max_generations) {
+        // an object that changes before it repeats wasn't stable on its own
+        Some(motion) if motion.start == 0 => motion,
+        _ => return unknown,
+    };
+    let mut phases = vec![];
+    for _ in 0..motion.period {
+        phases.push(Pattern::from_frame(&frame, |c| *c != dead).canonical());
+        frame = frame.next_frame(&rule);
+    }
+    if phases.iter().any(|p| p.population() == 0) {
+        return unknown;
+    }
+    let (dx, dy) = motion.displacement;
+    let (dx, dy) = (dx.unsigned_abs(), dy.unsigned_abs());
+    let kind = match (motion.period, (dx.max(dy), dx.min(dy))) {
+        (_, (0, 0)) if motion.period == 1 => Kind::StillLife,
+        (period, (0, 0)) => Kind::Oscillator { period },
+        (period, displacement) => Kind::Spaceship { period, displacement },
+    };
+    let name = known().into_iter()
+        .find(|(_, pattern)| phases.contains(&pattern.canonical()))
+        .map(|(name, _)| name);
+    Classification {
+        kind,
+        name,
+        representative: phases.into_iter().min().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::Alive;
+    use super::super::patterns::{Orientation, Pattern};
+    use super::{classify, objects, Census, Kind};
+
+    #[test]
+    fn segments() {
+        let mut frame = Frame::new(12, 10);
+        Pattern::block().place(&mut frame, 1, 1, Orientation::Identity, true);
+        Pattern::glider().place(&mut frame, 5, 1, Orientation::Identity, true);
+        // a blinker split by the left and right edges
+        Pattern::blinker().place(&mut frame, 11, 6, Orientation::Identity, true);
+        Pattern::blinker().place(&mut frame, 5, 6, Orientation::Rotate90, true);
+
+        let mut found = objects(&frame, |&c| c);
+        found.sort_by_key(|p| p.population());
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[2], Pattern::block());
+        assert_eq!(found[3], Pattern::glider());
+        assert!(found[..2].iter().all(|p| p.canonical() == Pattern::blinker().canonical()));
+
+        let census = Census::take(&frame, |&c| c);
+        assert_eq!(census.total(), 4);
+        assert_eq!(census.count(&Pattern::blinker()), 2);
+        assert_eq!(census.count(&Pattern::beehive()), 0);
+        let sorted = census.sorted();
+        assert_eq!(sorted[0], (&Pattern::blinker().canonical(), 2));
+        assert_eq!(sorted[1], (&Pattern::block().canonical(), 1));
+
+        let mut doubled = census.clone();
+        doubled.merge(&census);
+        assert_eq!(doubled.count(&Pattern::glider()), 2);
+    }
+
+    #[test]
+    fn classifies() {
+        let kind = |pattern: &Pattern| classify(pattern, Alive, game_of_life::rule, 100);
+        let block = kind(&Pattern::block());
+        assert_eq!((block.kind, block.name), (Kind::StillLife, Some("block")));
+        let toad = kind(&Pattern::toad().oriented(Orientation::Rotate90));
+        assert_eq!((toad.kind, toad.name), (Kind::Oscillator { period: 2 }, Some("toad")));
+        let glider = kind(&Pattern::glider().oriented(Orientation::FlipHorizontal));
+        assert_eq!(glider.kind, Kind::Spaceship { period: 4, displacement: (1, 1) });
+        assert_eq!(glider.to_string(), "glider");
+        // every phase of the lightweight spaceship is the same object
+        let mut frame = Frame::new(12, 12);
+        Pattern::lwss().place(&mut frame, 4, 4, Orientation::Identity, Alive);
+        let lwss = Pattern::from_frame(&frame.next_frame(game_of_life::rule), |&c| c == Alive);
+        assert_eq!(kind(&lwss), kind(&Pattern::lwss()));
+        assert_eq!(kind(&lwss).to_string(), "lwss");
+
+        let eater = kind(&Pattern::from_rows(&["OO..", "O.O.", "..O.", "..OO"]));
+        assert_eq!((eater.kind, eater.to_string()), (Kind::StillLife, "7 cell still life".into()));
+        // the R-pentomino takes over a thousand generations to settle
+        let r = kind(&Pattern::r_pentomino());
+        assert_eq!((r.kind, r.to_string()), (Kind::Unknown, "5 cell unknown object".into()));
+
+        let mut frame = Frame::new(20, 12);
+        Pattern::blinker().place(&mut frame, 1, 1, Orientation::Identity, Alive);
+        Pattern::blinker().place(&mut frame, 6, 1, Orientation::Rotate90, Alive);
+        Pattern::beehive().place(&mut frame, 10, 1, Orientation::Identity, Alive);
+        Pattern::glider().place(&mut frame, 1, 7, Orientation::Identity, Alive);
+        Pattern::glider().place(&mut frame, 10, 7, Orientation::Identity, Alive);
+        let frame = frame.next_frame(game_of_life::rule);
+        let census = Census::take(&frame, |&c| c == Alive);
+        let counts = census.classify(Alive, game_of_life::rule, 100).into_iter()
+            .map(|(c, n)| (c.to_string(), n))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [("blinker".to_string(), 2), ("glider".to_string(), 2),
+                            ("beehive".to_string(), 1)]);
+    }
+}
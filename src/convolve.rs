@@ -0,0 +1,217 @@
+use super::Frame;
+
+/// What cells past the edges of a frame are taken to be when convolving
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Boundary {
+    /// wrap around to the other side, like rules stepped with `next_frame`
+    #[default]
+    Wrap,
+    /// repeat the closest cell on the edge
+    Clamp,
+}
+
+impl Boundary {
+    /// The index that i + d lands on in 0..len
+    fn index(self, i: usize, d: isize, len: usize) -> usize {
+        let j = i as isize + d;
+        match self {
+            Boundary::Wrap => j.rem_euclid(len as isize) as usize,
+            Boundary::Clamp => j.clamp(0, len as isize - 1) as usize,
+        }
+    }
+}
+
+/// Weights centered on a cell, with an odd width and height
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel<T> {
+    weights: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Kernel<T> {
+    /// A kernel with weights given row by row, or None unless there are
+    /// width * height of them and both are odd
+    pub fn new(width: usize, height: usize, weights: Vec<T>) -> Option<Kernel<T>> {
+        if width.is_multiple_of(2) || height.is_multiple_of(2)
+            || width.checked_mul(height) != Some(weights.len()) {
+            return None;
+        }
+        Some(Kernel { weights, width, height })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// How far the kernel reaches from its center horizontally and vertically
+    pub fn radius(&self) -> (usize, usize) {
+        (self.width / 2, self.height / 2)
+    }
+
+    /// The weight of the cell (i, j) away from the center
+    pub fn get(&self, i: isize, j: isize) -> &T {
+        let (rx, ry) = self.radius();
+        &self.weights[(j + ry as isize) as usize * self.width + (i + rx as isize) as usize]
+    }
+
+    /// The weights row by row
+    pub fn as_slice(&self) -> &[T] {
+        &self.weights
+    }
+}
+
+impl Kernel<f32> {
+    /// A kernel from offsets and weights like `Lenia::kernel`, just big
+    /// enough to hold them, with 0 everywhere else
+    pub fn from_offsets(offsets: &[(isize, isize, f32)]) -> Kernel<f32> {
+        let rx = offsets.iter().map(|&(i, _, _)| i.unsigned_abs()).max().unwrap_or(0);
+        let ry = offsets.iter().map(|&(_, j, _)| j.unsigned_abs()).max().unwrap_or(0);
+        let (width, height) = (2 * rx + 1, 2 * ry + 1);
+        let mut weights = vec![0.; width * height];
+        for &(i, j, w) in offsets {
+            weights[(j + ry as isize) as usize * width + (i + rx as isize) as usize] += w;
+        }
+        Kernel { weights, width, height }
+    }
+
+    /// The 3x3 discrete Laplacian, the sum of the four closest cells minus
+    /// four times the center, as used for diffusion
+    pub fn laplacian() -> Kernel<f32> {
+        Kernel { weights: vec![0., 1., 0., 1., -4., 1., 0., 1., 0.], width: 3, height: 3 }
+    }
+
+    /// A normalized gaussian blur with standard deviation sigma, cut off
+    /// three deviations out
+    pub fn gaussian(sigma: f32) -> Kernel<f32> {
+        assert!(sigma > 0., "a gaussian needs a positive sigma, not {}", sigma);
+        let r = (3. * sigma).ceil() as isize;
+        let mut offsets = vec![];
+        for j in -r..r + 1 {
+            for i in -r..r + 1 {
+                let d2 = (i * i + j * j) as f32;
+                offsets.push((i, j, (-d2 / (2. * sigma * sigma)).exp()));
+            }
+        }
+        Kernel::from_offsets(&offsets).normalized()
+    }
+
+    /// A normalized ring of the cells at least inner and less than outer from
+    /// the center, a disk when inner is 0, with cells straddling either edge
+    /// getting partial weight to avoid aliasing
+    pub fn ring(inner: f32, outer: f32) -> Kernel<f32> {
+        assert!(0. <= inner && inner < outer, "a ring from {} to {} is empty", inner, outer);
+        let r = outer.ceil() as isize;
+        let mut offsets = vec![];
+        for j in -r..r + 1 {
+            for i in -r..r + 1 {
+                let d = ((i * i + j * j) as f32).sqrt();
+                let covered = |radius: f32| (radius + 0.5 - d).clamp(0., 1.);
+                let inside = if inner > 0. { covered(inner) } else { 0. };
+                offsets.push((i, j, covered(outer) - inside));
+            }
+        }
+        Kernel::from_offsets(&offsets).normalized()
+    }
+
+    /// The weights scaled to add up to 1, unchanged if they add up to 0
+    pub fn normalized(mut self) -> Kernel<f32> {
+        let total = self.weights.iter().sum::<f32>();
+        if total != 0. {
+            for w in &mut self.weights {
+                *w /= total;
+            }
+        }
+        self
+    }
+}
+
+impl Frame<f32> {
+    /// Each cell replaced by the kernel weighted sum of the cells around it,
+    /// wrapping around at the edges
+    pub fn convolve(&self, kernel: &Kernel<f32>) -> Frame<f32> {
+        self.convolve_with(kernel, Boundary::Wrap)
+    }
+
+    /// Each cell replaced by the kernel weighted sum of the cells around it,
+    /// with boundary deciding what lies past the edges
+    pub fn convolve_with(&self, kernel: &Kernel<f32>, boundary: Boundary) -> Frame<f32> {
+        let (w, h) = (self.width(), self.height());
+        let (rx, ry) = kernel.radius();
+        let data = self.as_slice();
+        let mut out = vec![0.; w * h];
+        let mut columns = vec![0; w];
+        // one pass over the frame per nonzero weight keeps the inner loop a
+        // plain multiply-add over a row, which matters for kernels like
+        // Lenia's that are mostly empty
+        for (k, &weight) in kernel.as_slice().iter().enumerate() {
+            if weight == 0. {
+                continue;
+            }
+            let di = (k % kernel.width()) as isize - rx as isize;
+            let dj = (k / kernel.width()) as isize - ry as isize;
+            for (x, column) in columns.iter_mut().enumerate() {
+                *column = boundary.index(x, di, w);
+            }
+            for (y, row) in out.chunks_mut(w).enumerate() {
+                let source = &data[boundary.index(y, dj, h) * w..][..w];
+                for (cell, &x) in row.iter_mut().zip(&columns) {
+                    *cell += weight * source[x];
+                }
+            }
+        }
+        Frame::from_vec(w, h, out).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::lenia::Lenia;
+    use super::{Boundary, Kernel};
+
+    #[test]
+    fn kernels() {
+        assert!(Kernel::new(2, 3, vec![0.; 6]).is_none());
+        assert!(Kernel::new(3, 3, vec![0.; 6]).is_none());
+        let kernel = Kernel::new(3, 1, vec![1., 2., 3.]).unwrap();
+        assert_eq!((kernel.radius(), *kernel.get(-1, 0)), ((1, 0), 1.));
+
+        let gaussian = Kernel::gaussian(1.);
+        assert_eq!((gaussian.width(), gaussian.height()), (7, 7));
+        assert!((gaussian.as_slice().iter().sum::<f32>() - 1.).abs() < 1e-5);
+        assert!(gaussian.get(0, 0) > gaussian.get(1, 0));
+
+        let ring = Kernel::ring(2., 4.);
+        assert_eq!(*ring.get(0, 0), 0.);
+        assert!(*ring.get(3, 0) > 0.);
+        assert_eq!(*ring.get(4, 4), 0.);
+    }
+
+    #[test]
+    fn laplacian() {
+        let mut frame = Frame::<f32>::new(4, 3);
+        *frame.get_mut(0, 0) = 1.;
+        let wrapped = frame.convolve(&Kernel::laplacian());
+        assert_eq!(wrapped.as_slice(), &[-4., 1., 0., 1., 1., 0., 0., 0., 1., 0., 0., 0.]);
+        let clamped = frame.convolve_with(&Kernel::laplacian(), Boundary::Clamp);
+        assert_eq!(clamped.as_slice(), &[-2., 1., 0., 0., 1., 0., 0., 0., 0., 0., 0., 0.]);
+    }
+
+    #[test]
+    fn lenia_potential() {
+        let lenia = Lenia::new(4, &[1.], 0.15, 0.015, 0.1);
+        let mut frame = Frame::<f32>::new(12, 10);
+        for (i, cell) in frame.as_mut_slice().iter_mut().enumerate() {
+            *cell = (i * 7 % 11) as f32 / 10.;
+        }
+        let potential = frame.convolve(&Kernel::from_offsets(lenia.kernel()));
+        for (x, y, &u) in potential.enumerate_squares() {
+            assert!((u - lenia.potential(&frame.square(x, y))).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use super::{Frame, Square};
+use super::summed_area::SummedArea;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// A cyclic cellular automaton. Each cell holds one of `states` states and
+/// advances to the next state (wrapping around) once enough of its neighbors
+/// already hold that successor state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cyclic {
+    states: u8,
+    threshold: usize,
+    range: isize,
+}
+
+impl Default for Cyclic {
+    fn default() -> Cyclic {
+        Cyclic::new(14, 1, 1)
+    }
+}
+
+impl Cyclic {
+    /// A cyclic automaton with states states, where cells advance once
+    /// threshold neighbors in the square of radius range around them hold
+    /// the successor
+    ///
+    /// # Panics
+    ///
+    /// if there are fewer than 2 states or range is negative
+    pub fn new(states: u8, threshold: usize, range: isize) -> Cyclic {
+        assert!(states >= 2, "a cyclic automaton needs at least 2 states, not {}", states);
+        assert!(range >= 0, "a neighborhood can't have a radius of {}", range);
+        Cyclic { states, threshold, range }
+    }
+
+    /// The number of states in the cycle
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// How many neighbors have to hold the successor for a cell to advance
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The radius of the square neighborhood that is considered
+    pub fn range(&self) -> isize {
+        self.range
+    }
+
+    /// The state that comes after s, which may be any state, even one past
+    /// the last
+    pub fn successor(&self, s: u8) -> u8 {
+        ((s as u16 + 1) % self.states as u16) as u8
+    }
+
+    /// The rule for a cyclic automaton, to be used with `Frame::next_frame`
+    pub fn rule(&self, curr: Square<u8>) -> u8 {
+        let this = *curr.get(0, 0);
+        let next = self.successor(this);
+        let count = curr.neighbors(self.range)
+            .filter(|&(_, _, e)| { *e == next })
+            .count();
+        if count >= self.threshold { next } else { this }
+    }
+
+    /// The frame after frame, the same as stepping it with `rule`, but with
+    /// the neighbors holding each successor counted with a `SummedArea` of
+    /// that state, so large ranges cost no more per cell than small ones.
+    /// Frames too small for the neighborhood to fit without wrapping onto
+    /// itself are stepped with `rule`.
+    pub fn step(&self, frame: &Frame<u8>) -> Frame<u8> {
+        let (w, h) = (frame.width(), frame.height());
+        let r = self.range as usize;
+        if 2 * r >= w || 2 * r >= h {
+            return frame.next_frame(|sq| self.rule(sq));
+        }
+        // only the states that some cell is waiting for need counting
+        let mut counts: Vec<Option<SummedArea<u32>>> = vec![None; self.states as usize];
+        for &c in frame.as_slice() {
+            let next = self.successor(c);
+            if counts[next as usize].is_none() {
+                let count = SummedArea::from_fn(frame, |&d| (d == next) as u32);
+                counts[next as usize] = Some(count);
+            }
+        }
+        let mut stepped = frame.clone();
+        for (i, cell) in stepped.as_mut_slice().iter_mut().enumerate() {
+            let next = self.successor(*cell);
+            let count = counts[next as usize].as_ref().expect("every successor is counted");
+            if count.box_sum(i % w, i / w, r) as usize >= self.threshold {
+                *cell = next;
+            }
+        }
+        stepped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::Cyclic;
+
+    #[test]
+    fn successor_wraps() {
+        let cyclic = Cyclic::new(3, 1, 1);
+        assert_eq!(cyclic.successor(0), 1);
+        assert_eq!(cyclic.successor(2), 0);
+        assert_eq!(Cyclic::new(255, 1, 1).successor(254), 0);
+        assert_eq!(Cyclic::new(3, 1, 1).successor(255), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn one_state() {
+        Cyclic::new(1, 1, 1);
+    }
+
+    #[test]
+    fn advance() {
+        let cyclic = Cyclic::new(3, 1, 1);
+        let mut frame = Frame::<u8>::new(4, 4);
+        *frame.get_mut(1, 1) = 1;
+
+        let next = frame.next_frame(|sq| cyclic.rule(sq));
+
+        // every neighbor of (1, 1) is eaten, (1, 1) itself has no 2 nearby
+        let mut expected = Frame::<u8>::new(4, 4);
+        for x in 0..3 {
+            for y in 0..3 {
+                *expected.get_mut(x, y) = 1;
+            }
+        }
+        assert_eq!(next, expected);
+    }
+
+    #[test]
+    fn step() {
+        let cyclic = Cyclic::new(4, 3, 2);
+        let cells = (0..13 * 11).map(|i| (i * 37 % 11 % 4) as u8).collect();
+        let mut frame = Frame::from_vec(13, 11, cells).unwrap();
+        for _ in 0..5 {
+            let next = cyclic.step(&frame);
+            assert_eq!(next, frame.next_frame(|sq| cyclic.rule(sq)));
+            frame = next;
+        }
+
+        // too small for a box of radius 2
+        let small = Frame::from_vec(4, 4, (0..16).map(|i| i % 4).collect()).unwrap();
+        assert_eq!(cyclic.step(&small), small.next_frame(|sq| cyclic.rule(sq)));
+    }
+
+    #[test]
+    fn threshold() {
+        let cyclic = Cyclic::new(3, 2, 1);
+        let mut frame = Frame::<u8>::new(4, 4);
+        *frame.get_mut(1, 1) = 1;
+        *frame.get_mut(2, 1) = 1;
+
+        let next = frame.next_frame(|sq| cyclic.rule(sq));
+
+        // only the cells next to both ones advance
+        assert_eq!(*next.get(1, 0), 1);
+        assert_eq!(*next.get(2, 2), 1);
+        assert_eq!(*next.get(0, 1), 0);
+        assert_eq!(*next.get(3, 1), 0);
+        assert_eq!(*next.get(1, 1), 1);
+    }
+}
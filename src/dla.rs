@@ -0,0 +1,208 @@
+use super::Frame;
+use super::agents::{Agent, Agents, Turn};
+
+use rand::{Rng, XorShiftRng};
+
+/// The state of a cell in diffusion-limited aggregation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum State {
+    #[default]
+    Empty,
+    /// part of the growing cluster
+    Cluster,
+}
+
+/// Diffusion-limited aggregation. Random walkers wander the frame until they
+/// touch the cluster, at which point they stick to it and a new walker is
+/// released somewhere else on the frame. The walkers are `Agents`, so they
+/// can also be stepped alongside a rule with `Simulation::step_with`.
+#[derive(Debug)]
+pub struct Dla<R> {
+    rng: R,
+    walkers: Agents<Walker>,
+}
+
+impl<R: Rng> Dla<R> {
+    /// Create a simulation without any walkers
+    pub fn new(rng: R) -> Dla<R> {
+        Dla { rng, walkers: Agents::default() }
+    }
+
+    /// The walkers that haven't stuck yet
+    pub fn walkers(&self) -> &Agents<Walker> {
+        &self.walkers
+    }
+
+    /// The walkers, e.g. to step them with `Simulation::step_with`
+    pub fn walkers_mut(&mut self) -> &mut Agents<Walker> {
+        &mut self.walkers
+    }
+
+    /// Release a walker at a random empty position on the frame. Returns false
+    /// if there was no room for it.
+    pub fn release(&mut self, frame: &Frame<State>) -> bool {
+        match Walker::release(frame, &mut self.rng) {
+            Some(walker) => {
+                self.walkers.push(walker);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move every walker one step in a random direction. Walkers that end up
+    /// next to the cluster stick to it and are replaced by a fresh walker.
+    pub fn step(&mut self, frame: &mut Frame<State>) {
+        self.walkers.tick(frame);
+    }
+}
+
+/// A random walker of diffusion-limited aggregation, with its own generator
+/// for where it goes
+#[derive(Clone, Debug)]
+pub struct Walker {
+    position: (usize, usize),
+    rng: XorShiftRng,
+}
+
+impl Walker {
+    /// A walker at a random empty position on frame, or None if there is no
+    /// room for one
+    pub fn release<R: Rng>(frame: &Frame<State>, rng: &mut R) -> Option<Walker> {
+        let empty = frame.as_slice().iter().filter(|&&s| s == State::Empty).count();
+        if empty == 0 {
+            return None;
+        }
+        let (x, y, _) = frame.enumerate_squares()
+            .filter(|&(_, _, s)| { *s == State::Empty })
+            .nth(rng.gen_range(0, empty))
+            .unwrap();
+        Some(Walker { position: (x, y), rng: rng.gen() })
+    }
+
+    /// Stick to the cluster where the walker is and release another in its
+    /// place
+    fn stick(&mut self, frame: &mut Frame<State>, turn: &mut Turn<Walker>) {
+        let (x, y) = self.position;
+        *frame.get_mut(x, y) = State::Cluster;
+        self.replace(frame, turn);
+    }
+
+    /// Release another walker in place of this one
+    fn replace(&mut self, frame: &Frame<State>, turn: &mut Turn<Walker>) {
+        if let Some(walker) = Walker::release(frame, &mut self.rng) {
+            turn.spawn(walker);
+        }
+    }
+}
+
+impl Agent for Walker {
+    type Cell = State;
+
+    fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    /// Take a step in a random direction, sticking to the cluster instead if
+    /// the step would land on it, or after it if it lands next to it. Either
+    /// way the walker sticks to a cell that was empty.
+    fn act(&mut self, frame: &mut Frame<State>, turn: &mut Turn<Walker>) -> bool {
+        let (x, y) = self.position;
+        // another walker stuck right where this one is
+        if *frame.get(x, y) == State::Cluster {
+            self.replace(frame, turn);
+            return false;
+        }
+        let (w, h) = (frame.width(), frame.height());
+        let (dx, dy) = (self.rng.gen_range(0, 3), self.rng.gen_range(0, 3));
+        let next = ((x + w + dx - 1) % w, (y + h + dy - 1) % h);
+        if *frame.get(next.0, next.1) == State::Cluster {
+            self.stick(frame, turn);
+            return false;
+        }
+        self.position = next;
+        if touches_cluster(frame, next.0, next.1) {
+            self.stick(frame, turn);
+            return false;
+        }
+        true
+    }
+}
+
+/// Whether (x, y) is part of or adjacent to the cluster
+fn touches_cluster(frame: &Frame<State>, x: usize, y: usize) -> bool {
+    let (w, h) = (frame.width(), frame.height());
+    for i in 0..3 {
+        for j in 0..3 {
+            if *frame.get((x + w + i - 1) % w, (y + h + j - 1) % h) == State::Cluster {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::agents::Agent;
+    use super::{Dla, State, Walker};
+
+    use rand::{SeedableRng, XorShiftRng};
+
+    #[test]
+    fn cluster_grows() {
+        let mut frame = Frame::<State>::new(16, 16);
+        *frame.get_mut(8, 8) = State::Cluster;
+
+        let mut dla = Dla::new(XorShiftRng::from_seed([1, 2, 3, 4]));
+        for _ in 0..10 {
+            assert!(dla.release(&frame));
+        }
+        for _ in 0..50 {
+            dla.step(&mut frame);
+        }
+
+        let cluster = frame.enumerate_squares()
+            .filter(|&(_, _, s)| { *s == State::Cluster })
+            .count();
+        assert!(cluster > 1);
+        assert_eq!(dla.walkers().len(), 10);
+        for walker in dla.walkers().as_slice() {
+            let (x, y) = walker.position();
+            assert_eq!(*frame.get(x, y), State::Empty);
+        }
+    }
+
+    #[test]
+    fn sticks_where_empty() {
+        // a walker surrounded by the cluster sticks where it is, whichever
+        // way it would have gone
+        let mut frame = Frame::<State>::new(3, 3);
+        for (i, cell) in frame.as_mut_slice().iter_mut().enumerate() {
+            if i != 4 {
+                *cell = State::Cluster;
+            }
+        }
+        let mut dla = Dla::new(XorShiftRng::from_seed([1, 2, 3, 4]));
+        assert!(dla.release(&frame));
+        assert_eq!(dla.walkers().as_slice()[0].position(), (1, 1));
+        dla.step(&mut frame);
+        assert!(frame.as_slice().iter().all(|&s| s == State::Cluster));
+        assert!(dla.walkers().is_empty());
+        assert!(Walker::release(&frame, &mut XorShiftRng::from_seed([1, 2, 3, 4])).is_none());
+    }
+
+    #[test]
+    fn full_frame() {
+        let mut frame = Frame::<State>::new(2, 2);
+        for x in 0..2 {
+            for y in 0..2 {
+                *frame.get_mut(x, y) = State::Cluster;
+            }
+        }
+        let mut dla = Dla::new(XorShiftRng::from_seed([1, 2, 3, 4]));
+        assert!(!dla.release(&frame));
+    }
+}
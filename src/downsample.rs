@@ -0,0 +1,121 @@
+use super::Frame;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+impl<T> Frame<T> {
+    /// A smaller frame where each cell is reducer applied to a block_w by
+    /// block_h block of this frame, for looking at huge boards zoomed out or
+    /// at how a pattern looks at a coarser scale, e.g.
+    ///
+    /// ```
+    /// use simulation::Frame;
+    /// use simulation::downsample::mean;
+    ///
+    /// let frame = Frame::from_vec(4, 2, vec![1u8, 1, 0, 0,
+    ///                                        1, 0, 0, 1]).unwrap();
+    /// let coarse = frame.downsample(2, 2, mean);
+    /// assert_eq!(coarse.as_slice(), &[0.75, 0.25]);
+    /// ```
+    ///
+    /// When the blocks don't divide the frame evenly the blocks along the
+    /// right and bottom edges are smaller rather than wrapping around.
+    ///
+    /// # Panics
+    ///
+    /// if a block is empty
+    pub fn downsample<U, F>(&self, block_w: usize, block_h: usize, reducer: F) -> Frame<U>
+    where F: Fn(&[&T]) -> U {
+        assert!(block_w > 0 && block_h > 0, "can't downsample by {}x{} blocks", block_w, block_h);
+        let (w, h) = (self.width(), self.height());
+        let (cols, rows) = (w.div_ceil(block_w), h.div_ceil(block_h));
+        let mut data = Vec::with_capacity(cols * rows);
+        let mut block = Vec::with_capacity(block_w * block_h);
+        for by in 0..rows {
+            for bx in 0..cols {
+                block.clear();
+                for y in by * block_h..((by + 1) * block_h).min(h) {
+                    for x in bx * block_w..((bx + 1) * block_w).min(w) {
+                        block.push(self.get(x, y));
+                    }
+                }
+                data.push(reducer(&block));
+            }
+        }
+        Frame::from_vec(cols, rows, data).unwrap()
+    }
+}
+
+/// A reducer for `Frame::downsample` giving the most common cell of a block,
+/// ties going to whichever comes first
+pub fn majority<T>(cells: &[&T]) -> T
+where T: Clone + PartialEq {
+    let count = |c: &T| cells.iter().filter(|d| **d == c).count();
+    let mut best = cells[0];
+    let mut most = count(best);
+    for &c in cells {
+        let n = count(c);
+        if n > most {
+            best = c;
+            most = n;
+        }
+    }
+    best.clone()
+}
+
+/// A reducer for `Frame::downsample` giving the mean of a block
+pub fn mean<T>(cells: &[&T]) -> f64
+where T: Copy + Into<f64> {
+    cells.iter().map(|&&c| c.into()).sum::<f64>() / cells.len() as f64
+}
+
+/// A reducer for `Frame::downsample` giving the largest cell of a block
+pub fn max<T>(cells: &[&T]) -> T
+where T: Clone + PartialOrd {
+    let mut best = cells[0];
+    for &c in &cells[1..] {
+        if c > best {
+            best = c;
+        }
+    }
+    best.clone()
+}
+
+/// A reducer for `Frame::downsample` giving the fraction of a block that is
+/// alive
+pub fn density<T, F>(alive: F) -> impl Fn(&[&T]) -> f64
+where F: Fn(&T) -> bool {
+    move |cells| cells.iter().filter(|c| alive(c)).count() as f64 / cells.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::{density, majority, max, mean};
+
+    fn frame() -> Frame<u8> {
+        Frame::from_vec(5, 3, vec![1, 1, 0, 2, 2,
+                                   1, 0, 0, 2, 0,
+                                   3, 3, 0, 0, 0]).unwrap()
+    }
+
+    #[test]
+    fn reduces_blocks() {
+        let frame = frame();
+        let coarse = frame.downsample(2, 2, majority);
+        // the blocks along the right and bottom edges are cut short
+        assert_eq!((coarse.width(), coarse.height()), (3, 2));
+        assert_eq!(coarse.as_slice(), &[1, 0, 2, 3, 0, 0]);
+        assert_eq!(frame.downsample(2, 2, max).as_slice(), &[1, 2, 2, 3, 0, 0]);
+        assert_eq!(frame.downsample(5, 3, mean).as_slice(), &[1.0]);
+        assert_eq!(frame.downsample(1, 3, density(|&c| c != 0)).as_slice(),
+                   &[1.0, 2.0 / 3.0, 0.0, 2.0 / 3.0, 1.0 / 3.0]);
+        assert_eq!(frame.downsample(1, 1, |c| *c[0]), frame);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_blocks() {
+        frame().downsample(0, 2, majority);
+    }
+}
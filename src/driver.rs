@@ -0,0 +1,174 @@
+use super::{Simulation, Square};
+
+use tokio::time::{self, Interval, MissedTickBehavior};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// A future stepping a simulation for as many generations as fit in a time
+/// budget and then yielding to the runtime before it resolves to how many
+/// generations were stepped. At least one generation is always stepped, so
+/// boards too large to step within the budget still make progress.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct StepBudgeted<'a, T, F> {
+    sim: &'a mut Simulation<T, F>,
+    budget: Duration,
+    stepped: Option<u64>,
+}
+
+impl<'a, T, F> Future for StepBudgeted<'a, T, F>
+where F: Fn(Square<T>) -> T {
+    type Output = u64;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u64> {
+        let this = self.get_mut();
+        if let Some(n) = this.stepped {
+            return Poll::Ready(n);
+        }
+        this.stepped = Some(step_within(this.sim, this.budget));
+        // let other tasks run before resolving
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Step sim until the next generation would likely take it past budget,
+/// judging by how long the last one took
+fn step_within<T, F>(sim: &mut Simulation<T, F>, budget: Duration) -> u64
+where F: Fn(Square<T>) -> T {
+    let start = Instant::now();
+    let mut stepped = 0;
+    loop {
+        let before = Instant::now();
+        sim.step();
+        stepped += 1;
+        let elapsed = start.elapsed();
+        if elapsed + before.elapsed() > budget {
+            return stepped;
+        }
+    }
+}
+
+impl<T, F> Simulation<T, F>
+where F: Fn(Square<T>) -> T {
+    /// Step as many generations as fit in budget without blocking an async
+    /// runtime for longer, e.g.
+    ///
+    /// ```no_run,edition2021
+    /// use simulation::{Frame, Simulation};
+    /// use simulation::game_of_life;
+    /// use std::time::Duration;
+    ///
+    /// async fn ui() {
+    ///     let mut sim = Simulation::new(Frame::new(4096, 4096), game_of_life::rule);
+    ///     loop {
+    ///         let stepped = sim.step_budgeted(Duration::from_millis(8)).await;
+    ///         println!("{} generations this tick", stepped);
+    ///     }
+    /// }
+    /// ```
+    pub fn step_budgeted(&mut self, budget: Duration) -> StepBudgeted<'_, T, F> {
+        StepBudgeted { sim: self, budget, stepped: None }
+    }
+}
+
+/// Runs a simulation on a tokio runtime at a steady rate, stepping it for up
+/// to a budget once every period. Ticks that are missed because the runtime
+/// was busy are skipped rather than made up in a burst.
+#[derive(Debug)]
+pub struct Driver<T, F> {
+    sim: Simulation<T, F>,
+    interval: Interval,
+    budget: Duration,
+}
+
+impl<T, F> Driver<T, F>
+where F: Fn(Square<T>) -> T {
+    /// Drive sim for up to budget every period
+    ///
+    /// # Panics
+    ///
+    /// if period is 0 or this isn't called from within a tokio runtime
+    pub fn new(sim: Simulation<T, F>, period: Duration, budget: Duration) -> Driver<T, F> {
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        Driver { sim, interval, budget }
+    }
+
+    /// Wait for the next tick and step the simulation for up to the budget,
+    /// resolving to how many generations were stepped
+    pub fn tick(&mut self) -> Tick<'_, T, F> {
+        Tick { driver: self }
+    }
+
+    pub fn simulation(&self) -> &Simulation<T, F> {
+        &self.sim
+    }
+
+    /// The simulation, for editing it between ticks
+    pub fn simulation_mut(&mut self) -> &mut Simulation<T, F> {
+        &mut self.sim
+    }
+
+    /// Stop driving the simulation, giving it back
+    pub fn into_simulation(self) -> Simulation<T, F> {
+        self.sim
+    }
+}
+
+/// The future returned by `Driver::tick`
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Tick<'a, T, F> {
+    driver: &'a mut Driver<T, F>,
+}
+
+impl<'a, T, F> Future for Tick<'a, T, F>
+where F: Fn(Square<T>) -> T {
+    type Output = u64;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u64> {
+        let driver = &mut *self.get_mut().driver;
+        match driver.interval.poll_tick(cx) {
+            Poll::Ready(_) => Poll::Ready(step_within(&mut driver.sim, driver.budget)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Simulation};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::Alive;
+    use super::Driver;
+
+    use tokio::runtime::Builder;
+
+    use std::time::Duration;
+
+    #[test]
+    fn budgeted() {
+        let runtime = Builder::new_current_thread().enable_time().build().unwrap();
+        let mut frame = Frame::new(16, 16);
+        *frame.get_mut(2, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        *frame.get_mut(2, 3) = Alive;
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+
+        // even a budget of nothing steps a generation
+        assert_eq!(runtime.block_on(sim.step_budgeted(Duration::ZERO)), 1);
+        let stepped = runtime.block_on(sim.step_budgeted(Duration::from_millis(5)));
+        assert!(stepped >= 1);
+        assert_eq!(sim.generation(), 1 + stepped);
+
+        let _context = runtime.enter();
+        let mut driver = Driver::new(sim, Duration::from_millis(1), Duration::ZERO);
+        assert_eq!(runtime.block_on(driver.tick()), 1);
+        assert_eq!(runtime.block_on(driver.tick()), 1);
+        assert_eq!(driver.into_simulation().generation(), 3 + stepped);
+    }
+}
@@ -0,0 +1,628 @@
+use super::Square;
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A rule parsed from the rule language, over cells with states 0 to 255, 0
+/// being dead. A rule is a single expression, optionally written as
+/// `next = ...`, e.g.
+///
+/// ```text
+/// next = if alive { n in {2, 3} } else { n == 3 }
+/// ```
+///
+/// Expressions are made of
+///
+/// - `state`, the state of the cell, and `alive`, whether it isn't 0
+/// - `n`, the number of the eight closest cells that are alive, and
+///   `count(s)`, the number of them in state s
+/// - whole numbers, `true` and `false`
+/// - `if c { a } else { b }`, which can be chained with `else if`
+/// - `x in {1, 3..5, 7..=8}`, ranges leaving out or including their end
+/// - `+ - * %` on numbers, where `% 0` gives 0, comparisons, and `! && ||`
+///
+/// A rule that gives a bool makes the cell state 1 if it is true and 0 if
+/// not, and one that gives a number is clamped to 0 to 255. Rules that only
+/// look at `alive` and `n` are compiled to a table of their 18 outcomes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    expr: Expr,
+    /// the next state for each of dead and alive and each neighbor count
+    table: Option<[[u8; 9]; 2]>,
+}
+
+/// Why a rule couldn't be parsed
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// where in the source the problem is, 1-based
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Add, Sub, Mul, Rem,
+    Eq, Ne, Lt, Le, Gt, Ge,
+    And, Or,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Int(i64),
+    Bool(bool),
+    State,
+    Alive,
+    Neighbors,
+    Count(Box<Expr>),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    /// whether the value is in any of the inclusive ranges
+    In(Box<Expr>, Vec<(i64, i64)>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// The live cells among the eight closest to the center of curr
+fn neighbors(curr: &Square<u8>) -> i64 {
+    let mut n = 0;
+    for j in -1..2 {
+        for i in -1..2 {
+            if (i != 0 || j != 0) && *curr.get(i, j) != 0 {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+impl Expr {
+    /// The value of the expression at curr, with bools as 0 and 1
+    fn eval(&self, curr: &Square<u8>) -> i64 {
+        match *self {
+            Expr::Int(v) => v,
+            Expr::Bool(b) => b as i64,
+            Expr::State => *curr.get(0, 0) as i64,
+            Expr::Alive => (*curr.get(0, 0) != 0) as i64,
+            Expr::Neighbors => neighbors(curr),
+            Expr::Count(ref s) => {
+                let s = s.eval(curr);
+                let mut n = 0;
+                for j in -1..2 {
+                    for i in -1..2 {
+                        if (i != 0 || j != 0) && *curr.get(i, j) as i64 == s {
+                            n += 1;
+                        }
+                    }
+                }
+                n
+            }
+            Expr::Neg(ref e) => e.eval(curr).wrapping_neg(),
+            Expr::Not(ref e) => (e.eval(curr) == 0) as i64,
+            Expr::Binary(Op::And, ref a, ref b) => {
+                (a.eval(curr) != 0 && b.eval(curr) != 0) as i64
+            }
+            Expr::Binary(Op::Or, ref a, ref b) => {
+                (a.eval(curr) != 0 || b.eval(curr) != 0) as i64
+            }
+            Expr::Binary(op, ref a, ref b) => {
+                let (a, b) = (a.eval(curr), b.eval(curr));
+                match op {
+                    Op::Add => a.wrapping_add(b),
+                    Op::Sub => a.wrapping_sub(b),
+                    Op::Mul => a.wrapping_mul(b),
+                    Op::Rem => a.checked_rem(b).unwrap_or(0),
+                    Op::Eq => (a == b) as i64,
+                    Op::Ne => (a != b) as i64,
+                    Op::Lt => (a < b) as i64,
+                    Op::Le => (a <= b) as i64,
+                    Op::Gt => (a > b) as i64,
+                    Op::Ge => (a >= b) as i64,
+                    Op::And | Op::Or => unreachable!(),
+                }
+            }
+            Expr::In(ref e, ref ranges) => {
+                let v = e.eval(curr);
+                ranges.iter().any(|&(lo, hi)| lo <= v && v <= hi) as i64
+            }
+            Expr::If(ref c, ref a, ref b) => {
+                if c.eval(curr) != 0 { a.eval(curr) } else { b.eval(curr) }
+            }
+        }
+    }
+
+    /// Whether the expression only looks at `alive` and `n`
+    fn life_like(&self) -> bool {
+        match *self {
+            Expr::Int(_) | Expr::Bool(_) | Expr::Alive | Expr::Neighbors => true,
+            Expr::State | Expr::Count(_) => false,
+            Expr::Neg(ref e) | Expr::Not(ref e) | Expr::In(ref e, _) => e.life_like(),
+            Expr::Binary(_, ref a, ref b) => a.life_like() && b.life_like(),
+            Expr::If(ref c, ref a, ref b) => c.life_like() && a.life_like() && b.life_like(),
+        }
+    }
+}
+
+impl Rule {
+    /// The next state of the cell at the center of curr
+    pub fn rule(&self, curr: Square<u8>) -> u8 {
+        match self.table {
+            Some(ref table) => {
+                table[(*curr.get(0, 0) != 0) as usize][neighbors(&curr) as usize]
+            }
+            None => self.expr.eval(&curr).clamp(0, 255) as u8,
+        }
+    }
+
+    /// Whether the rule was compiled to a table
+    pub fn is_tabulated(&self) -> bool {
+        self.table.is_some()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Rule, ParseError> {
+        let tokens = tokenize(source)?;
+        if tokens.len() > MAX_TOKENS {
+            let message = format!("rules can be at most {} tokens long", MAX_TOKENS);
+            return Err(ParseError { column: tokens[MAX_TOKENS].0, message });
+        }
+        let mut parser = Parser { tokens, next: 0, end: source.len() + 1, depth: 0 };
+        if parser.keyword("next") {
+            parser.expect("=")?;
+        }
+        let (expr, _) = parser.expr()?;
+        if let Some(&(column, ref token)) = parser.tokens.get(parser.next) {
+            return Err(ParseError { column, message: format!("unexpected {}", token) });
+        }
+
+        let table = if expr.life_like() {
+            let mut table = [[0; 9]; 2];
+            for (alive, row) in table.iter_mut().enumerate() {
+                for (n, next) in row.iter_mut().enumerate() {
+                    // a 3x3 neighborhood with n of the cells around the center
+                    // alive
+                    let mut cells = [0; 9];
+                    for cell in [0, 1, 2, 3, 5, 6, 7, 8].iter().take(n) {
+                        cells[*cell] = 1;
+                    }
+                    cells[4] = alive as u8;
+                    *next = expr.eval(&Square::new(&cells, 3, 3, 1, 1)).clamp(0, 255) as u8;
+                }
+            }
+            Some(table)
+        } else {
+            None
+        };
+        Ok(Rule { expr, table })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Int(i64),
+    Ident(String),
+    Sym(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Token::Int(v) => write!(f, "`{}`", v),
+            Token::Ident(ref s) => write!(f, "`{}`", s),
+            Token::Sym(s) => write!(f, "`{}`", s),
+        }
+    }
+}
+
+/// Symbols, longest first so that e.g. `<=` isn't read as `<`
+const SYMBOLS: [&str; 21] = ["..=", "==", "!=", "<=", ">=", "&&", "||", "..",
+                             "{", "}", "(", ")", ",", "=", "<", ">", "+", "-", "*", "%",
+                             "!"];
+
+/// Split source into tokens, each with its 1-based column
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = vec![];
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        let column = source.len() - rest.len() + 1;
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() {
+            let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let v = rest[..len].parse().map_err(|_| ParseError {
+                column, message: format!("{} is too large", &rest[..len]),
+            })?;
+            tokens.push((column, Token::Int(v)));
+            rest = &rest[len..];
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest.find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push((column, Token::Ident(rest[..len].to_string())));
+            rest = &rest[len..];
+        } else if let Some(&sym) = SYMBOLS.iter().find(|&&s| rest.starts_with(s)) {
+            tokens.push((column, Token::Sym(sym)));
+            rest = &rest[sym.len()..];
+        } else {
+            return Err(ParseError { column, message: format!("unexpected `{}`", c) });
+        }
+    }
+    Ok(tokens)
+}
+
+/// The two kinds of values, which can't be mixed
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Type {
+    Int,
+    Bool,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Type::Int => write!(f, "a number"),
+            Type::Bool => write!(f, "a bool"),
+        }
+    }
+}
+
+/// The most tokens a rule can have, which also bounds how deep the chains of
+/// operators in it go, as evaluating them recurses
+const MAX_TOKENS: usize = 4096;
+
+/// How deeply parentheses, `if`s and unary operators can be nested
+const MAX_DEPTH: usize = 64;
+
+/// A recursive descent parser that checks types as it goes
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// the column just past the end of the source
+    end: usize,
+    /// how many expressions the one being parsed is nested in
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|t| &t.1)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |t| t.0)
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, ParseError> {
+        Err(ParseError { column: self.column(), message })
+    }
+
+    /// Consume sym if it is next
+    fn eat(&mut self, sym: &str) -> bool {
+        match self.peek() {
+            Some(&Token::Sym(s)) if s == sym => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(w)) if w == word => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), ParseError> {
+        if self.eat(sym) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(token) => self.error(format!("expected `{}`, found {}", sym, token)),
+            None => self.error(format!("expected `{}`", sym)),
+        }
+    }
+
+    /// Parse something with parse and check it has type expected
+    fn typed<F>(&mut self, expected: Type, parse: F) -> Result<Expr, ParseError>
+    where F: FnOnce(&mut Parser) -> Result<(Expr, Type), ParseError> {
+        let column = self.column();
+        let (expr, ty) = parse(self)?;
+        if ty != expected {
+            let message = format!("expected {}, found {}", expected, ty);
+            return Err(ParseError { column, message });
+        }
+        Ok(expr)
+    }
+
+    /// Parse with parse one level deeper, failing rather than overflowing the
+    /// stack on deeply nested rules
+    fn nested<F>(&mut self, parse: F) -> Result<(Expr, Type), ParseError>
+    where F: FnOnce(&mut Parser) -> Result<(Expr, Type), ParseError> {
+        if self.depth >= MAX_DEPTH {
+            return self.error("expression nested too deeply".to_string());
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expr(&mut self) -> Result<(Expr, Type), ParseError> {
+        self.nested(Parser::conditional)
+    }
+
+    fn conditional(&mut self) -> Result<(Expr, Type), ParseError> {
+        if self.keyword("if") {
+            let cond = self.typed(Type::Bool, Parser::expr)?;
+            self.expect("{")?;
+            let (then, ty) = self.expr()?;
+            self.expect("}")?;
+            if !self.keyword("else") {
+                return self.error("expected `else`".to_string());
+            }
+            let otherwise = if self.peek() == Some(&Token::Ident("if".to_string())) {
+                self.typed(ty, Parser::expr)?
+            } else {
+                self.expect("{")?;
+                let otherwise = self.typed(ty, Parser::expr)?;
+                self.expect("}")?;
+                otherwise
+            };
+            return Ok((Expr::If(Box::new(cond), Box::new(then), Box::new(otherwise)), ty));
+        }
+        self.or()
+    }
+
+    fn or(&mut self) -> Result<(Expr, Type), ParseError> {
+        let (mut expr, ty) = self.and()?;
+        while self.peek() == Some(&Token::Sym("||")) {
+            if ty != Type::Bool {
+                return self.error(format!("`||` needs a bool, found {}", ty));
+            }
+            self.next += 1;
+            let rhs = self.typed(Type::Bool, Parser::and)?;
+            expr = Expr::Binary(Op::Or, Box::new(expr), Box::new(rhs));
+        }
+        Ok((expr, ty))
+    }
+
+    fn and(&mut self) -> Result<(Expr, Type), ParseError> {
+        let (mut expr, ty) = self.comparison()?;
+        while self.peek() == Some(&Token::Sym("&&")) {
+            if ty != Type::Bool {
+                return self.error(format!("`&&` needs a bool, found {}", ty));
+            }
+            self.next += 1;
+            let rhs = self.typed(Type::Bool, Parser::comparison)?;
+            expr = Expr::Binary(Op::And, Box::new(expr), Box::new(rhs));
+        }
+        Ok((expr, ty))
+    }
+
+    fn comparison(&mut self) -> Result<(Expr, Type), ParseError> {
+        let (lhs, ty) = self.sum()?;
+        if self.keyword("in") {
+            if ty != Type::Int {
+                return self.error(format!("`in` needs a number, found {}", ty));
+            }
+            return Ok((Expr::In(Box::new(lhs), self.set()?), Type::Bool));
+        }
+        let op = match self.peek() {
+            Some(&Token::Sym("==")) => Op::Eq,
+            Some(&Token::Sym("!=")) => Op::Ne,
+            Some(&Token::Sym("<")) => Op::Lt,
+            Some(&Token::Sym("<=")) => Op::Le,
+            Some(&Token::Sym(">")) => Op::Gt,
+            Some(&Token::Sym(">=")) => Op::Ge,
+            _ => return Ok((lhs, ty)),
+        };
+        if ty != Type::Int && op != Op::Eq && op != Op::Ne {
+            return self.error(format!("comparing needs numbers, found {}", ty));
+        }
+        self.next += 1;
+        let rhs = self.typed(ty, Parser::sum)?;
+        Ok((Expr::Binary(op, Box::new(lhs), Box::new(rhs)), Type::Bool))
+    }
+
+    /// A set of numbers like `{1, 3..5, 7..=8}` as inclusive ranges
+    fn set(&mut self) -> Result<Vec<(i64, i64)>, ParseError> {
+        self.expect("{")?;
+        let mut ranges = vec![];
+        loop {
+            let lo = self.constant()?;
+            let range = if self.eat("..=") {
+                (lo, self.constant()?)
+            } else if self.eat("..") {
+                (lo, self.constant()? - 1)
+            } else {
+                (lo, lo)
+            };
+            ranges.push(range);
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("}")?;
+        Ok(ranges)
+    }
+
+    fn constant(&mut self) -> Result<i64, ParseError> {
+        let negative = self.eat("-");
+        match self.peek() {
+            Some(&Token::Int(v)) => {
+                self.next += 1;
+                Ok(if negative { -v } else { v })
+            }
+            _ => self.error("expected a number".to_string()),
+        }
+    }
+
+    fn sum(&mut self) -> Result<(Expr, Type), ParseError> {
+        let (mut expr, ty) = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Some(&Token::Sym("+")) => Op::Add,
+                Some(&Token::Sym("-")) => Op::Sub,
+                _ => return Ok((expr, ty)),
+            };
+            if ty != Type::Int {
+                return self.error(format!("arithmetic needs numbers, found {}", ty));
+            }
+            self.next += 1;
+            let rhs = self.typed(Type::Int, Parser::product)?;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    fn product(&mut self) -> Result<(Expr, Type), ParseError> {
+        let (mut expr, ty) = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(&Token::Sym("*")) => Op::Mul,
+                Some(&Token::Sym("%")) => Op::Rem,
+                _ => return Ok((expr, ty)),
+            };
+            if ty != Type::Int {
+                return self.error(format!("arithmetic needs numbers, found {}", ty));
+            }
+            self.next += 1;
+            let rhs = self.typed(Type::Int, Parser::unary)?;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        }
+    }
+
+    fn unary(&mut self) -> Result<(Expr, Type), ParseError> {
+        self.nested(Parser::prefixed)
+    }
+
+    fn prefixed(&mut self) -> Result<(Expr, Type), ParseError> {
+        if self.eat("-") {
+            return Ok((Expr::Neg(Box::new(self.typed(Type::Int, Parser::unary)?)), Type::Int));
+        }
+        if self.eat("!") {
+            return Ok((Expr::Not(Box::new(self.typed(Type::Bool, Parser::unary)?)), Type::Bool));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<(Expr, Type), ParseError> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return self.error("expected an expression".to_string()),
+        };
+        self.next += 1;
+        match token {
+            Token::Int(v) => Ok((Expr::Int(v), Type::Int)),
+            Token::Sym("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Ident(ref name) => match &name[..] {
+                "true" => Ok((Expr::Bool(true), Type::Bool)),
+                "false" => Ok((Expr::Bool(false), Type::Bool)),
+                "state" => Ok((Expr::State, Type::Int)),
+                "alive" => Ok((Expr::Alive, Type::Bool)),
+                "n" => Ok((Expr::Neighbors, Type::Int)),
+                "count" => {
+                    self.expect("(")?;
+                    let state = self.typed(Type::Int, Parser::expr)?;
+                    self.expect(")")?;
+                    Ok((Expr::Count(Box::new(state)), Type::Int))
+                }
+                _ => {
+                    self.next -= 1;
+                    self.error(format!("unknown name `{}`", name))
+                }
+            },
+            token => {
+                self.next -= 1;
+                self.error(format!("unexpected {}", token))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::Rule;
+
+    fn glider() -> Frame<u8> {
+        let mut frame = Frame::new(8, 8);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            *frame.get_mut(x, y) = 1;
+        }
+        frame
+    }
+
+    #[test]
+    fn life() {
+        let life = |&c: &u8| if c != 0 { Alive } else { Dead };
+        for source in &["next = if alive { n in {2,3} } else { n == 3 }",
+                        "n == 3 || alive && n == 2",
+                        "if n == 3 { 1 } else if alive && n in {2..3} { 1 } else { 0 }",
+                        "state == 0 && count(1) == 3 || state == 1 && count(1) in {2..=3}"] {
+            let rule = source.parse::<Rule>().unwrap();
+            assert_eq!(rule.is_tabulated(), !source.contains("state"), "{}", source);
+            let (mut frame, mut expected) = (glider(), glider().map(life));
+            for _ in 0..10 {
+                frame = frame.next_frame(|sq| rule.rule(sq));
+                expected = expected.next_frame(game_of_life::rule);
+            }
+            assert_eq!(frame.map(life), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let rule = "next = (state + 1) % 3 * 100 - -5".parse::<Rule>().unwrap();
+        let mut frame = Frame::<u8>::new(3, 3);
+        *frame.get_mut(1, 1) = 2;
+        let next = frame.next_frame(|sq| rule.rule(sq));
+        assert_eq!((*next.get(0, 0), *next.get(1, 1)), (105, 5));
+        let rule = "state - 10 + n % 0".parse::<Rule>().unwrap();
+        assert_eq!(*frame.next_frame(|sq| rule.rule(sq)).get(0, 0), 0);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| source.parse::<Rule>().unwrap_err().to_string();
+        assert_eq!(error("next = if alive { 1 }"), "column 22: expected `else`");
+        assert_eq!(error("alive + 1"), "column 7: arithmetic needs numbers, found a bool");
+        assert_eq!(error("if n { 1 } else { 0 }"), "column 4: expected a bool, found a number");
+        assert_eq!(error("if alive { 1 } else { false }"),
+                   "column 23: expected a number, found a bool");
+        assert_eq!(error("n in {2, x}"), "column 10: expected a number");
+        assert_eq!(error("neighbors == 3"), "column 1: unknown name `neighbors`");
+        assert_eq!(error("n == 3 }"), "column 8: unexpected `}`");
+        assert_eq!(error("n == $"), "column 6: unexpected `$`");
+        assert_eq!(error("next ="), "column 7: expected an expression");
+        assert_eq!(error("99999999999999999999"), "column 1: 99999999999999999999 is too large");
+
+        // deep nesting is refused instead of overflowing the stack
+        let deep = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+        assert!(error(&deep).ends_with("expression nested too deeply"));
+        assert!(error(&format!("{}true", "!".repeat(100))).ends_with("nested too deeply"));
+        assert_eq!(error(&"1 + ".repeat(3000)),
+                   "column 8193: rules can be at most 4096 tokens long");
+        assert!("-(-(-1))".parse::<Rule>().is_ok());
+    }
+}
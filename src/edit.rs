@@ -0,0 +1,252 @@
+use super::Frame;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap, VecDeque};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, VecDeque};
+use std::mem;
+
+/// A cell that was set, with what it held before and after
+#[derive(Clone, Debug, PartialEq)]
+struct Change<T> {
+    x: usize,
+    y: usize,
+    before: T,
+    after: T,
+}
+
+/// The changes of a stroke being made, along with where in them each cell
+/// that was changed is
+#[derive(Clone, Debug, PartialEq)]
+struct Stroke<T> {
+    changes: Vec<Change<T>>,
+    index: BTreeMap<(usize, usize), usize>,
+}
+
+impl<T> Stroke<T> {
+    fn new() -> Stroke<T> {
+        Stroke { changes: vec![], index: BTreeMap::new() }
+    }
+}
+
+/// A frame that remembers the edits made to it so they can be undone and
+/// redone. Edits made between `begin_stroke` and `end_stroke`, like the cells
+/// painted while dragging the mouse, are undone together. Only up to a limit
+/// of changed cells are remembered, forgetting the oldest strokes first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Editor<T> {
+    frame: Frame<T>,
+    undo: VecDeque<Vec<Change<T>>>,
+    redo: Vec<Vec<Change<T>>>,
+    /// the stroke being made, if one has begun
+    stroke: Option<Stroke<T>>,
+    /// how many changed cells undo remembers at most
+    limit: usize,
+    /// how many changed cells undo remembers now
+    remembered: usize,
+}
+
+impl<T> Editor<T>
+where T: Clone + PartialEq {
+    /// Edit frame, remembering at most limit changed cells to undo
+    pub fn new(frame: Frame<T>, limit: usize) -> Editor<T> {
+        Editor {
+            frame,
+            undo: VecDeque::new(),
+            redo: vec![],
+            stroke: None,
+            limit,
+            remembered: 0,
+        }
+    }
+
+    pub fn frame(&self) -> &Frame<T> {
+        &self.frame
+    }
+
+    /// Stop editing, giving back the frame
+    pub fn into_frame(self) -> Frame<T> {
+        self.frame
+    }
+
+    /// Replace the frame, e.g. after stepping it, forgetting every edit made
+    /// to the old one
+    pub fn replace(&mut self, frame: Frame<T>) {
+        self.frame = frame;
+        self.undo.clear();
+        self.redo.clear();
+        self.stroke = self.stroke.as_ref().map(|_| Stroke::new());
+        self.remembered = 0;
+    }
+
+    /// Set the cell at (x, y) to value. Outside of a stroke this can be
+    /// undone on its own. Setting a cell to what it already holds isn't an
+    /// edit.
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        let cell = self.frame.get_mut(x, y);
+        if *cell == value {
+            return;
+        }
+        let before = mem::replace(cell, value.clone());
+        self.redo.clear();
+        match self.stroke {
+            Some(ref mut stroke) => match stroke.index.get(&(x, y)) {
+                // a cell painted twice in a stroke only needs to be undone once
+                Some(&i) => stroke.changes[i].after = value,
+                None => {
+                    stroke.index.insert((x, y), stroke.changes.len());
+                    stroke.changes.push(Change { x, y, before, after: value });
+                }
+            },
+            None => self.remember(vec![Change { x, y, before, after: value }]),
+        }
+    }
+
+    /// Start grouping edits into a single stroke, ending the current one
+    pub fn begin_stroke(&mut self) {
+        self.end_stroke();
+        self.stroke = Some(Stroke::new());
+    }
+
+    /// Stop grouping edits, remembering the stroke if anything changed
+    pub fn end_stroke(&mut self) {
+        if let Some(stroke) = self.stroke.take() {
+            if !stroke.changes.is_empty() {
+                self.remember(stroke.changes);
+            }
+        }
+    }
+
+    fn remember(&mut self, stroke: Vec<Change<T>>) {
+        self.remembered += stroke.len();
+        self.undo.push_back(stroke);
+        while self.remembered > self.limit {
+            match self.undo.pop_front() {
+                Some(oldest) => self.remembered -= oldest.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Undo the last stroke, ending the current one first. Returns whether
+    /// there was anything to undo.
+    pub fn undo(&mut self) -> bool {
+        self.end_stroke();
+        let stroke = match self.undo.pop_back() {
+            Some(stroke) => stroke,
+            None => return false,
+        };
+        self.remembered -= stroke.len();
+        for change in stroke.iter().rev() {
+            *self.frame.get_mut(change.x, change.y) = change.before.clone();
+        }
+        self.redo.push(stroke);
+        true
+    }
+
+    /// Redo the last stroke undone, as long as nothing was edited since.
+    /// Returns whether there was anything to redo.
+    pub fn redo(&mut self) -> bool {
+        self.end_stroke();
+        let stroke = match self.redo.pop() {
+            Some(stroke) => stroke,
+            None => return false,
+        };
+        for change in &stroke {
+            *self.frame.get_mut(change.x, change.y) = change.after.clone();
+        }
+        let redo = mem::take(&mut self.redo);
+        self.remember(stroke);
+        self.redo = redo;
+        true
+    }
+
+    /// Whether edits are being grouped into a stroke
+    pub fn in_stroke(&self) -> bool {
+        self.stroke.is_some()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.stroke.as_ref().is_some_and(|s| !s.changes.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::Editor;
+
+    #[test]
+    fn undo_redo() {
+        let mut editor = Editor::new(Frame::<u8>::new(3, 3), 100);
+        assert!(!editor.undo());
+        editor.set(0, 0, 1);
+        editor.set(1, 0, 2);
+        editor.set(1, 0, 2);
+        assert!(editor.undo());
+        assert_eq!(*editor.frame().get(1, 0), 0);
+        assert_eq!(*editor.frame().get(0, 0), 1);
+        assert!(editor.redo());
+        assert_eq!(*editor.frame().get(1, 0), 2);
+        assert!(!editor.redo());
+
+        // editing after undoing forgets what could be redone
+        editor.undo();
+        editor.set(2, 2, 3);
+        assert!(!editor.can_redo());
+        assert!(editor.undo() && editor.undo());
+        assert_eq!(*editor.frame(), Frame::new(3, 3));
+        assert!(!editor.can_undo());
+    }
+
+    #[test]
+    fn strokes() {
+        let mut editor = Editor::new(Frame::<u8>::new(3, 3), 100);
+        editor.set(2, 2, 9);
+        editor.begin_stroke();
+        for x in 0..3 {
+            editor.set(x, 1, 1);
+        }
+        // dragging back over a cell
+        editor.set(0, 1, 2);
+        assert!(editor.can_undo());
+        assert!(editor.in_stroke());
+        editor.end_stroke();
+        assert!(!editor.in_stroke());
+        assert!(editor.undo());
+        assert_eq!(editor.frame().as_slice(), &[0, 0, 0, 0, 0, 0, 0, 0, 9]);
+        assert!(editor.redo());
+        assert_eq!(editor.frame().as_slice(), &[0, 0, 0, 2, 1, 1, 0, 0, 9]);
+
+        // an empty stroke isn't remembered
+        editor.begin_stroke();
+        editor.end_stroke();
+        assert!(editor.undo());
+        assert_eq!(*editor.frame().get(0, 1), 0);
+    }
+
+    #[test]
+    fn limit() {
+        let mut editor = Editor::new(Frame::<u8>::new(4, 1), 2);
+        for x in 0..4 {
+            editor.set(x, 0, 1);
+        }
+        assert!(editor.undo() && editor.undo());
+        assert!(!editor.undo());
+        assert_eq!(editor.frame().as_slice(), &[1, 1, 0, 0]);
+
+        // a stroke bigger than the limit can't be undone at all
+        editor.begin_stroke();
+        for x in 0..4 {
+            editor.set(x, 0, 2);
+        }
+        editor.end_stroke();
+        assert!(!editor.undo());
+    }
+}
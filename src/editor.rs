@@ -0,0 +1,321 @@
+use super::{Frame, Square};
+use super::edit;
+use super::paint::Canvas;
+use super::lenia::Lenia;
+use super::render::ColorMap;
+use super::render::colormap::Gradient;
+use super::smooth_life::SmoothLife;
+
+use eframe;
+use eframe::egui;
+use rand;
+
+use std::io;
+
+/// The rules the editor can switch between. All of them work on cells in
+/// `[0, 1]`, Life treating anything from 0.5 up as alive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleChoice {
+    Life,
+    SmoothLife,
+    Lenia,
+}
+
+impl RuleChoice {
+    fn name(&self) -> &'static str {
+        match *self {
+            RuleChoice::Life => "Game of Life",
+            RuleChoice::SmoothLife => "SmoothLife",
+            RuleChoice::Lenia => "Lenia",
+        }
+    }
+}
+
+/// How many painted cells can be undone
+const UNDO_LIMIT: usize = 1 << 20;
+
+/// Conway's Game of Life on continuous cells
+fn life(curr: Square<f32>) -> f32 {
+    let alive = curr.neighbors(1).filter(|&(_, _, &v)| v >= 0.5).count();
+    match (*curr.get(0, 0) >= 0.5, alive) {
+        (true, 2) | (_, 3) => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// A native window for painting cells and tuning rule parameters while the
+/// simulation runs. Drag with the left mouse button to paint with the brush
+/// value and with the right one to erase. Each drag can be undone with
+/// ctrl+z and redone with ctrl+shift+z until the next step.
+pub struct Editor {
+    cells: edit::Editor<f32>,
+    generation: u64,
+    pub rule: RuleChoice,
+    pub smooth_life: SmoothLife,
+    pub lenia: Lenia,
+    pub running: bool,
+    /// the value painted by the left mouse button
+    pub brush: f32,
+    /// how far from the mouse cells are painted
+    pub brush_radius: usize,
+    /// how cell values are shown
+    pub colormap: Gradient,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl Editor {
+    pub fn new(frame: Frame<f32>) -> Editor {
+        Editor {
+            cells: edit::Editor::new(frame, UNDO_LIMIT),
+            generation: 0,
+            rule: RuleChoice::Lenia,
+            smooth_life: SmoothLife::new(6.0),
+            lenia: Lenia::orbium(),
+            running: false,
+            brush: 1.0,
+            brush_radius: 0,
+            colormap: Gradient::grayscale(),
+            texture: None,
+        }
+    }
+
+    pub fn frame(&self) -> &Frame<f32> {
+        self.cells.frame()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Advance one generation with the selected rule and its current
+    /// parameters
+    pub fn step(&mut self) {
+        let frame = self.cells.frame();
+        let next = match self.rule {
+            RuleChoice::Life => frame.next_frame(life),
+            RuleChoice::SmoothLife => self.smooth_life.step(frame),
+            RuleChoice::Lenia => self.lenia.step(frame),
+        };
+        self.cells.replace(next);
+        self.generation += 1;
+    }
+
+    /// Set the cells within the brush radius of (x, y) to value
+    pub fn paint(&mut self, x: usize, y: usize, value: f32) {
+        if x < self.frame().width() && y < self.frame().height() {
+            self.cells.paint_point(x as isize, y as isize, self.brush_radius, value);
+        }
+    }
+
+    /// Set every cell to what f gives, as a single edit
+    fn fill<F: FnMut() -> f32>(&mut self, mut f: F) {
+        self.cells.begin_stroke();
+        for y in 0..self.frame().height() {
+            for x in 0..self.frame().width() {
+                self.cells.set(x, y, f());
+            }
+        }
+        self.cells.end_stroke();
+    }
+
+    /// Undo the last edit, returning whether there was one
+    pub fn undo(&mut self) -> bool {
+        self.cells.undo()
+    }
+
+    /// Redo the last edit undone, returning whether there was one
+    pub fn redo(&mut self) -> bool {
+        self.cells.redo()
+    }
+
+    fn image(&self) -> egui::ColorImage {
+        let pixels = self.frame().enumerate_squares()
+            .map(|(_, _, v)| {
+                let [r, g, b] = self.colormap.color(v);
+                egui::Color32::from_rgb(r, g, b)
+            })
+            .collect();
+        egui::ColorImage { size: [self.frame().width(), self.frame().height()], pixels }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Simulation");
+        ui.label(format!("generation {}", self.generation));
+        ui.horizontal(|ui| {
+            if ui.button(if self.running { "Pause" } else { "Run" }).clicked() {
+                self.running = !self.running;
+            }
+            if ui.button("Step").clicked() {
+                self.step();
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Clear").clicked() {
+                self.fill(|| 0.0);
+            }
+            if ui.button("Randomize").clicked() {
+                self.fill(rand::random::<f32>);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.cells.can_undo(), egui::Button::new("Undo")).clicked() {
+                self.undo();
+            }
+            if ui.add_enabled(self.cells.can_redo(), egui::Button::new("Redo")).clicked() {
+                self.redo();
+            }
+        });
+        ui.add(egui::Slider::new(&mut self.brush, 0.0..=1.0).text("brush"));
+        ui.add(egui::Slider::new(&mut self.brush_radius, 0..=10).text("brush radius"));
+        ui.separator();
+
+        egui::ComboBox::from_label("rule")
+            .selected_text(self.rule.name())
+            .show_ui(ui, |ui| {
+                for &rule in &[RuleChoice::Life, RuleChoice::SmoothLife, RuleChoice::Lenia] {
+                    ui.selectable_value(&mut self.rule, rule, rule.name());
+                }
+            });
+        match self.rule {
+            RuleChoice::Life => {}
+            RuleChoice::SmoothLife => {
+                let s = &mut self.smooth_life;
+                ui.add(egui::Slider::new(&mut s.birth.0, 0.0..=1.0).text("birth from"));
+                ui.add(egui::Slider::new(&mut s.birth.1, 0.0..=1.0).text("birth to"));
+                ui.add(egui::Slider::new(&mut s.death.0, 0.0..=1.0).text("death from"));
+                ui.add(egui::Slider::new(&mut s.death.1, 0.0..=1.0).text("death to"));
+                ui.add(egui::Slider::new(&mut s.alpha_n, 0.001..=0.5).text("alpha n"));
+                ui.add(egui::Slider::new(&mut s.alpha_m, 0.001..=0.5).text("alpha m"));
+            }
+            RuleChoice::Lenia => {
+                let l = &mut self.lenia;
+                ui.add(egui::Slider::new(&mut l.mu, 0.0..=0.5).text("mu"));
+                ui.add(egui::Slider::new(&mut l.sigma, 0.001..=0.1).text("sigma"));
+                ui.add(egui::Slider::new(&mut l.dt, 0.01..=1.0).text("dt"));
+            }
+        }
+    }
+
+    fn canvas(&mut self, ui: &mut egui::Ui) {
+        let image = self.image();
+        let texture = match self.texture {
+            Some(ref mut texture) => {
+                texture.set(image, egui::TextureOptions::NEAREST);
+                texture.clone()
+            }
+            None => {
+                let texture = ui.ctx().load_texture("frame", image,
+                                                    egui::TextureOptions::NEAREST);
+                self.texture = Some(texture.clone());
+                texture
+            }
+        };
+
+        // fit the frame into the available space keeping cells square
+        let (w, h) = (self.frame().width() as f32, self.frame().height() as f32);
+        let available = ui.available_size();
+        let cell = (available.x / w).min(available.y / h).max(1.0);
+        let response = ui.add(egui::Image::new(&texture)
+            .fit_to_exact_size(egui::vec2(w * cell, h * cell))
+            .sense(egui::Sense::click_and_drag()));
+
+        let (painting, erasing) = ui.input(|i| {
+            (i.pointer.primary_down(), i.pointer.secondary_down())
+        });
+        if !painting && !erasing {
+            self.cells.end_stroke();
+        }
+        if let Some(pos) = response.interact_pointer_pos() {
+            if painting || erasing {
+                if !self.cells.in_stroke() {
+                    self.cells.begin_stroke();
+                }
+                let offset = pos - response.rect.min;
+                if offset.x >= 0.0 && offset.y >= 0.0 {
+                    let (x, y) = ((offset.x / cell) as usize, (offset.y / cell) as usize);
+                    let value = if erasing { 0.0 } else { self.brush };
+                    self.paint(x, y, value);
+                }
+            }
+        }
+    }
+}
+
+impl eframe::App for Editor {
+    fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
+        let (undo, redo) = ctx.input(|i| {
+            let z = i.modifiers.command && i.key_pressed(egui::Key::Z);
+            (z && !i.modifiers.shift, z && i.modifiers.shift)
+        });
+        if undo {
+            self.undo();
+        } else if redo {
+            self.redo();
+        }
+        if self.running {
+            self.step();
+            ctx.request_repaint();
+        }
+        egui::SidePanel::left("controls").show(ctx, |ui| self.controls(ui));
+        egui::CentralPanel::default().show(ctx, |ui| self.canvas(ui));
+    }
+}
+
+/// Open the editor on frame and run until its window is closed
+pub fn run(frame: Frame<f32>) -> io::Result<()> {
+    let editor = Editor::new(frame);
+    eframe::run_native("simulation", eframe::NativeOptions::default(),
+                       Box::new(|_| Ok(Box::new(editor))))
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::{Editor, RuleChoice};
+
+    #[test]
+    fn paint_and_step() {
+        let mut editor = Editor::new(Frame::new(6, 6));
+        editor.rule = RuleChoice::Life;
+        editor.paint(2, 1, 1.0);
+        editor.paint(2, 2, 0.7);
+        editor.paint(2, 3, 1.0);
+        editor.paint(10, 10, 1.0);
+        editor.step();
+        assert_eq!(editor.generation(), 1);
+        assert_eq!(*editor.frame().get(1, 2), 1.0);
+        assert_eq!(*editor.frame().get(2, 1), 0.0);
+    }
+
+    #[test]
+    fn undo() {
+        let mut editor = Editor::new(Frame::new(4, 4));
+        editor.rule = RuleChoice::Life;
+        editor.paint(1, 1, 1.0);
+        editor.fill(|| 0.5);
+        assert!(editor.undo());
+        assert_eq!(*editor.frame().get(0, 0), 0.0);
+        assert_eq!(*editor.frame().get(1, 1), 1.0);
+        assert!(editor.redo());
+        assert_eq!(*editor.frame().get(0, 0), 0.5);
+        editor.step();
+        assert!(!editor.undo());
+
+        // a wider brush paints a disc as one edit
+        editor.brush_radius = 1;
+        editor.paint(1, 1, 1.0);
+        assert_eq!(editor.frame().as_slice().iter().filter(|&&c| c == 1.0).count(), 5);
+    }
+
+    #[test]
+    fn parameters_apply() {
+        let frame = Frame::<f32>::new(20, 20).map(|_| 0.3);
+        let mut a = Editor::new(frame.clone());
+        let mut b = Editor::new(frame);
+        b.lenia.dt = a.lenia.dt / 2.0;
+        a.step();
+        b.step();
+        assert!(a.frame() != b.frame());
+    }
+}
@@ -0,0 +1,210 @@
+use super::{Frame, Square};
+use super::analysis;
+use super::parallel;
+
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+/// How a single run of an ensemble ended
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outcome {
+    /// the seed the run was set up from
+    pub seed: u64,
+    /// how many generations were stepped
+    pub generations: u64,
+    /// the number of live cells, those that aren't `T::default()`, when the
+    /// run started
+    pub initial_population: usize,
+    /// the number of live cells when the run ended
+    pub population: usize,
+    /// the fraction of cells that changed in the last generation stepped,
+    /// which stays above 0 for runs that keep going
+    pub activity: f64,
+    /// the generation the frame started repeating at, or None if it didn't
+    /// within the limit
+    pub settled: Option<u64>,
+    /// how many generations the repeating frames take to come back around
+    pub period: Option<u64>,
+}
+
+/// The outcomes of every run of an ensemble, in order of their seeds
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub runs: Vec<Outcome>,
+}
+
+impl Report {
+    /// The average population at the end of the runs
+    pub fn mean_population(&self) -> f64 {
+        mean(self.runs.iter().map(|o| o.population as f64))
+    }
+
+    /// The average of how many times larger the population was at the end of
+    /// each run than at the start, leaving out runs that started empty
+    pub fn mean_growth(&self) -> f64 {
+        mean(self.runs.iter()
+            .filter(|o| o.initial_population > 0)
+            .map(|o| o.population as f64 / o.initial_population as f64))
+    }
+
+    /// The average activity at the end of the runs
+    pub fn mean_activity(&self) -> f64 {
+        mean(self.runs.iter().map(|o| o.activity))
+    }
+
+    /// The fraction of runs that settled into a cycle
+    pub fn settled_fraction(&self) -> f64 {
+        mean(self.runs.iter().map(|o| if o.settled.is_some() { 1. } else { 0. }))
+    }
+
+    /// The average generation the runs that settled did so at, or None if
+    /// none of them did
+    pub fn mean_settling_time(&self) -> Option<f64> {
+        let times = self.runs.iter().filter_map(|o| o.settled).collect::<Vec<_>>();
+        if times.is_empty() { None } else { Some(mean(times.iter().map(|&t| t as f64))) }
+    }
+
+    /// How many runs settled with each period
+    pub fn periods(&self) -> BTreeMap<u64, usize> {
+        let mut periods = BTreeMap::new();
+        for period in self.runs.iter().filter_map(|o| o.period) {
+            *periods.entry(period).or_insert(0) += 1;
+        }
+        periods
+    }
+}
+
+fn mean<I: Iterator<Item = f64>>(values: I) -> f64 {
+    let (sum, n) = values.fold((0., 0), |(sum, n), v| (sum + v, n + 1));
+    if n == 0 { 0. } else { sum / n as f64 }
+}
+
+/// Step frame with rule until a frame repeats or max_generations have
+/// passed, reporting how it ended along with the last frame
+fn run<T, F>(seed: u64, frame: Frame<T>, rule: F, max_generations: u64)
+             -> (Outcome, Frame<T>)
+where T: Clone + Default + Eq + Hash, F: Fn(Square<T>) -> T {
+    let dead = T::default();
+    let population = |frame: &Frame<T>| frame.as_slice().iter().filter(|&c| *c != dead).count();
+    let initial_population = population(&frame);
+    let (mut frames, settled) = analysis::run_until_repeat(frame, rule, max_generations);
+    let frame = frames.pop().expect("a run has at least its first frame");
+    let generations = frames.len() as u64;
+    let changed = frames.last().map_or(0, |previous| {
+        previous.as_slice().iter().zip(frame.as_slice()).filter(|(a, b)| a != b).count()
+    });
+    let outcome = Outcome {
+        seed,
+        generations,
+        initial_population,
+        population: population(&frame),
+        activity: changed as f64 / frame.as_slice().len().max(1) as f64,
+        settled,
+        period: settled.map(|start| generations - start),
+    };
+    (outcome, frame)
+}
+
+/// Run the scenario scenario gives for every seed on up to threads threads,
+/// each for at most max_generations or until its frame repeats. scenario
+/// gives the starting frame and the rule for a seed, so stochastic rules can
+/// seed their rng from it. Runs that repeat a frame are taken to have
+/// settled, which for stochastic rules only means they happened to come back
+/// to the same frame.
+pub fn ensemble<T, F, S>(seeds: &[u64], threads: usize, max_generations: u64, scenario: S)
+                         -> Report
+where T: Clone + Default + Eq + Hash + Send,
+      F: Fn(Square<T>) -> T,
+      S: Fn(u64) -> (Frame<T>, F) + Sync {
+    ensemble_with_previews(seeds, threads, max_generations, scenario, |_| ()).0
+}
+
+/// Like `ensemble`, but also giving preview of the last frame of each run,
+/// in the same order as the runs of the report, e.g. a `Frame::thumbnail`
+/// to show alongside it
+pub fn ensemble_with_previews<T, F, S, P, R>(seeds: &[u64], threads: usize,
+                                             max_generations: u64, scenario: S, preview: P)
+                                             -> (Report, Vec<R>)
+where T: Clone + Default + Eq + Hash + Send,
+      F: Fn(Square<T>) -> T,
+      S: Fn(u64) -> (Frame<T>, F) + Sync,
+      P: Fn(&Frame<T>) -> R + Sync,
+      R: Send {
+    assert!(threads > 0, "an ensemble needs at least one thread");
+    let runs = parallel::map(seeds.len(), threads, |i| {
+        let (frame, rule) = scenario(seeds[i]);
+        let (outcome, last) = run(seeds[i], frame, rule, max_generations);
+        (outcome, preview(&last))
+    });
+    let (outcomes, previews) = runs.into_iter().unzip();
+    (Report { runs: outcomes }, previews)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Square};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::game_of_life::State::Alive;
+    use super::super::patterns::{Orientation, Pattern};
+    use super::super::stochastic_life::StochasticLife;
+    use super::{ensemble, ensemble_with_previews, Outcome};
+
+    use rand::{SeedableRng, XorShiftRng};
+
+    #[test]
+    fn deterministic() {
+        // seed n starts with a blinker for odd seeds and a block for even ones
+        let report = ensemble(&[1, 2, 3, 4, 5], 3, 10, |seed| {
+            let mut frame = Frame::new(8, 8);
+            let pattern = if seed % 2 == 1 { Pattern::blinker() } else { Pattern::block() };
+            pattern.place(&mut frame, 2, 2, Orientation::Identity, Alive);
+            (frame, game_of_life::rule)
+        });
+        let seeds = report.runs.iter().map(|o| o.seed).collect::<Vec<_>>();
+        assert_eq!(seeds, vec![1, 2, 3, 4, 5]);
+        assert_eq!(report.runs[0], Outcome {
+            seed: 1, generations: 2, initial_population: 3, population: 3,
+            activity: 4. / 64., settled: Some(0), period: Some(2),
+        });
+        assert_eq!(report.mean_growth(), 1.);
+        assert_eq!(report.mean_activity(), 3. * 4. / 64. / 5.);
+        assert_eq!(report.mean_population(), (3. * 3. + 2. * 4.) / 5.);
+        assert_eq!(report.settled_fraction(), 1.);
+        assert_eq!(report.mean_settling_time(), Some(0.));
+        assert_eq!(report.periods().into_iter().collect::<Vec<_>>(), vec![(1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn stochastic() {
+        let scenario = |seed: u64| {
+            let rng = XorShiftRng::from_seed([seed as u32 + 1, 2, 3, 4]);
+            let life = StochasticLife::new(0.9, 0.9, rng);
+            let mut frame = Frame::<State>::new(12, 12);
+            Pattern::r_pentomino().place(&mut frame, 4, 4, Orientation::Identity, Alive);
+            (frame, move |sq: Square<State>| life.rule(sq))
+        };
+        let seeds = (0..8).collect::<Vec<_>>();
+        let report = ensemble(&seeds, 4, 50, scenario);
+        assert_eq!(report.runs.len(), 8);
+        assert!(report.runs.iter().all(|o| o.generations <= 50));
+        // runs are reproducible whatever thread they end up on
+        assert_eq!(ensemble(&seeds, 1, 50, scenario), report);
+    }
+
+    #[test]
+    fn previews() {
+        let scenario = |seed: u64| {
+            let mut frame = Frame::new(8, 8);
+            let pattern = if seed % 2 == 1 { Pattern::blinker() } else { Pattern::block() };
+            pattern.place(&mut frame, 2, 2, Orientation::Identity, Alive);
+            (frame, game_of_life::rule)
+        };
+        let population = |frame: &Frame<State>| {
+            frame.as_slice().iter().filter(|&&c| c == Alive).count()
+        };
+        let (report, previews) = ensemble_with_previews(&[1, 2, 3], 2, 10, scenario, population);
+        assert_eq!(report, ensemble(&[1, 2, 3], 2, 10, scenario));
+        assert_eq!(previews, vec![3, 4, 3]);
+    }
+}
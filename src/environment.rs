@@ -0,0 +1,118 @@
+use super::{Frame, Square};
+
+/// A simulation whose rule also reads global values shared by every cell,
+/// like an ambient temperature or how much of a resource is left, which are
+/// updated from the whole frame once per generation. The rule sees the
+/// environment as it was before the step, and update is then given it along
+/// with the new frame to work out the next one, e.g.
+///
+/// ```
+/// use simulation::{Frame, Square};
+/// use simulation::environment::EnvironmentSimulation;
+/// use simulation::game_of_life::{self, State};
+///
+/// // Life that only allows births while there is food, which every live
+/// // cell eats some of and which slowly grows back
+/// let rule = |sq: Square<State>, food: &f64| {
+///     match game_of_life::rule(sq) {
+///         State::Alive if *food <= 0. && *sq.get(0, 0) == State::Dead => State::Dead,
+///         next => next,
+///     }
+/// };
+/// let update = |food: &f64, frame: &Frame<State>| {
+///     let alive = frame.as_slice().iter().filter(|&&c| c == State::Alive).count();
+///     food + 10. - alive as f64
+/// };
+/// let mut sim = EnvironmentSimulation::new(Frame::new(32, 32), 100., rule, update);
+/// sim.step_n(10);
+/// ```
+#[derive(Debug)]
+pub struct EnvironmentSimulation<T, E, F, U> {
+    frame: Frame<T>,
+    environment: E,
+    rule: F,
+    update: U,
+    generation: u64,
+}
+
+impl<T, E, F, U> EnvironmentSimulation<T, E, F, U>
+where F: Fn(Square<T>, &E) -> T, U: Fn(&E, &Frame<T>) -> E {
+    /// Start a simulation at generation 0 from frame and environment, where
+    /// rule gives the next state of a cell in the current environment and
+    /// update gives the next environment from the current one and the frame
+    /// just stepped
+    pub fn new(frame: Frame<T>, environment: E, rule: F, update: U)
+               -> EnvironmentSimulation<T, E, F, U> {
+        EnvironmentSimulation { frame, environment, rule, update, generation: 0 }
+    }
+
+    /// Advance the frame by one generation and then update the environment
+    pub fn step(&mut self) {
+        let (rule, environment) = (&self.rule, &self.environment);
+        self.frame = self.frame.next_frame(|sq| rule(sq, environment));
+        self.environment = (self.update)(&self.environment, &self.frame);
+        self.generation += 1;
+    }
+
+    /// Advance the simulation by n generations
+    pub fn step_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+}
+
+impl<T, E, F, U> EnvironmentSimulation<T, E, F, U> {
+    /// The current frame
+    pub fn frame(&self) -> &Frame<T> {
+        &self.frame
+    }
+
+    /// A mutable reference to the current frame, for editing it between steps
+    pub fn frame_mut(&mut self) -> &mut Frame<T> {
+        &mut self.frame
+    }
+
+    /// The environment the next step is taken in
+    pub fn environment(&self) -> &E {
+        &self.environment
+    }
+
+    /// A mutable reference to the environment, e.g. to turn up the
+    /// temperature between steps
+    pub fn environment_mut(&mut self) -> &mut E {
+        &mut self.environment
+    }
+
+    /// The number of generations that have passed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Square};
+    use super::EnvironmentSimulation;
+
+    #[test]
+    fn feedback() {
+        // cells grow by the temperature, which drops by a tenth of the total
+        let rule = |sq: Square<u32>, temperature: &u32| *sq.get(0, 0) + temperature;
+        let update = |temperature: &u32, frame: &Frame<u32>| {
+            temperature.saturating_sub(frame.as_slice().iter().sum::<u32>() / 10)
+        };
+        let mut sim = EnvironmentSimulation::new(Frame::new(2, 5), 3, rule, update);
+        sim.step();
+        // every cell grew by 3, so the total is 30 and the temperature 0
+        assert!(sim.frame().as_slice().iter().all(|&c| c == 3));
+        assert_eq!(*sim.environment(), 0);
+        sim.step();
+        assert!(sim.frame().as_slice().iter().all(|&c| c == 3));
+
+        *sim.environment_mut() = 1;
+        sim.step_n(2);
+        assert_eq!(sim.generation(), 4);
+        assert!(sim.frame().as_slice().iter().all(|&c| c == 4));
+    }
+}
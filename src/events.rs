@@ -0,0 +1,192 @@
+use super::Frame;
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// What happened to the cell at (x, y) in a generation, dead cells being
+/// those in `T::default()`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event<T> {
+    /// a dead cell came to life in state
+    Born { x: usize, y: usize, state: T },
+    /// a cell in state died
+    Died { x: usize, y: usize, state: T },
+    /// a live cell went from one live state to another
+    Changed { x: usize, y: usize, from: T, to: T },
+}
+
+impl<T> Event<T> {
+    /// What happened to the cell at (x, y) going from one state to another,
+    /// or None if it stayed the same
+    pub fn between(x: usize, y: usize, from: &T, to: &T) -> Option<Event<T>>
+    where T: Clone + Default + PartialEq {
+        let dead = T::default();
+        if from == to {
+            None
+        } else if *from == dead {
+            Some(Event::Born { x, y, state: to.clone() })
+        } else if *to == dead {
+            Some(Event::Died { x, y, state: from.clone() })
+        } else {
+            Some(Event::Changed { x, y, from: from.clone(), to: to.clone() })
+        }
+    }
+
+    /// Where it happened
+    pub fn position(&self) -> (usize, usize) {
+        match *self {
+            Event::Born { x, y, .. } | Event::Died { x, y, .. } | Event::Changed { x, y, .. } => {
+                (x, y)
+            }
+        }
+    }
+}
+
+/// The events that turn prev into next, row by row, for frames that weren't
+/// stepped with `Simulation::step_events`
+///
+/// # Panics
+///
+/// if the frames aren't the same size
+pub fn events<'a, T>(prev: &'a Frame<T>, next: &'a Frame<T>)
+                     -> impl Iterator<Item = Event<T>> + 'a
+where T: Clone + Default + PartialEq {
+    assert!((prev.width(), prev.height()) == (next.width(), next.height()),
+            "events between a {}x{} and a {}x{} frame",
+            prev.width(), prev.height(), next.width(), next.height());
+    let width = prev.width();
+    prev.as_slice().iter().zip(next.as_slice()).enumerate()
+        .filter_map(move |(i, (from, to))| Event::between(i % width, i / width, from, to))
+}
+
+/// How many of each kind of event happened
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub born: usize,
+    pub died: usize,
+    pub changed: usize,
+}
+
+impl Tally {
+    /// Count events
+    pub fn of<'a, T, I>(events: I) -> Tally
+    where T: 'a, I: IntoIterator<Item = &'a Event<T>> {
+        let mut tally = Tally::default();
+        for event in events {
+            match *event {
+                Event::Born { .. } => tally.born += 1,
+                Event::Died { .. } => tally.died += 1,
+                Event::Changed { .. } => tally.changed += 1,
+            }
+        }
+        tally
+    }
+}
+
+/// A subscriber's callback
+type Callback<'a, T> = Box<dyn FnMut(u64, &Event<T>) + 'a>;
+
+/// Callbacks that each get every event of a generation, e.g. to play a
+/// sound when a cell is born
+pub struct Subscribers<'a, T> {
+    callbacks: Vec<Callback<'a, T>>,
+}
+
+impl<'a, T> Default for Subscribers<'a, T> {
+    fn default() -> Subscribers<'a, T> {
+        Subscribers { callbacks: vec![] }
+    }
+}
+
+impl<'a, T> Subscribers<'a, T> {
+    pub fn new() -> Subscribers<'a, T> {
+        Subscribers::default()
+    }
+
+    /// Call callback with the generation each event led to and the event
+    pub fn subscribe<F>(&mut self, callback: F)
+    where F: FnMut(u64, &Event<T>) + 'a {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Deliver an event that led to generation to every subscriber, in the
+    /// order they subscribed
+    pub fn notify(&mut self, generation: u64, event: &Event<T>) {
+        for callback in &mut self.callbacks {
+            callback(generation, event);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.callbacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Simulation};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::{events, Event, Subscribers, Tally};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[test]
+    fn kinds() {
+        let prev = Frame::from_vec(2, 2, vec![0u8, 1, 2, 3]).unwrap();
+        let next = Frame::from_vec(2, 2, vec![4u8, 0, 2, 5]).unwrap();
+        let all = events(&prev, &next).collect::<Vec<_>>();
+        assert_eq!(all, vec![Event::Born { x: 0, y: 0, state: 4 },
+                             Event::Died { x: 1, y: 0, state: 1 },
+                             Event::Changed { x: 1, y: 1, from: 3, to: 5 }]);
+        assert_eq!(all[2].position(), (1, 1));
+        assert_eq!(Tally::of(&all), Tally { born: 1, died: 1, changed: 1 });
+    }
+
+    #[test]
+    fn blinker() {
+        let mut frame = Frame::new(5, 5);
+        for x in 1..4 {
+            *frame.get_mut(x, 2) = Alive;
+        }
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+        let mut births = vec![];
+        let mut deaths = 0;
+        {
+            let mut subscribers = Subscribers::new();
+            subscribers.subscribe(|generation, event: &Event<_>| {
+                if let Event::Born { x, y, .. } = *event {
+                    births.push((generation, x, y));
+                }
+            });
+            subscribers.subscribe(|_, event: &Event<_>| {
+                if let Event::Died { state, .. } = *event {
+                    assert_eq!(state, Alive);
+                    deaths += 1;
+                }
+            });
+            assert_eq!(subscribers.len(), 2);
+            sim.step_notify(&mut subscribers);
+        }
+        assert_eq!(births, vec![(1, 2, 1), (1, 2, 3)]);
+        assert_eq!(deaths, 2);
+
+        let tally = Tally::of(&sim.step_events());
+        assert_eq!(tally, Tally { born: 2, died: 2, changed: 0 });
+        assert_eq!(*sim.frame().get(1, 2), Alive);
+        assert_eq!(*sim.frame().get(2, 1), Dead);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched() {
+        events(&Frame::<u8>::new(2, 2), &Frame::new(2, 3)).count();
+    }
+}
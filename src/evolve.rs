@@ -0,0 +1,156 @@
+use super::ensemble::{ensemble, Report};
+use super::game_of_life::{LifeLike, State};
+use super::rule_space::RuleSpace;
+use super::search::SoupSearch;
+
+use rand::Rng;
+
+use std::collections::HashMap;
+
+/// A genetic search through Life-like rules for ones a fitness function
+/// scores highly. Each generation the best rules are kept as they are and
+/// the rest are replaced by crossing rules picked by tournament and mutating
+/// the result. Rules are scored on the same soups, so scores are comparable
+/// between rules and each rule only has to be run once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Evolution {
+    /// the rules searched through
+    pub space: RuleSpace,
+    /// how many rules there are each generation
+    pub population: usize,
+    /// how many generations of rules are scored
+    pub generations: usize,
+    /// how many of the best rules go on to the next generation unchanged
+    pub elite: usize,
+    /// the chance of each birth or survival count flipping in a new rule
+    pub mutation_rate: f64,
+    /// the soups rules are run on and for how long
+    pub soups: SoupSearch,
+    /// how many soups each rule is run on
+    pub trials: u64,
+}
+
+impl Default for Evolution {
+    /// 20 rules without B0 for 10 generations, each run on 4 16 by 16 soups
+    /// on a 64 by 64 board for 500 generations
+    fn default() -> Evolution {
+        Evolution {
+            space: RuleSpace::new().forbid_birth(0),
+            population: 20,
+            generations: 10,
+            elite: 4,
+            mutation_rate: 1. / 18.,
+            soups: SoupSearch { board: 64, max_generations: 500, ..SoupSearch::default() },
+            trials: 4,
+        }
+    }
+}
+
+/// A rule along with its score
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub rule: LifeLike,
+    pub fitness: f64,
+}
+
+impl Evolution {
+    /// The report of running rule on every soup, on up to threads threads
+    pub fn trial(&self, rule: LifeLike, threads: usize) -> Report {
+        let seeds = (0..self.trials).collect::<Vec<_>>();
+        ensemble(&seeds, threads, self.soups.max_generations, |seed| {
+            (self.soups.soup(seed, State::Alive), move |sq| rule.rule(sq))
+        })
+    }
+
+    /// Evolve rules scored by fitness, which is given the report of running
+    /// a rule on the soups, e.g. `Report::mean_activity` for rules that stay
+    /// busy. Returns every rule scored along the way, the best first.
+    pub fn run<R, F>(&self, rng: &mut R, threads: usize, fitness: F) -> Vec<Candidate>
+    where R: Rng, F: Fn(&Report) -> f64 {
+        assert!(self.population > 0 && self.elite <= self.population,
+                "can't keep {} of {} rules", self.elite, self.population);
+        let mut scores = HashMap::new();
+        let mut rules = (0..self.population)
+            .map(|_| self.space.sample_life_like(rng))
+            .collect::<Vec<_>>();
+        for generation in 0..self.generations {
+            let mut scored = rules.iter()
+                .map(|&rule| {
+                    let fitness = *scores.entry(rule)
+                        .or_insert_with(|| fitness(&self.trial(rule, threads)));
+                    Candidate { rule, fitness }
+                })
+                .collect::<Vec<_>>();
+            if generation + 1 == self.generations {
+                break;
+            }
+            scored.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+            rules = scored[..self.elite].iter().map(|c| c.rule).collect();
+            while rules.len() < self.population {
+                let a = tournament(&scored, rng);
+                let b = tournament(&scored, rng);
+                let counts = self.space.cross((a.birth(), a.survival()),
+                                              (b.birth(), b.survival()), rng);
+                let (birth, survival) = self.space.mutate(counts, self.mutation_rate, rng);
+                rules.push(LifeLike::from_counts(birth, survival));
+            }
+        }
+        let mut found = scores.into_iter()
+            .map(|(rule, fitness)| Candidate { rule, fitness })
+            .collect::<Vec<_>>();
+        // rules with the same score are ordered by rulestring so runs repeat
+        found.sort_by(|a, b| {
+            b.fitness.total_cmp(&a.fitness).then(a.rule.to_string().cmp(&b.rule.to_string()))
+        });
+        found
+    }
+}
+
+/// The better of two candidates picked at random
+fn tournament<R: Rng>(scored: &[Candidate], rng: &mut R) -> LifeLike {
+    let a = &scored[rng.gen_range(0, scored.len())];
+    let b = &scored[rng.gen_range(0, scored.len())];
+    if a.fitness >= b.fitness { a.rule } else { b.rule }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_of_life::LifeLike;
+    use super::super::rule_space::RuleSpace;
+    use super::super::search::SoupSearch;
+    use super::Evolution;
+
+    use rand::{SeedableRng, XorShiftRng};
+
+    fn evolution() -> Evolution {
+        Evolution {
+            space: RuleSpace::new().forbid_birth(0).forbid_birth(1),
+            population: 8,
+            generations: 4,
+            elite: 2,
+            soups: SoupSearch { size: 6, board: 16, max_generations: 20, ..SoupSearch::default() },
+            trials: 2,
+            ..Evolution::default()
+        }
+    }
+
+    #[test]
+    fn trials() {
+        let report = evolution().trial(LifeLike::default(), 2);
+        assert_eq!(report.runs.len(), 2);
+        assert!(report.runs.iter().all(|o| o.initial_population > 0 && o.generations <= 20));
+    }
+
+    #[test]
+    fn improves() {
+        let evolution = evolution();
+        let rng = || XorShiftRng::from_seed([1, 2, 3, 4]);
+        let found = evolution.run(&mut rng(), 2, |report| report.mean_population());
+        assert!(found.len() > evolution.population);
+        assert!(found.windows(2).all(|w| w[0].fitness >= w[1].fitness));
+        assert!(found.iter().all(|c| !c.rule.birth()[0] && !c.rule.birth()[1]));
+        // rules that keep a good part of the board alive are easy to come by
+        assert!(found[0].fitness > 100.);
+        assert_eq!(evolution.run(&mut rng(), 1, |report| report.mean_population()), found);
+    }
+}
@@ -0,0 +1,223 @@
+use super::Frame;
+use super::board::Board;
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::slice;
+
+/// A frame of numbers, which is how C sees the cells of every rule: the state
+/// index for discrete rules, 0 being dead, and the value for continuous ones
+pub struct SimulationFrame(Frame<f32>);
+
+/// `simulation_step` succeeded
+pub const SIMULATION_OK: c_int = 0;
+/// The frame or rule name was null, or the rule name wasn't valid UTF-8
+pub const SIMULATION_INVALID_ARGUMENT: c_int = -1;
+/// The rule isn't one of the built in rules
+pub const SIMULATION_UNKNOWN_RULE: c_int = -2;
+/// The frame isn't wider and taller than the neighborhood of the rule, so it
+/// can't be stepped. The frame is left as it was.
+pub const SIMULATION_STEP_FAILED: c_int = -3;
+
+/// Create an empty width by height frame, or return null if it would be too
+/// large to allocate
+#[no_mangle]
+pub extern "C" fn simulation_frame_new(width: usize, height: usize) -> *mut SimulationFrame {
+    match width.checked_mul(height) {
+        Some(cells) if cells <= isize::MAX as usize / 4 => {
+            Box::into_raw(Box::new(SimulationFrame(Frame::new(width, height))))
+        }
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Free a frame created by `simulation_frame_new`
+///
+/// # Safety
+///
+/// frame has to be null or a frame that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_frame_free(frame: *mut SimulationFrame) {
+    if !frame.is_null() {
+        drop(Box::from_raw(frame));
+    }
+}
+
+/// The width of frame, 0 if it is null
+///
+/// # Safety
+///
+/// frame has to be null or a live frame.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_frame_width(frame: *const SimulationFrame) -> usize {
+    frame.as_ref().map_or(0, |f| f.0.width())
+}
+
+/// The height of frame, 0 if it is null
+///
+/// # Safety
+///
+/// frame has to be null or a live frame.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_frame_height(frame: *const SimulationFrame) -> usize {
+    frame.as_ref().map_or(0, |f| f.0.height())
+}
+
+/// The cell at (x, y), or NaN if it is outside the frame
+///
+/// # Safety
+///
+/// frame has to be null or a live frame.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_frame_get(frame: *const SimulationFrame, x: usize,
+                                              y: usize) -> f32 {
+    match frame.as_ref() {
+        Some(f) if x < f.0.width() && y < f.0.height() => *f.0.get(x, y),
+        _ => f32::NAN,
+    }
+}
+
+/// Set the cell at (x, y), returning false if it is outside the frame
+///
+/// # Safety
+///
+/// frame has to be null or a live frame.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_frame_set(frame: *mut SimulationFrame, x: usize, y: usize,
+                                              value: f32) -> bool {
+    match frame.as_mut() {
+        Some(f) if x < f.0.width() && y < f.0.height() => {
+            *f.0.get_mut(x, y) = value;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The cells of frame row by row, valid until the frame is freed and updated
+/// in place by stepping, or null if frame is null
+///
+/// # Safety
+///
+/// frame has to be null or a live frame.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_frame_data(frame: *const SimulationFrame) -> *const f32 {
+    frame.as_ref().map_or(ptr::null(), |f| f.0.as_slice().as_ptr())
+}
+
+/// Copy the cells of frame row by row into out, which holds len floats.
+/// Returns the number of cells copied, which is 0 if out is too small for the
+/// whole frame.
+///
+/// # Safety
+///
+/// frame has to be null or a live frame and out has to point to len floats.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_frame_copy(frame: *const SimulationFrame, out: *mut f32,
+                                               len: usize) -> usize {
+    match frame.as_ref() {
+        Some(f) if !out.is_null() && len >= f.0.as_slice().len() => {
+            let cells = f.0.as_slice();
+            slice::from_raw_parts_mut(out, cells.len()).copy_from_slice(cells);
+            cells.len()
+        }
+        _ => 0,
+    }
+}
+
+/// Advance frame steps generations of the built in rule called rule, one of
+/// life, rainbow, cyclic, smooth-life or lenia. Cells are rounded or clamped
+/// to what the rule allows first. Returns `SIMULATION_OK` or one of the error
+/// codes.
+///
+/// # Safety
+///
+/// frame has to be null or a live frame and rule null or a NUL terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn simulation_step(frame: *mut SimulationFrame, rule: *const c_char,
+                                         steps: u64) -> c_int {
+    let frame = match frame.as_mut() {
+        Some(frame) => &mut frame.0,
+        None => return SIMULATION_INVALID_ARGUMENT,
+    };
+    if rule.is_null() {
+        return SIMULATION_INVALID_ARGUMENT;
+    }
+    let rule = match CStr::from_ptr(rule).to_str() {
+        Ok(rule) => rule,
+        Err(_) => return SIMULATION_INVALID_ARGUMENT,
+    };
+    let mut board = match Board::new(rule, frame.width(), frame.height()) {
+        Some(board) => board,
+        None => return SIMULATION_UNKNOWN_RULE,
+    };
+    // stepping panics on boards the rule doesn't fit in, and unwinding into
+    // C is undefined
+    if board.reach() >= frame.width() || board.reach() >= frame.height() {
+        return SIMULATION_STEP_FAILED;
+    }
+    for (x, y, &v) in frame.enumerate_squares() {
+        board.set(x, y, v);
+    }
+    board.step_n(steps);
+    frame.as_mut_slice().copy_from_slice(&board.values());
+    SIMULATION_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{simulation_frame_copy, simulation_frame_data, simulation_frame_free,
+                simulation_frame_get, simulation_frame_height, simulation_frame_new,
+                simulation_frame_set, simulation_frame_width, simulation_step};
+    use super::{SIMULATION_INVALID_ARGUMENT, SIMULATION_OK, SIMULATION_STEP_FAILED,
+                SIMULATION_UNKNOWN_RULE};
+
+    use std::ffi::CString;
+    use std::ptr;
+
+    #[test]
+    fn blinker() {
+        unsafe {
+            let frame = simulation_frame_new(5, 5);
+            assert_eq!((simulation_frame_width(frame), simulation_frame_height(frame)), (5, 5));
+            for y in 1..4 {
+                assert!(simulation_frame_set(frame, 2, y, 1.));
+            }
+            assert!(!simulation_frame_set(frame, 5, 0, 1.));
+            assert!(simulation_frame_get(frame, 0, 5).is_nan());
+
+            let life = CString::new("life").unwrap();
+            assert_eq!(simulation_step(frame, life.as_ptr(), 1), SIMULATION_OK);
+            let mut out = [0.; 25];
+            assert_eq!(simulation_frame_copy(frame, out.as_mut_ptr(), 24), 0);
+            assert_eq!(simulation_frame_copy(frame, out.as_mut_ptr(), 25), 25);
+            assert_eq!(&out[10..15], &[0., 1., 1., 1., 0.]);
+            assert_eq!(*simulation_frame_data(frame).offset(11), 1.);
+            simulation_frame_free(frame);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let frame = simulation_frame_new(5, 5);
+            let name = |s| CString::new(s).unwrap();
+            assert_eq!(simulation_step(frame, name("hashlife").as_ptr(), 1),
+                       SIMULATION_UNKNOWN_RULE);
+            assert_eq!(simulation_step(frame, ptr::null(), 1), SIMULATION_INVALID_ARGUMENT);
+            assert_eq!(simulation_step(ptr::null_mut(), name("life").as_ptr(), 1),
+                       SIMULATION_INVALID_ARGUMENT);
+            // lenia looks 13 cells away, further than a 5x5 frame reaches
+            simulation_frame_set(frame, 1, 1, 0.5);
+            assert_eq!(simulation_step(frame, name("lenia").as_ptr(), 1),
+                       SIMULATION_STEP_FAILED);
+            assert_eq!(simulation_frame_get(frame, 1, 1), 0.5);
+            simulation_frame_free(frame);
+            assert!(simulation_frame_new(usize::MAX, 2).is_null());
+            assert_eq!(simulation_frame_width(ptr::null()), 0);
+            simulation_frame_free(ptr::null_mut());
+        }
+    }
+}
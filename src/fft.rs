@@ -0,0 +1,230 @@
+use super::Frame;
+use super::convolve::Kernel;
+
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The radius past which `FftConvolver` is usually faster than
+/// `Frame::convolve`
+pub const FFT_RADIUS: usize = 8;
+
+/// Convolves frames of one size with one kernel through their Fourier
+/// transforms, so each cell costs O(log(width * height)) instead of a
+/// multiply per weight. The kernel's transform is computed once when the
+/// convolver is made, so make one per kernel and frame size and keep it
+/// around between generations. Edges always wrap around.
+pub struct FftConvolver {
+    width: usize,
+    height: usize,
+    /// the transform of the kernel, flipped so that multiplying by it gives
+    /// the same weighted sums as `Frame::convolve`, and scaled to undo the
+    /// scaling of the inverse transform
+    kernel: Vec<Complex<f32>>,
+    rows: Arc<dyn Fft<f32>>,
+    rows_inverse: Arc<dyn Fft<f32>>,
+    columns: Arc<dyn Fft<f32>>,
+    columns_inverse: Arc<dyn Fft<f32>>,
+}
+
+impl FftConvolver {
+    /// A convolver for width by height frames
+    pub fn new(kernel: &Kernel<f32>, width: usize, height: usize) -> FftConvolver {
+        assert!(width > 0 && height > 0, "can't convolve a {}x{} frame", width, height);
+        let mut planner = FftPlanner::new();
+        let mut convolver = FftConvolver {
+            width,
+            height,
+            kernel: vec![Complex::default(); width * height],
+            rows: planner.plan_fft_forward(width),
+            rows_inverse: planner.plan_fft_inverse(width),
+            columns: planner.plan_fft_forward(height),
+            columns_inverse: planner.plan_fft_inverse(height),
+        };
+
+        let (rx, ry) = kernel.radius();
+        let scale = 1. / (width * height) as f32;
+        for (k, &weight) in kernel.as_slice().iter().enumerate() {
+            let di = (k % kernel.width()) as isize - rx as isize;
+            let dj = (k / kernel.width()) as isize - ry as isize;
+            let x = (-di).rem_euclid(width as isize) as usize;
+            let y = (-dj).rem_euclid(height as isize) as usize;
+            convolver.kernel[y * width + x].re += weight * scale;
+        }
+        let mut spectrum = std::mem::take(&mut convolver.kernel);
+        convolver.transform(&mut spectrum, false);
+        convolver.kernel = spectrum;
+        convolver
+    }
+
+    /// The size of the frames this convolves
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// The same as `frame.convolve(kernel)`, up to rounding
+    ///
+    /// # Panics
+    ///
+    /// if frame isn't the size the convolver was made for
+    pub fn convolve(&self, frame: &Frame<f32>) -> Frame<f32> {
+        assert!((frame.width(), frame.height()) == (self.width, self.height),
+                "convolving a {}x{} frame with a convolver for {}x{} frames",
+                frame.width(), frame.height(), self.width, self.height);
+        let mut buffer = frame.as_slice().iter().map(|&c| Complex::new(c, 0.)).collect::<Vec<_>>();
+        self.transform(&mut buffer, false);
+        for (c, k) in buffer.iter_mut().zip(&self.kernel) {
+            *c *= k;
+        }
+        self.transform(&mut buffer, true);
+        Frame::from_vec(self.width, self.height, buffer.into_iter().map(|c| c.re).collect())
+            .unwrap()
+    }
+
+    /// The 2d transform of buffer in place, rows and then columns
+    fn transform(&self, buffer: &mut [Complex<f32>], inverse: bool) {
+        let (rows, columns) = if inverse {
+            (&self.rows_inverse, &self.columns_inverse)
+        } else {
+            (&self.rows, &self.columns)
+        };
+        rows.process(buffer);
+        let (w, h) = (self.width, self.height);
+        let mut transposed = vec![Complex::default(); w * h];
+        for (i, &c) in buffer.iter().enumerate() {
+            transposed[(i % w) * h + i / w] = c;
+        }
+        columns.process(&mut transposed);
+        for (i, &c) in transposed.iter().enumerate() {
+            buffer[(i % h) * w + i / h] = c;
+        }
+    }
+}
+
+/// An `FftConvolver` for one kernel that is planned the first time a frame is
+/// convolved and again whenever the frames change size, for rules that own a
+/// large kernel and convolve with it every generation. Clones share the
+/// convolver made so far.
+#[derive(Default)]
+pub struct CachedConvolver {
+    convolver: Mutex<Option<Arc<FftConvolver>>>,
+}
+
+impl CachedConvolver {
+    pub fn new() -> CachedConvolver {
+        CachedConvolver::default()
+    }
+
+    /// The same as `frame.convolve(kernel)`, up to rounding. kernel has to be
+    /// the same every time, as its transform is only computed when the size of
+    /// the frames changes.
+    pub fn convolve(&self, kernel: &Kernel<f32>, frame: &Frame<f32>) -> Frame<f32> {
+        let (w, h) = (frame.width(), frame.height());
+        if w == 0 || h == 0 {
+            return frame.clone();
+        }
+        let convolver = {
+            let mut cached = self.convolver.lock().unwrap_or_else(PoisonError::into_inner);
+            match *cached {
+                Some(ref convolver) if convolver.size() == (w, h) => convolver.clone(),
+                _ => {
+                    let convolver = Arc::new(FftConvolver::new(kernel, w, h));
+                    *cached = Some(convolver.clone());
+                    convolver
+                }
+            }
+        };
+        convolver.convolve(frame)
+    }
+
+    /// The size of the frames the cached convolver is for, if one has been
+    /// made
+    pub fn size(&self) -> Option<(usize, usize)> {
+        let cached = self.convolver.lock().unwrap_or_else(PoisonError::into_inner);
+        cached.as_ref().map(|c| c.size())
+    }
+}
+
+impl Clone for CachedConvolver {
+    fn clone(&self) -> CachedConvolver {
+        let cached = self.convolver.lock().unwrap_or_else(PoisonError::into_inner);
+        CachedConvolver { convolver: Mutex::new(cached.clone()) }
+    }
+}
+
+impl fmt::Debug for CachedConvolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CachedConvolver").field("size", &self.size()).finish()
+    }
+}
+
+/// Caches are equal whatever they hold, as they don't change what is computed,
+/// so rules holding one compare by their parameters
+impl PartialEq for CachedConvolver {
+    fn eq(&self, _: &CachedConvolver) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::convolve::Kernel;
+    use super::super::lenia::Lenia;
+    use super::{CachedConvolver, FftConvolver};
+
+    fn noise(width: usize, height: usize) -> Frame<f32> {
+        let data = (0..width * height).map(|i| (i * 7 % 11) as f32 / 10.).collect();
+        Frame::from_vec(width, height, data).unwrap()
+    }
+
+    fn assert_close(a: &Frame<f32>, b: &Frame<f32>) {
+        assert!(a.approx_eq(b, 1e-4), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn matches_direct() {
+        let frame = noise(20, 15);
+        let lenia = Lenia::orbium();
+        for kernel in &[Kernel::from_offsets(lenia.kernel()), Kernel::laplacian(),
+                        Kernel::new(3, 1, vec![1., 2., 3.]).unwrap()] {
+            let convolver = FftConvolver::new(kernel, 20, 15);
+            assert_eq!(convolver.size(), (20, 15));
+            assert_close(&convolver.convolve(&frame), &frame.convolve(kernel));
+        }
+    }
+
+    #[test]
+    fn reused() {
+        let kernel = Kernel::ring(3., 9.);
+        let convolver = FftConvolver::new(&kernel, 32, 32);
+        let mut frame = noise(32, 32);
+        for _ in 0..3 {
+            let next = convolver.convolve(&frame);
+            assert_close(&next, &frame.convolve(&kernel));
+            frame = next;
+        }
+    }
+
+    #[test]
+    fn cached() {
+        let kernel = Kernel::ring(2., 5.);
+        let cache = CachedConvolver::new();
+        assert_eq!(cache.size(), None);
+        for &(w, h) in &[(16, 12), (16, 12), (9, 20)] {
+            let frame = noise(w, h);
+            assert_close(&cache.convolve(&kernel, &frame), &frame.convolve(&kernel));
+            assert_eq!(cache.size(), Some((w, h)));
+        }
+        assert_eq!(cache.clone().size(), Some((9, 20)));
+        assert_eq!(cache.convolve(&kernel, &Frame::new(0, 3)), Frame::new(0, 3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_size() {
+        FftConvolver::new(&Kernel::laplacian(), 4, 4).convolve(&Frame::new(4, 5));
+    }
+}
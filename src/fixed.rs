@@ -0,0 +1,128 @@
+use super::Square;
+#[cfg(feature = "alloc")]
+use super::Frame;
+
+use std::array;
+
+/// A W by H frame stored inline, so it needs no allocator. Rules written for
+/// `Frame` step it the same way, wrapping around at the edges, e.g. to drive
+/// an LED matrix from a microcontroller.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedFrame<T, const W: usize, const H: usize> {
+    cells: [[T; W]; H],
+}
+
+impl<T, const W: usize, const H: usize> Default for FixedFrame<T, W, H>
+where T: Default {
+    fn default() -> FixedFrame<T, W, H> {
+        FixedFrame::new()
+    }
+}
+
+impl<T, const W: usize, const H: usize> FixedFrame<T, W, H> {
+    /// Creates an empty frame
+    pub fn new() -> FixedFrame<T, W, H>
+    where T: Default {
+        FixedFrame { cells: array::from_fn(|_| array::from_fn(|_| T::default())) }
+    }
+
+    /// Creates a frame holding rows, indexed by y and then x
+    pub fn from_rows(rows: [[T; W]; H]) -> FixedFrame<T, W, H> {
+        FixedFrame { cells: rows }
+    }
+
+    /// the width of the frame
+    pub fn width(&self) -> usize {
+        W
+    }
+
+    /// the height of the frame
+    pub fn height(&self) -> usize {
+        H
+    }
+
+    /// the data at (x, y)
+    pub fn get(&self, x: usize, y: usize) -> &T {
+        &self.cells[y][x]
+    }
+
+    /// get a mutable reference to the data at (x, y)
+    pub fn get_mut(&mut self, x: usize, y: usize) -> &mut T {
+        &mut self.cells[y][x]
+    }
+
+    /// the rows of the frame, indexed by y and then x
+    pub fn rows(&self) -> &[[T; W]; H] {
+        &self.cells
+    }
+
+    /// The square centered at (x, y)
+    pub fn square(&self, x: usize, y: usize) -> Square<'_, T> {
+        Square::new(self.cells.as_flattened(), W, H, x, y)
+    }
+
+    /// return the next frame of the simulation using the same step functions
+    /// as `Frame::next_frame`
+    pub fn next_frame<F>(&self, step: F) -> FixedFrame<T, W, H>
+    where F: Fn(Square<T>) -> T {
+        FixedFrame { cells: array::from_fn(|y| array::from_fn(|x| step(self.square(x, y)))) }
+    }
+
+    /// Advance the frame a generation in place
+    pub fn step<F>(&mut self, step: F)
+    where F: Fn(Square<T>) -> T {
+        *self = self.next_frame(step);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, const W: usize, const H: usize> FixedFrame<T, W, H>
+where T: Clone {
+    /// A copy of frame, or None if it isn't W by H
+    pub fn from_frame(frame: &Frame<T>) -> Option<FixedFrame<T, W, H>> {
+        if (frame.width(), frame.height()) != (W, H) {
+            return None;
+        }
+        Some(FixedFrame { cells: array::from_fn(|y| array::from_fn(|x| frame.get(x, y).clone())) })
+    }
+
+    /// A copy of this frame that can be resized, saved and rendered
+    pub fn to_frame(&self) -> Frame<T> {
+        let data = self.cells.as_flattened().to_vec();
+        Frame::from_vec(W, H, data).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::FixedFrame;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn glider() {
+        let mut fixed = FixedFrame::<game_of_life::State, 6, 5>::new();
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            *fixed.get_mut(x, y) = Alive;
+        }
+        let mut frame = fixed.to_frame();
+        for _ in 0..12 {
+            fixed.step(game_of_life::rule);
+            frame = frame.next_frame(game_of_life::rule);
+        }
+        assert_eq!(fixed.to_frame(), frame);
+        assert_eq!(FixedFrame::from_frame(&frame), Some(fixed));
+        assert_eq!(FixedFrame::<game_of_life::State, 5, 5>::from_frame(&frame), None);
+    }
+
+    #[test]
+    fn rows() {
+        let fixed = FixedFrame::from_rows([[Dead, Alive, Dead], [Dead, Alive, Dead],
+                                           [Dead, Alive, Dead]]);
+        assert_eq!((fixed.width(), fixed.height()), (3, 3));
+        assert_eq!(fixed.rows()[1], [Dead, Alive, Dead]);
+        assert_eq!(*fixed.square(0, 0).get(-2, -1), Alive);
+        assert_eq!(fixed.next_frame(game_of_life::rule), FixedFrame::from_rows([[Alive; 3]; 3]));
+    }
+}
@@ -1,18 +1,147 @@
+use super::{Square, Topology};
+
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::error;
+use std::fmt;
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
+#[cfg(all(feature = "serde", not(feature = "std")))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
 /// Represents a frame of a simulation
 /// This internal representation is not stable and should not be relied upon
-#[derive(Debug, PartialEq)]
+///
+/// A frame with a side of 0 has no cells. It can be made with `new`, but not
+/// with `try_new`: iterating over it yields nothing, stepping it gives
+/// another empty frame and projecting it onto a screen gives a blank screen.
+/// Anything that has to look up a cell of it, like `get` or `Square`s, panics.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawFrame<T>"))]
 pub struct Frame<T> {
     data: Vec<T>,
     width: usize,
     height: usize,
 }
 
+/// The serialized form of a frame, which has to be checked for consistency
+/// before it can be used as a frame
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawFrame<T> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<RawFrame<T>> for Frame<T> {
+    type Error = String;
+
+    fn try_from(raw: RawFrame<T>) -> Result<Frame<T>, String> {
+        let (width, height, len) = (raw.width, raw.height, raw.data.len());
+        Frame::from_vec(width, height, raw.data).ok_or_else(|| {
+            format!("a {}x{} frame can't have {} cells", width, height, len)
+        })
+    }
+}
+
+/// Why a frame of some size couldn't be made
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeError {
+    /// a side is 0, so the frame would have no cells
+    Empty { width: usize, height: usize },
+    /// the cells wouldn't fit in memory even in principle
+    TooLarge { width: usize, height: usize },
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SizeError::Empty { width, height } => {
+                write!(f, "a {}x{} frame has no cells", width, height)
+            }
+            SizeError::TooLarge { width, height } => {
+                write!(f, "a {}x{} frame is too large", width, height)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for SizeError {}
+
+/// The number of cells of a width by height frame of T, checking that its
+/// cells can be allocated
+pub(crate) fn cells<T>(width: usize, height: usize) -> Result<usize, SizeError> {
+    let too_large = SizeError::TooLarge { width, height };
+    let cells = width.checked_mul(height).ok_or(too_large)?;
+    match cells.checked_mul(mem::size_of::<T>()) {
+        Some(bytes) if bytes <= isize::MAX as usize => Ok(cells),
+        _ => Err(too_large),
+    }
+}
+
+/// The number of cells of a width by height frame of T
+///
+/// # Panics
+///
+/// if the frame is too large
+fn cells_or_panic<T>(width: usize, height: usize) -> usize {
+    cells::<T>(width, height).unwrap_or_else(|e| panic!("{}", e))
+}
+
 impl<T> Frame<T>
-where T: Default + Clone {
+where T: Default {
     /// Creates an empty frame
+    ///
+    /// # Panics
+    ///
+    /// if the frame is too large for its cells to fit in memory
     pub fn new(x: usize, y: usize) -> Frame<T> {
-        let data = vec![T::default(); x * y];
-        Frame::<T> {data: data, width: x, height: y}
+        Frame::new_from_fn(x, y, |_, _| T::default())
+    }
+
+    /// Creates an empty frame, or an error if a side is 0 or the frame is too
+    /// large for its cells to fit in memory, for sizes that come from users
+    pub fn try_new(width: usize, height: usize) -> Result<Frame<T>, SizeError> {
+        if width == 0 || height == 0 {
+            return Err(SizeError::Empty { width, height });
+        }
+        cells::<T>(width, height)?;
+        Ok(Frame::new(width, height))
+    }
+}
+
+impl<T> Frame<T>
+where T: Clone {
+    /// Creates a frame with every cell set to value, for cells without a
+    /// default or with a background other than it
+    ///
+    /// # Panics
+    ///
+    /// if the frame is too large for its cells to fit in memory
+    pub fn new_with(width: usize, height: usize, value: T) -> Frame<T> {
+        Frame { data: vec![value; cells_or_panic::<T>(width, height)], width, height }
+    }
+}
+
+impl<T> Frame<T> {
+    /// Creates a frame with each cell (x, y) set to f(x, y), called row by row
+    ///
+    /// # Panics
+    ///
+    /// if the frame is too large for its cells to fit in memory
+    pub fn new_from_fn<F>(width: usize, height: usize, mut f: F) -> Frame<T>
+    where F: FnMut(usize, usize) -> T {
+        let cells = cells_or_panic::<T>(width, height);
+        let data = (0..cells).map(|i| f(i % width, i / width)).collect();
+        Frame { data, width, height }
     }
 }
 
@@ -30,96 +159,111 @@ impl<T> Frame<T> {
 
     /// the data at (x, y)
     pub fn get(&self, x: usize, y: usize) -> &T {
-        &self.data[y * self.height + x]
+        &self.data[y * self.width + x]
     }
 
     /// get a mutable reference to the data at (x, y)
     pub fn get_mut(&mut self, x: usize, y: usize) -> &mut T {
-        &mut self.data[y * self.height + x]
+        &mut self.data[y * self.width + x]
     }
-}
 
-/// Represents a single square in the frame
-#[derive(Debug, PartialEq)]
-pub struct Square<'a, T>
-where T: 'a {
-    frame: &'a Frame<T>,
-    point: (usize, usize),
-}
+    /// the data row by row
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// the data row by row, mutably
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    /// the data row by row, taking apart the frame
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
 
-/// Add x and y mod m
-fn add_modulo(x: usize, y: isize, m: usize) -> usize {
-    // y should not be greater than the modulo we are working with
-    assert!((y.abs() as usize) < m);
-    let base = (x % m) + m;
-    let delta = if y.is_negative() { y + (m as isize) } else { y } as usize;
-    (base + delta) % m
+    /// the bytes allocated for the cells, not counting anything the cells
+    /// themselves point to
+    pub fn memory_usage(&self) -> usize {
+        self.data.capacity() * mem::size_of::<T>()
+    }
 }
 
-impl<'a, T> Square<'a, T>
-where T: 'a {
-    /// Return a point relative to the square
-    pub fn get(&self, i: isize, j: isize) -> &T {
-        let (x, y) = self.point;
-        let width = self.frame.width();
-        let height = self.frame.height();
-        let (x, y) = (add_modulo(x, i, width), add_modulo(y, j, height));
-        self.frame.get(x, y)
-    }
-
-    /// The coordinate of the square
-    pub fn coordinate(&self) -> (usize, usize) {
-        self.point
-    }
-}
-
-impl<'a, T> Square<'a, T>
-where T: 'a + Clone {
-    /// Return a nondeterministic Vec of &T's within a square with side length r
-    /// self in an ortholinear fashion. Does not contain the current node.
-    pub fn within_ortholinear(&self, r: isize) -> Vec<T> {
-        let mut nodes = vec![];
-        for i in -r..r + 1 {
-            for j in -r..r + 1 {
-                if i != 0 || j != 0 {
-                    nodes.push(self.get(i, j).clone());
-                }
-            }
+impl<T> Frame<T> {
+    /// Creates a frame holding data row by row, or None if there isn't
+    /// exactly one element per cell
+    pub fn from_vec(width: usize, height: usize, data: Vec<T>) -> Option<Frame<T>> {
+        if width.checked_mul(height) != Some(data.len()) {
+            return None;
         }
-        nodes
+        Some(Frame { data, width, height })
     }
 }
 
-impl<T> Frame<T>
-where T: Clone {
+impl<T> Frame<T> {
+    /// The square centered at (x, y)
+    pub fn square(&self, x: usize, y: usize) -> Square<'_, T> {
+        Square::new(&self.data, self.width, self.height, x, y)
+    }
+
+    /// The square centered at (x, y) with the edges of the frame joined
+    /// according to topology, where lookups past bounded edges give outside
+    pub fn square_on<'a>(&'a self, x: usize, y: usize, topology: Topology, outside: &'a T)
+                         -> Square<'a, T> {
+        Square::with_topology(&self.data, self.width, self.height, x, y, topology, outside)
+    }
+}
+
+impl<T> Frame<T> {
     /// return the next frame of the simulation advancing the simulation using
     /// a step function that computes the value for any cell given a certain
     /// board
     pub fn next_frame<F>(&self, step: F) -> Frame<T>
     where F: Fn(Square<T>) -> T {
-        let mut data = self.data.clone();
-        for (x, y, _) in self.enumerate_squares() {
-            let square = Square {
-                frame: &self,
-                point: (x, y),
-            };
-            data[self.height * y + x] = step(square);
+        let width = self.width;
+        let data = (0..self.data.len()).map(|i| step(self.square(i % width, i / width))).collect();
+
+        Frame {
+            data,
+            width: self.width(),
+            height: self.height(),
         }
+    }
+
+    /// Like `next_frame`, but with the edges of the frame joined according
+    /// to topology, where cells past bounded edges are outside
+    pub fn next_frame_on<F>(&self, topology: Topology, outside: &T, step: F) -> Frame<T>
+    where F: Fn(Square<T>) -> T {
+        let width = self.width;
+        let data = (0..self.data.len())
+            .map(|i| step(self.square_on(i % width, i / width, topology, outside)))
+            .collect();
 
         Frame {
-            data: data,
+            data,
             width: self.width(),
             height: self.height(),
         }
     }
 }
 
-/// An iterator over a Frame
+/// An iterator over a Frame, row by row, from either end
 #[derive(Debug, Clone, PartialEq)]
 pub struct FrameIterator<'a, T>
 where T: 'a {
     frame: &'a Frame<T>,
-    next_index: (usize, usize),
+    /// the index of the next cell from the front
+    front: usize,
+    /// one past the index of the next cell from the back
+    back: usize,
+}
+
+impl<'a, T> FrameIterator<'a, T>
+where T: 'a {
+    fn at(&self, i: usize) -> (usize, usize, &'a T) {
+        let width = self.frame.width;
+        (i % width, i / width, &self.frame.data[i])
+    }
 }
 
 impl<'a, T> Iterator for FrameIterator<'a, T>
@@ -127,16 +271,42 @@ where T: 'a {
     type Item = (usize, usize, &'a T);
 
     fn next(&mut self) -> Option<(usize, usize, &'a T)> {
-        let (x, y) = self.next_index;
-        if y < self.frame.width() {
-            let val = self.frame.get(x, y);
-            self.next_index =
-                if x + 1 < self.frame.width() { (x + 1, y) }
-                else { (0, y + 1) };
-            Some((x, y, val))
-        } else {
-            None
+        if self.front == self.back {
+            return None;
         }
+        self.front += 1;
+        Some(self.at(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for FrameIterator<'a, T>
+where T: 'a {
+    fn next_back(&mut self) -> Option<(usize, usize, &'a T)> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.at(self.back))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for FrameIterator<'a, T>
+where T: 'a {}
+
+impl<'a, T> FusedIterator for FrameIterator<'a, T>
+where T: 'a {}
+
+impl<'a, T> IntoIterator for &'a Frame<T> {
+    type Item = (usize, usize, &'a T);
+    type IntoIter = FrameIterator<'a, T>;
+
+    fn into_iter(self) -> FrameIterator<'a, T> {
+        self.enumerate_squares()
     }
 }
 
@@ -170,18 +340,116 @@ where T: 'a {
 impl<T> Frame<T> {
     /// Returns an iterator over tuples of coordinate and the element at that
     /// coordinate
-    pub fn enumerate_squares(&self) -> FrameIterator<T> {
+    pub fn enumerate_squares(&self) -> FrameIterator<'_, T> {
         FrameIterator {
-            frame: &self,
-            next_index: (0, 0),
+            frame: self,
+            front: 0,
+            back: self.data.len(),
+        }
+    }
+
+    /// Returns a frame of the same size with f applied to every element
+    pub fn map<U, F>(&self, f: F) -> Frame<U>
+    where F: Fn(&T) -> U {
+        Frame {
+            data: self.data.iter().map(f).collect(),
+            width: self.width(),
+            height: self.height(),
         }
     }
 }
 
+impl<T> Frame<T> {
+    /// Panics unless other is the same size as this frame
+    fn assert_same_size<U>(&self, other: &Frame<U>) {
+        assert!((self.width, self.height) == (other.width, other.height),
+                "combining a {}x{} frame with a {}x{} frame",
+                self.width, self.height, other.width, other.height);
+    }
+}
+
+/// Element-wise arithmetic between frames of the same size, which panics if
+/// they aren't, and between a frame and a scalar applied to every element
+macro_rules! frame_op {
+    ($op:ident, $method:ident, $op_assign:ident, $method_assign:ident) => {
+        impl<T> $op for Frame<T>
+        where T: $op<Output = T> {
+            type Output = Frame<T>;
+
+            fn $method(self, rhs: Frame<T>) -> Frame<T> {
+                self.assert_same_size(&rhs);
+                let (width, height) = (self.width, self.height);
+                let data = self.data.into_iter().zip(rhs.data).map(|(a, b)| a.$method(b));
+                Frame { data: data.collect(), width, height }
+            }
+        }
+
+        impl<'a, 'b, T> $op<&'b Frame<T>> for &'a Frame<T>
+        where T: Clone + $op<Output = T> {
+            type Output = Frame<T>;
+
+            fn $method(self, rhs: &'b Frame<T>) -> Frame<T> {
+                self.assert_same_size(rhs);
+                let data = self.data.iter().zip(&rhs.data)
+                    .map(|(a, b)| a.clone().$method(b.clone()));
+                Frame { data: data.collect(), width: self.width, height: self.height }
+            }
+        }
+
+        impl<T> $op<T> for Frame<T>
+        where T: Clone + $op<Output = T> {
+            type Output = Frame<T>;
+
+            fn $method(self, rhs: T) -> Frame<T> {
+                let (width, height) = (self.width, self.height);
+                let data = self.data.into_iter().map(|a| a.$method(rhs.clone()));
+                Frame { data: data.collect(), width, height }
+            }
+        }
+
+        impl<'a, T> $op<T> for &'a Frame<T>
+        where T: Clone + $op<Output = T> {
+            type Output = Frame<T>;
+
+            fn $method(self, rhs: T) -> Frame<T> {
+                self.map(|a| a.clone().$method(rhs.clone()))
+            }
+        }
+
+        impl<'a, T> $op_assign<&'a Frame<T>> for Frame<T>
+        where T: Clone + $op_assign {
+            fn $method_assign(&mut self, rhs: &'a Frame<T>) {
+                self.assert_same_size(rhs);
+                for (a, b) in self.data.iter_mut().zip(&rhs.data) {
+                    a.$method_assign(b.clone());
+                }
+            }
+        }
+
+        impl<T> $op_assign<T> for Frame<T>
+        where T: Clone + $op_assign {
+            fn $method_assign(&mut self, rhs: T) {
+                for a in &mut self.data {
+                    a.$method_assign(rhs.clone());
+                }
+            }
+        }
+    }
+}
+
+frame_op!(Add, add, AddAssign, add_assign);
+frame_op!(Sub, sub, SubAssign, sub_assign);
+frame_op!(Mul, mul, MulAssign, mul_assign);
+
 #[cfg(test)]
 mod tests {
     use super::Frame;
 
+    #[cfg(not(feature = "std"))]
+    use alloc::boxed::Box;
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
     #[test]
     fn frame_init() {
         let frame = Frame::<i32>::new(10, 10);
@@ -194,6 +462,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn frame_init_without_default() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Wall(char);
+
+        let frame = Frame::new_with(3, 2, Wall('#'));
+        assert_eq!((frame.width(), frame.height()), (3, 2));
+        assert!(frame.as_slice().iter().all(|c| *c == Wall('#')));
+
+        let mut calls = 0;
+        let frame = Frame::new_from_fn(3, 2, |x, y| {
+            calls += 1;
+            x * 10 + y
+        });
+        assert_eq!(frame.as_slice(), &[0, 10, 20, 1, 11, 21]);
+        assert_eq!(calls, 6);
+    }
+
+    #[test]
+    fn frame_sizes() {
+        use super::SizeError;
+
+        assert_eq!(Frame::<u8>::try_new(3, 2), Ok(Frame::new(3, 2)));
+        assert_eq!(Frame::<u8>::try_new(0, 2), Err(SizeError::Empty { width: 0, height: 2 }));
+        let huge = usize::MAX / 2;
+        assert_eq!(Frame::<u8>::try_new(huge, 3),
+                   Err(SizeError::TooLarge { width: huge, height: 3 }));
+        assert_eq!(Frame::<u64>::try_new(huge / 8, 2),
+                   Err(SizeError::TooLarge { width: huge / 8, height: 2 }));
+
+        // frames with no cells still work, they just have nothing in them
+        for &(w, h) in &[(0, 3), (3, 0), (0, 0)] {
+            let empty = Frame::<u8>::new(w, h);
+            assert_eq!(empty.enumerate_squares().count(), 0);
+            let next = empty.next_frame(|sq| *sq.get(0, 0));
+            assert_eq!((next.width(), next.height()), (w, h));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "too large")]
+    fn frame_too_large() {
+        Frame::<u8>::new(usize::MAX / 2, 3);
+    }
+
     #[test]
     fn frame_mut() {
         let mut frame = Frame::<i32>::new(2, 2);
@@ -201,6 +514,115 @@ mod tests {
         assert_eq!(*frame.get(1, 1), 1)
     }
 
+    #[test]
+    fn frame_rectangular() {
+        let mut frame = Frame::<usize>::new(3, 5);
+        for x in 0..3 {
+            for y in 0..5 {
+                *frame.get_mut(x, y) = x * 10 + y;
+            }
+        }
+        for (x, y, v) in frame.enumerate_squares() {
+            assert_eq!(*v, x * 10 + y);
+        }
+        assert_eq!(frame.enumerate_squares().count(), 15);
+    }
+
+    #[test]
+    fn frame_iterator() {
+        let frame = Frame::from_vec(3, 2, (0..6).collect::<Vec<_>>()).unwrap();
+        let mut iter = frame.enumerate_squares();
+        assert_eq!(iter.len(), 6);
+        assert_eq!(iter.next(), Some((0, 0, &0)));
+        assert_eq!(iter.next_back(), Some((2, 1, &5)));
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.rev().map(|(x, y, _)| (x, y)).collect::<Vec<_>>(),
+                   vec![(1, 1), (0, 1), (2, 0), (1, 0)]);
+
+        let mut total = 0;
+        for (x, y, &v) in &frame {
+            assert_eq!(v, y * 3 + x);
+            total += v;
+        }
+        assert_eq!(total, 15);
+    }
+
+    #[test]
+    fn frame_vec() {
+        let mut frame = Frame::from_vec(3, 2, (0..6).collect()).unwrap();
+        assert_eq!(*frame.get(1, 1), 4);
+        frame.as_mut_slice()[0] = 9;
+        assert_eq!(frame.as_slice(), &[9, 1, 2, 3, 4, 5]);
+        assert_eq!(frame.into_vec().len(), 6);
+        assert!(Frame::from_vec(3, 3, vec![0; 6]).is_none());
+        assert!(Frame::from_vec(usize::MAX, 2, Vec::<u8>::new()).is_none());
+    }
+
+    #[test]
+    fn frame_map() {
+        let mut frame = Frame::<i32>::new(2, 2);
+        *frame.get_mut(1, 0) = 3;
+        let doubled = frame.map(|e| { e * 2 });
+        assert_eq!(*doubled.get(1, 0), 6);
+        assert_eq!(*doubled.get(0, 1), 0);
+    }
+
+    #[test]
+    fn frame_arithmetic() {
+        let a = Frame::from_vec(2, 2, vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+        let b = Frame::from_vec(2, 2, vec![0.5, 0.5, 1.0, 2.0]).unwrap();
+        assert_eq!((&a + &b).as_slice(), &[1.5, 2.5, 4.0, 6.0]);
+        assert_eq!((&a - &b).as_slice(), &[0.5, 1.5, 2.0, 2.0]);
+        assert_eq!((a.clone() * b.clone()).as_slice(), &[0.5, 1.0, 3.0, 8.0]);
+        assert_eq!((&a * 2.0).as_slice(), &[2.0, 4.0, 6.0, 8.0]);
+        assert_eq!((a.clone() - 1.0).as_slice(), &[0.0, 1.0, 2.0, 3.0]);
+
+        let mut c = a.clone();
+        c += &b;
+        c -= 0.5;
+        c *= &b;
+        assert_eq!(c.as_slice(), &[0.5, 1.0, 3.5, 11.0]);
+        assert_eq!(((a + 1.0) * 3.0).as_slice(), &[6.0, 9.0, 12.0, 15.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "combining a 2x2 frame with a 2x3 frame")]
+    fn frame_arithmetic_sizes() {
+        let _ = Frame::<i32>::new(2, 2) + Frame::new(2, 3);
+    }
+
+    #[test]
+    fn frame_not_clone() {
+        use super::super::Simulation;
+
+        // cells holding boxed data that can't be cloned
+        #[derive(Debug, Default, PartialEq)]
+        struct Counter(Box<u32>);
+
+        let mut sim = Simulation::new(Frame::<Counter>::new(3, 2), |sq| {
+            let most = sq.neighbors(1).map(|(_, _, c)| *c.0).max().unwrap();
+            Counter(Box::new(most.max(*sq.get(0, 0).0) + 1))
+        });
+        *sim.frame_mut().get_mut(2, 1) = Counter(Box::new(5));
+        sim.step_n(2);
+        assert!(sim.frame().as_slice().iter().all(|c| *c.0 == 7));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn frame_serde() {
+        use serde_json;
+
+        let mut frame = Frame::<i32>::new(3, 2);
+        *frame.get_mut(2, 1) = 5;
+        let json = serde_json::to_string(&frame).unwrap();
+        assert_eq!(json, r#"{"data":[0,0,0,0,0,5],"width":3,"height":2}"#);
+        assert_eq!(serde_json::from_str::<Frame<i32>>(&json).unwrap(), frame);
+
+        let bad = r#"{"data":[0,0,0],"width":3,"height":2}"#;
+        assert!(serde_json::from_str::<Frame<i32>>(bad).is_err());
+    }
+
     #[test]
     fn frame_next() {
         let mut frame1 = Frame::<i32>::new(2, 2);
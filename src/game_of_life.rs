@@ -1,24 +1,54 @@
 use super::Square;
 
+use std::fmt;
+use std::ops::BitXor;
+use std::str::FromStr;
+
 /// The state of a node in a GOL
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum State {
     Alive,
+    #[default]
     Dead,
 }
 
-impl Default for State {
-    fn default() -> State { State::Dead }
+/// Displayed as `1` for alive and `0` for dead
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            State::Alive => write!(f, "1"),
+            State::Dead => write!(f, "0"),
+        }
+    }
+}
+
+impl FromStr for State {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<State, ()> {
+        match s {
+            "1" => Ok(State::Alive),
+            "0" => Ok(State::Dead),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Alive when exactly one of the two is, for building second order rules
+impl BitXor for State {
+    type Output = State;
+
+    fn bitxor(self, other: State) -> State {
+        if self == other { State::Dead } else { State::Alive }
+    }
 }
 
 impl<'a> Square<'a, State>
 where State: 'a {
     /// Return the number of nodes alive surrounding this number
-    fn alive_count(&self) -> usize {
-        self.within_ortholinear(1)
-            .iter()
-            .filter(|e| { **e == State::Alive })
-            .count()
+    pub fn alive_count(&self) -> usize {
+        self.count_moore(1, |e| *e == State::Alive)
     }
 }
 
@@ -40,13 +70,128 @@ pub fn rule(curr: Square<State>) -> State {
     }
 }
 
-#[cfg(test)]
+/// A Life-like rule, where dead cells with a number of live neighbors in
+/// birth are born and live cells with a number in survival stay alive, named
+/// by rulestrings like `B3/S23` for Conway's Game of Life
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LifeLike {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl Default for LifeLike {
+    /// Conway's Game of Life
+    fn default() -> LifeLike {
+        LifeLike::new(&[3], &[2, 3])
+    }
+}
+
+impl LifeLike {
+    /// The rule that gives birth to cells with a number of live neighbors in
+    /// birth and keeps cells with a number in survival
+    ///
+    /// # Panics
+    ///
+    /// if any of the numbers is more than 8, the most neighbors a cell has
+    pub fn new(birth: &[usize], survival: &[usize]) -> LifeLike {
+        if let Some(n) = birth.iter().chain(survival).find(|&&n| n > 8) {
+            panic!("cells have at most 8 neighbors, not {}", n);
+        }
+        let mut counts = ([false; 9], [false; 9]);
+        for &n in birth {
+            counts.0[n] = true;
+        }
+        for &n in survival {
+            counts.1[n] = true;
+        }
+        LifeLike::from_counts(counts.0, counts.1)
+    }
+
+    /// The rule where birth[n] says whether dead cells with n live neighbors
+    /// are born and survival[n] whether live cells with n stay alive
+    pub fn from_counts(birth: [bool; 9], survival: [bool; 9]) -> LifeLike {
+        LifeLike { birth, survival }
+    }
+
+    pub fn birth(&self) -> [bool; 9] {
+        self.birth
+    }
+
+    pub fn survival(&self) -> [bool; 9] {
+        self.survival
+    }
+
+    /// The rule for this Life-like rule, to be used with `Frame::next_frame`
+    pub fn rule(&self, curr: Square<State>) -> State {
+        let alive = curr.alive_count();
+        match *curr.get(0, 0) {
+            State::Dead if self.birth[alive] => State::Alive,
+            State::Alive if self.survival[alive] => State::Alive,
+            _ => State::Dead,
+        }
+    }
+}
+
+/// Displayed as a rulestring, e.g. `B36/S23` for HighLife
+impl fmt::Display for LifeLike {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for n in (0..9).filter(|&n| self.birth[n]) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for n in (0..9).filter(|&n| self.survival[n]) {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parsed from a rulestring in B/S form, e.g. `B3/S23`, in either case and
+/// with the parts in either order
+impl FromStr for LifeLike {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<LifeLike, ()> {
+        let mut parts = s.split('/');
+        let (a, b) = (parts.next().ok_or(())?, parts.next().ok_or(())?);
+        if parts.next().is_some() {
+            return Err(());
+        }
+        let counts = |part: &str, letter: char| {
+            let mut chars = part.chars();
+            if chars.next().map(|c| c.to_ascii_uppercase()) != Some(letter) {
+                return None;
+            }
+            let mut counts = [false; 9];
+            for c in chars {
+                let n = c.to_digit(9)? as usize;
+                if counts[n] {
+                    return None;
+                }
+                counts[n] = true;
+            }
+            Some(counts)
+        };
+        match (counts(a, 'B'), counts(b, 'S'), counts(a, 'S'), counts(b, 'B')) {
+            (Some(birth), Some(survival), _, _) | (_, _, Some(survival), Some(birth)) => {
+                Ok(LifeLike::from_counts(birth, survival))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::super::Frame;
-    use super::rule;
+    use super::{rule, LifeLike};
     use super::State;
     use super::State::Alive;
 
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
     #[test]
     fn oscillator() {
         // Create a board that should transform as shown:
@@ -112,4 +257,30 @@ mod tests {
 
         assert_eq!(frame, frame_new);
     }
+
+    #[test]
+    fn life_like() {
+        let highlife = "B36/S23".parse::<LifeLike>().unwrap();
+        assert_eq!(highlife, LifeLike::new(&[3, 6], &[2, 3]));
+        assert_eq!(highlife.to_string(), "B36/S23");
+        assert_eq!("s23/b3".parse(), Ok(LifeLike::default()));
+        assert_eq!(LifeLike::new(&[1], &[]).to_string(), "B1/S");
+        assert_eq!("B/S".parse(), Ok(LifeLike::new(&[], &[])));
+        for bad in &["B3", "B3/S23/C3", "B9/S23", "B33/S23", "X3/S23", "B3/B23"] {
+            assert_eq!(bad.parse::<LifeLike>(), Err(()), "{}", bad);
+        }
+
+        let mut frame = Frame::<State>::new(6, 6);
+        *frame.get_mut(1, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        *frame.get_mut(2, 3) = Alive;
+        let conway = LifeLike::default();
+        assert_eq!(frame.next_frame(|sq| conway.rule(sq)), frame.next_frame(rule));
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 8 neighbors")]
+    fn too_many_neighbors() {
+        LifeLike::new(&[3], &[2, 9]);
+    }
 }
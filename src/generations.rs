@@ -0,0 +1,251 @@
+use super::Square;
+use super::game_of_life::{self, LifeLike};
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// The most dying states there are characters to write them as, every
+/// character from `A` on but `O` and the surrogates
+const MAX_DYING: usize = char::MAX as usize - 'A' as usize - 0x800;
+
+/// A cell of a rule with N states where cells don't die straight away but
+/// first pass through N - 2 dying states, like Generations rules. Cells can
+/// only be made alive, dead or dying at a level N has, so N has to be at
+/// least 2, which is checked when code using the cells is compiled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "usize", into = "usize"))]
+pub struct DecayState<const N: usize> {
+    /// 0 when alive, N - 1 when dead and how many generations the cell has
+    /// been dying for in between
+    level: usize,
+}
+
+impl<const N: usize> DecayState<N> {
+    /// The level of dead cells
+    const DEAD_LEVEL: usize = {
+        assert!(N >= 2, "decaying cells need at least a live and a dead state");
+        assert!(N - 2 <= MAX_DYING, "there aren't enough characters for that many states");
+        N - 1
+    };
+
+    pub const ALIVE: DecayState<N> = {
+        // checks N like DEAD does
+        let _ = Self::DEAD_LEVEL;
+        DecayState { level: 0 }
+    };
+
+    pub const DEAD: DecayState<N> = DecayState { level: Self::DEAD_LEVEL };
+
+    /// A cell that has been dying for level generations, or None unless
+    /// level is from 1 to N - 2
+    pub fn dying(level: usize) -> Option<DecayState<N>> {
+        if level >= 1 && level < Self::DEAD_LEVEL { Some(DecayState { level }) } else { None }
+    }
+
+    /// The state a cell is in one generation later if nothing keeps it alive
+    pub fn decay(self) -> DecayState<N> {
+        DecayState { level: (self.level + 1).min(Self::DEAD_LEVEL) }
+    }
+
+    /// How far the cell has decayed, 0 when alive and N - 1 when dead
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.level == 0
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.level == Self::DEAD_LEVEL
+    }
+}
+
+impl<const N: usize> Default for DecayState<N> {
+    fn default() -> DecayState<N> {
+        DecayState::DEAD
+    }
+}
+
+/// A cell decayed to level, or an error if N has no such level
+impl<const N: usize> TryFrom<usize> for DecayState<N> {
+    type Error = &'static str;
+
+    fn try_from(level: usize) -> Result<DecayState<N>, &'static str> {
+        if level <= Self::DEAD_LEVEL {
+            Ok(DecayState { level })
+        } else {
+            Err("the cell has decayed further than it can")
+        }
+    }
+}
+
+impl<const N: usize> From<DecayState<N>> for usize {
+    fn from(state: DecayState<N>) -> usize {
+        state.level
+    }
+}
+
+/// Dying cells are no longer alive in Life
+impl<const N: usize> From<DecayState<N>> for game_of_life::State {
+    fn from(state: DecayState<N>) -> game_of_life::State {
+        if state.is_alive() { game_of_life::State::Alive } else { game_of_life::State::Dead }
+    }
+}
+
+impl<const N: usize> From<game_of_life::State> for DecayState<N> {
+    fn from(state: game_of_life::State) -> DecayState<N> {
+        match state {
+            game_of_life::State::Alive => DecayState::ALIVE,
+            game_of_life::State::Dead => DecayState::DEAD,
+        }
+    }
+}
+
+/// A Generations rule with N states. Dead cells with a number of live
+/// neighbors in birth are born, live cells with a number in survival stay
+/// alive and every other live or dying cell decays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Generations<const N: usize> {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl<const N: usize> Generations<N> {
+    /// The rule that gives birth to cells with a number of live neighbors in
+    /// birth and keeps cells with a number in survival, e.g. `&[2]` and `&[]`
+    /// for Brian's Brain
+    ///
+    /// # Panics
+    ///
+    /// if any of the numbers is more than 8, like `LifeLike::new`
+    pub fn new(birth: &[usize], survival: &[usize]) -> Generations<N> {
+        let life = LifeLike::new(birth, survival);
+        Generations::from_counts(life.birth(), life.survival())
+    }
+
+    /// The rule where birth[n] says whether dead cells with n live neighbors
+    /// are born and survival[n] whether live cells with n stay alive
+    pub fn from_counts(birth: [bool; 9], survival: [bool; 9]) -> Generations<N> {
+        assert!(N >= 2, "a Generations rule needs at least a live and a dead state");
+        Generations { birth, survival }
+    }
+
+    pub fn birth(&self) -> [bool; 9] {
+        self.birth
+    }
+
+    pub fn survival(&self) -> [bool; 9] {
+        self.survival
+    }
+
+    /// The rule for Generations, to be used with `Frame::next_frame`
+    pub fn rule(&self, curr: Square<DecayState<N>>) -> DecayState<N> {
+        let this = *curr.get(0, 0);
+        let alive = curr.count_moore(1, DecayState::is_alive);
+        if (this.is_dead() && self.birth[alive]) || (this.is_alive() && self.survival[alive]) {
+            DecayState::ALIVE
+        } else {
+            this.decay()
+        }
+    }
+}
+
+impl Generations<3> {
+    /// Brian's Brain, where cells are born with exactly two live neighbors
+    /// and always die the generation after
+    pub fn brians_brain() -> Generations<3> {
+        Generations::new(&[2], &[])
+    }
+}
+
+/// Displayed as a rulestring with the number of states last, e.g.
+/// `B2/S/C3` for Brian's Brain
+impl<const N: usize> fmt::Display for Generations<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/C{}", LifeLike::from_counts(self.birth, self.survival), N)
+    }
+}
+
+/// Parsed from a rulestring like `B2/S/C3`, which has to have N states
+impl<const N: usize> FromStr for Generations<N> {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Generations<N>, ()> {
+        let (life, states) = s.rsplit_once('/').ok_or(())?;
+        if !states.starts_with(['C', 'c']) || states[1..].parse() != Ok(N) || N < 2 {
+            return Err(());
+        }
+        let life = life.parse::<LifeLike>()?;
+        Ok(Generations::from_counts(life.birth(), life.survival()))
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::{DecayState, Generations};
+
+    use std::convert::TryFrom;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::string::ToString;
+
+    #[test]
+    fn decay() {
+        let alive = DecayState::<4>::ALIVE;
+        assert_eq!(alive.decay(), DecayState::dying(1).unwrap());
+        assert_eq!(alive.decay().decay(), DecayState::dying(2).unwrap());
+        assert_eq!(alive.decay().decay().decay(), DecayState::DEAD);
+        assert_eq!(DecayState::<4>::DEAD.decay(), DecayState::DEAD);
+        assert_eq!(DecayState::<2>::ALIVE.decay(), DecayState::DEAD);
+        let dying = DecayState::<4>::dying(1).unwrap();
+        assert_eq!(game_of_life::State::from(dying), game_of_life::State::Dead);
+        assert_eq!(DecayState::<4>::from(game_of_life::State::Alive), alive);
+
+        // only the levels between alive and dead are dying
+        assert_eq!(DecayState::<4>::dying(0), None);
+        assert_eq!(DecayState::<4>::dying(3), None);
+        assert_eq!(DecayState::<2>::dying(1), None);
+        assert_eq!(DecayState::<4>::try_from(3), Ok(DecayState::DEAD));
+        assert!(DecayState::<4>::try_from(4).is_err());
+    }
+
+    #[test]
+    fn brians_brain() {
+        // two live cells next to each other give birth to the four cells
+        // touching both and then start dying
+        let brain = Generations::brians_brain();
+        let mut frame = Frame::new(6, 6);
+        *frame.get_mut(2, 2) = DecayState::ALIVE;
+        *frame.get_mut(3, 2) = DecayState::ALIVE;
+        let next = frame.next_frame(|sq| brain.rule(sq));
+        assert_eq!(*next.get(2, 2), DecayState::dying(1).unwrap());
+        let born = next.as_slice().iter().filter(|c| c.is_alive()).count();
+        assert_eq!(born, 4);
+        assert_eq!(*next.next_frame(|sq| brain.rule(sq)).get(2, 2), DecayState::DEAD);
+
+        // with two states nothing is dying and Generations is just life-like
+        let life = Generations::<2>::new(&[3], &[2, 3]);
+        let mut blinker = Frame::<DecayState<2>>::new(5, 5);
+        for y in 1..4 {
+            *blinker.get_mut(2, y) = DecayState::ALIVE;
+        }
+        let next = blinker.next_frame(|sq| life.rule(sq)).map(|&c| game_of_life::State::from(c));
+        let gol = blinker.map(|&c| game_of_life::State::from(c)).next_frame(game_of_life::rule);
+        assert_eq!(next, gol);
+    }
+
+    #[test]
+    fn rulestrings() {
+        assert_eq!(Generations::brians_brain().to_string(), "B2/S/C3");
+        assert_eq!("b2/s/c3".parse(), Ok(Generations::<3>::brians_brain()));
+        let star_wars = "B2/S345/C4".parse::<Generations<4>>().unwrap();
+        assert_eq!(star_wars, Generations::new(&[2], &[3, 4, 5]));
+        assert_eq!("B2/S345/C4".parse::<Generations<3>>(), Err(()));
+        assert_eq!("B2/S345".parse::<Generations<4>>(), Err(()));
+    }
+}
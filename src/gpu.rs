@@ -0,0 +1,372 @@
+use super::{game_of_life, CellState, Frame};
+use super::lenia::Lenia;
+
+use pollster;
+use wgpu;
+use wgpu::util::DeviceExt;
+
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::result;
+use std::sync::mpsc;
+
+/// Outer totalistic rules: a cell is born or survives depending only on how
+/// many of its eight neighbors are alive
+const OUTER_TOTALISTIC: &str = "
+struct Params { width: u32, height: u32, birth: u32, survival: u32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> src: array<u32>;
+@group(0) @binding(2) var<storage, read_write> dst: array<u32>;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    var n = 0u;
+    for (var j = 0u; j < 3u; j++) {
+        for (var i = 0u; i < 3u; i++) {
+            if (i != 1u || j != 1u) {
+                let x = (id.x + params.width + i - 1u) % params.width;
+                let y = (id.y + params.height + j - 1u) % params.height;
+                n += src[y * params.width + x];
+            }
+        }
+    }
+    let index = id.y * params.width + id.x;
+    let mask = select(params.birth, params.survival, src[index] != 0u);
+    dst[index] = (mask >> n) & 1u;
+}
+";
+
+/// Lenia: the kernel weighted sum of a cell's neighborhood sets how much it
+/// grows
+const LENIA: &str = "
+struct Params { width: u32, height: u32, kernel_len: u32, mu: f32, sigma: f32, dt: f32 }
+struct Weight { dx: i32, dy: i32, w: f32, pad: f32 }
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> src: array<f32>;
+@group(0) @binding(2) var<storage, read_write> dst: array<f32>;
+@group(0) @binding(3) var<storage, read> kernel: array<Weight>;
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let w = i32(params.width);
+    let h = i32(params.height);
+    var u = 0.0;
+    for (var k = 0u; k < params.kernel_len; k++) {
+        let weight = kernel[k];
+        let x = ((i32(id.x) + weight.dx) % w + w) % w;
+        let y = ((i32(id.y) + weight.dy) % h + h) % h;
+        u += src[y * w + x] * weight.w;
+    }
+    let d = (u - params.mu) / params.sigma;
+    let growth = 2.0 * exp(-d * d / 2.0) - 1.0;
+    let index = id.y * params.width + id.x;
+    dst[index] = clamp(src[index] + params.dt * growth, 0.0, 1.0);
+}
+";
+
+/// Errors setting up or talking to the gpu
+#[derive(Debug)]
+pub enum Error {
+    /// no gpu adapter is available
+    NoAdapter,
+    /// the adapter couldn't provide a device
+    Device(wgpu::RequestDeviceError),
+    /// reading a frame back from the gpu failed
+    Map(wgpu::BufferAsyncError),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NoAdapter => write!(f, "no gpu adapter is available"),
+            Error::Device(ref e) => write!(f, "{}", e),
+            Error::Map(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// The bytes of words as the gpu reads them
+fn bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+/// A bit mask with bit n set for every n in counts
+fn mask(counts: &[usize]) -> u32 {
+    counts.iter().filter(|&&n| n <= 8).fold(0, |m, &n| m | 1 << n)
+}
+
+/// A simulation stepped by a compute shader. The board stays in gpu memory
+/// between steps and is only read back by `frame`, so stepping many
+/// generations between reads is much cheaper than reading every generation.
+pub struct GpuSimulation<T> {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    /// the two boards, with the current one at index `current`
+    boards: [wgpu::Buffer; 2],
+    /// bind groups stepping from board i to the other one
+    bind_groups: [wgpu::BindGroup; 2],
+    /// where boards are copied to be read back
+    staging: wgpu::Buffer,
+    current: usize,
+    width: usize,
+    height: usize,
+    generation: u64,
+    cell: PhantomData<T>,
+}
+
+impl GpuSimulation<game_of_life::State> {
+    /// A simulation of the outer totalistic rule where dead cells with a
+    /// number of live neighbors in birth are born and live cells with a number
+    /// in survival survive, e.g. `&[3]` and `&[2, 3]` for Life
+    pub fn outer_totalistic(frame: &Frame<game_of_life::State>, birth: &[usize],
+                            survival: &[usize]) -> Result<Self> {
+        let params = bytes(&[frame.width() as u32, frame.height() as u32, mask(birth),
+                             mask(survival)]);
+        GpuSimulation::new(frame, OUTER_TOTALISTIC, &params, None)
+    }
+
+    /// Conway's Game of Life
+    pub fn life(frame: &Frame<game_of_life::State>) -> Result<Self> {
+        GpuSimulation::outer_totalistic(frame, &[3], &[2, 3])
+    }
+}
+
+impl GpuSimulation<f32> {
+    /// A simulation of lenia. Unlike on the cpu, frames may be smaller than
+    /// the kernel.
+    pub fn lenia(frame: &Frame<f32>, lenia: &Lenia) -> Result<Self> {
+        // padded to 32 bytes for the uniform buffer
+        let params = bytes(&[frame.width() as u32, frame.height() as u32,
+                             lenia.kernel().len() as u32, lenia.mu.to_bits(),
+                             lenia.sigma.to_bits(), lenia.dt.to_bits(), 0, 0]);
+        let kernel = lenia.kernel().iter()
+            .flat_map(|&(i, j, w)| vec![i as i32 as u32, j as i32 as u32, w.to_bits(), 0])
+            .collect::<Vec<_>>();
+        GpuSimulation::new(frame, LENIA, &params, Some(&bytes(&kernel)))
+    }
+}
+
+/// The word the gpu stores cell as, which holds all of its bits for the
+/// types of cell gpu simulations are made of
+fn to_word<T: CellState>(cell: &T) -> u32 {
+    cell.to_bits() as u32
+}
+
+impl<T: CellState + Default> GpuSimulation<T> {
+    fn new(frame: &Frame<T>, shader: &str, params: &[u8], kernel: Option<&[u8]>)
+           -> Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+            .ok_or(Error::NoAdapter)?;
+        let descriptor = wgpu::DeviceDescriptor {
+            required_limits: adapter.limits(),
+            ..Default::default()
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&descriptor, None))
+            .map_err(Error::Device)?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(shader)),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let init = |contents: &[u8], usage| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None, contents, usage,
+            })
+        };
+        let params = init(params, wgpu::BufferUsages::UNIFORM);
+        let kernel = kernel.map(|k| init(k, wgpu::BufferUsages::STORAGE));
+        let cells = bytes(&frame.as_slice().iter().map(to_word).collect::<Vec<_>>());
+        let usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST;
+        let boards = [init(&cells, usage), init(&cells, usage)];
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: cells.len() as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = pipeline.get_bind_group_layout(0);
+        let bind_group = |src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            let mut entries = vec![
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: src.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: dst.as_entire_binding() },
+            ];
+            if let Some(ref kernel) = kernel {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3, resource: kernel.as_entire_binding(),
+                });
+            }
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None, layout: &layout, entries: &entries,
+            })
+        };
+        let bind_groups = [bind_group(&boards[0], &boards[1]),
+                           bind_group(&boards[1], &boards[0])];
+
+        Ok(GpuSimulation {
+            device, queue, pipeline, boards, bind_groups, staging,
+            current: 0,
+            width: frame.width(),
+            height: frame.height(),
+            generation: 0,
+            cell: PhantomData,
+        })
+    }
+
+    /// The number of steps taken so far
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Advance the simulation one generation
+    pub fn step(&mut self) {
+        self.step_n(1)
+    }
+
+    /// Advance the simulation n generations in a single submission
+    pub fn step_n(&mut self, n: u64) {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            for _ in 0..n {
+                pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+                pass.dispatch_workgroups((self.width as u32).div_ceil(8),
+                                         (self.height as u32).div_ceil(8), 1);
+                self.current = 1 - self.current;
+            }
+        }
+        self.queue.submit(Some(encoder.finish()));
+        self.generation += n;
+    }
+
+    /// Read the current frame back from the gpu, waiting for any steps still
+    /// running
+    pub fn frame(&self) -> Result<Frame<T>> {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&self.boards[self.current], 0, &self.staging, 0,
+                                      self.staging.size());
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = self.staging.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |mapped| sender.send(mapped).unwrap());
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap().map_err(Error::Map)?;
+        let data = slice.get_mapped_range()
+            .chunks(4)
+            .map(|b| {
+                let word = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                T::from_bits(word as u64).unwrap_or_default()
+            })
+            .collect();
+        self.staging.unmap();
+        Ok(Frame::from_vec(self.width, self.height, data).unwrap())
+    }
+
+    /// Replace the current frame, which has to be the same size
+    pub fn set_frame(&mut self, frame: &Frame<T>) {
+        assert_eq!((frame.width(), frame.height()), (self.width, self.height));
+        let cells = bytes(&frame.as_slice().iter().map(to_word).collect::<Vec<_>>());
+        self.queue.write_buffer(&self.boards[self.current], 0, &cells);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{game_of_life, Frame};
+    use super::super::game_of_life::State::Alive;
+    use super::super::lenia::Lenia;
+    use super::super::patterns::Pattern;
+    use super::{Error, GpuSimulation, LENIA, OUTER_TOTALISTIC};
+
+    use wgpu::naga;
+
+    /// The gpu backend only gets tested where there is a gpu
+    fn available<T>(sim: Result<T, Error>) -> Option<T> {
+        match sim {
+            Err(Error::NoAdapter) => None,
+            sim => Some(sim.unwrap()),
+        }
+    }
+
+    #[test]
+    fn shaders() {
+        for shader in &[OUTER_TOTALISTIC, LENIA] {
+            let module = naga::front::wgsl::parse_str(shader).unwrap();
+            naga::valid::Validator::new(Default::default(), Default::default())
+                .validate(&module)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn life() {
+        let mut frame = Frame::new(12, 10);
+        Pattern::glider().place(&mut frame, 1, 1, Default::default(), Alive);
+        let mut sim = match available(GpuSimulation::life(&frame)) {
+            Some(sim) => sim,
+            None => return,
+        };
+        sim.step_n(7);
+        let mut cpu = frame.clone();
+        for _ in 0..7 {
+            cpu = cpu.next_frame(game_of_life::rule);
+        }
+        assert_eq!(sim.generation(), 7);
+        assert_eq!(sim.frame().unwrap(), cpu);
+        sim.set_frame(&frame);
+        assert_eq!(sim.frame().unwrap(), frame);
+    }
+
+    #[test]
+    fn lenia() {
+        let lenia = Lenia::new(4, &[1.], 0.15, 0.015, 0.1);
+        let mut frame = Frame::<f32>::new(16, 16);
+        for y in 5..10 {
+            for x in 6..9 {
+                *frame.get_mut(x, y) = 0.2 * (x + y) as f32 / 10.;
+            }
+        }
+        let mut sim = match available(GpuSimulation::lenia(&frame, &lenia)) {
+            Some(sim) => sim,
+            None => return,
+        };
+        sim.step_n(3);
+        let mut cpu = frame;
+        for _ in 0..3 {
+            cpu = cpu.next_frame(|sq| lenia.rule(sq));
+        }
+        let gpu = sim.frame().unwrap();
+        for (a, b) in gpu.as_slice().iter().zip(cpu.as_slice()) {
+            assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use super::{Frame, Topology};
+
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use std::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+/// Cells that are nodes of a graph, each with an explicit list of the cells
+/// it neighbors, for boards that aren't grids like Penrose tilings,
+/// small-world networks and irregular meshes. The graph is shared between
+/// the frames stepped from one another rather than copied for each.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawGraphFrame<T>", into = "RawGraphFrame<T>"))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "T: Clone + ::serde::Serialize",
+                                          deserialize = "T: ::serde::Deserialize<'de>")))]
+pub struct GraphFrame<T> {
+    cells: Vec<T>,
+    adjacency: Arc<Vec<Vec<usize>>>,
+}
+
+/// A graph frame as it is serialized, whose indices are checked when it is
+/// turned back into one
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RawGraphFrame<T> {
+    pub cells: Vec<T>,
+    pub adjacency: Vec<Vec<usize>>,
+}
+
+impl<T> TryFrom<RawGraphFrame<T>> for GraphFrame<T> {
+    type Error = &'static str;
+
+    fn try_from(raw: RawGraphFrame<T>) -> Result<GraphFrame<T>, &'static str> {
+        GraphFrame::from_vec(raw.cells, raw.adjacency)
+            .ok_or("graphs need a list of neighbors per cell, each of them a cell")
+    }
+}
+
+impl<T> From<GraphFrame<T>> for RawGraphFrame<T> {
+    fn from(frame: GraphFrame<T>) -> RawGraphFrame<T> {
+        let adjacency = Arc::try_unwrap(frame.adjacency).unwrap_or_else(|a| (*a).clone());
+        RawGraphFrame { cells: frame.cells, adjacency }
+    }
+}
+
+/// A cell of a graph frame together with its neighbors, what graph rules
+/// look at
+#[derive(Debug)]
+pub struct Node<'a, T>
+where T: 'a {
+    cells: &'a [T],
+    neighbors: &'a [usize],
+    index: usize,
+}
+
+// derived impls would needlessly require T: Clone
+impl<'a, T> Clone for Node<'a, T> {
+    fn clone(&self) -> Node<'a, T> {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Node<'a, T> {}
+
+impl<'a, T> Node<'a, T>
+where T: 'a {
+    /// The cell itself
+    pub fn get(&self) -> &'a T {
+        &self.cells[self.index]
+    }
+
+    /// The index of the cell in the graph
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The neighbors of the cell, in the order they were listed
+    pub fn neighbors(&self) -> impl Iterator<Item = &'a T> + 'a {
+        let cells = self.cells;
+        self.neighbors.iter().map(move |&n| &cells[n])
+    }
+
+    /// How many neighbors f holds for
+    pub fn count<F>(&self, f: F) -> usize
+    where F: Fn(&T) -> bool {
+        self.neighbors().filter(|c| f(c)).count()
+    }
+
+    /// The number of neighbors the cell has
+    pub fn degree(&self) -> usize {
+        self.neighbors.len()
+    }
+}
+
+impl<T> GraphFrame<T>
+where T: Default {
+    /// A graph with every cell empty where adjacency lists the neighbors of
+    /// each cell by index, or None if an index is out of range
+    pub fn new(adjacency: Vec<Vec<usize>>) -> Option<GraphFrame<T>> {
+        let cells = (0..adjacency.len()).map(|_| T::default()).collect();
+        GraphFrame::from_vec(cells, adjacency)
+    }
+
+    /// A ring of n cells each joined to the k closest cells on either side,
+    /// the starting point of small-world networks
+    pub fn ring(n: usize, k: usize) -> GraphFrame<T> {
+        assert!(2 * k < n, "a ring of {} cells can't have {} neighbors on each side", n, k);
+        let adjacency = (0..n)
+            .map(|i| (1..k + 1).flat_map(|d| vec![(i + n - d) % n, (i + d) % n]).collect())
+            .collect();
+        GraphFrame::new(adjacency).unwrap()
+    }
+}
+
+impl<T> GraphFrame<T> {
+    /// A graph holding cells where adjacency lists the neighbors of each
+    /// cell by index, or None unless there is one list per cell and every
+    /// index is in range
+    pub fn from_vec(cells: Vec<T>, adjacency: Vec<Vec<usize>>) -> Option<GraphFrame<T>> {
+        if cells.len() != adjacency.len()
+            || adjacency.iter().flatten().any(|&n| n >= cells.len()) {
+            return None;
+        }
+        Some(GraphFrame { cells, adjacency: Arc::new(adjacency) })
+    }
+
+    /// The grid of cells of frame with each joined to the 8 around it, like
+    /// the squares rules see, with edges joined according to topology
+    pub fn from_frame(frame: Frame<T>, topology: Topology) -> GraphFrame<T> {
+        let (w, h) = (frame.width(), frame.height());
+        let indices = Frame::from_vec(w, h, (0..w * h).collect()).unwrap();
+        let outside = usize::MAX;
+        let adjacency = (0..w * h)
+            .map(|i| {
+                let mut neighbors: Vec<usize> = indices.square_on(i % w, i / w, topology, &outside)
+                    .neighbors(1)
+                    .map(|(_, _, &n)| n)
+                    .filter(|&n| n != outside)
+                    .collect();
+                neighbors.sort_unstable();
+                neighbors.dedup();
+                neighbors.retain(|&n| n != i);
+                neighbors
+            })
+            .collect();
+        GraphFrame { cells: frame.into_vec(), adjacency: Arc::new(adjacency) }
+    }
+
+    /// The number of cells
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Whether there are no cells
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The cell at index
+    pub fn get(&self, index: usize) -> &T {
+        &self.cells[index]
+    }
+
+    /// A mutable reference to the cell at index
+    pub fn get_mut(&mut self, index: usize) -> &mut T {
+        &mut self.cells[index]
+    }
+
+    /// The cells in order of their index
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// The indices of the neighbors of the cell at index
+    pub fn adjacent(&self, index: usize) -> &[usize] {
+        &self.adjacency[index]
+    }
+
+    /// The cell at index together with its neighbors
+    pub fn node(&self, index: usize) -> Node<'_, T> {
+        Node { cells: &self.cells, neighbors: &self.adjacency[index], index }
+    }
+
+    /// Whether other is a frame of the very same graph, e.g. one stepped
+    /// from this one, rather than just an equal one
+    pub fn shares_graph<U>(&self, other: &GraphFrame<U>) -> bool {
+        Arc::ptr_eq(&self.adjacency, &other.adjacency)
+    }
+
+    /// The next frame, with step giving the next state of every cell from
+    /// its node. The graph itself doesn't change and is shared with the new
+    /// frame.
+    pub fn next_frame<F>(&self, step: F) -> GraphFrame<T>
+    where F: Fn(Node<T>) -> T {
+        GraphFrame {
+            cells: (0..self.cells.len()).map(|i| step(self.node(i))).collect(),
+            adjacency: Arc::clone(&self.adjacency),
+        }
+    }
+}
+
+/// Drives a graph frame like `Simulation` drives a frame, keeping track of
+/// the current frame, the rule used to advance it, and how many generations
+/// have passed, e.g.
+///
+/// ```
+/// use simulation::game_of_life::State;
+/// use simulation::graph::{GraphFrame, GraphSimulation, Node};
+///
+/// // a cell is alive when exactly one of its neighbors was
+/// let rule = |node: Node<State>| {
+///     if node.count(|&c| c == State::Alive) == 1 { State::Alive } else { State::Dead }
+/// };
+/// let mut ring = GraphFrame::ring(64, 2);
+/// *ring.get_mut(0) = State::Alive;
+/// let mut sim = GraphSimulation::new(ring, rule);
+/// sim.step_n(10);
+/// ```
+#[derive(Debug)]
+pub struct GraphSimulation<T, F> {
+    frame: GraphFrame<T>,
+    rule: F,
+    generation: u64,
+}
+
+impl<T, F> GraphSimulation<T, F>
+where F: Fn(Node<T>) -> T {
+    /// Start a simulation at generation 0 from frame
+    pub fn new(frame: GraphFrame<T>, rule: F) -> GraphSimulation<T, F> {
+        GraphSimulation { frame, rule, generation: 0 }
+    }
+
+    /// Advance the simulation by one generation
+    pub fn step(&mut self) {
+        self.frame = self.frame.next_frame(&self.rule);
+        self.generation += 1;
+    }
+
+    /// Advance the simulation by n generations
+    pub fn step_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+}
+
+impl<T, F> GraphSimulation<T, F> {
+    /// The current frame
+    pub fn frame(&self) -> &GraphFrame<T> {
+        &self.frame
+    }
+
+    /// A mutable reference to the current frame, for editing it between steps
+    pub fn frame_mut(&mut self) -> &mut GraphFrame<T> {
+        &mut self.frame
+    }
+
+    /// The number of generations that have passed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Topology};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::{GraphFrame, GraphSimulation, Node};
+
+    /// Life on any graph, born with 3 neighbors and surviving with 2 or 3
+    fn life(node: Node<State>) -> State {
+        match (*node.get(), node.count(|&c| c == Alive)) {
+            (_, 3) | (Alive, 2) => Alive,
+            _ => Dead,
+        }
+    }
+
+    #[test]
+    fn matches_grid() {
+        let mut frame = Frame::new(6, 6);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            *frame.get_mut(x, y) = Alive;
+        }
+        let graph = GraphFrame::from_frame(frame.clone(), Topology::torus());
+        let mut sim = GraphSimulation::new(graph.clone(), life);
+        for _ in 0..8 {
+            frame = frame.next_frame(game_of_life::rule);
+            sim.step();
+            assert_eq!(sim.frame().as_slice(), frame.as_slice());
+        }
+        assert_eq!(sim.generation(), 8);
+        assert!(sim.frame().shares_graph(&graph));
+
+        let plane = GraphFrame::<State>::from_frame(Frame::new(3, 3), Topology::plane());
+        assert_eq!(plane.adjacent(0), &[1, 3, 4]);
+        assert_eq!(plane.node(4).degree(), 8);
+    }
+
+    #[test]
+    fn ring() {
+        let mut ring = GraphFrame::<State>::ring(6, 1);
+        assert_eq!(ring.adjacent(0), &[5, 1]);
+        *ring.get_mut(0) = Alive;
+        let spread = ring.next_frame(|node| if node.count(|&c| c == Alive) > 0 { Alive } else { Dead });
+        assert_eq!(spread.as_slice().iter().filter(|&&c| c == Alive).count(), 2);
+
+        assert!(GraphFrame::<State>::new(vec![vec![1], vec![2]]).is_none());
+        assert!(GraphFrame::from_vec(vec![Dead], vec![]).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn graph_serde() {
+        use serde_json;
+
+        let mut ring = GraphFrame::<State>::ring(5, 1);
+        *ring.get_mut(2) = Alive;
+        let json = serde_json::to_string(&ring).unwrap();
+        assert_eq!(serde_json::from_str::<GraphFrame<State>>(&json).unwrap(), ring);
+
+        // neighbors that aren't cells are refused
+        let bad = json.replace("[4,1]", "[4,7]");
+        assert!(serde_json::from_str::<GraphFrame<State>>(&bad).is_err());
+    }
+}
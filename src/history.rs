@@ -0,0 +1,288 @@
+use super::{Frame, FramePool};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use std::mem;
+
+/// The cells that changed from one generation to the next, by their index in
+/// the frame's slice
+type Delta<T> = Vec<(usize, T)>;
+
+/// A whole frame followed by the generations after it as deltas
+#[derive(Clone, Debug, PartialEq)]
+struct Segment<T> {
+    generation: u64,
+    keyframe: Frame<T>,
+    deltas: Vec<Delta<T>>,
+}
+
+impl<T> Segment<T> {
+    /// The last generation the segment holds
+    fn last(&self) -> u64 {
+        self.generation + self.deltas.len() as u64
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.keyframe.memory_usage() + self.deltas.iter().map(delta_usage).sum::<usize>()
+    }
+}
+
+/// The bytes held by delta
+fn delta_usage<T>(delta: &Delta<T>) -> usize {
+    mem::size_of::<Delta<T>>() + delta.capacity() * mem::size_of::<(usize, T)>()
+}
+
+/// The frames of a run, kept as the cells that changed each generation with a
+/// whole frame every interval generations, so that long runs can be rewound
+/// without a copy of every frame. Frames are rebuilt from the keyframe before
+/// them, so getting one costs at most interval deltas.
+///
+/// ```
+/// use simulation::Frame;
+/// use simulation::game_of_life::{self, State};
+/// use simulation::history::History;
+///
+/// let mut frame = Frame::new(8, 8);
+/// *frame.get_mut(3, 3) = State::Alive;
+/// let mut history = History::new(&frame, 0, 16);
+/// for _ in 0..100 {
+///     frame = frame.next_frame(game_of_life::rule);
+///     history.push(&frame);
+/// }
+/// assert_eq!(history.frame_at(100), Some(frame));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct History<T> {
+    segments: Vec<Segment<T>>,
+    /// the newest frame, which the next one is compared to
+    last: Frame<T>,
+    interval: usize,
+    /// the bytes held by segments
+    held: usize,
+    /// the bytes segments may hold before the oldest are forgotten
+    budget: Option<usize>,
+}
+
+impl<T> History<T>
+where T: Clone + PartialEq {
+    /// Start a history at frame, which is at generation, keeping a whole
+    /// frame every interval generations
+    ///
+    /// # Panics
+    ///
+    /// if interval is 0
+    pub fn new(frame: &Frame<T>, generation: u64, interval: usize) -> History<T> {
+        assert!(interval > 0, "histories need a keyframe at least every generation");
+        History {
+            segments: vec![Segment { generation, keyframe: frame.clone(), deltas: vec![] }],
+            last: frame.clone(),
+            interval,
+            held: frame.memory_usage(),
+            budget: None,
+        }
+    }
+
+    /// Forget the oldest frames once the history holds more than bytes,
+    /// a whole keyframe's worth at a time, or never forget any with None.
+    /// Frames since the latest keyframe are always kept.
+    pub fn set_budget(&mut self, bytes: Option<usize>) {
+        self.budget = bytes;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return,
+        };
+        let mut forget = 0;
+        while self.memory_usage() > budget && self.segments.len() - forget > 1 {
+            self.held -= self.segments[forget].memory_usage();
+            forget += 1;
+        }
+        self.segments.drain(..forget);
+    }
+
+    /// Remember frame as the generation after the last one. A frame of a
+    /// different size than the last, e.g. after `Simulation::expand`, is kept
+    /// whole.
+    pub fn push(&mut self, frame: &Frame<T>) {
+        let generation = self.last_generation() + 1;
+        let resized = (frame.width(), frame.height()) != (self.last.width(), self.last.height());
+        let segment = self.segments.last_mut().expect("histories have a segment");
+        if resized || segment.deltas.len() + 1 >= self.interval {
+            self.held += frame.memory_usage();
+            self.segments.push(Segment { generation, keyframe: frame.clone(), deltas: vec![] });
+        } else {
+            let delta = frame.as_slice().iter().zip(self.last.as_slice())
+                .enumerate()
+                .filter(|&(_, (now, then))| now != then)
+                .map(|(i, (now, _))| (i, now.clone()))
+                .collect();
+            self.held += delta_usage(&delta);
+            segment.deltas.push(delta);
+        }
+        if resized {
+            self.last = frame.clone();
+        } else {
+            self.last.as_mut_slice().clone_from_slice(frame.as_slice());
+        }
+        self.evict();
+    }
+
+    /// The frame at generation, or None if it is before the start or after
+    /// the last frame pushed
+    pub fn frame_at(&self, generation: u64) -> Option<Frame<T>> {
+        self.frame_at_in(&mut FramePool::new(0), generation)
+    }
+
+    /// Like `frame_at`, but in a buffer from pool if it has one, for stepping
+    /// through many generations
+    pub fn frame_at_in(&self, pool: &mut FramePool<T>, generation: u64) -> Option<Frame<T>> {
+        if generation < self.first_generation() || generation > self.last_generation() {
+            return None;
+        }
+        let i = self.segments.partition_point(|s| s.generation <= generation) - 1;
+        let segment = &self.segments[i];
+        let mut frame = pool.take_clone(&segment.keyframe);
+        let cells = frame.as_mut_slice();
+        for delta in &segment.deltas[..(generation - segment.generation) as usize] {
+            for (j, cell) in delta {
+                cells[*j] = cell.clone();
+            }
+        }
+        Some(frame)
+    }
+
+    /// Forget the frames after generation, e.g. to branch off from a frame
+    /// that was rewound to. Does nothing if generation is the last one or
+    /// later.
+    ///
+    /// # Panics
+    ///
+    /// if generation is before the start
+    pub fn truncate(&mut self, generation: u64) {
+        assert!(generation >= self.first_generation(),
+                "generation {} is before the history starts", generation);
+        if generation >= self.last_generation() {
+            return;
+        }
+        self.last = self.frame_at(generation).expect("generation is in the history");
+        let i = self.segments.partition_point(|s| s.generation <= generation);
+        self.segments.truncate(i);
+        let segment = self.segments.last_mut().expect("histories have a segment");
+        segment.deltas.truncate((generation - segment.generation) as usize);
+        self.held = self.segments.iter().map(Segment::memory_usage).sum();
+    }
+}
+
+impl<T> History<T> {
+    /// The generation of the first frame
+    pub fn first_generation(&self) -> u64 {
+        self.segments[0].generation
+    }
+
+    /// The generation of the last frame pushed
+    pub fn last_generation(&self) -> u64 {
+        self.segments.last().expect("histories have a segment").last()
+    }
+
+    /// The newest frame
+    pub fn last(&self) -> &Frame<T> {
+        &self.last
+    }
+
+    /// The bytes held by the frames and deltas of the history
+    pub fn memory_usage(&self) -> usize {
+        self.held + self.last.memory_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, FramePool};
+    use super::super::game_of_life::{self, State};
+    use super::super::patterns::{Orientation, Pattern};
+    use super::History;
+
+    #[test]
+    fn frame_at() {
+        let mut frame = Frame::new(10, 10);
+        Pattern::glider().place(&mut frame, 2, 2, Orientation::Identity, State::Alive);
+        let mut frames = vec![frame.clone()];
+        let mut history = History::new(&frame, 5, 4);
+        for _ in 0..20 {
+            frame = frame.next_frame(game_of_life::rule);
+            history.push(&frame);
+            frames.push(frame.clone());
+        }
+        assert_eq!((history.first_generation(), history.last_generation()), (5, 25));
+        for (i, f) in frames.iter().enumerate() {
+            assert_eq!(history.frame_at(5 + i as u64).as_ref(), Some(f));
+        }
+        let mut pool = FramePool::new(1);
+        for (i, f) in frames.iter().enumerate() {
+            let rebuilt = history.frame_at_in(&mut pool, 5 + i as u64).unwrap();
+            assert_eq!(&rebuilt, f);
+            pool.recycle(rebuilt);
+        }
+        assert_eq!(history.frame_at(4), None);
+        assert_eq!(history.frame_at(26), None);
+
+        // frames that change size are kept whole
+        let bigger = Frame::new(12, 12);
+        history.push(&bigger);
+        assert_eq!(history.frame_at(26), Some(bigger));
+        assert_eq!(history.frame_at(25).as_ref(), Some(&frame));
+    }
+
+    #[test]
+    fn truncate() {
+        let mut frame = Frame::new(8, 8);
+        Pattern::blinker().place(&mut frame, 3, 3, Orientation::Identity, State::Alive);
+        let mut history = History::new(&frame, 0, 3);
+        let mut frames = vec![frame.clone()];
+        for _ in 0..10 {
+            frame = frame.next_frame(game_of_life::rule);
+            history.push(&frame);
+            frames.push(frame.clone());
+        }
+        history.truncate(4);
+        assert_eq!(history.last_generation(), 4);
+        assert_eq!(history.last(), &frames[4]);
+        assert_eq!(history.frame_at(5), None);
+
+        let mut edited = frames[4].clone();
+        *edited.get_mut(0, 0) = State::Alive;
+        history.push(&edited);
+        assert_eq!(history.frame_at(5), Some(edited));
+        for (i, f) in frames[..5].iter().enumerate() {
+            assert_eq!(history.frame_at(i as u64).as_ref(), Some(f));
+        }
+    }
+
+    #[test]
+    fn budget() {
+        let mut frame = Frame::new(16, 16);
+        Pattern::glider().place(&mut frame, 2, 2, Orientation::Identity, State::Alive);
+        let mut history = History::new(&frame, 0, 4);
+        for _ in 0..40 {
+            frame = frame.next_frame(game_of_life::rule);
+            history.push(&frame);
+        }
+        let unbounded = history.memory_usage();
+        // a keyframe, the newest frame and a few small deltas
+        history.set_budget(Some(3 * 256));
+        assert!(history.memory_usage() < unbounded);
+        assert_eq!((history.first_generation(), history.last_generation()), (40, 40));
+        assert_eq!(history.frame_at(39), None);
+        assert_eq!(history.frame_at(40).as_ref(), Some(&frame));
+
+        for _ in 0..3 {
+            frame = frame.next_frame(game_of_life::rule);
+            history.push(&frame);
+        }
+        assert_eq!(history.first_generation(), 40);
+        assert_eq!(history.frame_at(43), Some(frame));
+    }
+}
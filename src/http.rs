@@ -0,0 +1,423 @@
+use super::{Simulation, Square};
+use super::render::ColorMap;
+use super::render::{png, raster};
+
+use axum;
+use axum::Router;
+use axum::body::{Body, Bytes};
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::StatusCode;
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use image;
+use tokio;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant, Sleep};
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::WatchStream;
+
+use std::convert::Infallible;
+use std::future::{self, Future, IntoFuture};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The longest control body read, which is plenty for any command
+pub const MAX_BODY: usize = 64;
+
+/// The boundary between the images of the live stream
+const BOUNDARY: &str = "frame";
+
+/// The page served at `/`, which shows the stream with buttons for the
+/// controls
+const PAGE: &str = "<!doctype html>
+<title>simulation</title>
+<style>img { image-rendering: pixelated; max-width: 100%; }</style>
+<p>
+<button onclick=\"control('play')\">play</button>
+<button onclick=\"control('pause')\">pause</button>
+<button onclick=\"control('step')\">step</button>
+</p>
+<img src=\"/stream.mjpg\">
+<script>
+function control(command) { fetch('/control', { method: 'POST', body: command }); }
+</script>
+";
+
+/// The simulation being viewed and how it is drawn and played
+struct Playing<T, F, C> {
+    sim: Simulation<T, F>,
+    colormap: C,
+    scale: usize,
+    paused: bool,
+    delay: Duration,
+}
+
+/// What the handlers and the task stepping the simulation share. Each new
+/// generation is sent on parts while anyone is streaming, and slow streams
+/// skip to the latest one rather than falling behind.
+struct Shared<T, F, C> {
+    playing: Mutex<Playing<T, F, C>>,
+    parts: watch::Sender<Bytes>,
+}
+
+/// Serves a simulation to web browsers, so runs on remote machines can be
+/// watched without installing anything.
+///
+/// `GET /` is a page with the live view and buttons for the controls,
+/// `GET /frame.png` the current frame and `GET /stream.mjpg` an MJPEG stream
+/// with an image for every generation. `POST /control` with a body of `play`,
+/// `pause` or `step`, which advances a single generation, controls the
+/// simulation. Control bodies longer than `MAX_BODY` are refused.
+///
+/// Clones share the simulation, so one can be kept to control it while
+/// another serves.
+pub struct Viewer<T, F, C> {
+    shared: Arc<Shared<T, F, C>>,
+}
+
+impl<T, F, C> Clone for Viewer<T, F, C> {
+    fn clone(&self) -> Viewer<T, F, C> {
+        Viewer { shared: self.shared.clone() }
+    }
+}
+
+impl<T, F, C> Viewer<T, F, C>
+where T: Send + 'static,
+      F: Fn(Square<T>) -> T + Send + 'static,
+      C: ColorMap<T> + Send + 'static {
+    /// View sim drawn with colormap. The simulation starts out playing, a
+    /// generation every 50ms, with a pixel per cell.
+    pub fn new(sim: Simulation<T, F>, colormap: C) -> Viewer<T, F, C> {
+        let playing = Playing {
+            sim, colormap,
+            scale: 1,
+            paused: false,
+            delay: Duration::from_millis(50),
+        };
+        let (parts, _) = watch::channel(Bytes::new());
+        Viewer { shared: Arc::new(Shared { playing: Mutex::new(playing), parts }) }
+    }
+
+    /// The simulation and its settings. Something panicking while holding
+    /// them only breaks the requests that need the simulation, so the
+    /// settings can still be read and changed.
+    fn playing(&self) -> MutexGuard<'_, Playing<T, F, C>> {
+        self.shared.playing.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.playing().sim.generation()
+    }
+
+    /// The number of live streams being sent
+    pub fn streams(&self) -> usize {
+        self.shared.parts.receiver_count()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.playing().paused
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.playing().paused = paused;
+    }
+
+    /// Set the time between generations while playing
+    pub fn set_delay(&self, delay: Duration) {
+        self.playing().delay = delay;
+    }
+
+    /// Set the side length of the block of pixels drawn for each cell
+    pub fn set_scale(&self, scale: usize) {
+        assert!(scale > 0);
+        self.playing().scale = scale;
+    }
+
+    /// The routes of the viewer, for serving it as part of a larger app.
+    /// Nothing steps the simulation while it plays unless `serve` is
+    /// running too.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", get(|| future::ready(Html(PAGE))))
+            .route("/frame.png", get(|State(shared): State<Arc<Shared<T, F, C>>>| {
+                future::ready(frame(&shared))
+            }))
+            .route("/stream.mjpg", get(|State(shared): State<Arc<Shared<T, F, C>>>| {
+                future::ready(stream(&shared))
+            }))
+            .route("/control", post(|State(shared): State<Arc<Shared<T, F, C>>>,
+                                     command: String| {
+                future::ready(control(&shared, &command))
+            }))
+            .layer(DefaultBodyLimit::max(MAX_BODY))
+            .with_state(self.shared.clone())
+    }
+
+    /// Serve browsers on listener, stepping the simulation while it plays,
+    /// until the listener fails or the future is dropped
+    ///
+    /// # Panics
+    ///
+    /// if this isn't called from within a tokio runtime
+    pub fn serve(&self, listener: TcpListener) -> Serve {
+        let player = Player {
+            shared: self.shared.clone(),
+            sleep: Box::pin(time::sleep(self.playing().delay)),
+        };
+        Serve {
+            served: Box::pin(axum::serve(listener, self.router()).into_future()),
+            player: tokio::spawn(player),
+        }
+    }
+}
+
+/// The future returned by `Viewer::serve`
+#[must_use = "futures do nothing unless polled"]
+pub struct Serve {
+    served: Pin<Box<dyn Future<Output = io::Result<()>> + Send>>,
+    player: JoinHandle<()>,
+}
+
+impl Future for Serve {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.served.as_mut().poll(cx)
+    }
+}
+
+impl Drop for Serve {
+    fn drop(&mut self) {
+        self.player.abort();
+    }
+}
+
+/// A task stepping the simulation every delay while it isn't paused, until
+/// something panics while holding it
+struct Player<T, F, C> {
+    shared: Arc<Shared<T, F, C>>,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl<T, F, C> Future for Player<T, F, C>
+where F: Fn(Square<T>) -> T, C: ColorMap<T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        if this.sleep.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let mut playing = match this.shared.playing.lock() {
+            Ok(playing) => playing,
+            Err(_) => return Poll::Ready(()),
+        };
+        if !playing.paused && this.shared.step(&mut playing).is_err() {
+            return Poll::Ready(());
+        }
+        this.sleep.as_mut().reset(Instant::now() + playing.delay);
+        // let other tasks run before waiting for the next generation
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+impl<T, F, C> Shared<T, F, C>
+where F: Fn(Square<T>) -> T, C: ColorMap<T> {
+    /// Lock the simulation, failing the request rather than panicking if
+    /// something panicked while holding it
+    fn lock(&self) -> Result<MutexGuard<'_, Playing<T, F, C>>, StatusCode> {
+        self.playing.lock().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Step playing, sending the new generation to the streams if there are
+    /// any
+    fn step(&self, playing: &mut Playing<T, F, C>) -> io::Result<()> {
+        playing.sim.step();
+        if self.parts.receiver_count() > 0 {
+            self.parts.send_replace(playing.part()?);
+        }
+        Ok(())
+    }
+}
+
+impl<T, F, C> Playing<T, F, C>
+where C: ColorMap<T> {
+    /// The current frame as a part of the live stream
+    fn part(&self) -> io::Result<Bytes> {
+        let img = raster::to_image(self.sim.frame(), &self.colormap, self.scale);
+        let (w, h) = img.dimensions();
+        let mut jpeg = vec![];
+        image::jpeg::JPEGEncoder::new(&mut jpeg).encode(&img, w, h, image::RGB(8))?;
+        let mut part = format!("--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+                               BOUNDARY, jpeg.len()).into_bytes();
+        part.extend_from_slice(&jpeg);
+        part.extend_from_slice(b"\r\n");
+        Ok(part.into())
+    }
+}
+
+fn internal_error(_: io::Error) -> StatusCode {
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+fn frame<T, F, C>(shared: &Shared<T, F, C>) -> Result<Response, StatusCode>
+where F: Fn(Square<T>) -> T, C: ColorMap<T> {
+    let playing = shared.lock()?;
+    let mut buf = vec![];
+    png::write(&mut buf, playing.sim.frame(), &playing.colormap, playing.scale)
+        .map_err(internal_error)?;
+    Ok(([(CONTENT_TYPE, "image/png"), (CACHE_CONTROL, "no-cache")], buf).into_response())
+}
+
+fn stream<T, F, C>(shared: &Shared<T, F, C>) -> Result<Response, StatusCode>
+where F: Fn(Square<T>) -> T, C: ColorMap<T> {
+    // parts are only made while someone is streaming, so the stream starts
+    // from the current frame rather than whichever was sent last
+    let part = shared.lock()?.part().map_err(internal_error)?;
+    shared.parts.send_replace(part);
+    let parts = WatchStream::new(shared.parts.subscribe()).map(Ok::<_, Infallible>);
+    let content_type = format!("multipart/x-mixed-replace; boundary={}", BOUNDARY);
+    Ok(([(CONTENT_TYPE, content_type), (CACHE_CONTROL, "no-cache".to_string())],
+        Body::from_stream(parts)).into_response())
+}
+
+fn control<T, F, C>(shared: &Shared<T, F, C>, command: &str) -> Result<Response, StatusCode>
+where F: Fn(Square<T>) -> T, C: ColorMap<T> {
+    let mut playing = shared.lock()?;
+    match command.trim() {
+        "play" => playing.paused = false,
+        "pause" => playing.paused = true,
+        "step" => shared.step(&mut playing).map_err(internal_error)?,
+        command => {
+            let error = format!("unknown command {}\n", command);
+            return Ok((StatusCode::BAD_REQUEST, error).into_response());
+        }
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Simulation, Square};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::{self, Alive};
+    use super::super::render::ColorMap;
+    use super::{Viewer, MAX_BODY};
+
+    use image;
+    use tokio::net::TcpListener;
+    use tokio::runtime::Builder;
+
+    use std::io::{Read, Write};
+    use std::net::{self, SocketAddr, TcpStream};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Serve viewer from a runtime on another thread, returning its address
+    fn serve<T, F, C>(viewer: &Viewer<T, F, C>) -> SocketAddr
+    where T: Send + 'static,
+          F: Fn(Square<T>) -> T + Send + 'static,
+          C: ColorMap<T> + Send + 'static {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let viewer = viewer.clone();
+        thread::spawn(move || {
+            let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+            let _context = runtime.enter();
+            let listener = TcpListener::from_std(listener).unwrap();
+            runtime.block_on(viewer.serve(listener)).unwrap();
+        });
+        addr
+    }
+
+    fn request(addr: SocketAddr, head: &str, body: &str) -> Vec<u8> {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        // in one write, so the whole request has arrived by the time the
+        // server reads its head
+        let request = format!("{}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                              head, body.len(), body);
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = vec![];
+        stream.read_to_end(&mut response).unwrap();
+        response
+    }
+
+    fn split(response: &[u8]) -> (String, &[u8]) {
+        let end = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        (String::from_utf8_lossy(&response[..end]).to_lowercase(), &response[end + 4..])
+    }
+
+    fn colors(c: &State) -> [u8; 3] {
+        if *c == Alive { [255, 255, 255] } else { [0, 0, 0] }
+    }
+
+    #[test]
+    fn view() {
+        let mut frame = Frame::new(5, 5);
+        for y in 1..4 {
+            *frame.get_mut(2, y) = Alive;
+        }
+        let viewer = Viewer::new(Simulation::new(frame, game_of_life::rule), colors);
+        viewer.set_paused(true);
+        viewer.set_scale(2);
+        let addr = serve(&viewer);
+
+        let (status, body) = split(&request(addr, "GET /frame.png HTTP/1.1", ""));
+        assert!(status.starts_with("http/1.1 200 ok"));
+        assert!(status.contains("content-type: image/png"));
+        let img = image::load_from_memory_with_format(body, image::PNG).unwrap().to_rgb();
+        assert_eq!(img.dimensions(), (10, 10));
+        assert_eq!(img.get_pixel(4, 2).data, [255, 255, 255]);
+
+        let step = request(addr, "POST /control HTTP/1.1", "step");
+        assert!(split(&step).0.starts_with("http/1.1 204"));
+        assert_eq!(viewer.generation(), 1);
+        let (status, body) = split(&request(addr, "POST /control HTTP/1.1", "dance"));
+        assert!(status.starts_with("http/1.1 400"));
+        assert_eq!(body, b"unknown command dance\n");
+        assert!(split(&request(addr, "GET /nothing HTTP/1.1", "")).0.starts_with("http/1.1 404"));
+        assert!(split(&request(addr, "GET /control HTTP/1.1", "")).0.starts_with("http/1.1 405"));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET /stream.mjpg HTTP/1.1\r\n\r\n").unwrap();
+        let part = b"--frame\r\nContent-Type: image/jpeg\r\n";
+        let mut received = vec![];
+        let mut buf = [0; 256];
+        while !received.windows(part.len()).any(|w| w == part) {
+            let n = stream.read(&mut buf).unwrap();
+            assert!(n > 0);
+            received.extend_from_slice(&buf[..n]);
+        }
+        assert!(split(&received).0.contains("multipart/x-mixed-replace; boundary=frame"));
+        assert_eq!(viewer.streams(), 1);
+    }
+
+    #[test]
+    fn playing() {
+        let viewer = Viewer::new(Simulation::new(Frame::new(4, 4), game_of_life::rule), colors);
+        viewer.set_delay(Duration::from_millis(1));
+        let addr = serve(&viewer);
+
+        // a client that never finishes its request doesn't hold up stepping
+        let mut idle = TcpStream::connect(addr).unwrap();
+        write!(idle, "GET / HTTP/1.1\r\n").unwrap();
+        let start = Instant::now();
+        while viewer.generation() < 10 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // and control bodies that are too long are refused
+        let long = "a".repeat(MAX_BODY + 1);
+        let refused = request(addr, "POST /control HTTP/1.1", &long);
+        assert!(split(&refused).0.starts_with("http/1.1 413"));
+    }
+}
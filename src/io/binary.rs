@@ -0,0 +1,177 @@
+use super::super::{CellState, Frame};
+use super::super::frame::cells;
+use super::{Error, Result};
+
+use flate2;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"SIMF";
+const VERSION: u8 = 1;
+
+/// The most cells a binary frame may have to be read, so that a corrupt
+/// header can't ask for more memory than the machine has
+pub const MAX_CELLS: usize = 1 << 30;
+
+/// How the cells of a binary frame are stored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// raw cells, fastest to read and write
+    #[default]
+    None,
+    /// zlib compressed cells, much smaller for sparse or smooth frames
+    Deflate,
+}
+
+/// A compact binary format for fast checkpoints of large frames: the magic
+/// bytes `SIMF`, a version byte, a compression byte, the number of bytes per
+/// cell, the width and height as little endian u32s and then the cells row by
+/// row, compressed if asked for. Cells are stored exactly, as the low `BYTES`
+/// bytes of their `CellState` bits in little endian order.
+impl<T: CellState> Frame<T> {
+    pub fn write_binary<W: Write>(&self, mut writer: W, compression: Compression) -> Result<()> {
+        let too_big = || Error::Format("frame is too large for the binary format".to_string());
+        let width = u32::try_from(self.width()).map_err(|_| too_big())?;
+        let height = u32::try_from(self.height()).map_err(|_| too_big())?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, compression as u8, T::BYTES as u8])?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+
+        let mut cells = Vec::with_capacity(self.width() * self.height() * T::BYTES);
+        for (_, _, cell) in self.enumerate_squares() {
+            cells.extend_from_slice(&cell.to_bits().to_le_bytes()[..T::BYTES]);
+        }
+        match compression {
+            Compression::None => writer.write_all(&cells)?,
+            Compression::Deflate => {
+                let mut encoder = ZlibEncoder::new(writer, flate2::Compression::Default);
+                encoder.write_all(&cells)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_binary<R: Read>(mut reader: R) -> Result<Frame<T>>
+    where T: Default + Clone {
+        let mut header = [0; 15];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(Error::Format("not a binary frame".to_string()));
+        }
+        if header[4] != VERSION {
+            return Err(Error::Format(format!("unsupported version {}", header[4])));
+        }
+        if header[6] as usize != T::BYTES {
+            return Err(Error::Format(format!("expected {} bytes per cell but found {}",
+                                             T::BYTES, header[6])));
+        }
+        let mut size = [0; 4];
+        size.copy_from_slice(&header[7..11]);
+        let width = u32::from_le_bytes(size) as usize;
+        size.copy_from_slice(&header[11..15]);
+        let height = u32::from_le_bytes(size) as usize;
+        let len = cells::<T>(width, height).ok()
+            .filter(|&n| n <= MAX_CELLS)
+            .and_then(|n| n.checked_mul(T::BYTES))
+            .ok_or_else(|| Error::Format(format!("a {}x{} frame is too large to read",
+                                                 width, height)))?;
+
+        // read no more than the input holds rather than allocating the whole
+        // frame up front, since the header may not match the input
+        let mut cells = vec![];
+        match header[5] {
+            0 => reader.take(len as u64).read_to_end(&mut cells)?,
+            1 => ZlibDecoder::new(reader).take(len as u64).read_to_end(&mut cells)?,
+            c => return Err(Error::Format(format!("unknown compression {}", c))),
+        };
+        if cells.len() != len {
+            return Err(Error::Format(format!("expected {} bytes of cells but found {}",
+                                             len, cells.len())));
+        }
+        let mut frame = Frame::new(width, height);
+        for (i, bytes) in cells.chunks(T::BYTES).enumerate() {
+            let mut bits = [0; 8];
+            bits[..T::BYTES].copy_from_slice(bytes);
+            *frame.get_mut(i % width, i / width) = T::from_bits(u64::from_le_bytes(bits))
+                .ok_or_else(|| Error::Format("invalid cell".to_string()))?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::game_of_life::State;
+    use super::super::Error;
+    use super::Compression;
+
+    fn smooth() -> Frame<f32> {
+        let mut frame = Frame::new(64, 48);
+        for y in 0..48 {
+            for x in 0..64 {
+                *frame.get_mut(x, y) = if x < 32 { 0.0 } else { (y as f32) / 48.0 };
+            }
+        }
+        frame
+    }
+
+    #[test]
+    fn round_trip() {
+        for &compression in &[Compression::None, Compression::Deflate] {
+            let mut out = vec![];
+            smooth().write_binary(&mut out, compression).unwrap();
+            assert_eq!(Frame::<f32>::read_binary(&out[..]).unwrap(), smooth());
+        }
+
+        let mut frame = Frame::new(3, 5);
+        *frame.get_mut(2, 4) = State::Alive;
+        let mut out = vec![];
+        frame.write_binary(&mut out, Compression::None).unwrap();
+        assert_eq!(out.len(), 15 + 15);
+        assert_eq!(Frame::<State>::read_binary(&out[..]).unwrap(), frame);
+    }
+
+    #[test]
+    fn compresses() {
+        let mut raw = vec![];
+        smooth().write_binary(&mut raw, Compression::None).unwrap();
+        let mut deflated = vec![];
+        smooth().write_binary(&mut deflated, Compression::Deflate).unwrap();
+        assert_eq!(raw.len(), 15 + 64 * 48 * 4);
+        assert!(deflated.len() * 10 < raw.len());
+    }
+
+    #[test]
+    fn errors() {
+        let mut out = vec![];
+        smooth().write_binary(&mut out, Compression::Deflate).unwrap();
+        // wrong cell type
+        assert!(Frame::<u8>::read_binary(&out[..]).is_err());
+        // truncated
+        assert!(Frame::<f32>::read_binary(&out[..out.len() / 2]).is_err());
+        // bad magic
+        out[0] = b'X';
+        assert!(Frame::<f32>::read_binary(&out[..]).is_err());
+        // invalid states
+        let mut out = vec![];
+        Frame::<u8>::new(2, 2).map(|_| 7u8).write_binary(&mut out, Compression::None).unwrap();
+        assert!(Frame::<State>::read_binary(&out[..]).is_err());
+        // sizes that overflow or are larger than the input
+        for &(width, height) in &[(u32::MAX, u32::MAX), (1 << 15, 1 << 15)] {
+            let mut out = b"SIMF\x01\x00\x01".to_vec();
+            out.extend_from_slice(&width.to_le_bytes());
+            out.extend_from_slice(&height.to_le_bytes());
+            out.extend_from_slice(&[0; 16]);
+            match Frame::<u8>::read_binary(&out[..]) {
+                Err(Error::Format(_)) => (),
+                other => panic!("expected a format error, not {:?}", other),
+            }
+        }
+    }
+}
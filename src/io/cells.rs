@@ -0,0 +1,118 @@
+use super::super::{CellState, Frame};
+use super::{Error, Result};
+
+use std::io::{BufRead, Write};
+
+/// A two state pattern read from a plaintext `.cells` file
+#[derive(Debug, PartialEq)]
+pub struct Cells {
+    /// the cells of the pattern, 1 is alive
+    pub pattern: Frame<u8>,
+    /// the name from a `!Name:` line, if there was one
+    pub name: Option<String>,
+    /// all other comment lines
+    pub comments: Vec<String>,
+}
+
+impl Cells {
+    /// Copy the pattern onto frame with its top left corner at (x, y)
+    pub fn place<T: CellState>(&self, frame: &mut Frame<T>, x: usize, y: usize) {
+        super::place(&self.pattern, frame, x, y)
+    }
+}
+
+/// Read a pattern in plaintext format. Rows may have different lengths, the
+/// pattern is as wide as the longest one.
+pub fn read<R: BufRead>(reader: R) -> Result<Cells> {
+    let mut name = None;
+    let mut comments = vec![];
+    let mut rows = vec![];
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(n) => name = Some(n.trim().to_string()),
+                None => comments.push(comment.trim().to_string()),
+            }
+            continue;
+        }
+        let row = line.chars()
+            .map(|c| match c {
+                '.' => Ok(0),
+                'O' | '*' => Ok(1),
+                _ => Err(Error::parse(n + 1, format!("unexpected `{}`", c))),
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        rows.push(row);
+    }
+
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut pattern = Frame::new(width, rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &s) in row.iter().enumerate() {
+            *pattern.get_mut(x, y) = s;
+        }
+    }
+    Ok(Cells { pattern, name, comments })
+}
+
+/// Write frame in plaintext format with an optional name. Every row is written
+/// in full so that the size of the frame is preserved. The format only has
+/// two states, so every cell that isn't dead is written as alive.
+pub fn write<W, T>(mut writer: W, frame: &Frame<T>, name: Option<&str>) -> Result<()>
+where W: Write, T: CellState {
+    if let Some(name) = name {
+        writeln!(writer, "!Name: {}", name)?;
+    }
+    for y in 0..frame.height() {
+        let row = (0..frame.width())
+            .map(|x| if frame.get(x, y).index() != 0 { 'O' } else { '.' })
+            .collect::<String>();
+        writeln!(writer, "{}", row)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::game_of_life::State;
+    use super::super::super::game_of_life::State::Alive;
+    use super::{read, write};
+
+    #[test]
+    fn read_glider() {
+        let text = "!Name: Glider\n!The smallest spaceship\n.O\n..O\nOOO\n";
+        let cells = read(text.as_bytes()).unwrap();
+        assert_eq!(cells.name, Some("Glider".to_string()));
+        assert_eq!(cells.comments, vec!["The smallest spaceship"]);
+        assert_eq!(cells.pattern.width(), 3);
+        assert_eq!(cells.pattern.height(), 3);
+        assert_eq!(*cells.pattern.get(1, 0), 1);
+        assert_eq!(*cells.pattern.get(0, 1), 0);
+        assert_eq!(*cells.pattern.get(2, 1), 1);
+        assert_eq!(*cells.pattern.get(2, 2), 1);
+    }
+
+    #[test]
+    fn bad_character() {
+        assert!(read("..O\n.X.\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut frame = Frame::<State>::new(4, 2);
+        *frame.get_mut(0, 0) = Alive;
+        *frame.get_mut(3, 1) = Alive;
+
+        let mut out = vec![];
+        write(&mut out, &frame, Some("Pair")).unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(), "!Name: Pair\nO...\n...O\n");
+
+        let cells = read(&out[..]).unwrap();
+        let mut copy = Frame::new(4, 2);
+        cells.place(&mut copy, 0, 0);
+        assert_eq!(copy, frame);
+    }
+}
@@ -0,0 +1,66 @@
+use super::super::{CellState, Frame};
+use super::{Error, Result};
+
+use std::io::{BufRead, Write};
+
+/// Read a frame with one row per line and one character per cell, as given
+/// by `CellState::from_char`. Every row has to be the same length.
+pub fn read<R, T>(reader: R) -> Result<Frame<T>>
+where R: BufRead, T: CellState + Default {
+    let mut rows: Vec<Vec<T>> = vec![];
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let row = line.chars()
+            .map(|c| {
+                T::from_char(c).ok_or_else(|| Error::parse(n + 1, format!("unexpected `{}`", c)))
+            })
+            .collect::<Result<Vec<T>>>()?;
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(Error::parse(n + 1, format!("expected {} cells but found {}",
+                                                       first.len(), row.len())));
+            }
+        }
+        rows.push(row);
+    }
+
+    let width = rows.first().map_or(0, |r| r.len());
+    let height = rows.len();
+    Ok(Frame::from_vec(width, height, rows.into_iter().flatten().collect()).unwrap())
+}
+
+/// Write frame with one row per line and one character per cell, as given by
+/// `CellState::to_char`
+pub fn write<W, T>(mut writer: W, frame: &Frame<T>) -> Result<()>
+where W: Write, T: CellState {
+    for y in 0..frame.height() {
+        let row = (0..frame.width()).map(|x| frame.get(x, y).to_char()).collect::<String>();
+        writeln!(writer, "{}", row)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::rainbow_life::State;
+    use super::{read, write};
+
+    #[test]
+    fn round_trip() {
+        let mut frame = Frame::new(3, 2);
+        *frame.get_mut(0, 0) = State::Red;
+        *frame.get_mut(2, 1) = State::Blue;
+
+        let mut out = vec![];
+        write(&mut out, &frame).unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(), "R..\n..B\n");
+        assert_eq!(read::<_, State>(&out[..]).unwrap(), frame);
+        assert!(read::<_, State>("R.\n.\n".as_bytes()).is_err());
+        assert!(read::<_, State>("RX\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use super::super::Frame;
+use super::{Error, Result};
+
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+/// Read a frame of numbers (or anything else that can be parsed) where each
+/// line is a row and cells are separated by delimiter, e.g. `,` for csv or
+/// `\t` for tsv. Every row has to have the same number of cells.
+pub fn read<R, T>(reader: R, delimiter: char) -> Result<Frame<T>>
+where R: BufRead, T: FromStr + Default + Clone {
+    let mut rows: Vec<Vec<T>> = vec![];
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = line.split(delimiter)
+            .map(|cell| {
+                cell.trim().parse::<T>()
+                    .map_err(|_| Error::parse(n + 1, format!("invalid cell `{}`", cell.trim())))
+            })
+            .collect::<Result<Vec<T>>>()?;
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(Error::parse(n + 1, format!("expected {} cells but found {}",
+                                                       first.len(), row.len())));
+            }
+        }
+        rows.push(row);
+    }
+
+    let width = rows.first().map_or(0, |r| r.len());
+    let mut frame = Frame::new(width, rows.len());
+    for (y, row) in rows.into_iter().enumerate() {
+        for (x, cell) in row.into_iter().enumerate() {
+            *frame.get_mut(x, y) = cell;
+        }
+    }
+    Ok(frame)
+}
+
+/// Write frame with one row per line and cells separated by delimiter
+pub fn write<W, T>(mut writer: W, frame: &Frame<T>, delimiter: char) -> Result<()>
+where W: Write, T: Display {
+    for y in 0..frame.height() {
+        for x in 0..frame.width() {
+            if x > 0 {
+                write!(writer, "{}", delimiter)?;
+            }
+            write!(writer, "{}", frame.get(x, y))?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::{read, write};
+
+    #[test]
+    fn read_csv() {
+        let frame = read::<_, u32>("1,2,3\n4, 5 ,6\n\n".as_bytes(), ',').unwrap();
+        assert_eq!(frame.width(), 3);
+        assert_eq!(frame.height(), 2);
+        assert_eq!(*frame.get(0, 0), 1);
+        assert_eq!(*frame.get(1, 1), 5);
+        assert_eq!(*frame.get(2, 1), 6);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(read::<_, u32>("1,2\n3\n".as_bytes(), ',').is_err());
+        assert!(read::<_, u32>("1,x\n".as_bytes(), ',').is_err());
+    }
+
+    #[test]
+    fn tsv_round_trip() {
+        let mut frame = Frame::<f32>::new(2, 3);
+        *frame.get_mut(0, 0) = 0.25;
+        *frame.get_mut(1, 2) = -1.5;
+
+        let mut out = vec![];
+        write(&mut out, &frame, '\t').unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(), "0.25\t0\n0\t0\n0\t-1.5\n");
+        assert_eq!(read::<_, f32>(&out[..], '\t').unwrap(), frame);
+    }
+}
@@ -0,0 +1,100 @@
+use super::super::Frame;
+use super::super::render::Color;
+use super::super::render::raster;
+use super::Result;
+
+use image;
+use image::DynamicImage;
+
+use std::path::Path;
+
+/// Convert every pixel of an image into a cell with mapper
+fn to_frame<T, F>(img: DynamicImage, mapper: F) -> Frame<T>
+where T: Default + Clone, F: Fn(Color) -> T {
+    raster::from_image(&img.to_rgb(), mapper)
+}
+
+/// Load the image at path as a frame with one cell per pixel, converting
+/// pixels into cells with mapper. The format is guessed from the extension.
+pub fn load<P, T, F>(path: P, mapper: F) -> Result<Frame<T>>
+where P: AsRef<Path>, T: Default + Clone, F: Fn(Color) -> T {
+    Ok(to_frame(image::open(path)?, mapper))
+}
+
+/// Like `load`, but for an image that is already in memory. The format is
+/// guessed from the contents.
+pub fn from_memory<T, F>(buf: &[u8], mapper: F) -> Result<Frame<T>>
+where T: Default + Clone, F: Fn(Color) -> T {
+    Ok(to_frame(image::load_from_memory(buf)?, mapper))
+}
+
+/// The perceived brightness of a color, from 0 to 1. Comparing this against a
+/// threshold is the usual way to map an image to a two state frame.
+pub fn brightness(color: Color) -> f32 {
+    let [r, g, b] = color;
+    (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.
+}
+
+/// A mapper that picks the state whose color is closest to the pixel, for
+/// loading multi-state frames. `raster::inverse` builds the palette from a
+/// `ColorMap`.
+pub fn nearest<T: Clone>(palette: &[(Color, T)]) -> impl Fn(Color) -> T + '_ {
+    assert!(!palette.is_empty());
+    move |color| {
+        let distance = |c: &Color| {
+            c.iter().zip(&color)
+                .map(|(&a, &b)| (a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        };
+        palette.iter()
+            .min_by_key(|&(c, _)| distance(c))
+            .map(|(_, s)| s.clone())
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::render::png;
+    use super::super::super::rainbow_life::State;
+    use super::{brightness, from_memory, nearest};
+
+    #[test]
+    fn threshold() {
+        let mut frame = Frame::<u8>::new(3, 2);
+        *frame.get_mut(0, 0) = 255;
+        *frame.get_mut(2, 1) = 200;
+        *frame.get_mut(1, 1) = 50;
+
+        let mut buf = vec![];
+        png::write(&mut buf, &frame, &|&v: &u8| [v, v, v], 1).unwrap();
+
+        let loaded = from_memory(&buf, |c| brightness(c) > 0.5).unwrap();
+        assert_eq!(loaded.width(), 3);
+        assert_eq!(loaded.height(), 2);
+        assert!(*loaded.get(0, 0));
+        assert!(*loaded.get(2, 1));
+        assert!(!*loaded.get(1, 1));
+        assert!(!*loaded.get(1, 0));
+    }
+
+    #[test]
+    fn palette() {
+        let palette = [
+            ([0, 0, 0], State::Dead),
+            ([255, 0, 0], State::Red),
+            ([0, 255, 0], State::Green),
+            ([0, 0, 255], State::Blue),
+        ];
+        let mapper = nearest(&palette);
+        assert_eq!(mapper([200, 30, 30]), State::Red);
+        assert_eq!(mapper([10, 10, 40]), State::Dead);
+        assert_eq!(mapper([0, 100, 200]), State::Blue);
+    }
+
+    #[test]
+    fn not_an_image() {
+        assert!(from_memory(b"definitely not a png", |c| brightness(c) > 0.5).is_err());
+    }
+}
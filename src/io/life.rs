@@ -0,0 +1,234 @@
+use super::super::{CellState, Frame};
+use super::{from_cells, Error, Result};
+
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+use std::iter;
+
+/// A two state pattern read from a Life 1.05 or 1.06 file. Those formats use
+/// signed coordinates, so the pattern remembers where its top left cell is.
+#[derive(Debug, PartialEq)]
+pub struct Life {
+    /// the cells of the pattern cropped to its live cells, 1 is alive
+    pub pattern: Frame<u8>,
+    /// the coordinate of the top left cell of the pattern
+    pub origin: (isize, isize),
+    /// the rule from a `#R` line, if there was one
+    pub rule: Option<String>,
+    /// the lines of any `#D` descriptions
+    pub description: Vec<String>,
+}
+
+impl Life {
+    /// Copy the pattern onto frame so that the coordinate (0, 0) of the file
+    /// ends up at (x, y), wrapping around the edges
+    pub fn place<T: CellState>(&self, frame: &mut Frame<T>, x: usize, y: usize) {
+        super::place_signed(&self.pattern, self.origin, frame, x, y)
+    }
+}
+
+/// Parse a pair of signed coordinates
+fn coordinates<'a, I>(line: usize, mut words: I) -> Result<(isize, isize)>
+where I: Iterator<Item = &'a str> {
+    let mut next = || {
+        words.next()
+            .and_then(|w| w.parse::<isize>().ok())
+            .ok_or_else(|| Error::parse(line, "expected two integer coordinates"))
+    };
+    Ok((next()?, next()?))
+}
+
+/// Read a pattern in either Life 1.05 or Life 1.06 format, depending on the
+/// header
+pub fn read<R: BufRead>(reader: R) -> Result<Life> {
+    let mut lines = reader.lines();
+    let version = match lines.next() {
+        Some(line) => line?.trim().to_string(),
+        None => return Err(Error::parse(1, "missing header")),
+    };
+    let v106 = match &version[..] {
+        "#Life 1.05" => false,
+        "#Life 1.06" => true,
+        _ => return Err(Error::parse(1, format!("unknown header `{}`", version))),
+    };
+
+    let mut cells = vec![];
+    let mut rule = None;
+    let mut description = vec![];
+    // the position of the next row in the current 1.05 block
+    let mut block = None;
+    for (n, line) in lines.enumerate() {
+        let line = line?;
+        let lineno = n + 2;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            let mut words = line.split_whitespace();
+            match words.next().unwrap() {
+                "#D" => description.push(line[2..].trim().to_string()),
+                "#N" => rule = Some("23/3".to_string()),
+                "#R" => rule = words.next().map(|r| r.to_string()),
+                "#P" if !v106 => block = Some(coordinates(lineno, words)?),
+                _ => return Err(Error::parse(lineno, format!("unknown line `{}`", line))),
+            }
+        } else if v106 {
+            let (x, y) = coordinates(lineno, line.split_whitespace())?;
+            cells.push((x, y, 1));
+        } else {
+            let (x0, y) = block.ok_or_else(|| Error::parse(lineno, "cells before `#P` line"))?;
+            let too_far = || Error::parse(lineno, "cell is too far from the origin");
+            for (i, c) in line.chars().enumerate() {
+                match c {
+                    '*' => {
+                        let x = isize::try_from(i).ok().and_then(|i| x0.checked_add(i))
+                            .ok_or_else(too_far)?;
+                        cells.push((x, y, 1));
+                    }
+                    '.' => (),
+                    _ => return Err(Error::parse(lineno, format!("unexpected `{}`", c))),
+                }
+            }
+            block = Some((x0, y.checked_add(1).ok_or_else(too_far)?));
+        }
+    }
+
+    let (pattern, origin) = from_cells(&cells)?;
+    Ok(Life { pattern, origin, rule, description })
+}
+
+/// The live cells of frame, as (x, y) pairs. Life files only have two
+/// states, so every cell that isn't dead is alive.
+fn live_cells<T: CellState>(frame: &Frame<T>) -> Vec<(usize, usize)> {
+    frame.enumerate_squares()
+        .filter(|&(_, _, e)| e.index() != 0)
+        .map(|(x, y, _)| (x, y))
+        .collect()
+}
+
+/// Write the live cells of frame in Life 1.06 format. The top left corner of
+/// the frame is written as (0, 0).
+pub fn write_106<W, T>(mut writer: W, frame: &Frame<T>) -> Result<()>
+where W: Write, T: CellState {
+    writeln!(writer, "#Life 1.06")?;
+    for (x, y) in live_cells(frame) {
+        writeln!(writer, "{} {}", x, y)?;
+    }
+    Ok(())
+}
+
+/// Write frame in Life 1.05 format as a single block placed at (0, 0), with an
+/// optional rule in survival/birth form (e.g. `23/3`)
+pub fn write_105<W, T>(mut writer: W, frame: &Frame<T>, rule: Option<&str>) -> Result<()>
+where W: Write, T: CellState {
+    writeln!(writer, "#Life 1.05")?;
+    match rule {
+        Some(rule) => writeln!(writer, "#R {}", rule)?,
+        None => writeln!(writer, "#N")?,
+    }
+    let cells = live_cells(frame);
+    writeln!(writer, "#P 0 0")?;
+    // the cells come row by row, so each row is the next run of them
+    let rows = cells.last().map_or(0, |c| c.1 + 1);
+    let mut rest = &cells[..];
+    let mut row = String::new();
+    for y in 0..rows {
+        let n = rest.iter().take_while(|c| c.1 == y).count();
+        row.clear();
+        for &(x, _) in &rest[..n] {
+            let gap = x - row.len();
+            row.extend(iter::repeat_n('.', gap));
+            row.push('*');
+        }
+        rest = &rest[n..];
+        writeln!(writer, "{}", if row.is_empty() { "." } else { &row })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::Error;
+    use super::{read, write_105, write_106};
+
+    fn glider() -> Frame<u8> {
+        let mut glider = Frame::new(3, 3);
+        *glider.get_mut(1, 0) = 1;
+        *glider.get_mut(2, 1) = 1;
+        *glider.get_mut(0, 2) = 1;
+        *glider.get_mut(1, 2) = 1;
+        *glider.get_mut(2, 2) = 1;
+        glider
+    }
+
+    #[test]
+    fn read_106() {
+        let text = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+        let life = read(text.as_bytes()).unwrap();
+        assert_eq!(life.origin, (-1, -1));
+        assert_eq!(life.pattern, glider());
+    }
+
+    #[test]
+    fn far_apart() {
+        let too_large = |text: &str| matches!(read(text.as_bytes()), Err(Error::Format(_)));
+        assert!(too_large("#Life 1.06\n0 0\n1000000000 1000000000\n"));
+        assert!(too_large("#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n"));
+        assert!(read("#Life 1.06\n0 0\n4000 4000\n".as_bytes()).is_ok());
+        let edge = "#Life 1.05\n#P 9223372036854775807 0\n.*\n";
+        assert!(matches!(read(edge.as_bytes()), Err(Error::Parse { line: 3, .. })));
+    }
+
+    #[test]
+    fn read_105() {
+        let text = "#Life 1.05\n#D A glider\n#D in two blocks\n#N\n\
+                    #P -1 -1\n.*\n..*\n#P -1 1\n***\n";
+        let life = read(text.as_bytes()).unwrap();
+        assert_eq!(life.origin, (-1, -1));
+        assert_eq!(life.rule, Some("23/3".to_string()));
+        assert_eq!(life.description, vec!["A glider", "in two blocks"]);
+        assert_eq!(life.pattern, glider());
+    }
+
+    #[test]
+    fn place_signed() {
+        let text = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+        let life = read(text.as_bytes()).unwrap();
+        let mut frame = Frame::<u8>::new(4, 4);
+        life.place(&mut frame, 0, 0);
+        assert_eq!(*frame.get(0, 3), 1);
+        assert_eq!(*frame.get(1, 0), 1);
+        assert_eq!(*frame.get(3, 1), 1);
+        assert_eq!(*frame.get(0, 1), 1);
+        assert_eq!(*frame.get(1, 1), 1);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut frame = Frame::<u8>::new(6, 5);
+        *frame.get_mut(1, 1) = 1;
+        *frame.get_mut(4, 1) = 1;
+        *frame.get_mut(2, 3) = 1;
+
+        let mut out = vec![];
+        write_106(&mut out, &frame).unwrap();
+        let life = read(&out[..]).unwrap();
+        assert_eq!(life.origin, (1, 1));
+        let mut copy = Frame::new(6, 5);
+        life.place(&mut copy, 0, 0);
+        assert_eq!(copy, frame);
+
+        let mut out = vec![];
+        write_105(&mut out, &frame, Some("23/36")).unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(),
+                   "#Life 1.05\n#R 23/36\n#P 0 0\n.\n.*..*\n.\n..*\n");
+        let life = read(&out[..]).unwrap();
+        assert_eq!(life.rule, Some("23/36".to_string()));
+        let mut copy = Frame::new(6, 5);
+        life.place(&mut copy, 0, 0);
+        assert_eq!(copy, frame);
+    }
+}
@@ -0,0 +1,424 @@
+use super::super::{CellState, Frame};
+use super::{Error, Result};
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// The most cells the live part of a pattern read from a macrocell file may
+/// span. Macrocell files can describe astronomically large patterns by
+/// sharing nodes, but they are read into a flat frame rather than kept as a
+/// quadtree, so larger ones are refused.
+pub const MAX_CELLS: usize = 1 << 28;
+
+/// A pattern read from a Golly macrocell file. Macrocell files store a
+/// quadtree whose root is centered on (0, 0), so the pattern remembers where
+/// its top left cell is.
+#[derive(Debug, PartialEq)]
+pub struct Macrocell {
+    /// the cells of the pattern cropped to its live cells
+    pub pattern: Frame<u8>,
+    /// the coordinate of the top left cell of the pattern
+    pub origin: (isize, isize),
+    /// the rule from the `#R` line, if there was one
+    pub rule: Option<String>,
+}
+
+impl Macrocell {
+    /// Copy the pattern onto frame so that the coordinate (0, 0) of the file
+    /// ends up at (x, y), wrapping around the edges
+    pub fn place<T: CellState>(&self, frame: &mut Frame<T>, x: usize, y: usize) {
+        super::place_signed(&self.pattern, self.origin, frame, x, y)
+    }
+}
+
+/// A node of the quadtree
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    /// an 8x8 two state leaf, row major
+    Leaf(Vec<u8>),
+    /// a 2x2 multi-state leaf, in nw ne sw se order
+    States([u8; 4]),
+    /// a node of size 2^level with children in nw ne sw se order, where 0 is
+    /// an empty child
+    Inner(u32, [usize; 4]),
+}
+
+impl Node {
+    fn size(&self) -> isize {
+        match *self {
+            Node::Leaf(_) => 8,
+            Node::States(_) => 2,
+            Node::Inner(level, _) => 1 << level,
+        }
+    }
+
+    /// The smallest and largest x and y of the live cells of the node,
+    /// relative to its top left corner, given those of the nodes before it
+    fn bounds(&self, before: &[Option<Bounds>]) -> Option<Bounds> {
+        let mut bounds = None;
+        match *self {
+            Node::Leaf(ref cells) => {
+                for (i, _) in cells.iter().enumerate().filter(|&(_, &s)| s != 0) {
+                    bounds = union(bounds, Some(((i % 8) as isize, (i / 8) as isize,
+                                                 (i % 8) as isize, (i / 8) as isize)));
+                }
+            }
+            Node::States(states) => {
+                for (i, _) in states.iter().enumerate().filter(|&(_, &s)| s != 0) {
+                    bounds = union(bounds, Some(((i % 2) as isize, (i / 2) as isize,
+                                                 (i % 2) as isize, (i / 2) as isize)));
+                }
+            }
+            Node::Inner(level, children) => {
+                let half = 1 << (level - 1);
+                for (i, &child) in children.iter().enumerate().filter(|&(_, &c)| c != 0) {
+                    let (dx, dy) = ((i % 2) as isize * half, (i / 2) as isize * half);
+                    let child = before[child - 1].map(|(x0, y0, x1, y1)| {
+                        (x0 + dx, y0 + dy, x1 + dx, y1 + dy)
+                    });
+                    bounds = union(bounds, child);
+                }
+            }
+        }
+        bounds
+    }
+}
+
+/// The smallest and largest x and y of some cells
+type Bounds = (isize, isize, isize, isize);
+
+fn union(a: Option<Bounds>, b: Option<Bounds>) -> Option<Bounds> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Parse an 8x8 leaf like `$.**$*$`
+fn parse_leaf(line: usize, text: &str) -> Result<Node> {
+    let mut cells = vec![0; 64];
+    let (mut x, mut y) = (0, 0);
+    for c in text.chars() {
+        match c {
+            '$' => {
+                x = 0;
+                y += 1;
+                continue;
+            }
+            '.' => (),
+            '*' if x < 8 && y < 8 => cells[y * 8 + x] = 1,
+            _ => return Err(Error::parse(line, format!("invalid leaf `{}`", text))),
+        }
+        x += 1;
+    }
+    Ok(Node::Leaf(cells))
+}
+
+/// Parse a `level nw ne sw se` line
+fn parse_node(line: usize, text: &str, nodes: &[Node]) -> Result<Node> {
+    let err = || Error::parse(line, format!("invalid node `{}`", text));
+    let nums = text.split_whitespace()
+        .map(|w| w.parse::<usize>().map_err(|_| err()))
+        .collect::<Result<Vec<_>>>()?;
+    if nums.len() != 5 {
+        return Err(err());
+    }
+    let level = nums[0];
+    if level == 1 {
+        if nums[1..].iter().any(|&s| s > u8::MAX as usize) {
+            return Err(err());
+        }
+        return Ok(Node::States([nums[1] as u8, nums[2] as u8, nums[3] as u8, nums[4] as u8]));
+    }
+    if !(2..=62).contains(&level) {
+        return Err(err());
+    }
+    // children have to be earlier nodes of the next level down
+    for &child in &nums[1..] {
+        if child > nodes.len() || (child > 0 && nodes[child - 1].size() != 1 << (level - 1)) {
+            return Err(err());
+        }
+    }
+    Ok(Node::Inner(level as u32, [nums[1], nums[2], nums[3], nums[4]]))
+}
+
+/// Write the live cells of node, whose top left corner is at (x, y), into
+/// pattern, whose top left corner is at origin
+fn collect(nodes: &[Node], node: usize, x: isize, y: isize,
+           origin: (isize, isize), pattern: &mut Frame<u8>) {
+    if node == 0 {
+        return;
+    }
+    let mut set = |i: isize, j: isize, s: u8| {
+        *pattern.get_mut((x + i - origin.0) as usize, (y + j - origin.1) as usize) = s;
+    };
+    match nodes[node - 1] {
+        Node::Leaf(ref leaf) => {
+            for (i, &s) in leaf.iter().enumerate() {
+                if s != 0 {
+                    set((i % 8) as isize, (i / 8) as isize, s);
+                }
+            }
+        }
+        Node::States(states) => {
+            for (i, &s) in states.iter().enumerate() {
+                if s != 0 {
+                    set((i % 2) as isize, (i / 2) as isize, s);
+                }
+            }
+        }
+        Node::Inner(level, children) => {
+            let half = 1 << (level - 1);
+            for (i, &child) in children.iter().enumerate() {
+                let (dx, dy) = ((i % 2) as isize * half, (i / 2) as isize * half);
+                collect(nodes, child, x + dx, y + dy, origin, pattern);
+            }
+        }
+    }
+}
+
+/// Read a pattern in macrocell format
+pub fn read<R: BufRead>(reader: R) -> Result<Macrocell> {
+    let mut lines = reader.lines();
+    match lines.next() {
+        Some(line) => {
+            if !line?.starts_with("[M2]") {
+                return Err(Error::parse(1, "missing `[M2]` header"));
+            }
+        }
+        None => return Err(Error::parse(1, "missing `[M2]` header")),
+    }
+
+    let mut rule = None;
+    let mut nodes = vec![];
+    let mut bounds = vec![];
+    for (n, line) in lines.enumerate() {
+        let line = line?;
+        let lineno = n + 2;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line.chars().next().unwrap() {
+            '#' => {
+                if let Some(r) = line.strip_prefix("#R") {
+                    rule = Some(r.trim().to_string());
+                }
+            }
+            '.' | '*' | '$' => {
+                let node = parse_leaf(lineno, line)?;
+                bounds.push(node.bounds(&bounds));
+                nodes.push(node);
+            }
+            _ => {
+                let node = parse_node(lineno, line, &nodes)?;
+                bounds.push(node.bounds(&bounds));
+                nodes.push(node);
+            }
+        }
+    }
+
+    // check the size of the pattern before expanding shared nodes, which are
+    // written straight into a frame of that size
+    let (pattern, origin) = match bounds.last() {
+        Some(&Some((x0, y0, x1, y1))) => {
+            let (w, h) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+            if w.checked_mul(h).is_none_or(|cells| cells > MAX_CELLS) {
+                return Err(Error::Format(format!("a {}x{} pattern is too large to read, the \
+                                                  most is {} cells", w, h, MAX_CELLS)));
+            }
+            // the bounds are relative to the top left corner of the root
+            let half = nodes.last().unwrap().size() / 2;
+            let origin = (x0 - half, y0 - half);
+            let mut pattern = Frame::new(w, h);
+            collect(&nodes, nodes.len(), -half, -half, origin, &mut pattern);
+            (pattern, origin)
+        }
+        _ => (Frame::new(0, 0), (0, 0)),
+    };
+    Ok(Macrocell { pattern, origin, rule })
+}
+
+/// Builds a quadtree, sharing identical nodes
+struct Builder {
+    nodes: Vec<Node>,
+    index: HashMap<Node, usize>,
+}
+
+impl Builder {
+    fn add(&mut self, node: Node) -> usize {
+        let nodes = &mut self.nodes;
+        *self.index.entry(node).or_insert_with_key(|node| {
+            nodes.push(node.clone());
+            nodes.len()
+        })
+    }
+
+    /// Build the node of the given size with its top left corner at (x, y)
+    fn build<F>(&mut self, x: usize, y: usize, level: u32, alive: &F) -> usize
+    where F: Fn(usize, usize) -> bool {
+        if level == 3 {
+            let leaf = (0..64)
+                .map(|i| alive(x + i % 8, y + i / 8) as u8)
+                .collect::<Vec<_>>();
+            if leaf.iter().all(|&s| s == 0) {
+                return 0;
+            }
+            return self.add(Node::Leaf(leaf));
+        }
+        let half = 1 << (level - 1);
+        let children = [
+            self.build(x, y, level - 1, alive),
+            self.build(x + half, y, level - 1, alive),
+            self.build(x, y + half, level - 1, alive),
+            self.build(x + half, y + half, level - 1, alive),
+        ];
+        if children == [0; 4] {
+            return 0;
+        }
+        self.add(Node::Inner(level, children))
+    }
+}
+
+/// Write the live cells of frame in macrocell format with an optional rule.
+/// The frame is padded to a square with a power of two side whose center is
+/// written as (0, 0). Macrocell files are written with two states, so every
+/// cell that isn't dead is alive.
+pub fn write<W, T>(mut writer: W, frame: &Frame<T>, rule: Option<&str>) -> Result<()>
+where W: Write, T: CellState {
+    let mut level = 3;
+    while 1 << level < frame.width().max(frame.height()) {
+        level += 1;
+    }
+    // the root has to be an inner node
+    let level = level.max(4);
+
+    let mut builder = Builder { nodes: vec![], index: HashMap::new() };
+    let lookup = |x: usize, y: usize| {
+        x < frame.width() && y < frame.height() && frame.get(x, y).index() != 0
+    };
+    let root = builder.build(0, 0, level, &lookup);
+    if root == 0 {
+        // an empty pattern still needs a root
+        builder.nodes.push(Node::Inner(level, [0; 4]));
+    }
+
+    writeln!(writer, "[M2] (simulation)")?;
+    if let Some(rule) = rule {
+        writeln!(writer, "#R {}", rule)?;
+    }
+    for node in &builder.nodes {
+        match *node {
+            Node::Leaf(ref cells) => {
+                let mut rows = cells.chunks(8)
+                    .map(|row| {
+                        let len = row.iter().rposition(|&s| s != 0).map_or(0, |i| i + 1);
+                        row[..len].iter()
+                            .map(|&s| if s != 0 { '*' } else { '.' })
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>();
+                while rows.last().is_some_and(|r| r.is_empty()) {
+                    rows.pop();
+                }
+                let text = rows.iter().map(|r| format!("{}$", r)).collect::<String>();
+                writeln!(writer, "{}", text)?;
+            }
+            Node::States(s) => writeln!(writer, "1 {} {} {} {}", s[0], s[1], s[2], s[3])?,
+            Node::Inner(level, c) => {
+                writeln!(writer, "{} {} {} {} {}", level, c[0], c[1], c[2], c[3])?
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::Error;
+    use super::{read, write};
+
+    #[test]
+    fn read_glider() {
+        // a glider in the se quadrant's nw leaf
+        let text = "[M2] (golly 2.0)\n#R B3/S23\n.*$..*$***$\n4 0 0 0 1\n";
+        let mc = read(text.as_bytes()).unwrap();
+        assert_eq!(mc.rule, Some("B3/S23".to_string()));
+        assert_eq!(mc.origin, (0, 0));
+        let mut glider = Frame::new(3, 3);
+        *glider.get_mut(1, 0) = 1;
+        *glider.get_mut(2, 1) = 1;
+        *glider.get_mut(0, 2) = 1;
+        *glider.get_mut(1, 2) = 1;
+        *glider.get_mut(2, 2) = 1;
+        assert_eq!(mc.pattern, glider);
+    }
+
+    #[test]
+    fn shared_nodes() {
+        // the same leaf used in all four quadrants of a level 5 node
+        let text = "[M2]\n*$\n4 1 0 0 0\n5 2 2 2 2\n";
+        let mc = read(text.as_bytes()).unwrap();
+        assert_eq!(mc.origin, (-16, -16));
+        assert_eq!(mc.pattern.width(), 17);
+        assert_eq!(mc.pattern.height(), 17);
+        assert_eq!(*mc.pattern.get(0, 0), 1);
+        assert_eq!(*mc.pattern.get(16, 0), 1);
+        assert_eq!(*mc.pattern.get(0, 16), 1);
+        assert_eq!(*mc.pattern.get(16, 16), 1);
+        assert_eq!(mc.pattern.enumerate_squares().filter(|e| *e.2 == 1).count(), 4);
+    }
+
+    #[test]
+    fn multi_state() {
+        let text = "[M2]\n1 0 2 0 0\n1 3 0 0 0\n2 1 0 0 2\n";
+        let mc = read(text.as_bytes()).unwrap();
+        assert_eq!(mc.origin, (-1, -2));
+        assert_eq!(*mc.pattern.get(0, 0), 2);
+        assert_eq!(*mc.pattern.get(1, 2), 3);
+    }
+
+    #[test]
+    fn invalid_child() {
+        assert!(read("[M2]\n*$\n5 1 0 0 0\n".as_bytes()).is_err());
+        assert!(read("[M2]\n4 7 0 0 0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn too_large() {
+        // two cells at opposite corners of a level 40 node
+        let mut text = "[M2]\n*$\n$$$$$$$.......*$\n4 1 0 0 0\n4 0 0 0 2\n".to_string();
+        for level in 5..40 {
+            text += &format!("{} {} 0 0 0\n", level, 2 * level - 7);
+            text += &format!("{} 0 0 0 {}\n", level, 2 * level - 6);
+        }
+        text += "40 73 0 0 74\n";
+        match read(text.as_bytes()) {
+            Err(Error::Format(_)) => (),
+            other => panic!("expected the pattern to be refused, not {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut frame = Frame::<u8>::new(20, 12);
+        *frame.get_mut(1, 1) = 1;
+        *frame.get_mut(9, 1) = 1;
+        *frame.get_mut(17, 1) = 1;
+        *frame.get_mut(19, 11) = 1;
+
+        let mut out = vec![];
+        write(&mut out, &frame, Some("B3/S23")).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        // the three identical 8x8 blocks are shared
+        assert_eq!(text.lines().filter(|l| l.starts_with('$')).count(), 2);
+
+        let mc = read(&out[..]).unwrap();
+        assert_eq!(mc.rule, Some("B3/S23".to_string()));
+        let mut copy = Frame::new(20, 12);
+        mc.place(&mut copy, 16, 16);
+        assert_eq!(copy, frame);
+    }
+}
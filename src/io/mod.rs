@@ -0,0 +1,150 @@
+use super::{CellState, Frame};
+
+use image::ImageError;
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::result;
+
+/// Run Length Encoded patterns, the most common format for sharing patterns
+pub mod rle;
+/// The Life 1.05 and Life 1.06 formats
+pub mod life;
+/// The plaintext `.cells` format
+pub mod cells;
+/// Golly's quadtree based macrocell format
+pub mod macrocell;
+/// Delimiter separated values, for numeric frames
+pub mod csv;
+/// Loading frames from images
+pub mod image;
+/// Saving whole simulations
+pub mod save;
+/// Golly's `.rule` files for custom automata
+pub mod rule;
+/// Newline delimited json output for streaming generations to other tools
+pub mod ndjson;
+/// A compact binary format with optional compression
+pub mod binary;
+/// One character per cell, for any `CellState`
+pub mod chars;
+/// Recordings of runs and the edits made during them, for reproducing them
+pub mod replay;
+
+/// Errors that can happen while reading or writing frames
+#[derive(Debug)]
+pub enum Error {
+    /// the underlying reader or writer failed
+    Io(io::Error),
+    /// the input is malformed, line is 1-based
+    Parse { line: usize, message: String },
+    /// an image couldn't be decoded
+    Image(ImageError),
+    /// binary input is malformed
+    Format(String),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl Error {
+    fn parse<S: Into<String>>(line: usize, message: S) -> Error {
+        Error::Parse { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => write!(f, "{}", e),
+            Error::Parse { line, ref message } => write!(f, "line {}: {}", line, message),
+            Error::Image(ref e) => write!(f, "{}", e),
+            Error::Format(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref e) => Some(e),
+            Error::Parse { .. } | Error::Format(_) => None,
+            Error::Image(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<ImageError> for Error {
+    fn from(e: ImageError) -> Error {
+        Error::Image(e)
+    }
+}
+
+/// The cell numbered s in a pattern. Patterns can have more states than
+/// cells do, so the states past a cell's last one wrap around its live
+/// states.
+pub fn state<T: CellState>(s: u8) -> T {
+    let s = s as usize;
+    let s = if s < T::STATES { s } else { 1 + (s - 1) % (T::STATES - 1) };
+    T::from_index(s).expect("states below STATES have a number")
+}
+
+/// Copy a pattern of state indices onto frame with its top left corner at
+/// (x, y), wrapping around the edges. Dead cells (index 0) are copied too.
+pub fn place<T: CellState>(pattern: &Frame<u8>, frame: &mut Frame<T>, x: usize, y: usize) {
+    let (w, h) = (frame.width(), frame.height());
+    for j in 0..pattern.height() {
+        for i in 0..pattern.width() {
+            *frame.get_mut((x + i) % w, (y + j) % h) = state(*pattern.get(i, j));
+        }
+    }
+}
+
+/// Like `place`, but the pattern's top left corner is at origin relative to
+/// (x, y)
+pub fn place_signed<T: CellState>(pattern: &Frame<u8>, origin: (isize, isize),
+                                  frame: &mut Frame<T>, x: usize, y: usize) {
+    let wrap = |v: usize, d: isize, m: usize| {
+        (v as isize + d).rem_euclid(m as isize) as usize
+    };
+    let x = wrap(x, origin.0, frame.width());
+    let y = wrap(y, origin.1, frame.height());
+    place(pattern, frame, x, y)
+}
+
+/// The most cells a pattern given as a list of cells can span, so that a
+/// few cells far apart can't ask for more memory than there is
+pub const MAX_CELLS: usize = 1 << 26;
+
+/// Build a pattern cropped to a list of (x, y, state) cells, returning it with
+/// the coordinate of its top left corner
+fn from_cells(cells: &[(isize, isize, u8)]) -> Result<(Frame<u8>, (isize, isize))> {
+    if cells.is_empty() {
+        return Ok((Frame::new(0, 0), (0, 0)));
+    }
+    let min_x = cells.iter().map(|c| c.0).min().unwrap();
+    let max_x = cells.iter().map(|c| c.0).max().unwrap();
+    let min_y = cells.iter().map(|c| c.1).min().unwrap();
+    let max_y = cells.iter().map(|c| c.1).max().unwrap();
+
+    let span = |min: isize, max: isize| {
+        max.checked_sub(min).and_then(|d| d.checked_add(1)).map(|d| d as usize)
+    };
+    let (w, h) = match (span(min_x, max_x), span(min_y, max_y)) {
+        (Some(w), Some(h)) if w.checked_mul(h).is_some_and(|n| n <= MAX_CELLS) => (w, h),
+        _ => return Err(Error::Format(format!(
+            "a pattern from {},{} to {},{} is too large to read, the most is {} cells",
+            min_x, min_y, max_x, max_y, MAX_CELLS))),
+    };
+    let mut pattern = Frame::new(w, h);
+    for &(x, y, s) in cells {
+        *pattern.get_mut((x - min_x) as usize, (y - min_y) as usize) = s;
+    }
+    Ok((pattern, (min_x, min_y)))
+}
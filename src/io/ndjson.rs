@@ -0,0 +1,152 @@
+use super::super::{Frame, Simulation, Square};
+use super::Result;
+
+use std::io::Write;
+
+/// Streams generations as newline delimited json, one object per line, e.g.
+/// `{"generation":4,"width":3,"height":2,"live":[[0,1],[2,1]]}` or, with the
+/// full data, `{"generation":4,"width":3,"height":2,"data":[[0,0.5,0],[1,0,0]]}`.
+/// Each line is flushed as soon as it is written so readers on the other end
+/// of a pipe see generations as they happen.
+#[derive(Debug)]
+pub struct NdjsonSink<W> {
+    writer: W,
+}
+
+impl<W: Write> NdjsonSink<W> {
+    pub fn new(writer: W) -> NdjsonSink<W> {
+        NdjsonSink { writer }
+    }
+
+    /// Write the coordinates of the cells of frame for which alive is true
+    pub fn write_live<T, F>(&mut self, generation: u64, frame: &Frame<T>, alive: F) -> Result<()>
+    where F: Fn(&T) -> bool {
+        self.header(generation, frame)?;
+        write!(self.writer, ",\"live\":[")?;
+        let live = frame.enumerate_squares().filter(|&(_, _, e)| alive(e));
+        for (i, (x, y, _)) in live.enumerate() {
+            if i > 0 {
+                write!(self.writer, ",")?;
+            }
+            write!(self.writer, "[{},{}]", x, y)?;
+        }
+        self.end()
+    }
+
+    /// Write every cell of frame as a number, row by row. Numbers that aren't
+    /// finite are written as null since json can't represent them.
+    pub fn write_data<T, F>(&mut self, generation: u64, frame: &Frame<T>, value: F) -> Result<()>
+    where F: Fn(&T) -> f64 {
+        self.header(generation, frame)?;
+        write!(self.writer, ",\"data\":[")?;
+        for y in 0..frame.height() {
+            write!(self.writer, "{}[", if y > 0 { "," } else { "" })?;
+            for x in 0..frame.width() {
+                if x > 0 {
+                    write!(self.writer, ",")?;
+                }
+                let v = value(frame.get(x, y));
+                if v.is_finite() {
+                    write!(self.writer, "{}", v)?;
+                } else {
+                    write!(self.writer, "null")?;
+                }
+            }
+            write!(self.writer, "]")?;
+        }
+        self.end()
+    }
+
+    /// Write the live cells of the current generation of sim and then advance
+    /// it, `generations` times
+    pub fn record_live<T, F, A>(&mut self, sim: &mut Simulation<T, F>, generations: usize,
+                                alive: A) -> Result<()>
+    where T: Clone, F: Fn(Square<T>) -> T, A: Fn(&T) -> bool {
+        for _ in 0..generations {
+            self.write_live(sim.generation(), sim.frame(), &alive)?;
+            sim.step();
+        }
+        Ok(())
+    }
+
+    /// Write the full data of the current generation of sim and then advance
+    /// it, `generations` times
+    pub fn record_data<T, F, V>(&mut self, sim: &mut Simulation<T, F>, generations: usize,
+                                value: V) -> Result<()>
+    where T: Clone, F: Fn(Square<T>) -> T, V: Fn(&T) -> f64 {
+        for _ in 0..generations {
+            self.write_data(sim.generation(), sim.frame(), &value)?;
+            sim.step();
+        }
+        Ok(())
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn header<T>(&mut self, generation: u64, frame: &Frame<T>) -> Result<()> {
+        write!(self.writer, "{{\"generation\":{},\"width\":{},\"height\":{}",
+               generation, frame.width(), frame.height())?;
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<()> {
+        writeln!(self.writer, "]}}")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{Frame, Simulation};
+    use super::super::super::game_of_life;
+    use super::super::super::game_of_life::State::Alive;
+    use super::NdjsonSink;
+
+    #[test]
+    fn live_cells() {
+        let mut frame = Frame::new(5, 5);
+        *frame.get_mut(2, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        *frame.get_mut(2, 3) = Alive;
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+
+        let mut sink = NdjsonSink::new(vec![]);
+        sink.record_live(&mut sim, 2, |s| *s == Alive).unwrap();
+        assert_eq!(String::from_utf8(sink.into_inner()).unwrap(),
+                   "{\"generation\":0,\"width\":5,\"height\":5,\"live\":[[2,1],[2,2],[2,3]]}\n\
+                    {\"generation\":1,\"width\":5,\"height\":5,\"live\":[[1,2],[2,2],[3,2]]}\n");
+    }
+
+    #[test]
+    fn data() {
+        let mut frame = Frame::<f32>::new(3, 2);
+        *frame.get_mut(1, 0) = 0.5;
+        *frame.get_mut(0, 1) = f32::NAN;
+
+        let mut sink = NdjsonSink::new(vec![]);
+        sink.write_data(7, &frame, |&v| v as f64).unwrap();
+        assert_eq!(String::from_utf8(sink.into_inner()).unwrap(),
+                   "{\"generation\":7,\"width\":3,\"height\":2,\"data\":[[0,0.5,0],[null,0,0]]}\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn valid_json() {
+        use serde_json;
+
+        let mut frame = Frame::<f32>::new(2, 2);
+        *frame.get_mut(1, 1) = -1.25e-3;
+        let mut sink = NdjsonSink::new(vec![]);
+        sink.write_data(0, &frame, |&v| v as f64).unwrap();
+        sink.write_live(1, &frame, |&v| v != 0.0).unwrap();
+        let out = String::from_utf8(sink.into_inner()).unwrap();
+        for line in out.lines() {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["width"], 2);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use super::super::{Checkpoint, Frame};
+use super::{csv, Error, Result};
+
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+const MAGIC: &str = "#replay 1";
+
+/// A cell set by hand while a simulation was running
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edit<T> {
+    /// the generation the simulation was at when the cell was set
+    pub generation: u64,
+    pub x: usize,
+    pub y: usize,
+    pub value: T,
+}
+
+/// Everything needed to run a simulation again exactly as it went: where it
+/// started, the rule it ran with its seed and parameters, and the edits made
+/// to it along the way in the order they were made
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay<T> {
+    pub start: Checkpoint<T>,
+    pub rule: String,
+    /// the seed of random rules
+    pub seed: Option<u64>,
+    /// rule parameters as names and values, in the order they were given
+    pub parameters: Vec<(String, String)>,
+    pub edits: Vec<Edit<T>>,
+}
+
+impl<T> Replay<T> {
+    /// A replay of a simulation starting from start and running the rule
+    /// called rule, without a seed, parameters or edits yet
+    pub fn new(start: Checkpoint<T>, rule: &str) -> Replay<T> {
+        Replay { start, rule: rule.to_string(), seed: None, parameters: vec![], edits: vec![] }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Replay<T> {
+        Replay { seed: Some(seed), ..self }
+    }
+
+    pub fn with_parameter(mut self, name: &str, value: &str) -> Replay<T> {
+        self.parameters.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// The value of the parameter called name
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Remember that the cell at (x, y) was set to value at generation
+    pub fn record(&mut self, generation: u64, x: usize, y: usize, value: T) {
+        self.edits.push(Edit { generation, x, y, value });
+    }
+}
+
+/// Write replay as `#` header lines, one per edit, followed by the starting
+/// frame as comma separated values like a save
+pub fn write<W, T>(mut writer: W, replay: &Replay<T>) -> Result<()>
+where W: Write, T: Display {
+    let words = |s: &str| !s.is_empty() && !s.contains(char::is_whitespace);
+    if replay.rule.contains('\n') {
+        return Err(Error::parse(2, "rule names can't contain newlines"));
+    }
+    if let Some((n, v)) = replay.parameters.iter().find(|(n, v)| !words(n) || v.contains('\n')) {
+        return Err(Error::Format(format!("invalid parameter `{}` = `{}`", n, v)));
+    }
+    writeln!(writer, "{}", MAGIC)?;
+    writeln!(writer, "#rule {}", replay.rule)?;
+    if let Some(seed) = replay.seed {
+        writeln!(writer, "#seed {}", seed)?;
+    }
+    for (name, value) in &replay.parameters {
+        writeln!(writer, "#param {} {}", name, value)?;
+    }
+    for edit in &replay.edits {
+        writeln!(writer, "#edit {} {} {} {}", edit.generation, edit.x, edit.y, edit.value)?;
+    }
+    writeln!(writer, "#generation {}", replay.start.generation)?;
+    let frame = &replay.start.frame;
+    writeln!(writer, "#size {} {}", frame.width(), frame.height())?;
+    csv::write(writer, frame, ',')
+}
+
+/// Read a replay written by write
+pub fn read<R, T>(reader: R) -> Result<Replay<T>>
+where R: BufRead, T: FromStr + Default + Clone {
+    let mut lines = reader.lines();
+    let mut replay = Replay::new(Checkpoint { frame: Frame::new(0, 0), generation: 0 }, "");
+    let (mut rule, mut generation, mut size) = (None, None, None);
+    let mut n = 0;
+    while size.is_none() {
+        n += 1;
+        let line = lines.next().ok_or_else(|| Error::parse(n, "expected #size"))??;
+        let line = line.trim_end_matches('\r');
+        if n == 1 {
+            if line != MAGIC {
+                return Err(Error::parse(n, "not a replay"));
+            }
+            continue;
+        }
+        let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let invalid = || Error::parse(n, format!("invalid `{}`", line));
+        let number = |s: &str| s.parse::<u64>().map_err(|_| invalid());
+        let fields = rest.split(' ').collect::<Vec<_>>();
+        match key {
+            "#rule" => rule = Some(rest.to_string()),
+            "#seed" => replay.seed = Some(number(rest)?),
+            "#param" => {
+                let (name, value) = rest.split_once(' ').unwrap_or((rest, ""));
+                replay.parameters.push((name.to_string(), value.to_string()));
+            }
+            "#edit" if fields.len() == 4 => replay.edits.push(Edit {
+                generation: number(fields[0])?,
+                x: number(fields[1])? as usize,
+                y: number(fields[2])? as usize,
+                value: fields[3].parse().map_err(|_| invalid())?,
+            }),
+            "#generation" => generation = Some(number(rest)?),
+            "#size" if fields.len() == 2 => {
+                size = Some((number(fields[0])? as usize, number(fields[1])? as usize))
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    let (width, height) = size.unwrap();
+    replay.rule = rule.ok_or_else(|| Error::parse(n, "expected #rule before #size"))?;
+
+    let rest = lines.collect::<::std::io::Result<Vec<_>>>()?.join("\n");
+    let frame: Frame<T> = csv::read(rest.as_bytes(), ',').map_err(|e| match e {
+        Error::Parse { line, message } => Error::parse(line + n, message),
+        e => e,
+    })?;
+    if frame.width() != width || frame.height() != height {
+        return Err(Error::parse(n, format!("expected a {}x{} frame but found {}x{}",
+                                           width, height, frame.width(), frame.height())));
+    }
+    if let Some(edit) = replay.edits.iter().find(|e| e.x >= width || e.y >= height) {
+        return Err(Error::Format(format!("edit at ({}, {}) is outside of the frame",
+                                         edit.x, edit.y)));
+    }
+    replay.start = Checkpoint { frame, generation: generation.unwrap_or(0) };
+    Ok(replay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{Checkpoint, Frame};
+    use super::{read, write, Replay};
+
+    fn replay() -> Replay<u8> {
+        let mut frame = Frame::new(3, 2);
+        *frame.get_mut(1, 0) = 1;
+        let mut replay = Replay::new(Checkpoint { frame, generation: 4 }, "stochastic life")
+            .with_seed(42)
+            .with_parameter("birth", "0.5");
+        replay.record(6, 2, 1, 1);
+        replay.record(9, 0, 0, 0);
+        replay
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut out = vec![];
+        write(&mut out, &replay()).unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(),
+                   "#replay 1\n#rule stochastic life\n#seed 42\n#param birth 0.5\n\
+                    #edit 6 2 1 1\n#edit 9 0 0 0\n#generation 4\n#size 3 2\n0,1,0\n0,0,0\n");
+        let read = read::<_, u8>(&out[..]).unwrap();
+        assert_eq!(read, replay());
+        assert_eq!(read.parameter("birth"), Some("0.5"));
+        assert_eq!(read.parameter("survival"), None);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(write(vec![], &replay().with_parameter("two words", "1")).is_err());
+        assert!(read::<_, u8>("#simulation 1\n".as_bytes()).is_err());
+        assert!(read::<_, u8>("#replay 1\n#rule r\n#seed x\n".as_bytes()).is_err());
+        assert!(read::<_, u8>("#replay 1\n#size 1 1\n0\n".as_bytes()).is_err());
+        let outside = "#replay 1\n#rule r\n#edit 0 5 0 1\n#size 1 1\n0\n";
+        assert!(read::<_, u8>(outside.as_bytes()).is_err());
+        let bad_cell = "#replay 1\n#rule r\n#size 2 1\n1,x\n";
+        match read::<_, u8>(bad_cell.as_bytes()) {
+            Err(super::Error::Parse { line, .. }) => assert_eq!(line, 4),
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
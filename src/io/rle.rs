@@ -0,0 +1,348 @@
+use super::super::{CellState, Frame};
+use super::super::dsl::{self, ParseError};
+use super::super::frame::cells;
+use super::{Error, Result};
+
+use std::convert::TryFrom;
+use std::io::{BufRead, Write};
+use std::result;
+
+/// A pattern read from an RLE file. Cells are stored as state indices where 0
+/// is dead, 1 is alive, and higher numbers are the extra states of multi-state
+/// rules.
+#[derive(Debug, PartialEq)]
+pub struct Rle {
+    /// the cells of the pattern, sized according to the header
+    pub pattern: Frame<u8>,
+    /// the rule from the header, if there was one
+    pub rule: Option<String>,
+}
+
+impl Rle {
+    /// Copy the pattern onto frame with its top left corner at (x, y)
+    pub fn place<T: CellState>(&self, frame: &mut Frame<T>, x: usize, y: usize) {
+        super::place(&self.pattern, frame, x, y)
+    }
+
+    /// The rule from the header read as a rule in the rule language, or None
+    /// if the header has no rule. Rules in other notations, like `B3/S23`,
+    /// give an error.
+    pub fn dsl_rule(&self) -> Option<result::Result<dsl::Rule, ParseError>> {
+        self.rule.as_ref().map(|rule| rule.parse())
+    }
+}
+
+/// Parse the `x = m, y = n, rule = abc` header line. The rule comes last and
+/// takes the rest of the line, so rules in the rule language can contain
+/// commas.
+fn parse_header(line: usize, header: &str) -> Result<(usize, usize, Option<String>)> {
+    let (mut x, mut y, mut rule) = (None, None, None);
+    let mut fields = header;
+    while !fields.is_empty() {
+        let (field, rest) = fields.split_once(',').unwrap_or((fields, ""));
+        let (key, value) = field.split_once('=')
+            .ok_or_else(|| Error::parse(line, format!("expected `=` in `{}`", field)))?;
+        let (key, value) = (key.trim(), value.trim());
+        let dim = || {
+            value.parse::<usize>()
+                .map_err(|_| Error::parse(line, format!("invalid dimension `{}`", value)))
+        };
+        match key {
+            "x" => x = Some(dim()?),
+            "y" => y = Some(dim()?),
+            "rule" => {
+                rule = Some(fields.split_once('=').unwrap().1.trim().to_string());
+                break;
+            }
+            _ => return Err(Error::parse(line, format!("unknown header field `{}`", key))),
+        }
+        fields = rest;
+    }
+    match (x, y) {
+        (Some(x), Some(y)) => Ok((x, y, rule)),
+        _ => Err(Error::parse(line, "header is missing x or y")),
+    }
+}
+
+/// Read a pattern in RLE format
+pub fn read<R: BufRead>(reader: R) -> Result<Rle> {
+    let mut header = None;
+    let mut pattern = Frame::new(0, 0);
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut count = None;
+    let mut prefix = None;
+    let mut lines = 0;
+
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let lineno = n + 1;
+        lines = lineno;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if header.is_none() {
+            let (w, h, rule) = parse_header(lineno, trimmed)?;
+            cells::<u8>(w, h).map_err(|e| Error::parse(lineno, e.to_string()))?;
+            pattern = Frame::new(w, h);
+            header = Some(rule);
+            continue;
+        }
+
+        for c in trimmed.chars() {
+            if c.is_whitespace() {
+                continue;
+            }
+            if let Some(d) = c.to_digit(10) {
+                let n = count.unwrap_or(0usize).checked_mul(10)
+                    .and_then(|n| n.checked_add(d as usize))
+                    .ok_or_else(|| Error::parse(lineno, "run count is too large"))?;
+                count = Some(n);
+                continue;
+            }
+            if prefix.is_none() && ('p'..='y').contains(&c) {
+                prefix = Some(c as u8 - b'p' + 1);
+                continue;
+            }
+            let run = count.take().unwrap_or(1);
+            let state = match (prefix.take(), c) {
+                (None, '$') => {
+                    x = 0;
+                    // rows past the end are caught by the next run of cells
+                    y = y.saturating_add(run);
+                    continue;
+                }
+                (None, '!') => return Ok(Rle { pattern, rule: header.unwrap() }),
+                (None, 'b') | (None, '.') => 0,
+                (None, 'o') => 1,
+                (None, 'A'..='X') => c as u8 - b'A' + 1,
+                (Some(p), 'A'..='X') => {
+                    let state = p as usize * 24 + (c as u8 - b'A') as usize + 1;
+                    u8::try_from(state)
+                        .map_err(|_| Error::parse(lineno, format!("no state {}", state)))?
+                }
+                _ => return Err(Error::parse(lineno, format!("unexpected `{}`", c))),
+            };
+            if x.checked_add(run).is_none_or(|end| end > pattern.width())
+                || y >= pattern.height() {
+                return Err(Error::parse(lineno, "pattern is larger than its header"));
+            }
+            for _ in 0..run {
+                *pattern.get_mut(x, y) = state;
+                x += 1;
+            }
+        }
+    }
+
+    match header {
+        Some(rule) => Ok(Rle { pattern, rule }),
+        // the header should have come before the end of the input
+        None => Err(Error::parse(lines + 1, "missing header")),
+    }
+}
+
+/// The RLE token for a state, b/o are used when there are only two states
+fn token(state: u8, two_state: bool) -> String {
+    match state {
+        0 if two_state => "b".to_string(),
+        1 if two_state => "o".to_string(),
+        0 => ".".to_string(),
+        s if s <= 24 => ((b'A' + s - 1) as char).to_string(),
+        s => {
+            let (p, s) = ((s - 1) / 24, (s - 1) % 24);
+            format!("{}{}", (b'p' + p - 1) as char, (b'A' + s) as char)
+        }
+    }
+}
+
+/// Add a token to a list of runs, extending the last run if it matches
+fn push(runs: &mut Vec<(usize, String)>, t: String) {
+    match runs.last_mut() {
+        Some(&mut (ref mut n, ref last)) if *last == t => *n += 1,
+        _ => runs.push((1, t)),
+    }
+}
+
+/// Write frame in RLE format with an optional rule in the header. RLE has
+/// 256 states, so frames with cells past state 255 can't be written.
+pub fn write<W, T>(mut writer: W, frame: &Frame<T>, rule: Option<&str>) -> Result<()>
+where W: Write, T: CellState {
+    let (w, h) = (frame.width(), frame.height());
+    let states = frame.as_slice().iter()
+        .map(|e| u8::try_from(e.index())
+            .map_err(|_| Error::Format(format!("RLE has no state {}", e.index()))))
+        .collect::<Result<Vec<_>>>()?;
+    match rule {
+        Some(rule) => writeln!(writer, "x = {}, y = {}, rule = {}", w, h, rule)?,
+        None => writeln!(writer, "x = {}, y = {}", w, h)?,
+    }
+
+    let two_state = states.iter().all(|&s| s <= 1);
+    let mut runs = vec![];
+    for y in 0..h {
+        let row = &states[y * w..(y + 1) * w];
+        // trailing dead cells are implied by the end of the row
+        let len = row.iter().rposition(|&s| s != 0).map_or(0, |i| i + 1);
+        for &s in &row[..len] {
+            push(&mut runs, token(s, two_state));
+        }
+        if y + 1 < h {
+            push(&mut runs, "$".to_string());
+        }
+    }
+    // trailing empty rows are implied by the end of the pattern
+    while runs.last().is_some_and(|(_, t)| t == "$") {
+        runs.pop();
+    }
+
+    let mut line = String::new();
+    for (n, t) in runs {
+        let item = if n == 1 { t } else { format!("{}{}", n, t) };
+        if line.len() + item.len() > 70 {
+            writeln!(writer, "{}", line)?;
+            line.clear();
+        }
+        line.push_str(&item);
+    }
+    line.push('!');
+    writeln!(writer, "{}", line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::game_of_life::State;
+    use super::super::super::game_of_life::State::{Alive, Dead};
+    use super::super::{state, Error};
+    use super::{read, write};
+
+    const GLIDER: &str = "#N Glider\n#C A comment\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n";
+
+    #[test]
+    fn read_glider() {
+        let rle = read(GLIDER.as_bytes()).unwrap();
+        assert_eq!(rle.rule, Some("B3/S23".to_string()));
+
+        let mut expected = Frame::<u8>::new(3, 3);
+        *expected.get_mut(1, 0) = 1;
+        *expected.get_mut(2, 1) = 1;
+        *expected.get_mut(0, 2) = 1;
+        *expected.get_mut(1, 2) = 1;
+        *expected.get_mut(2, 2) = 1;
+        assert_eq!(rle.pattern, expected);
+    }
+
+    #[test]
+    fn place_offset() {
+        let rle = read(GLIDER.as_bytes()).unwrap();
+        let mut frame = Frame::<State>::new(5, 5);
+        rle.place(&mut frame, 3, 3);
+        assert_eq!(*frame.get(4, 3), Alive);
+        assert_eq!(*frame.get(0, 4), Alive);
+        assert_eq!(*frame.get(3, 0), Alive);
+        assert_eq!(*frame.get(3, 3), Dead);
+    }
+
+    #[test]
+    fn multi_line_and_runs() {
+        let rle = read("x = 2, y = 1\n3o!".as_bytes());
+        assert!(rle.is_err());
+
+        let rle = read("x = 12, y = 4\n10o\n2o$2$11bo!".as_bytes()).unwrap();
+        for x in 0..12 {
+            assert_eq!(*rle.pattern.get(x, 0), 1);
+        }
+        assert_eq!(*rle.pattern.get(11, 3), 1);
+        assert_eq!(*rle.pattern.get(10, 3), 0);
+    }
+
+    #[test]
+    fn malformed() {
+        let line = |text: &str| match read(text.as_bytes()) {
+            Err(Error::Parse { line, .. }) => line,
+            other => panic!("expected a parse error, not {:?}", other),
+        };
+        assert_eq!(line("x = 2, y = 1\n99999999999999999999999o!"), 2);
+        assert_eq!(line("x = 2, y = 1\n18446744073709551615bo!"), 2);
+        assert_eq!(line("x = 2, y = 1\n18446744073709551615$o!"), 2);
+        assert_eq!(line("x = 99999999999, y = 99999999999\n!"), 1);
+        assert_eq!(line(""), 1);
+        assert_eq!(line("#N Nothing\n#C at all\n"), 3);
+    }
+
+    #[test]
+    fn multi_state() {
+        let rle = read("x = 4, y = 1, rule = Generations\n.ABpA!".as_bytes()).unwrap();
+        assert_eq!(rle.pattern.map(|s| *s), {
+            let mut f = Frame::new(4, 1);
+            *f.get_mut(1, 0) = 1;
+            *f.get_mut(2, 0) = 2;
+            *f.get_mut(3, 0) = 25;
+            f
+        });
+    }
+
+    #[test]
+    fn high_states() {
+        let rle = read("x = 2, y = 1\nyOyN!".as_bytes()).unwrap();
+        assert_eq!((*rle.pattern.get(0, 0), *rle.pattern.get(1, 0)), (255, 254));
+        match read("x = 1, y = 1\nyP!".as_bytes()) {
+            Err(Error::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, not {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rule_with_commas() {
+        let rle = read("x = 3, y = 1, rule = n in {2, 3}\n3o!".as_bytes()).unwrap();
+        assert_eq!(rle.rule, Some("n in {2, 3}".to_string()));
+        assert_eq!(rle.dsl_rule(), Some("n in {2, 3}".parse()));
+        assert!(read(GLIDER.as_bytes()).unwrap().dsl_rule().unwrap().is_err());
+        assert!(read("x = 3, rule = B3/S23, y = 1\n3o!".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut frame = Frame::<State>::new(6, 4);
+        *frame.get_mut(0, 0) = Alive;
+        *frame.get_mut(1, 0) = Alive;
+        *frame.get_mut(5, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+
+        let mut out = vec![];
+        write(&mut out, &frame, Some("B3/S23")).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text, "x = 6, y = 4, rule = B3/S23\n2o$5bo$2bo!\n");
+
+        let rle = read(text.as_bytes()).unwrap();
+        assert_eq!(rle.pattern.map(|&s| state::<State>(s)), frame);
+    }
+
+    #[test]
+    fn multi_state_round_trip() {
+        let mut frame = Frame::<u8>::new(3, 2);
+        *frame.get_mut(0, 0) = 2;
+        *frame.get_mut(2, 1) = 30;
+
+        let mut out = vec![];
+        write(&mut out, &frame, None).unwrap();
+        let rle = read(&out[..]).unwrap();
+        assert_eq!(rle.pattern, frame);
+        assert_eq!(rle.rule, None);
+    }
+
+    #[test]
+    fn too_many_states() {
+        use super::super::super::two_color_life;
+
+        let mut frame = Frame::<two_color_life::State>::new(2, 1);
+        *frame.get_mut(0, 0) = two_color_life::State::Alive(254);
+        let mut out = vec![];
+        write(&mut out, &frame, None).unwrap();
+        assert_eq!(read(&out[..]).unwrap().pattern.get(0, 0), &255);
+
+        *frame.get_mut(1, 0) = two_color_life::State::Alive(255);
+        assert!(matches!(write(&mut vec![], &frame, None), Err(Error::Format(_))));
+    }
+}
@@ -0,0 +1,525 @@
+use super::super::Square;
+use super::{Error, Result};
+
+use std::io::BufRead;
+
+/// A rule loaded from one of Golly's `.rule` files
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleFile {
+    /// the name from the `@RULE` line
+    pub name: String,
+    pub rule: Rule,
+}
+
+/// An executable rule over cells that hold state indices, 0 being the
+/// background state
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rule {
+    Table(Table),
+    Tree(Tree),
+}
+
+impl Rule {
+    /// The number of states cells can be in
+    pub fn states(&self) -> u8 {
+        match *self {
+            Rule::Table(ref t) => t.states,
+            Rule::Tree(ref t) => t.states,
+        }
+    }
+
+    /// The next state of the cell at the center of curr
+    pub fn rule(&self, curr: Square<u8>) -> u8 {
+        match *self {
+            Rule::Table(ref t) => t.rule(curr),
+            Rule::Tree(ref t) => t.rule(curr),
+        }
+    }
+}
+
+/// Which cells a table looks at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighborhood {
+    Moore,
+    VonNeumann,
+}
+
+impl Neighborhood {
+    /// Offsets of the neighbors in the order transitions list them, clockwise
+    /// from north
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match *self {
+            Neighborhood::Moore => &[(0, -1), (1, -1), (1, 0), (1, 1),
+                                     (0, 1), (-1, 1), (-1, 0), (-1, -1)],
+            Neighborhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+        }
+    }
+}
+
+/// Part of a transition: either a fixed state or a variable
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Term {
+    State(u8),
+    Var(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Transition {
+    /// the center followed by the neighbors
+    inputs: Vec<Term>,
+    output: Term,
+}
+
+/// A rule given by a `@TABLE` section. Transitions are tried in order and the
+/// first that matches under one of the symmetries decides the new state; if
+/// none match the cell stays as it is. Every occurrence of a variable in a
+/// transition has to take the same value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    states: u8,
+    neighborhood: Neighborhood,
+    /// for each variable which states it can stand for
+    vars: Vec<Vec<bool>>,
+    transitions: Vec<Transition>,
+    /// for each symmetry, the index of the neighbor term that each neighbor is
+    /// compared against; None means every permutation
+    symmetries: Option<Vec<Vec<usize>>>,
+}
+
+impl Table {
+    /// The next state of the cell at the center of curr
+    pub fn rule(&self, curr: Square<u8>) -> u8 {
+        let center = *curr.get(0, 0);
+        let neighbors = self.neighborhood.offsets().iter()
+            .map(|&(i, j)| *curr.get(i, j))
+            .collect::<Vec<_>>();
+        self.transitions.iter()
+            .filter_map(|t| self.apply(t, center, &neighbors))
+            .next()
+            .unwrap_or(center)
+    }
+
+    fn apply(&self, t: &Transition, center: u8, neighbors: &[u8]) -> Option<u8> {
+        let mut bound = vec![None; self.vars.len()];
+        if !self.bind(t.inputs[0], center, &mut bound) {
+            return None;
+        }
+        let terms = &t.inputs[1..];
+        let matched = match self.symmetries {
+            Some(ref symmetries) => symmetries.iter().filter_map(|perm| {
+                let mut bound = bound.clone();
+                let all = neighbors.iter().zip(perm)
+                    .all(|(&n, &k)| self.bind(terms[k], n, &mut bound));
+                if all { Some(bound) } else { None }
+            }).next(),
+            None => {
+                let mut used = vec![false; terms.len()];
+                if self.permuted(terms, neighbors, &mut used, &mut bound) {
+                    Some(bound)
+                } else {
+                    None
+                }
+            }
+        };
+        matched.map(|bound| match t.output {
+            Term::State(s) => s,
+            Term::Var(v) => bound[v].unwrap(),
+        })
+    }
+
+    /// Match neighbors against terms in any order
+    fn permuted(&self, terms: &[Term], neighbors: &[u8], used: &mut [bool],
+                bound: &mut Vec<Option<u8>>) -> bool {
+        let (&n, rest) = match neighbors.split_first() {
+            Some(split) => split,
+            None => return true,
+        };
+        for k in 0..terms.len() {
+            if used[k] {
+                continue;
+            }
+            let saved = bound.clone();
+            if self.bind(terms[k], n, bound) {
+                used[k] = true;
+                if self.permuted(terms, rest, used, bound) {
+                    return true;
+                }
+                used[k] = false;
+            }
+            *bound = saved;
+        }
+        false
+    }
+
+    /// Whether state fits term, binding the variable if it isn't yet
+    fn bind(&self, term: Term, state: u8, bound: &mut [Option<u8>]) -> bool {
+        match term {
+            Term::State(s) => s == state,
+            Term::Var(v) => match bound[v] {
+                Some(b) => b == state,
+                None if self.vars[v].get(state as usize) == Some(&true) => {
+                    bound[v] = Some(state);
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// A rule given by a `@TREE` section, a decision tree over the neighbors in
+/// the order nw, ne, sw, se, n, w, e, s, center (or n, w, e, s, center for 4
+/// neighbors)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tree {
+    states: u8,
+    neighbors: usize,
+    /// the children of each node, or the new states for nodes at level 1
+    nodes: Vec<Vec<usize>>,
+}
+
+impl Tree {
+    /// The next state of the cell at the center of curr
+    pub fn rule(&self, curr: Square<u8>) -> u8 {
+        let offsets: &[(isize, isize)] = if self.neighbors == 8 {
+            &[(-1, -1), (1, -1), (-1, 1), (1, 1), (0, -1), (-1, 0), (1, 0), (0, 1), (0, 0)]
+        } else {
+            &[(0, -1), (-1, 0), (1, 0), (0, 1), (0, 0)]
+        };
+        let mut node = self.nodes.len() - 1;
+        for &(i, j) in offsets {
+            match self.nodes[node].get(*curr.get(i, j) as usize) {
+                Some(&next) => node = next,
+                None => return *curr.get(0, 0),
+            }
+        }
+        node as u8
+    }
+}
+
+/// Read a `.rule` file. Only the `@RULE` line and the `@TABLE` or `@TREE`
+/// section are used, other sections like `@COLORS` are skipped.
+pub fn read<R: BufRead>(reader: R) -> Result<RuleFile> {
+    let mut name = None;
+    let mut section = String::new();
+    let mut table = vec![];
+    let mut tree = vec![];
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.split('#').next().unwrap().trim().to_string();
+        if let Some(header) = line.strip_prefix('@') {
+            let mut words = header.split_whitespace();
+            section = words.next().unwrap_or("").to_string();
+            if section == "RULE" {
+                name = words.next().map(|s| s.to_string());
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        match &section[..] {
+            "TABLE" => table.push((n + 1, line)),
+            "TREE" => tree.push((n + 1, line)),
+            _ => {}
+        }
+    }
+
+    let name = name.ok_or_else(|| Error::parse(1, "missing @RULE"))?;
+    let rule = match (table.is_empty(), tree.is_empty()) {
+        (false, true) => Rule::Table(read_table(&table)?),
+        (true, false) => Rule::Tree(read_tree(&tree)?),
+        (true, true) => return Err(Error::parse(1, "missing @TABLE or @TREE")),
+        (false, false) => return Err(Error::parse(1, "both @TABLE and @TREE")),
+    };
+    Ok(RuleFile { name, rule })
+}
+
+fn read_table(lines: &[(usize, String)]) -> Result<Table> {
+    let mut states = None;
+    let mut neighborhood = Neighborhood::Moore;
+    let mut symmetry = "none".to_string();
+    let mut names: Vec<String> = vec![];
+    let mut vars: Vec<Vec<bool>> = vec![];
+    let mut transitions = vec![];
+
+    for &(n, ref line) in lines {
+        if let Some(def) = line.strip_prefix("var ") {
+            let states = states.ok_or_else(|| Error::parse(n, "n_states must come first"))?;
+            let (var, values) = def.split_once('=')
+                .ok_or_else(|| Error::parse(n, "expected `var name={...}`"))?;
+            let values = values.trim().strip_prefix('{').and_then(|v| v.strip_suffix('}'))
+                .ok_or_else(|| Error::parse(n, "expected `{` and `}` around the values"))?;
+            let mut allowed = vec![false; states as usize];
+            for value in values.split(',') {
+                match term(value.trim(), &names, states, n)? {
+                    Term::State(s) => allowed[s as usize] = true,
+                    Term::Var(v) => {
+                        for (a, &b) in allowed.iter_mut().zip(&vars[v]) {
+                            *a |= b;
+                        }
+                    }
+                }
+            }
+            names.push(var.trim().to_string());
+            vars.push(allowed);
+        } else if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => {
+                    let s = value.parse::<u8>().ok().filter(|&s| s >= 2)
+                        .ok_or_else(|| Error::parse(n, format!("invalid n_states `{}`", value)))?;
+                    states = Some(s);
+                }
+                "neighborhood" => neighborhood = match value {
+                    "Moore" => Neighborhood::Moore,
+                    "vonNeumann" => Neighborhood::VonNeumann,
+                    _ => return Err(Error::parse(n, format!("unsupported neighborhood `{}`",
+                                                            value))),
+                },
+                "symmetries" => symmetry = value.to_string(),
+                _ => return Err(Error::parse(n, format!("unknown key `{}`", key.trim()))),
+            }
+        } else {
+            let states = states.ok_or_else(|| Error::parse(n, "n_states must come first"))?;
+            let count = neighborhood.offsets().len() + 2;
+            let tokens = if line.contains(',') {
+                line.split(',').map(|t| t.trim().to_string()).collect::<Vec<_>>()
+            } else {
+                line.chars().filter(|c| !c.is_whitespace()).map(|c| c.to_string()).collect()
+            };
+            if tokens.len() != count {
+                return Err(Error::parse(n, format!("expected {} states in a transition but \
+                                                    found {}", count, tokens.len())));
+            }
+            let mut terms = tokens.iter()
+                .map(|t| term(t, &names, states, n))
+                .collect::<Result<Vec<_>>>()?;
+            let output = terms.pop().unwrap();
+            if let Term::Var(_) = output {
+                if !terms.contains(&output) {
+                    return Err(Error::parse(n, "the new state can only be a variable that is \
+                                                used in the same transition"));
+                }
+            }
+            transitions.push(Transition { inputs: terms, output });
+        }
+    }
+
+    let states = states.ok_or_else(|| Error::parse(lines[0].0, "missing n_states"))?;
+    let symmetries = symmetries(&symmetry, neighborhood)
+        .ok_or_else(|| Error::parse(lines[0].0, format!("unsupported symmetries `{}`",
+                                                        symmetry)))?;
+    Ok(Table { states, neighborhood, vars, transitions, symmetries })
+}
+
+/// Parse a state or the name of an already defined variable
+fn term(token: &str, names: &[String], states: u8, n: usize) -> Result<Term> {
+    if let Ok(s) = token.parse::<u8>() {
+        return if s < states { Ok(Term::State(s)) } else {
+            Err(Error::parse(n, format!("state {} is out of range", s)))
+        };
+    }
+    names.iter().position(|name| name == token)
+        .map(Term::Var)
+        .ok_or_else(|| Error::parse(n, format!("unknown variable `{}`", token)))
+}
+
+/// The neighbor permutations for a symmetry, or Some(None) for permute
+fn symmetries(symmetry: &str, neighborhood: Neighborhood) -> Option<Option<Vec<Vec<usize>>>> {
+    let size = neighborhood.offsets().len();
+    // how far a quarter turn moves a neighbor
+    let quarter = size / 4;
+    let (rotations, reflect) = match symmetry {
+        "none" => (vec![0], false),
+        "rotate4" => ((0..4).map(|k| k * quarter).collect(), false),
+        "rotate8" if neighborhood == Neighborhood::Moore => ((0..8).collect(), false),
+        "reflect_horizontal" => (vec![0], true),
+        "rotate4reflect" => ((0..4).map(|k| k * quarter).collect(), true),
+        "rotate8reflect" if neighborhood == Neighborhood::Moore => ((0..8).collect(), true),
+        "permute" => return Some(None),
+        _ => return None,
+    };
+    let mut perms = vec![];
+    for &k in &rotations {
+        perms.push((0..size).map(|i| (i + k) % size).collect());
+        if reflect {
+            perms.push((0..size).map(|i| (size - i + k) % size).collect());
+        }
+    }
+    Some(Some(perms))
+}
+
+fn read_tree(lines: &[(usize, String)]) -> Result<Tree> {
+    let mut states = None;
+    let mut neighbors = None;
+    let mut nodes: Vec<(usize, Vec<usize>)> = vec![];
+    for &(n, ref line) in lines {
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().parse::<usize>()
+                .map_err(|_| Error::parse(n, format!("invalid {}", key.trim())))?;
+            match key.trim() {
+                "num_states" if (2..=256).contains(&value) => states = Some(value),
+                "num_neighbors" if value == 4 || value == 8 => neighbors = Some(value),
+                "num_nodes" => {}
+                _ => return Err(Error::parse(n, format!("invalid {}", key.trim()))),
+            }
+            continue;
+        }
+        let (states, neighbors) = match (states, neighbors) {
+            (Some(s), Some(k)) => (s, k),
+            _ => return Err(Error::parse(n, "num_states and num_neighbors must come first")),
+        };
+        let numbers = line.split_whitespace()
+            .map(|w| w.parse::<usize>())
+            .collect::<::std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::parse(n, "expected a node"))?;
+        let (&level, children) = numbers.split_first()
+            .ok_or_else(|| Error::parse(n, "expected a node"))?;
+        if level == 0 || level > neighbors + 1 || children.len() != states {
+            return Err(Error::parse(n, "invalid node"));
+        }
+        let valid = children.iter().all(|&c| if level == 1 { c < states } else {
+            nodes.get(c).map(|&(l, _)| l) == Some(level - 1)
+        });
+        if !valid {
+            return Err(Error::parse(n, "invalid node"));
+        }
+        nodes.push((level, children.to_vec()));
+    }
+
+    let last = lines.last().map_or(1, |&(n, _)| n);
+    match (states, neighbors, nodes.last()) {
+        (Some(states), Some(neighbors), Some(&(level, _))) if level == neighbors + 1 => Ok(Tree {
+            states: states as u8,
+            neighbors,
+            nodes: nodes.into_iter().map(|(_, c)| c).collect(),
+        }),
+        _ => Err(Error::parse(last, "the last node must be the root of the tree")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::game_of_life;
+    use super::super::super::game_of_life::State;
+    use super::read;
+
+    use std::fmt::Write;
+
+    const LIFE_TABLE: &str = "@RULE Life
+# Conway's Game of Life as a table
+@TABLE
+n_states:2
+neighborhood:Moore
+symmetries:permute
+var a={0,1}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+var g={a}
+var h={a}
+var i={a}
+0,1,1,1,0,0,0,0,0,1
+1,1,1,0,0,0,0,0,0,1
+1,1,1,1,0,0,0,0,0,1
+1,a,b,c,d,e,f,g,h,0
+@COLORS
+1 255 255 255
+";
+
+    /// Build the tree for Life, where the node at each level for a given count
+    /// of live neighbors seen so far branches to the counts one level down
+    fn life_tree_text() -> String {
+        let mut out = "@RULE Life\n@TREE\nnum_states=2\nnum_neighbors=8\n".to_string();
+        // at level 1 the count is complete and the center decides
+        for count in 0..9 {
+            let dead = if count == 3 { 1 } else { 0 };
+            let alive = if count == 2 || count == 3 { 1 } else { 0 };
+            writeln!(out, "1 {} {}", dead, alive).unwrap();
+        }
+        let mut below: Vec<usize> = (0..9).collect();
+        let mut next = 9;
+        for level in 2..10 {
+            let mut here = vec![];
+            for count in 0..10 - level {
+                writeln!(out, "{} {} {}", level, below[count], below[count + 1]).unwrap();
+                here.push(next);
+                next += 1;
+            }
+            below = here;
+        }
+        out
+    }
+
+    fn soup() -> Frame<State> {
+        let mut frame = Frame::new(12, 10);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (6, 5), (7, 5), (8, 5),
+                         (7, 6), (5, 7), (9, 1), (10, 2), (9, 2)] {
+            *frame.get_mut(x, y) = State::Alive;
+        }
+        frame
+    }
+
+    fn matches_life(rule: &super::Rule) {
+        let mut frame = soup();
+        let mut cells = frame.map(|s| if *s == State::Alive { 1u8 } else { 0 });
+        for _ in 0..8 {
+            frame = frame.next_frame(game_of_life::rule);
+            cells = cells.next_frame(|sq| rule.rule(sq));
+            assert_eq!(cells, frame.map(|s| if *s == State::Alive { 1 } else { 0 }));
+        }
+    }
+
+    #[test]
+    fn life_table() {
+        let file = read(LIFE_TABLE.as_bytes()).unwrap();
+        assert_eq!(file.name, "Life");
+        assert_eq!(file.rule.states(), 2);
+        matches_life(&file.rule);
+    }
+
+    #[test]
+    fn life_tree() {
+        let file = read(life_tree_text().as_bytes()).unwrap();
+        assert_eq!(file.name, "Life");
+        matches_life(&file.rule);
+    }
+
+    #[test]
+    fn symmetric_table() {
+        // an empty cell with a 1 to its north becomes 2, and under rotate4 so
+        // does one with a 1 in any of the other directions
+        let text = "@RULE Turn\n@TABLE\nn_states:3\nneighborhood:vonNeumann\n\
+                    symmetries:rotate4\n010002\n";
+        let rule = read(text.as_bytes()).unwrap().rule;
+        let mut frame = Frame::<u8>::new(5, 5);
+        *frame.get_mut(2, 2) = 1;
+        let next = frame.next_frame(|sq| rule.rule(sq));
+        assert_eq!(*next.get(2, 1), 2);
+        assert_eq!(*next.get(3, 2), 2);
+        assert_eq!(*next.get(2, 3), 2);
+        assert_eq!(*next.get(1, 2), 2);
+        // the 1 itself has nothing to match and stays
+        assert_eq!(*next.get(2, 2), 1);
+        assert_eq!(*next.get(1, 1), 0);
+    }
+
+    #[test]
+    fn errors() {
+        let bad = |table: &str| read(format!("@RULE x\n@TABLE\n{}", table).as_bytes()).is_err();
+        assert!(bad("n_states:2\n0,0,0,0,0,0,0,0,0\n"));
+        assert!(bad("n_states:2\n0,0,0,0,0,0,0,0,0,2\n"));
+        assert!(bad("n_states:2\n0,0,0,0,0,0,0,0,0,a\n"));
+        assert!(bad("n_states:2\nvar a={0,1}\n0,0,0,0,0,0,0,0,0,a\n"));
+        assert!(bad("n_states:2\nneighborhood:hexagonal\n"));
+        assert!(bad("n_states:2\nneighborhood:vonNeumann\nsymmetries:rotate8\n"));
+        assert!(read("@TABLE\nn_states:2\n".as_bytes()).is_err());
+        assert!(read("@RULE x\n".as_bytes()).is_err());
+        assert!(read("@RULE x\n@TREE\nnum_states=2\nnum_neighbors=4\n1 0 0\n".as_bytes()).is_err());
+        assert!(read("@RULE x\n@TREE\nnum_states=2\nnum_neighbors=4\n2 5 5\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use super::super::{Checkpoint, Edge, Frame, Topology};
+use super::{csv, Error, Result};
+
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+const MAGIC: &str = "#simulation 1";
+
+/// What a simulation was running with besides its frame: the name of its
+/// rule, the seed to carry on the rng of random rules from and how the edges
+/// of its frame are joined
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Setup {
+    pub rule: String,
+    /// the seed random rules were reseeded with when the simulation was
+    /// saved, e.g. by `StochasticLife::checkpoint_seed`
+    pub seed: Option<[u32; 4]>,
+    pub topology: Topology,
+}
+
+impl Setup {
+    /// The setup of a simulation running the rule called rule on a torus,
+    /// without a seed
+    pub fn new(rule: &str) -> Setup {
+        Setup { rule: rule.to_string(), ..Setup::default() }
+    }
+
+    pub fn with_seed(self, seed: [u32; 4]) -> Setup {
+        Setup { seed: Some(seed), ..self }
+    }
+
+    pub fn with_topology(self, topology: Topology) -> Setup {
+        Setup { topology, ..self }
+    }
+}
+
+/// A simulation saved to disk, together with its setup so that the same rule
+/// can be picked when it is loaded again
+#[derive(Clone, Debug, PartialEq)]
+pub struct Save<T> {
+    pub checkpoint: Checkpoint<T>,
+    pub setup: Setup,
+}
+
+fn edge_name(edge: Edge) -> &'static str {
+    match edge {
+        Edge::Wrap => "wrap",
+        Edge::Flip => "flip",
+        Edge::Bounded => "bounded",
+    }
+}
+
+fn parse_edge(name: &str) -> Option<Edge> {
+    match name {
+        "wrap" => Some(Edge::Wrap),
+        "flip" => Some(Edge::Flip),
+        "bounded" => Some(Edge::Bounded),
+        _ => None,
+    }
+}
+
+/// Write checkpoint and the setup it was running with. The file starts with
+/// a few `#` header lines followed by the cells as comma separated values, so
+/// any `T` that can be displayed and parsed back can be saved.
+pub fn write<W, T>(mut writer: W, checkpoint: &Checkpoint<T>, setup: &Setup) -> Result<()>
+where W: Write, T: Display {
+    #[cfg(feature = "tracing")]
+    let _span = debug_span!("write_save", generation = checkpoint.generation,
+                            rule = setup.rule.as_str()).entered();
+    if setup.rule.contains('\n') {
+        return Err(Error::Format("rule names can't contain newlines".to_string()));
+    }
+    writeln!(writer, "{}", MAGIC)?;
+    writeln!(writer, "#generation {}", checkpoint.generation)?;
+    writeln!(writer, "#rule {}", setup.rule)?;
+    if let Some([a, b, c, d]) = setup.seed {
+        writeln!(writer, "#seed {} {} {} {}", a, b, c, d)?;
+    }
+    writeln!(writer, "#topology {} {}",
+             edge_name(setup.topology.x), edge_name(setup.topology.y))?;
+    writeln!(writer, "#size {} {}", checkpoint.frame.width(), checkpoint.frame.height())?;
+    csv::write(writer, &checkpoint.frame, ',')
+}
+
+/// Read a simulation written by write
+pub fn read<R, T>(mut reader: R) -> Result<Save<T>>
+where R: BufRead, T: FromStr + Default + Clone {
+    #[cfg(feature = "tracing")]
+    let _span = debug_span!("read_save").entered();
+    let mut lineno = 0;
+    let mut line = String::new();
+    let mut next = |line: &mut String| -> Result<usize> {
+        lineno += 1;
+        line.clear();
+        reader.read_line(line)?;
+        let len = line.trim_end_matches(['\r', '\n']).len();
+        line.truncate(len);
+        Ok(lineno)
+    };
+    let field = |line: &str, n: usize, key: &str| -> Result<String> {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(' '))
+            .map(|rest| rest.to_string())
+            .ok_or_else(|| Error::parse(n, format!("expected {}", key)))
+    };
+    let numbers = |text: &str, n: usize, len: usize, what: &str| -> Result<Vec<u64>> {
+        text.split(' ')
+            .map(|num| num.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+            .filter(|nums| nums.len() == len)
+            .ok_or_else(|| Error::parse(n, format!("invalid {} `{}`", what, text)))
+    };
+
+    let n = next(&mut line)?;
+    if line != MAGIC {
+        return Err(Error::parse(n, "not a saved simulation"));
+    }
+    let n = next(&mut line)?;
+    let generation = numbers(&field(&line, n, "#generation")?, n, 1, "generation")?[0];
+    let n = next(&mut line)?;
+    let mut setup = Setup::new(&field(&line, n, "#rule")?);
+
+    let mut n = next(&mut line)?;
+    if line.starts_with("#seed") {
+        let seed = field(&line, n, "#seed")?;
+        let nums = numbers(&seed, n, 4, "seed")?;
+        if nums.iter().any(|&s| s > u32::MAX as u64) {
+            return Err(Error::parse(n, format!("invalid seed `{}`", seed)));
+        }
+        setup.seed = Some([nums[0] as u32, nums[1] as u32, nums[2] as u32, nums[3] as u32]);
+        n = next(&mut line)?;
+    }
+    let topology = field(&line, n, "#topology")?;
+    let edges = topology.split(' ').map(parse_edge).collect::<Option<Vec<_>>>()
+        .filter(|edges| edges.len() == 2)
+        .ok_or_else(|| Error::parse(n, format!("invalid topology `{}`", topology)))?;
+    setup.topology = Topology { x: edges[0], y: edges[1] };
+
+    let n = next(&mut line)?;
+    let size = numbers(&field(&line, n, "#size")?, n, 2, "size")?;
+    let (width, height) = (size[0] as usize, size[1] as usize);
+
+    let frame: Frame<T> = csv::read(reader, ',').map_err(|e| match e {
+        Error::Parse { line, message } => Error::parse(line + n, message),
+        e => e,
+    })?;
+    if frame.width() != width || frame.height() != height {
+        return Err(Error::parse(n, format!("expected a {}x{} frame but found {}x{}",
+                                           width, height, frame.width(), frame.height())));
+    }
+    Ok(Save { checkpoint: Checkpoint { frame, generation }, setup })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{Checkpoint, Frame, Topology};
+    use super::{read, write, Setup};
+
+    fn checkpoint() -> Checkpoint<f32> {
+        let mut frame = Frame::new(3, 2);
+        *frame.get_mut(0, 0) = 0.5;
+        *frame.get_mut(2, 1) = 1.0;
+        Checkpoint { frame, generation: 1234 }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut out = vec![];
+        write(&mut out, &checkpoint(), &Setup::new("lenia orbium")).unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(),
+                   "#simulation 1\n#generation 1234\n#rule lenia orbium\n#topology wrap wrap\n\
+                    #size 3 2\n0.5,0,0\n0,0,1\n");
+        let save = read::<_, f32>(&out[..]).unwrap();
+        assert_eq!(save.checkpoint, checkpoint());
+        assert_eq!(save.setup, Setup::new("lenia orbium"));
+
+        let setup = Setup::new("stochastic 0.5 0.5")
+            .with_seed([1, 2, 3, u32::MAX])
+            .with_topology(Topology::mobius());
+        let mut out = vec![];
+        write(&mut out, &checkpoint(), &setup).unwrap();
+        assert!(String::from_utf8(out.clone()).unwrap()
+                .contains("#seed 1 2 3 4294967295\n#topology flip bounded\n"));
+        let save = read::<_, f32>(&out[..]).unwrap();
+        assert_eq!(save.checkpoint, checkpoint());
+        assert_eq!(save.setup, setup);
+    }
+
+    #[test]
+    fn errors() {
+        match write(vec![], &checkpoint(), &Setup::new("a\nb")) {
+            Err(super::Error::Format(_)) => (),
+            _ => panic!("expected a format error"),
+        }
+        assert!(read::<_, f32>("1,2\n".as_bytes()).is_err());
+        assert!(read::<_, f32>("#simulation 1\n#generation x\n".as_bytes()).is_err());
+        let wrong_size = "#simulation 1\n#generation 0\n#rule r\n#topology wrap wrap\n\
+                          #size 2 2\n1,2\n";
+        assert!(read::<_, f32>(wrong_size.as_bytes()).is_err());
+        let bad_seed = "#simulation 1\n#generation 0\n#rule r\n#seed 1 2 3 4294967296\n\
+                        #topology wrap wrap\n#size 1 1\n1\n";
+        assert!(read::<_, f32>(bad_seed.as_bytes()).is_err());
+        let bad_topology = "#simulation 1\n#generation 0\n#rule r\n#topology wrap\n\
+                            #size 1 1\n1\n";
+        assert!(read::<_, f32>(bad_topology.as_bytes()).is_err());
+        let bad_cell = "#simulation 1\n#generation 0\n#rule r\n#topology wrap bounded\n\
+                        #size 2 1\n1,x\n";
+        match read::<_, f32>(bad_cell.as_bytes()) {
+            Err(super::Error::Parse { line, .. }) => assert_eq!(line, 6),
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use super::{Frame, Square};
+use super::convolve::Kernel;
+#[cfg(feature = "fft")]
+use super::fft::{CachedConvolver, FFT_RADIUS};
+
+/// Parameters and precomputed kernel for Lenia, a continuous automaton with
+/// smooth ring-shaped kernels and a gaussian growth mapping
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lenia {
+    /// the neighborhood potential with the highest growth
+    pub mu: f32,
+    /// how quickly growth falls off away from mu
+    pub sigma: f32,
+    /// the fraction of the growth applied each step
+    pub dt: f32,
+    radius: usize,
+    kernel: Vec<(isize, isize, f32)>,
+    /// the kernel laid out for `Frame::convolve`
+    weights: Kernel<f32>,
+    /// the transform of the kernel, for convolving with it when it is large
+    #[cfg(feature = "fft")]
+    transform: CachedConvolver,
+}
+
+/// The bump used for each ring of the kernel, defined on (0, 1)
+fn kernel_core(r: f32) -> f32 {
+    if r <= 0. || r >= 1. {
+        0.
+    } else {
+        (4. - 1. / (r * (1. - r))).exp()
+    }
+}
+
+/// The cells of Orbium from Bert Chan's Lenia, row by row, to be run with
+/// `Lenia::orbium`
+const ORBIUM: [[f32; 20]; 20] = [
+    [0., 0., 0., 0., 0., 0., 0.1, 0.14, 0.1, 0., 0., 0.03, 0.03, 0., 0., 0.3, 0., 0., 0., 0.],
+    [0., 0., 0., 0., 0., 0.08, 0.24, 0.3, 0.3, 0.18, 0.14, 0.15, 0.16, 0.15, 0.09, 0.2, 0., 0., 0., 0.],
+    [0., 0., 0., 0., 0., 0.15, 0.34, 0.44, 0.46, 0.38, 0.18, 0.14, 0.11, 0.13, 0.19, 0.18, 0.45, 0., 0., 0.],
+    [0., 0., 0., 0., 0.06, 0.13, 0.39, 0.5, 0.5, 0.37, 0.06, 0., 0., 0., 0.02, 0.16, 0.68, 0., 0., 0.],
+    [0., 0., 0., 0.11, 0.17, 0.17, 0.33, 0.4, 0.38, 0.28, 0.14, 0., 0., 0., 0., 0., 0.18, 0.42, 0., 0.],
+    [0., 0., 0.09, 0.18, 0.13, 0.06, 0.08, 0.26, 0.32, 0.32, 0.27, 0., 0., 0., 0., 0., 0., 0.82, 0., 0.],
+    [0.27, 0., 0.16, 0.12, 0., 0., 0., 0.25, 0.38, 0.44, 0.45, 0.34, 0., 0., 0., 0., 0., 0.22, 0.17, 0.],
+    [0., 0.07, 0.2, 0.02, 0., 0., 0., 0.31, 0.48, 0.57, 0.6, 0.57, 0., 0., 0., 0., 0., 0., 0.49, 0.],
+    [0., 0.59, 0.19, 0., 0., 0., 0., 0.2, 0.57, 0.69, 0.76, 0.76, 0.49, 0., 0., 0., 0., 0., 0.36, 0.],
+    [0., 0.58, 0.19, 0., 0., 0., 0., 0., 0.67, 0.83, 0.9, 0.92, 0.87, 0.12, 0., 0., 0., 0., 0.22, 0.07],
+    [0., 0., 0.46, 0., 0., 0., 0., 0., 0.7, 0.93, 1., 1., 1., 0.61, 0., 0., 0., 0., 0.18, 0.11],
+    [0., 0., 0.82, 0., 0., 0., 0., 0., 0.47, 1., 1., 0.98, 1., 0.96, 0.27, 0., 0., 0., 0.19, 0.1],
+    [0., 0., 0.46, 0., 0., 0., 0., 0., 0.25, 1., 1., 0.84, 0.92, 0.97, 0.54, 0.14, 0.04, 0.1, 0.21, 0.05],
+    [0., 0., 0., 0.4, 0., 0., 0., 0., 0.09, 0.8, 1., 0.82, 0.8, 0.85, 0.63, 0.31, 0.18, 0.19, 0.2, 0.01],
+    [0., 0., 0., 0.36, 0.1, 0., 0., 0., 0.05, 0.54, 0.86, 0.79, 0.74, 0.72, 0.6, 0.39, 0.28, 0.24, 0.13, 0.],
+    [0., 0., 0., 0.01, 0.3, 0.07, 0., 0., 0.08, 0.36, 0.64, 0.7, 0.64, 0.6, 0.51, 0.39, 0.29, 0.19, 0.04, 0.],
+    [0., 0., 0., 0., 0.1, 0.24, 0.14, 0.1, 0.15, 0.29, 0.45, 0.53, 0.52, 0.46, 0.4, 0.31, 0.21, 0.08, 0., 0.],
+    [0., 0., 0., 0., 0., 0.08, 0.21, 0.21, 0.22, 0.29, 0.36, 0.39, 0.37, 0.33, 0.26, 0.18, 0.09, 0., 0., 0.],
+    [0., 0., 0., 0., 0., 0., 0.03, 0.13, 0.19, 0.22, 0.24, 0.24, 0.23, 0.18, 0.13, 0.05, 0., 0., 0., 0.],
+    [0., 0., 0., 0., 0., 0., 0., 0., 0.02, 0.06, 0.08, 0.09, 0.07, 0.05, 0.01, 0., 0., 0., 0., 0.],
+];
+
+impl Lenia {
+    /// Create a Lenia rule with the given kernel radius and ring peaks. There
+    /// is one concentric ring of the kernel per peak, with the given height.
+    pub fn new(radius: usize, peaks: &[f32], mu: f32, sigma: f32, dt: f32) -> Lenia {
+        assert!(!peaks.is_empty());
+        let r = radius as isize;
+
+        let mut kernel = vec![];
+        for i in -r..r + 1 {
+            for j in -r..r + 1 {
+                let d = ((i * i + j * j) as f32).sqrt() / radius as f32;
+                if d >= 1. {
+                    continue;
+                }
+                let br = d * peaks.len() as f32;
+                let w = peaks[br as usize] * kernel_core(br.fract());
+                if w > 0. {
+                    kernel.push((i, j, w));
+                }
+            }
+        }
+
+        // normalize so the potential stays in [0, 1]
+        let total = kernel.iter().map(|&(_, _, w)| w).sum::<f32>();
+        for e in &mut kernel {
+            e.2 /= total;
+        }
+
+        let weights = Kernel::from_offsets(&kernel);
+        Lenia {
+            mu,
+            sigma,
+            dt,
+            radius,
+            kernel,
+            weights,
+            #[cfg(feature = "fft")]
+            transform: CachedConvolver::new(),
+        }
+    }
+
+    /// The parameters for Orbium, the classic glider-like Lenia creature
+    pub fn orbium() -> Lenia {
+        Lenia::new(13, &[1.], 0.15, 0.015, 0.1)
+    }
+
+    /// A 20 by 20 frame holding Orbium, which glides with `Lenia::orbium`, to
+    /// be placed in a larger frame with room for it to move
+    pub fn orbium_cells() -> Frame<f32> {
+        Frame::new_from_fn(20, 20, |x, y| ORBIUM[y][x])
+    }
+
+    /// The parameters for Hydrogeminium natans, a self-replicating creature
+    /// with a three ring kernel
+    pub fn hydrogeminium() -> Lenia {
+        Lenia::new(18, &[0.5, 1., 2. / 3.], 0.26, 0.036, 0.5)
+    }
+
+    /// The radius of the kernel
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    /// The weight of each neighbor as its offset and weight, which add up to 1
+    pub fn kernel(&self) -> &[(isize, isize, f32)] {
+        &self.kernel
+    }
+
+    /// Map a neighborhood potential to a growth in [-1, 1]
+    pub fn growth(&self, u: f32) -> f32 {
+        let d = (u - self.mu) / self.sigma;
+        2. * (-d * d / 2.).exp() - 1.
+    }
+
+    /// The potential of a cell, i.e. the kernel weighted sum of its neighbors
+    pub fn potential(&self, curr: &Square<f32>) -> f32 {
+        self.kernel.iter()
+            .map(|&(i, j, w)| { curr.get(i, j) * w })
+            .sum()
+    }
+
+    /// The potential of every cell of frame at once, summed by convolving
+    /// the frame with the kernel. With the fft feature, kernels with a radius
+    /// of at least `FFT_RADIUS` are convolved through their transform, which
+    /// is kept for as long as frames stay the same size.
+    pub fn potentials(&self, frame: &Frame<f32>) -> Frame<f32> {
+        #[cfg(feature = "fft")]
+        {
+            if self.radius >= FFT_RADIUS {
+                return self.transform.convolve(&self.weights, frame);
+            }
+        }
+        frame.convolve(&self.weights)
+    }
+
+    /// The rule for Lenia, to be used with `Frame::next_frame`. This sums the
+    /// whole kernel for every cell, so `step` is much faster for stepping a
+    /// whole frame.
+    pub fn rule(&self, curr: Square<f32>) -> f32 {
+        let u = self.potential(&curr);
+        self.grow(*curr.get(0, 0), u)
+    }
+
+    /// The frame after frame, the same as stepping it with `rule` up to
+    /// rounding
+    pub fn step(&self, frame: &Frame<f32>) -> Frame<f32> {
+        let potentials = self.potentials(frame);
+        let cells = frame.as_slice().iter().zip(potentials.as_slice())
+            .map(|(&c, &u)| self.grow(c, u))
+            .collect();
+        Frame::from_vec(frame.width(), frame.height(), cells).unwrap()
+    }
+
+    /// The next value of a cell holding c with potential u
+    fn grow(&self, c: f32, u: f32) -> f32 {
+        (c + self.dt * self.growth(u)).clamp(0., 1.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::Lenia;
+
+    #[test]
+    fn kernel_normalized() {
+        for lenia in &[Lenia::orbium(), Lenia::hydrogeminium()] {
+            let total = lenia.kernel.iter().map(|&(_, _, w)| w).sum::<f32>();
+            assert!((total - 1.).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn growth() {
+        let lenia = Lenia::orbium();
+        assert!((lenia.growth(lenia.mu) - 1.).abs() < 1e-6);
+        assert!((lenia.growth(0.) + 1.).abs() < 1e-6);
+        assert!((lenia.growth(1.) + 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn empty_stays_empty() {
+        let lenia = Lenia::new(3, &[1.], 0.15, 0.015, 0.1);
+        let frame = Frame::<f32>::new(8, 8);
+        let next = frame.next_frame(|sq| lenia.rule(sq));
+        assert_eq!(next, frame);
+    }
+
+    #[test]
+    fn orbium_survives() {
+        let lenia = Lenia::orbium();
+        let mut frame = Frame::<f32>::new(64, 64);
+        for (x, y, &v) in Lenia::orbium_cells().enumerate_squares() {
+            *frame.get_mut(x + 10, y + 10) = v;
+        }
+        let mass = |f: &Frame<f32>| f.as_slice().iter().sum::<f32>();
+        let start = mass(&frame);
+        for _ in 0..20 {
+            frame = lenia.step(&frame);
+        }
+        // a creature keeps roughly its mass rather than dying out or filling
+        // the frame
+        assert!((mass(&frame) - start).abs() < start / 2., "{} became {}", start, mass(&frame));
+    }
+
+    #[test]
+    fn step() {
+        let lenia = Lenia::new(4, &[0.5, 1.], 0.15, 0.03, 0.1);
+        let mut frame = Frame::<f32>::new(12, 10);
+        for (i, cell) in frame.as_mut_slice().iter_mut().enumerate() {
+            *cell = (i * 7 % 11) as f32 / 10.;
+        }
+        let fast = lenia.step(&frame);
+        let slow = frame.next_frame(|sq| lenia.rule(sq));
+        for (a, b) in fast.as_slice().iter().zip(slow.as_slice()) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn full_decays() {
+        let lenia = Lenia::new(3, &[1.], 0.15, 0.015, 0.1);
+        let mut frame = Frame::<f32>::new(8, 8);
+        for x in 0..8 {
+            for y in 0..8 {
+                *frame.get_mut(x, y) = 1.;
+            }
+        }
+        let next = frame.next_frame(|sq| lenia.rule(sq));
+        for (_, _, v) in next.enumerate_squares() {
+            assert!((*v - 0.9).abs() < 1e-4);
+        }
+    }
+}
@@ -1,12 +1,282 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// without std the parts of std that are in core are still reached through std
+#[cfg(not(feature = "std"))]
+extern crate core as std;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "http")]
+extern crate axum;
+#[cfg(feature = "approx")]
+#[cfg_attr(test, macro_use)]
+extern crate approx;
+#[cfg(feature = "tui")]
+extern crate crossterm;
+#[cfg(feature = "editor")]
+extern crate eframe;
+#[cfg(feature = "std")]
+extern crate flate2;
+#[cfg(feature = "std")]
+extern crate gif;
+#[cfg(feature = "image")]
+extern crate image;
+// the pyo3 macros refer to ::core, which edition 2015 crates have to declare
+#[cfg(feature = "python")]
+extern crate core;
+#[cfg(feature = "python")]
+extern crate numpy;
+#[cfg(feature = "memmap")]
+extern crate memmap2;
+#[cfg(feature = "gpu")]
+extern crate pollster;
+#[cfg(feature = "testing")]
+extern crate proptest;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "window")]
+extern crate minifb;
+#[cfg(feature = "rand")]
 extern crate rand;
+#[cfg(feature = "ratatui")]
+extern crate ratatui;
+#[cfg(feature = "script")]
+extern crate rhai;
+#[cfg(feature = "fft")]
+extern crate rustfft;
+#[cfg(feature = "sdl")]
+extern crate sdl2;
+#[cfg(feature = "server")]
+extern crate tungstenite;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "http")]
+extern crate tokio_stream;
+#[cfg(feature = "tracing")]
+#[macro_use]
+extern crate tracing;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "gpu")]
+extern crate wgpu;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+/// The neighborhood of a cell that rules look at
+mod square;
+pub use square::*;
+
+/// Rules for stepping cells, and rules that can be undone
+mod rule;
+pub use rule::*;
+
+/// A uniform way of numbering, writing and coloring the states of cells
+mod state;
+pub use state::*;
 
 /// Contains functions that deal with handling individual frames of a simulation
+#[cfg(feature = "alloc")]
 mod frame;
 
 // Expose all of this at the root
+#[cfg(feature = "alloc")]
 pub use frame::*;
 
+/// Frames with a size known at compile time, for running without an allocator
+mod fixed;
+pub use fixed::*;
+
+/// Finding and cropping to the part of a frame that is alive
+#[cfg(feature = "alloc")]
+mod bounds;
+#[cfg(feature = "alloc")]
+pub use bounds::*;
+
+/// Reusing the buffers of frames that are no longer needed
+#[cfg(feature = "alloc")]
+mod pool;
+#[cfg(feature = "alloc")]
+pub use pool::*;
+
+/// Comparing frames of floating point cells up to rounding
+#[cfg(feature = "alloc")]
+mod approximate;
+
+/// Frames filled at random
+#[cfg(feature = "rand")]
+mod random;
+
+/// Drives a simulation forward one generation at a time
+#[cfg(feature = "alloc")]
+mod simulation;
+#[cfg(feature = "alloc")]
+pub use simulation::*;
+/// Editing frames with undo and redo
+#[cfg(feature = "alloc")]
+pub mod edit;
+/// Brushes, shapes and stamps for painting cells
+#[cfg(feature = "alloc")]
+pub mod paint;
+/// Copying, cutting and pasting rectangles of cells
+#[cfg(feature = "alloc")]
+pub mod selection;
+/// Boards whose cells are the nodes of an arbitrary graph
+#[cfg(feature = "alloc")]
+pub mod graph;
+/// Entities that move around a frame and read and write its cells, stepped
+/// alongside the rule
+#[cfg(feature = "alloc")]
+pub mod agents;
+/// The frames of a run kept as the cells that change each generation
+#[cfg(feature = "alloc")]
+pub mod history;
+/// Switching between rules from generation to generation and region to
+/// region
+#[cfg(feature = "alloc")]
+pub mod schedule;
+/// Global values, like a temperature, that rules read and that are updated
+/// from the whole frame every generation
+#[cfg(feature = "alloc")]
+pub mod environment;
+/// Births, deaths and other changes of state that happen while stepping
+#[cfg(feature = "alloc")]
+pub mod events;
+
 /// Public functions that define Conway's Game of Life
 pub mod game_of_life;
+#[cfg(feature = "alloc")]
 pub mod two_color_life;
+#[cfg(feature = "std")]
 pub mod rainbow_life;
+#[cfg(feature = "std")]
+pub mod smooth_life;
+#[cfg(feature = "std")]
+pub mod lenia;
+#[cfg(feature = "alloc")]
+pub mod cyclic;
+/// Rules where cells take several generations to die, like Brian's Brain
+pub mod generations;
+/// Enumerating and sampling Life-like and Generations rules
+pub mod rule_space;
+/// Automata that can be run backwards, second order ones whatever their rule
+/// and block ones whose rule has an inverse
+#[cfg(feature = "alloc")]
+pub mod reversible;
+/// Weighted sums over the neighborhood of every cell at once, the core of
+/// continuous rules like SmoothLife and Lenia
+#[cfg(feature = "std")]
+pub mod convolve;
+#[cfg(feature = "std")]
+pub mod dla;
+/// Smooth random starting frames from gradient noise
+#[cfg(feature = "std")]
+pub mod noise;
+#[cfg(feature = "std")]
+pub mod stochastic_life;
+#[cfg(feature = "alloc")]
+pub mod lookup;
+/// Constant time sums over rectangles of a frame, for rules with large
+/// neighborhoods
+#[cfg(feature = "alloc")]
+pub mod summed_area;
+/// Shrinking frames by reducing blocks of cells to one
+#[cfg(feature = "alloc")]
+pub mod downsample;
+/// A small language for writing rules as text, e.g. in scenario files
+#[cfg(feature = "std")]
+pub mod dsl;
+/// Stepping large frames on several threads, each owning a strip of rows
+#[cfg(feature = "std")]
+pub mod tiled;
+/// Frames kept in memory mapped files and stepped a strip of rows at a time
+#[cfg(feature = "memmap")]
+pub mod mapped;
+#[cfg(feature = "alloc")]
+pub mod patterns;
+/// Working through many independent runs on several threads
+#[cfg(feature = "std")]
+mod parallel;
+/// Finding out what patterns do when run, like how long they take to repeat
+#[cfg(feature = "std")]
+pub mod analysis;
+/// Running the same scenario from many seeds and summarizing how the runs end
+#[cfg(feature = "std")]
+pub mod ensemble;
+/// Evaluating a measure of how runs go over a grid of rule parameters
+#[cfg(feature = "std")]
+pub mod sweep;
+/// Splitting boards into objects and counting them
+#[cfg(feature = "std")]
+pub mod census;
+/// Running random soups and tallying the objects they leave behind
+#[cfg(feature = "std")]
+pub mod search;
+/// Evolving rules that score well on a fitness function
+#[cfg(feature = "std")]
+pub mod evolve;
+/// Timing the parts of each step, for comparing rules and backends
+#[cfg(feature = "std")]
+pub mod perf;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod render;
+/// Built in rules picked by name at runtime, for bindings to other languages
+#[cfg(feature = "std")]
+pub mod board;
+/// An interactive terminal frontend
+#[cfg(feature = "tui")]
+pub mod tui;
+/// A native window frontend
+#[cfg(feature = "window")]
+pub mod window;
+/// An SDL2 window frontend
+#[cfg(feature = "sdl")]
+pub mod sdl;
+/// An egui editor for painting cells and tuning continuous rules
+#[cfg(feature = "editor")]
+pub mod editor;
+/// Bindings for running boards in a web page
+#[cfg(feature = "wasm")]
+pub mod wasm;
+/// Bindings for driving simulations from Python
+#[cfg(feature = "python")]
+pub mod python;
+/// A C API for embedding the built in rules. Frames are handed out as pointers
+/// that have to be given back to `simulation_frame_free`, and null pointers
+/// are treated as missing rather than dereferenced. `include/simulation.h` is
+/// regenerated from it when building with the feature.
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// Stepping large boards with compute shaders
+#[cfg(feature = "gpu")]
+pub mod gpu;
+/// Streaming simulations to WebSocket clients
+#[cfg(feature = "server")]
+pub mod server;
+/// Stepping simulations from async code without blocking the runtime
+#[cfg(feature = "tokio")]
+pub mod driver;
+/// Watching simulations in a web browser over http
+#[cfg(feature = "http")]
+pub mod http;
+/// Rules written as Rhai scripts and loaded at runtime
+#[cfg(feature = "script")]
+pub mod scripted;
+/// Rules loaded at runtime instead of written in Rust, e.g.
+/// `rules::scripted::load("life.rhai")`
+#[cfg(feature = "script")]
+pub mod rules {
+    pub use super::scripted;
+}
+/// Strategies for property testing rules and checks of the laws rules
+/// usually obey
+#[cfg(feature = "testing")]
+pub mod testing;
+/// Convolution through Fourier transforms, for kernels too large to sum
+/// directly
+#[cfg(feature = "fft")]
+pub mod fft;
@@ -0,0 +1,308 @@
+use super::Frame;
+use super::Square;
+
+#[cfg(feature = "std")]
+use std::error;
+use std::fmt;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Why a rule couldn't be compiled into a table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// there were no states to compile the rule for
+    NoStates,
+    /// a table for this many states wouldn't fit in memory
+    TooManyStates(usize),
+    /// the rule gave a state that isn't one of the states
+    UnknownState,
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TableError::NoStates => write!(f, "a lookup table needs at least one state"),
+            TableError::TooManyStates(n) => {
+                write!(f, "a lookup table for {} states would be too large", n)
+            }
+            TableError::UnknownState => {
+                write!(f, "the rule gave a state that isn't in the lookup table")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for TableError {}
+
+/// A radius 1 rule over a small set of states compiled into a table with an
+/// entry for every possible neighborhood. Stepping with the table avoids
+/// calling the rule for every cell of every generation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LookupTable<T> {
+    states: Vec<T>,
+    /// the index of the next state for every neighborhood, which is numbered
+    /// by the indices of its cells in base `states.len()`, column by column
+    /// from the top left, so that moving along a row drops the lowest three
+    /// digits
+    table: Vec<u8>,
+}
+
+impl<T> LookupTable<T>
+where T: Clone + Default + PartialEq {
+    /// Compile rule for all neighborhoods made of the given states. The rule
+    /// must only look at cells within distance 1. The table has
+    /// `states.len()^9` entries, so this is only practical for a handful of
+    /// states, and compiling fails if it wouldn't fit in memory or the rule
+    /// gives a state that isn't one of the states.
+    pub fn compile<F>(states: &[T], rule: F) -> Result<LookupTable<T>, TableError>
+    where F: Fn(Square<T>) -> T {
+        let n = states.len();
+        if n == 0 {
+            return Err(TableError::NoStates);
+        }
+        let size = match n.checked_pow(9) {
+            Some(size) if n <= u8::MAX as usize => size,
+            _ => return Err(TableError::TooManyStates(n)),
+        };
+        let mut table = Vec::new();
+        table.try_reserve_exact(size).map_err(|_| TableError::TooManyStates(n))?;
+
+        let mut neighborhood = Frame::new(3, 3);
+        for config in 0..size {
+            let mut rest = config;
+            for i in 0..3 {
+                for j in 0..3 {
+                    *neighborhood.get_mut(i, j) = states[rest % n].clone();
+                    rest /= n;
+                }
+            }
+            let next = rule(neighborhood.square(1, 1));
+            let index = states.iter()
+                .position(|s| { *s == next })
+                .ok_or(TableError::UnknownState)?;
+            table.push(index as u8);
+        }
+
+        Ok(LookupTable { states: states.to_vec(), table })
+    }
+
+    /// The number of entries in the table
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Whether the table has no entries, which never happens for a compiled
+    /// table
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// The index in the table's states of every cell of frame, for stepping
+    /// with `next_indices`
+    ///
+    /// # Panics
+    ///
+    /// if the frame contains a state the table wasn't compiled for
+    pub fn indices(&self, frame: &Frame<T>) -> Frame<u8> {
+        frame.map(|e| {
+            self.states.iter()
+                .position(|s| { s == e })
+                .expect("frame contains a state that isn't in the table") as u8
+        })
+    }
+
+    /// The cells of a frame of indices from `indices`
+    pub fn states(&self, indices: &Frame<u8>) -> Frame<T> {
+        indices.map(|&i| self.states[i as usize].clone())
+    }
+
+    /// Return the next frame by looking up every neighborhood in the table.
+    /// Panics if the frame contains a state the table wasn't compiled for.
+    pub fn next_frame(&self, frame: &Frame<T>) -> Frame<T> {
+        self.states(&self.next_indices(&self.indices(frame)))
+    }
+
+    /// The next frame of state indices, for stepping many generations
+    /// without looking up the index of every cell each time. Each row is
+    /// stepped by keeping the number of the neighborhood and rolling it along
+    /// by a column at a time.
+    ///
+    /// # Panics
+    ///
+    /// if an index isn't one of the table's states
+    pub fn next_indices(&self, indices: &Frame<u8>) -> Frame<u8> {
+        let (w, h) = (indices.width(), indices.height());
+        if w == 0 || h == 0 {
+            return indices.clone();
+        }
+        let n = self.states.len();
+        let cube = n * n * n;
+        let cells = indices.as_slice();
+        let mut next = Vec::with_capacity(cells.len());
+        for y in 0..h {
+            let rows = [(y + h - 1) % h, y, (y + 1) % h].map(|r| &cells[r * w..][..w]);
+            let column = |x: usize| {
+                let [above, at, below] = rows.map(|row| row[x] as usize);
+                assert!(above.max(at).max(below) < n, "frame contains an index past the states");
+                above + at * n + below * n * n
+            };
+            let mut config = column(w - 1) + column(0) * cube + column(1 % w) * cube * cube;
+            for x in 0..w {
+                next.push(self.table[config]);
+                config = config / cube + column((x + 2) % w) * cube * cube;
+            }
+        }
+        Frame::from_vec(w, h, next).unwrap()
+    }
+
+    /// A frame one cell larger than pattern on every side whose next
+    /// generation matches pattern, or None if there is no such frame, in
+    /// which case pattern is a Garden of Eden: nothing evolves into it. Only
+    /// the cells under pattern have to match, and the frame is taken to be
+    /// part of an unbounded plane, so it doesn't wrap around.
+    ///
+    /// The search backtracks over the cells of the frame row by row, checking
+    /// each cell of pattern as soon as its whole neighborhood is chosen, so
+    /// it takes time exponential in the size of pattern in the worst case and
+    /// is only practical for small patterns.
+    ///
+    /// # Panics
+    ///
+    /// if pattern contains a state the table wasn't compiled for
+    pub fn predecessor(&self, pattern: &Frame<T>) -> Option<Frame<T>> {
+        let n = self.states.len();
+        let target = pattern.map(|e| {
+            self.states.iter()
+                .position(|s| s == e)
+                .expect("pattern contains a state that isn't in the table")
+        });
+        let (w, h) = (pattern.width() + 2, pattern.height() + 2);
+        // the state chosen for each cell of the frame so far, row by row
+        let mut chosen = vec![0; w * h];
+        // whether the cell of pattern whose neighborhood is completed by the
+        // k-th cell of the frame, if any, comes out right
+        let fits = |chosen: &[usize], k: usize| {
+            let (x, y) = (k % w, k / w);
+            if x < 2 || y < 2 {
+                return true;
+            }
+            let mut config = 0;
+            for i in (0..3).rev() {
+                for j in (0..3).rev() {
+                    config = config * n + chosen[(y - 2 + j) * w + x - 2 + i];
+                }
+            }
+            self.table[config] as usize == *target.get(x - 2, y - 2)
+        };
+
+        let mut k = 0;
+        while k < w * h {
+            if chosen[k] == n {
+                // every state failed here, so the cell before has to change
+                chosen[k] = 0;
+                if k == 0 {
+                    return None;
+                }
+                k -= 1;
+                chosen[k] += 1;
+            } else if fits(&chosen, k) {
+                k += 1;
+            } else {
+                chosen[k] += 1;
+            }
+        }
+        let states = chosen.into_iter().map(|i| self.states[i].clone()).collect();
+        Frame::from_vec(w, h, states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::super::cyclic::Cyclic;
+    use super::{LookupTable, TableError};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[test]
+    fn life_table() {
+        let table = LookupTable::compile(&[Dead, Alive], game_of_life::rule).unwrap();
+        assert_eq!(table.len(), 512);
+
+        // a glider plus a blinker
+        let mut frame = Frame::new(8, 8);
+        *frame.get_mut(1, 0) = Alive;
+        *frame.get_mut(2, 1) = Alive;
+        *frame.get_mut(0, 2) = Alive;
+        *frame.get_mut(1, 2) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        *frame.get_mut(5, 5) = Alive;
+        *frame.get_mut(5, 6) = Alive;
+        *frame.get_mut(5, 7) = Alive;
+
+        let mut expected = frame.next_frame(game_of_life::rule);
+        let mut actual = table.next_frame(&frame);
+        for _ in 0..8 {
+            assert_eq!(actual, expected);
+            expected = expected.next_frame(game_of_life::rule);
+            actual = table.next_frame(&actual);
+        }
+    }
+
+    #[test]
+    fn predecessor() {
+        let table = LookupTable::compile(&[Dead, Alive], game_of_life::rule).unwrap();
+        let mut blinker = Frame::new(3, 3);
+        for x in 0..3 {
+            *blinker.get_mut(x, 1) = Alive;
+        }
+        let before = table.predecessor(&blinker).unwrap();
+        assert_eq!((before.width(), before.height()), (5, 5));
+
+        // stepped in the middle of a dead frame, the predecessor turns into
+        // the blinker wherever the blinker is
+        let mut frame = Frame::new(9, 9);
+        for (x, y, cell) in before.enumerate_squares() {
+            *frame.get_mut(x + 2, y + 2) = *cell;
+        }
+        let after = table.next_frame(&frame);
+        for (x, y, cell) in blinker.enumerate_squares() {
+            assert_eq!(after.get(x + 3, y + 3), cell);
+        }
+
+        // nothing turns into a live cell under a rule that kills everything
+        let death = LookupTable::compile(&[Dead, Alive], |_| Dead).unwrap();
+        assert_eq!(death.predecessor(&blinker), None);
+        assert!(death.predecessor(&Frame::new(2, 2)).is_some());
+    }
+
+    #[test]
+    fn multi_state_table() {
+        let cyclic = Cyclic::new(3, 2, 1);
+        let table = LookupTable::compile(&[0, 1, 2], |sq| cyclic.rule(sq)).unwrap();
+
+        let mut frame = Frame::<u8>::new(6, 6);
+        for x in 0..6 {
+            for y in 0..6 {
+                *frame.get_mut(x, y) = ((x * 7 + y * 3) % 3) as u8;
+            }
+        }
+        assert_eq!(table.next_frame(&frame), frame.next_frame(|sq| cyclic.rule(sq)));
+
+        // narrow frames wrap onto the same cells from both sides
+        let thin = Frame::from_vec(2, 3, vec![1, 2, 0, 0, 1, 1]).unwrap();
+        assert_eq!(table.next_frame(&thin), thin.next_frame(|sq| cyclic.rule(sq)));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(LookupTable::<u8>::compile(&[], |_| 0), Err(TableError::NoStates));
+        let states = (0..200).collect::<Vec<u16>>();
+        assert_eq!(LookupTable::compile(&states, |_| 0), Err(TableError::TooManyStates(200)));
+        assert_eq!(LookupTable::compile(&[0u8, 1], |_| 2), Err(TableError::UnknownState));
+    }
+}
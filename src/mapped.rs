@@ -0,0 +1,200 @@
+use super::{CellState, Frame, SizeError, Square};
+use super::frame::cells;
+use super::tiled::TiledSimulation;
+
+use memmap2::MmapMut;
+
+use std::fs::{File, OpenOptions};
+use std::io::{Error, ErrorKind, Result};
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// A frame kept in a file mapped into memory, one byte per cell holding its
+/// state number, so boards larger than memory can be stepped a strip of rows
+/// at a time while the operating system pages the rest in and out. Like
+/// frames, the board wraps around its edges.
+///
+/// ```no_run
+/// use simulation::game_of_life::{self, State};
+/// use simulation::mapped::MappedFrame;
+///
+/// let mut front = MappedFrame::<State>::create("front.cells", 100_000, 100_000).unwrap();
+/// let mut back = MappedFrame::<State>::create("back.cells", 100_000, 100_000).unwrap();
+/// front.set(10, 10, State::Alive);
+/// for _ in 0..10 {
+///     front.step_into(&mut back, game_of_life::rule, 1, 1024, 8);
+///     std::mem::swap(&mut front, &mut back);
+/// }
+/// front.flush().unwrap();
+/// ```
+pub struct MappedFrame<T> {
+    map: MmapMut,
+    width: usize,
+    height: usize,
+    cells: PhantomData<T>,
+}
+
+impl<T: CellState> MappedFrame<T> {
+    /// A width by height frame of state 0 in a new file at path, replacing
+    /// any file already there
+    pub fn create<P: AsRef<Path>>(path: P, width: usize, height: usize)
+                                  -> Result<MappedFrame<T>> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true)
+            .open(path)?;
+        file.set_len(size::<T>(width, height)? as u64)?;
+        MappedFrame::map(&file, width, height)
+    }
+
+    /// The width by height frame in the file at path, as written by an
+    /// earlier `create`
+    pub fn open<P: AsRef<Path>>(path: P, width: usize, height: usize)
+                                -> Result<MappedFrame<T>> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len();
+        if len != size::<T>(width, height)? as u64 {
+            return Err(Error::new(ErrorKind::InvalidData, format!(
+                "a {}x{} frame doesn't take {} bytes", width, height, len)));
+        }
+        MappedFrame::map(&file, width, height)
+    }
+
+    fn map(file: &File, width: usize, height: usize) -> Result<MappedFrame<T>> {
+        // the frame can't stop other processes changing the file, which is
+        // only as unsafe as any file they share
+        let map = unsafe { MmapMut::map_mut(file)? };
+        Ok(MappedFrame { map, width, height, cells: PhantomData })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The cell at (x, y)
+    ///
+    /// # Panics
+    ///
+    /// if the file holds a number that isn't a state
+    pub fn get(&self, x: usize, y: usize) -> T {
+        let n = self.map[y * self.width + x];
+        T::from_index(n as usize).unwrap_or_else(|| panic!("{} isn't a state", n))
+    }
+
+    /// Set the cell at (x, y) to cell
+    pub fn set(&mut self, x: usize, y: usize, cell: T) {
+        self.map[y * self.width + x] = cell.index() as u8;
+    }
+
+    /// The rows y..y + rows in a frame, wrapping around the bottom edge
+    pub fn rows(&self, y: usize, rows: usize) -> Frame<T> {
+        let (w, h) = (self.width, self.height);
+        Frame::new_from_fn(w, rows, |i, j| self.get(i, (y + j) % h))
+    }
+
+    /// Write the rows of frame to the rows starting at y, wrapping around the
+    /// bottom edge
+    ///
+    /// # Panics
+    ///
+    /// if frame isn't as wide as this frame
+    pub fn set_rows(&mut self, y: usize, frame: &Frame<T>) {
+        assert_eq!(frame.width(), self.width, "rows have to be as wide as the frame");
+        for (i, j, cell) in frame.enumerate_squares() {
+            let row = (y + j) % self.height;
+            self.map[row * self.width + i] = cell.index() as u8;
+        }
+    }
+
+    /// Write the next generation of this frame into next with rule, which
+    /// looks at most radius cells away. Only strip rows, along with radius
+    /// rows on either side, are in memory at a time, and each strip is
+    /// stepped by up to threads workers.
+    ///
+    /// # Panics
+    ///
+    /// if next isn't the same size, strip is 0, or the frame isn't wider
+    /// than radius
+    pub fn step_into<F>(&self, next: &mut MappedFrame<T>, rule: F, radius: usize, strip: usize,
+                        threads: usize)
+    where T: Clone + Send + Sync, F: Fn(Square<T>) -> T + Sync {
+        assert!((next.width, next.height) == (self.width, self.height),
+                "stepping a {}x{} frame into a {}x{} one",
+                self.width, self.height, next.width, next.height);
+        assert!(strip > 0, "strips need at least one row");
+        let h = self.height;
+        for y in (0..h).step_by(strip) {
+            let rows = strip.min(h - y);
+            // the strip with radius ghost rows from its neighbors, which see
+            // the wrong rows past the ends of the strip but aren't kept
+            let ghosts = self.rows((y + h * (radius / h + 1) - radius) % h, rows + 2 * radius);
+            let mut tiled = TiledSimulation::new(ghosts, &rule, radius, threads);
+            tiled.step();
+            let stepped = tiled.frame();
+            next.set_rows(y, &stepped.crop(super::Rect {
+                x: 0, y: radius, width: self.width, height: rows,
+            }));
+        }
+    }
+
+    /// Write changes through to the file
+    pub fn flush(&self) -> Result<()> {
+        self.map.flush()
+    }
+
+    /// A copy of the whole frame in memory
+    pub fn to_frame(&self) -> Frame<T> {
+        self.rows(0, self.height)
+    }
+}
+
+/// The bytes a width by height frame of T takes, without allocating them
+fn size<T: CellState>(width: usize, height: usize) -> Result<usize> {
+    assert!(T::STATES <= 256, "a mapped frame holds at most 256 states");
+    let invalid = |e: SizeError| Error::new(ErrorKind::InvalidInput, e.to_string());
+    if width == 0 || height == 0 {
+        return Err(invalid(SizeError::Empty { width, height }));
+    }
+    cells::<u8>(width, height).map_err(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life::{self, State};
+    use super::super::patterns::{Orientation, Pattern};
+    use super::MappedFrame;
+
+    use std::env;
+    use std::mem;
+
+    #[test]
+    fn steps_in_strips() {
+        let dir = env::temp_dir();
+        let mut front = MappedFrame::create(dir.join("simulation_mapped_front"), 12, 10).unwrap();
+        let mut back = MappedFrame::create(dir.join("simulation_mapped_back"), 12, 10).unwrap();
+        let mut frame = Frame::new(12, 10);
+        // a glider crossing the bottom edge and a blinker across strips
+        Pattern::glider().place(&mut frame, 3, 8, Orientation::Identity, State::Alive);
+        Pattern::blinker().place(&mut frame, 7, 3, Orientation::Rotate90, State::Alive);
+        front.set_rows(0, &frame);
+        assert_eq!(front.to_frame(), frame);
+
+        for _ in 0..8 {
+            front.step_into(&mut back, game_of_life::rule, 1, 3, 2);
+            mem::swap(&mut front, &mut back);
+            frame = frame.next_frame(game_of_life::rule);
+            assert_eq!(front.to_frame(), frame);
+        }
+        front.flush().unwrap();
+        mem::drop(front);
+        mem::drop(back);
+
+        let path = dir.join("simulation_mapped_front");
+        let reopened = MappedFrame::<State>::open(&path, 12, 10).unwrap();
+        assert_eq!(reopened.to_frame(), frame);
+        assert!(MappedFrame::<State>::open(&path, 12, 11).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use super::Frame;
+
+use rand::Rng;
+
+/// Settings for fractal gradient noise, which adds up octaves of Perlin
+/// noise, each at twice the frequency and persistence times the amplitude of
+/// the one before
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fractal {
+    /// roughly how many features the first octave has per cell, so 0.05 gives
+    /// blobs about 20 cells across
+    pub frequency: f64,
+    /// how many octaves to add up, at least 1
+    pub octaves: u32,
+    /// how much each octave is scaled down from the one before
+    pub persistence: f64,
+}
+
+impl Default for Fractal {
+    fn default() -> Fractal {
+        Fractal { frequency: 0.05, octaves: 4, persistence: 0.5 }
+    }
+}
+
+/// Perlin gradient noise with a lattice shuffled by a random generator
+#[derive(Clone)]
+pub struct Perlin {
+    permutation: [u8; 256],
+}
+
+/// Smooth the position within a lattice cell so the noise has no creases
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// The dot product of (x, y) with one of eight gradients picked by hash
+fn gradient(hash: u8, x: f64, y: f64) -> f64 {
+    match hash & 7 {
+        0 => x + y,
+        1 => y - x,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+impl Perlin {
+    pub fn new<R: Rng>(rng: &mut R) -> Perlin {
+        let mut permutation = [0; 256];
+        for (i, p) in permutation.iter_mut().enumerate() {
+            *p = i as u8;
+        }
+        rng.shuffle(&mut permutation);
+        Perlin { permutation }
+    }
+
+    /// The noise at (x, y), between about -1 and 1, repeating every period
+    /// lattice cells each way so that frames filled with it wrap seamlessly
+    pub fn get(&self, x: f64, y: f64, period: (usize, usize)) -> f64 {
+        let (px, py) = (period.0.max(1), period.1.max(1));
+        let (xf, yf) = (x.floor(), y.floor());
+        let (x0, y0) = ((xf as i64).rem_euclid(px as i64) as usize,
+                        (yf as i64).rem_euclid(py as i64) as usize);
+        let (x1, y1) = ((x0 + 1) % px, (y0 + 1) % py);
+        let (x, y) = (x - xf, y - yf);
+
+        let hash = |i: usize, j: usize| {
+            let p = &self.permutation;
+            p[(p[i & 255] as usize + j) & 255]
+        };
+        let (u, v) = (fade(x), fade(y));
+        lerp(v,
+             lerp(u, gradient(hash(x0, y0), x, y), gradient(hash(x1, y0), x - 1., y)),
+             lerp(u, gradient(hash(x0, y1), x, y - 1.), gradient(hash(x1, y1), x - 1., y - 1.)))
+    }
+
+    /// The octaves of fractal at cell (x, y) of a width by height frame added
+    /// up, scaled to lie between 0 and 1
+    pub fn fractal(&self, fractal: &Fractal, x: usize, y: usize, width: usize, height: usize)
+                   -> f64 {
+        let mut total = 0.;
+        let mut amplitudes = 0.;
+        let mut amplitude = 1.;
+        for octave in 0..fractal.octaves.max(1) {
+            // round the frequency so a whole number of lattice cells spans
+            // the frame
+            let scale = (1 << octave) as f64;
+            let px = ((width as f64 * fractal.frequency * scale).round() as usize).max(1);
+            let py = ((height as f64 * fractal.frequency * scale).round() as usize).max(1);
+            let (fx, fy) = (px as f64 / width as f64, py as f64 / height as f64);
+            total += amplitude * self.get(x as f64 * fx, y as f64 * fy, (px, py));
+            amplitudes += amplitude;
+            amplitude *= fractal.persistence;
+        }
+        (total / amplitudes * 0.5 + 0.5).clamp(0., 1.)
+    }
+}
+
+/// A width by height frame of fractal noise between 0 and 1 that wraps
+/// around at the edges without seams, e.g. as a starting point for Lenia
+pub fn noise<R: Rng>(width: usize, height: usize, rng: &mut R, fractal: &Fractal) -> Frame<f32> {
+    let perlin = Perlin::new(rng);
+    let data = (0..width * height)
+        .map(|i| perlin.fractal(fractal, i % width, i / width, width, height) as f32)
+        .collect();
+    Frame::from_vec(width, height, data).unwrap()
+}
+
+/// A width by height frame of blobs, the cells where fractal noise is above
+/// threshold. Higher thresholds give fewer, smaller blobs.
+pub fn blobs<R: Rng>(width: usize, height: usize, rng: &mut R, fractal: &Fractal,
+                     threshold: f32) -> Frame<bool> {
+    noise(width, height, rng, fractal).map(|&v| v > threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blobs, noise, Fractal, Perlin};
+
+    use rand::{SeedableRng, XorShiftRng};
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([1, 2, 3, 4])
+    }
+
+    #[test]
+    fn lattice() {
+        let perlin = Perlin::new(&mut rng());
+        // the noise is 0 on lattice points and repeats every period
+        assert_eq!(perlin.get(3., 2., (8, 8)), 0.);
+        let (a, b) = (perlin.get(1.25, 2.5, (4, 3)), perlin.get(5.25, -0.5, (4, 3)));
+        assert!((a - b).abs() < 1e-12);
+        assert!((0..100).any(|i| perlin.get(i as f64 * 0.37, 0.5, (8, 8)).abs() > 0.1));
+    }
+
+    #[test]
+    fn smooth_and_seamless() {
+        let fractal = Fractal { octaves: 2, ..Fractal::default() };
+        let frame = noise(60, 40, &mut rng(), &fractal);
+        assert_eq!(frame, noise(60, 40, &mut rng(), &fractal));
+        assert!(frame.as_slice().iter().all(|&v| (0. ..=1.).contains(&v)));
+        // neighbors are close, including across the edges
+        let step = |a: &f32, b: &f32| (a - b).abs();
+        for y in 0..40 {
+            for x in 0..60 {
+                let here = frame.get(x, y);
+                assert!(step(here, frame.get((x + 1) % 60, y)) < 0.2);
+                assert!(step(here, frame.get(x, (y + 1) % 40)) < 0.2);
+            }
+        }
+    }
+
+    #[test]
+    fn thresholds() {
+        let count = |threshold| {
+            let frame = blobs(50, 50, &mut rng(), &Fractal::default(), threshold);
+            frame.as_slice().iter().filter(|&&c| c).count()
+        };
+        assert_eq!(count(1.), 0);
+        assert!(count(0.6) < count(0.5));
+        assert!(count(0.5) < count(0.4));
+        assert_eq!(count(-0.1), 2500);
+    }
+}
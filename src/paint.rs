@@ -0,0 +1,193 @@
+use super::{Frame, Rect};
+use super::edit::Editor;
+use super::patterns::{Orientation, Pattern};
+
+/// Something cells can be painted on, which wraps around at its edges like a
+/// frame. Shapes are painted with a round brush, a brush of radius 0 only
+/// covering a single cell. Coordinates are signed so that shapes can hang
+/// over the top and left edges too.
+pub trait Canvas<T: Clone> {
+    /// The width and height
+    fn size(&self) -> (usize, usize);
+
+    /// The cell at (x, y), which is within the canvas
+    fn get(&self, x: usize, y: usize) -> &T;
+
+    /// Set the cell at (x, y), which is within the canvas, to value
+    fn set(&mut self, x: usize, y: usize, value: T);
+
+    /// Set every cell at most radius from (x, y) to value
+    fn paint_point(&mut self, x: isize, y: isize, radius: usize, value: T) {
+        let (w, h) = self.size();
+        let r = radius as isize;
+        for j in -r..r + 1 {
+            for i in -r..r + 1 {
+                if i * i + j * j <= r * r {
+                    let x = (x + i).rem_euclid(w as isize) as usize;
+                    let y = (y + j).rem_euclid(h as isize) as usize;
+                    self.set(x, y, value.clone());
+                }
+            }
+        }
+    }
+
+    /// Paint a straight line from one point to another, both included
+    fn paint_line(&mut self, from: (isize, isize), to: (isize, isize), radius: usize, value: T) {
+        let ((mut x, mut y), (x1, y1)) = (from, to);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut error = dx + dy;
+        loop {
+            self.paint_point(x, y, radius, value.clone());
+            if (x, y) == to {
+                return;
+            }
+            let e2 = 2 * error;
+            if e2 >= dy {
+                error += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Paint the outline of the circle of radius r around (x, y)
+    fn paint_circle(&mut self, x: isize, y: isize, r: usize, radius: usize, value: T) {
+        let (mut i, mut j) = (r as isize, 0);
+        let mut error = 1 - i;
+        while i >= j {
+            // the point in each octant
+            let points = [(i, j), (j, i), (-j, i), (-i, j), (-i, -j), (-j, -i), (j, -i), (i, -j)];
+            for &(a, b) in &points {
+                self.paint_point(x + a, y + b, radius, value.clone());
+            }
+            j += 1;
+            if error < 0 {
+                error += 2 * j + 1;
+            } else {
+                i -= 1;
+                error += 2 * (j - i) + 1;
+            }
+        }
+    }
+
+    /// Paint the outline of rect
+    fn paint_rect(&mut self, rect: Rect, radius: usize, value: T) {
+        if rect.area() == 0 {
+            return;
+        }
+        let (l, t) = (rect.x as isize, rect.y as isize);
+        let (r, b) = (l + rect.width as isize - 1, t + rect.height as isize - 1);
+        self.paint_line((l, t), (r, t), radius, value.clone());
+        self.paint_line((r, t), (r, b), radius, value.clone());
+        self.paint_line((r, b), (l, b), radius, value.clone());
+        self.paint_line((l, b), (l, t), radius, value);
+    }
+
+    /// Set every cell of rect to value
+    fn fill_rect(&mut self, rect: Rect, value: T) {
+        let (w, h) = self.size();
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                self.set(x % w, y % h, value.clone());
+            }
+        }
+    }
+
+    /// Paint every live cell of pattern, in the given orientation, with the
+    /// top left corner of the pattern at (x, y). Dead cells are left alone.
+    fn stamp(&mut self, pattern: &Pattern, x: isize, y: isize, orientation: Orientation,
+             radius: usize, value: T) {
+        let (w, h) = (pattern.width(), pattern.height());
+        for &(i, j) in pattern.cells() {
+            let (i, j) = orientation.apply(i, j, w, h);
+            self.paint_point(x + i as isize, y + j as isize, radius, value.clone());
+        }
+    }
+}
+
+impl<T: Clone> Canvas<T> for Frame<T> {
+    fn size(&self) -> (usize, usize) {
+        (self.width(), self.height())
+    }
+
+    fn get(&self, x: usize, y: usize) -> &T {
+        Frame::get(self, x, y)
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: T) {
+        *self.get_mut(x, y) = value;
+    }
+}
+
+/// Painting with an editor can be undone
+impl<T: Clone + PartialEq> Canvas<T> for Editor<T> {
+    fn size(&self) -> (usize, usize) {
+        (self.frame().width(), self.frame().height())
+    }
+
+    fn get(&self, x: usize, y: usize) -> &T {
+        self.frame().get(x, y)
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: T) {
+        Editor::set(self, x, y, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Rect};
+    use super::super::edit::Editor;
+    use super::super::patterns::{Orientation, Pattern};
+    use super::Canvas;
+
+    #[cfg(not(feature = "std"))]
+    use alloc::string::String;
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    fn rows(frame: &Frame<u8>) -> Vec<String> {
+        (0..frame.height())
+            .map(|y| (0..frame.width()).map(|x| (b'0' + *frame.get(x, y)) as char).collect())
+            .collect()
+    }
+
+    #[test]
+    fn points_and_lines() {
+        let mut frame = Frame::<u8>::new(7, 5);
+        frame.paint_point(0, 0, 1, 1);
+        frame.paint_line((2, 4), (6, 2), 0, 2);
+        assert_eq!(rows(&frame), vec!["1100001", "1000000", "0000022", "0002200", "1020000"]);
+    }
+
+    #[test]
+    fn shapes() {
+        let mut frame = Frame::<u8>::new(7, 7);
+        frame.paint_circle(3, 3, 2, 0, 1);
+        assert_eq!(rows(&frame),
+                   vec!["0000000", "0011100", "0100010", "0100010", "0100010", "0011100",
+                        "0000000"]);
+
+        let mut frame = Frame::<u8>::new(5, 4);
+        frame.paint_rect(Rect { x: 1, y: 0, width: 3, height: 3 }, 0, 1);
+        frame.fill_rect(Rect { x: 4, y: 3, width: 2, height: 1 }, 2);
+        assert_eq!(rows(&frame), vec!["01110", "01010", "01110", "20002"]);
+    }
+
+    #[test]
+    fn stamps() {
+        let mut editor = Editor::new(Frame::<u8>::new(5, 5), 100);
+        editor.begin_stroke();
+        editor.stamp(&Pattern::glider(), -1, 1, Orientation::Rotate90, 0, 1);
+        editor.end_stroke();
+        let mut placed = Frame::new(5, 5);
+        Pattern::glider().place(&mut placed, 4, 1, Orientation::Rotate90, 1);
+        assert_eq!(*editor.frame(), placed);
+        assert!(editor.undo());
+        assert_eq!(*editor.frame(), Frame::new(5, 5));
+    }
+}
@@ -0,0 +1,28 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// f of every number below n, in order, worked out on up to threads threads
+/// that each take the next number as soon as they are done with their last,
+/// so a few slow numbers don't hold up the rest
+pub(crate) fn map<F, R>(n: usize, threads: usize, f: F) -> Vec<R>
+where F: Fn(usize) -> R + Sync, R: Send {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..n).map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..threads.min(n) {
+            let (next, results, f) = (&next, &results, &f);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= n {
+                    break;
+                }
+                let result = f(i);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter()
+        .map(|r| r.expect("every number was worked on"))
+        .collect()
+}
@@ -0,0 +1,550 @@
+use super::Frame;
+use super::paint::Canvas;
+
+use std::iter;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// One of the eight ways a pattern can be rotated or reflected
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    #[default]
+    Identity,
+    /// rotated a quarter turn clockwise
+    Rotate90,
+    Rotate180,
+    /// rotated a quarter turn counterclockwise
+    Rotate270,
+    /// mirrored left to right
+    FlipHorizontal,
+    /// mirrored top to bottom
+    FlipVertical,
+    /// mirrored along the main diagonal
+    Transpose,
+    /// mirrored along the other diagonal
+    AntiTranspose,
+}
+
+impl Orientation {
+    /// All eight orientations
+    pub fn all() -> [Orientation; 8] {
+        use self::Orientation::*;
+        [Identity, Rotate90, Rotate180, Rotate270,
+         FlipHorizontal, FlipVertical, Transpose, AntiTranspose]
+    }
+
+    /// Whether this orientation swaps the width and height of a pattern
+    pub fn swaps_axes(&self) -> bool {
+        use self::Orientation::*;
+        matches!(*self, Rotate90 | Rotate270 | Transpose | AntiTranspose)
+    }
+
+    /// Where (x, y) in a w by h rectangle ends up after applying this
+    /// orientation to the rectangle
+    pub fn apply(&self, x: usize, y: usize, w: usize, h: usize) -> (usize, usize) {
+        use self::Orientation::*;
+        match *self {
+            Identity => (x, y),
+            Rotate90 => (h - 1 - y, x),
+            Rotate180 => (w - 1 - x, h - 1 - y),
+            Rotate270 => (y, w - 1 - x),
+            FlipHorizontal => (w - 1 - x, y),
+            FlipVertical => (x, h - 1 - y),
+            Transpose => (y, x),
+            AntiTranspose => (h - 1 - y, w - 1 - x),
+        }
+    }
+}
+
+/// A symmetry a soup can be given, named the way soup searches name them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// no symmetry
+    #[default]
+    C1,
+    /// unchanged by a half turn
+    C2,
+    /// unchanged by a quarter turn, which only square frames can be
+    C4,
+    /// mirrored left to right and top to bottom
+    D4,
+    /// unchanged by every rotation and reflection, which only square frames
+    /// can be
+    D8,
+}
+
+impl Symmetry {
+    /// The orientations that leave a pattern with this symmetry unchanged
+    pub fn orientations(&self) -> &'static [Orientation] {
+        use self::Orientation::*;
+        match *self {
+            Symmetry::C1 => &[Identity],
+            Symmetry::C2 => &[Identity, Rotate180],
+            Symmetry::C4 => &[Identity, Rotate90, Rotate180, Rotate270],
+            Symmetry::D4 => &[Identity, FlipHorizontal, FlipVertical, Rotate180],
+            Symmetry::D8 => &[Identity, Rotate90, Rotate180, Rotate270,
+                              FlipHorizontal, FlipVertical, Transpose, AntiTranspose],
+        }
+    }
+
+    /// Whether only square frames can have this symmetry
+    pub fn needs_square(&self) -> bool {
+        self.orientations().iter().any(|o| o.swaps_axes())
+    }
+}
+
+/// A two state pattern, stored as the coordinates of its live cells within its
+/// bounding rectangle. Patterns are ordered by width, then height, then cells.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pattern {
+    width: usize,
+    height: usize,
+    cells: Vec<(usize, usize)>,
+}
+
+impl Pattern {
+    /// Create a pattern from rows in plaintext style, where `O` or `*` are live
+    /// cells and anything else is dead
+    pub fn from_rows(rows: &[&str]) -> Pattern {
+        let mut cells = vec![];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == 'O' || c == '*' {
+                    cells.push((x, y));
+                }
+            }
+        }
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        Pattern::from_cells(width, rows.len(), cells)
+    }
+
+    /// Create a pattern from the cells of frame for which alive is true
+    pub fn from_frame<T, F>(frame: &Frame<T>, alive: F) -> Pattern
+    where F: Fn(&T) -> bool {
+        let cells = frame.enumerate_squares()
+            .filter(|&(_, _, e)| alive(e))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        Pattern::from_cells(frame.width(), frame.height(), cells)
+    }
+
+    /// Create a width by height pattern from the coordinates of its live
+    /// cells, which have to be within it
+    pub fn from_cells(width: usize, height: usize, mut cells: Vec<(usize, usize)>) -> Pattern {
+        assert!(cells.iter().all(|&(x, y)| x < width && y < height),
+                "a cell is outside the {}x{} pattern", width, height);
+        cells.sort_by_key(|&(x, y)| (y, x));
+        Pattern { width, height, cells }
+    }
+
+    /// The width of the bounding rectangle
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the bounding rectangle
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The live cells, ordered by row
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// The number of live cells
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// The pattern rotated or reflected by orientation
+    pub fn oriented(&self, orientation: Orientation) -> Pattern {
+        let (w, h) = (self.width, self.height);
+        let cells = self.cells.iter()
+            .map(|&(x, y)| orientation.apply(x, y, w, h))
+            .collect();
+        if orientation.swaps_axes() {
+            Pattern::from_cells(h, w, cells)
+        } else {
+            Pattern::from_cells(w, h, cells)
+        }
+    }
+
+    /// Set the live cells of the pattern, in the given orientation, to alive
+    /// on frame with the top left corner of the pattern at (x, y). The pattern
+    /// wraps around the edges of the frame and dead cells are left untouched.
+    pub fn place<T: Clone>(&self, frame: &mut Frame<T>, x: usize, y: usize,
+                           orientation: Orientation, alive: T) {
+        frame.stamp(self, x as isize, y as isize, orientation, 0, alive);
+    }
+
+    /// A frame just big enough for the pattern, with live cells set to alive
+    pub fn to_frame<T: Default + Clone>(&self, alive: T) -> Frame<T> {
+        let mut frame = Frame::new(self.width, self.height);
+        self.place(&mut frame, 0, 0, Orientation::Identity, alive);
+        frame
+    }
+
+    /// The pattern with its bounding rectangle shrunk to its live cells, which
+    /// is empty if it has none
+    pub fn cropped(&self) -> Pattern {
+        let x0 = self.cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let y0 = self.cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let width = self.cells.iter().map(|&(x, _)| x - x0 + 1).max().unwrap_or(0);
+        let height = self.cells.iter().map(|&(_, y)| y - y0 + 1).max().unwrap_or(0);
+        let cells = self.cells.iter().map(|&(x, y)| (x - x0, y - y0)).collect();
+        Pattern::from_cells(width, height, cells)
+    }
+
+    /// The same representative of the pattern whatever its position or
+    /// orientation: cropped to its live cells and in whichever of the eight
+    /// orientations orders first
+    pub fn canonical(&self) -> Pattern {
+        let cropped = self.cropped();
+        Orientation::all().iter().map(|&o| cropped.oriented(o)).min().unwrap()
+    }
+
+    /// Whether the patterns only differ in position or orientation
+    pub fn equivalent(&self, other: &Pattern) -> bool {
+        self.population() == other.population() && self.canonical() == other.canonical()
+    }
+
+    /// A hash of the canonical pattern, equal for patterns that only differ
+    /// in position or orientation. It is FNV-1a rather than std's hasher, so
+    /// hashes stay the same between builds and can be stored in pattern
+    /// databases.
+    pub fn canonical_hash(&self) -> u64 {
+        let canonical = self.canonical();
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let values = iter::once(canonical.width).chain(iter::once(canonical.height))
+            .chain(canonical.cells.iter().flat_map(|&(x, y)| [x, y]));
+        for v in values {
+            for byte in (v as u64).to_le_bytes() {
+                hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+impl<T> Frame<T>
+where T: Clone + Default + PartialEq {
+    /// Whether the cells that aren't `T::default()` on the two frames are the
+    /// same up to moving, rotating and reflecting them, comparing their
+    /// states as well as where they are, so frames can be checked to hold
+    /// the same pattern without lining them up by hand. Like
+    /// `bounding_box`, this doesn't see patterns as whole when they wrap
+    /// around an edge.
+    pub fn same_pattern(&self, other: &Frame<T>) -> bool {
+        let dead = T::default();
+        let crop = |frame: &Frame<T>| frame.bounding_box(|c| *c != dead).map(|r| frame.crop(r));
+        let (a, b) = match (crop(self), crop(other)) {
+            (Some(a), Some(b)) => (a, b),
+            (a, b) => return a.is_none() && b.is_none(),
+        };
+        let (w, h) = (a.width(), a.height());
+        Orientation::all().iter().any(|o| {
+            let size = if o.swaps_axes() { (h, w) } else { (w, h) };
+            size == (b.width(), b.height()) && a.enumerate_squares().all(|(x, y, cell)| {
+                let (x, y) = o.apply(x, y, w, h);
+                b.get(x, y) == cell
+            })
+        })
+    }
+}
+
+impl<T> Frame<T> {
+    /// Every place pattern occurs on the frame in one of orientations, as the
+    /// top left corner of its bounding rectangle and the orientation it is in.
+    /// A match needs every cell in the rectangle to agree with the pattern on
+    /// whether alive holds for it, and matches wrap around the edges of the
+    /// frame. Orientations that leave the pattern looking the same are only
+    /// searched once, the first of them being the one reported.
+    pub fn find_pattern<F>(&self, pattern: &Pattern, orientations: &[Orientation], alive: F)
+                           -> Vec<(usize, usize, Orientation)>
+    where F: Fn(&T) -> bool {
+        let (fw, fh) = (self.width(), self.height());
+        let mut searched: Vec<Pattern> = vec![];
+        let mut found = vec![];
+        for &orientation in orientations {
+            let oriented = pattern.oriented(orientation);
+            let (w, h) = (oriented.width, oriented.height);
+            // a pattern bigger than the frame would overlap itself
+            if w > fw || h > fh || searched.contains(&oriented) {
+                continue;
+            }
+            let mut live = vec![false; w * h];
+            for &(x, y) in &oriented.cells {
+                live[y * w + x] = true;
+            }
+            for y in 0..fh {
+                for x in 0..fw {
+                    let matches = (0..h).all(|j| (0..w).all(|i| {
+                        alive(self.get((x + i) % fw, (y + j) % fh)) == live[j * w + i]
+                    }));
+                    if matches {
+                        found.push((x, y, orientation));
+                    }
+                }
+            }
+            searched.push(oriented);
+        }
+        found
+    }
+}
+
+/// Canonical patterns
+impl Pattern {
+    /// The block, the most common still life
+    pub fn block() -> Pattern {
+        Pattern::from_rows(&["OO", "OO"])
+    }
+
+    /// The beehive still life
+    pub fn beehive() -> Pattern {
+        Pattern::from_rows(&[".OO.", "O..O", ".OO."])
+    }
+
+    /// The blinker, a period 2 oscillator
+    pub fn blinker() -> Pattern {
+        Pattern::from_rows(&["OOO"])
+    }
+
+    /// The toad, a period 2 oscillator
+    pub fn toad() -> Pattern {
+        Pattern::from_rows(&[".OOO", "OOO."])
+    }
+
+    /// The beacon, a period 2 oscillator
+    pub fn beacon() -> Pattern {
+        Pattern::from_rows(&["OO..", "OO..", "..OO", "..OO"])
+    }
+
+    /// The pulsar, a period 3 oscillator
+    pub fn pulsar() -> Pattern {
+        Pattern::from_rows(&[
+            "..OOO...OOO..",
+            ".............",
+            "O....O.O....O",
+            "O....O.O....O",
+            "O....O.O....O",
+            "..OOO...OOO..",
+            ".............",
+            "..OOO...OOO..",
+            "O....O.O....O",
+            "O....O.O....O",
+            "O....O.O....O",
+            ".............",
+            "..OOO...OOO..",
+        ])
+    }
+
+    /// The glider, which moves one cell diagonally every four generations
+    pub fn glider() -> Pattern {
+        Pattern::from_rows(&[".O.", "..O", "OOO"])
+    }
+
+    /// The lightweight spaceship, which moves two cells left every four
+    /// generations
+    pub fn lwss() -> Pattern {
+        Pattern::from_rows(&[".O..O", "O....", "O...O", "OOOO."])
+    }
+
+    /// The R-pentomino, a methuselah that stabilizes after 1103 generations
+    pub fn r_pentomino() -> Pattern {
+        Pattern::from_rows(&[".OO", "OO.", ".O."])
+    }
+
+    /// Diehard, a methuselah that vanishes after 130 generations
+    pub fn diehard() -> Pattern {
+        Pattern::from_rows(&["......O.", "OO......", ".O...OOO"])
+    }
+
+    /// The acorn, a methuselah that takes 5206 generations to stabilize
+    pub fn acorn() -> Pattern {
+        Pattern::from_rows(&[".O.....", "...O...", "OO..OOO"])
+    }
+
+    /// The Gosper glider gun, which emits a glider every 30 generations
+    pub fn gosper_glider_gun() -> Pattern {
+        Pattern::from_rows(&[
+            "........................O...........",
+            "......................O.O...........",
+            "............OO......OO............OO",
+            "...........O...O....OO............OO",
+            "OO........O.....O...OO..............",
+            "OO........O...O.OO....O.O...........",
+            "..........O.....O.......O...........",
+            "...........O...O....................",
+            "............OO......................",
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::game_of_life::State::Alive;
+    use super::{Orientation, Pattern};
+
+    fn population(frame: &Frame<State>) -> usize {
+        frame.enumerate_squares().filter(|&(_, _, s)| *s == Alive).count()
+    }
+
+    fn run(frame: Frame<State>, generations: usize) -> Frame<State> {
+        (0..generations).fold(frame, |f, _| f.next_frame(game_of_life::rule))
+    }
+
+    #[test]
+    fn orientations() {
+        let glider = Pattern::glider();
+        assert_eq!(glider.oriented(Orientation::Identity), glider);
+        assert_eq!(glider.oriented(Orientation::Rotate90),
+                   Pattern::from_rows(&["O..", "O.O", "OO."]));
+        assert_eq!(glider.oriented(Orientation::FlipHorizontal),
+                   Pattern::from_rows(&[".O.", "O..", "OOO"]));
+
+        let lwss = Pattern::lwss();
+        for &o in &Orientation::all() {
+            let oriented = lwss.oriented(o);
+            assert_eq!(oriented.population(), 9);
+            assert_eq!(oriented.width() == lwss.width(), !o.swaps_axes());
+        }
+        assert_eq!(lwss.oriented(Orientation::Rotate90).oriented(Orientation::Rotate270), lwss);
+        assert_eq!(lwss.oriented(Orientation::Transpose).oriented(Orientation::Transpose), lwss);
+    }
+
+    #[test]
+    fn glider_moves() {
+        let mut frame = Frame::new(10, 10);
+        Pattern::glider().place(&mut frame, 2, 2, Orientation::Identity, Alive);
+
+        let mut expected = Frame::new(10, 10);
+        Pattern::glider().place(&mut expected, 3, 3, Orientation::Identity, Alive);
+        assert_eq!(run(frame, 4), expected);
+    }
+
+    #[test]
+    fn rotated_lwss_moves() {
+        // rotated a quarter turn the lwss travels up instead of left
+        let lwss = Pattern::lwss();
+        let mut frame = Frame::new(12, 12);
+        lwss.place(&mut frame, 4, 6, Orientation::Rotate90, Alive);
+
+        let mut expected = Frame::new(12, 12);
+        lwss.place(&mut expected, 4, 4, Orientation::Rotate90, Alive);
+        assert_eq!(run(frame, 4), expected);
+    }
+
+    #[test]
+    fn oscillators() {
+        let pulsar = Pattern::pulsar().to_frame(State::Dead);
+        assert_eq!(pulsar.width(), 13);
+        let mut frame = Frame::new(17, 17);
+        Pattern::pulsar().place(&mut frame, 2, 2, Orientation::Identity, Alive);
+        assert_eq!(population(&frame), 48);
+        assert_eq!(run(frame.clone(), 3), frame);
+        assert!(run(frame.clone(), 1) != frame);
+    }
+
+    #[test]
+    fn gun_fires() {
+        let mut frame = Frame::new(60, 40);
+        Pattern::gosper_glider_gun().place(&mut frame, 1, 1, Orientation::Identity, Alive);
+        assert_eq!(population(&frame), 36);
+        // one period later the gun is back with a glider on its way out
+        assert_eq!(population(&run(frame, 30)), 41);
+    }
+
+    #[test]
+    fn from_frame() {
+        let frame = Pattern::beehive().to_frame(State::Dead);
+        let frame = frame.map(|_| State::Dead);
+        assert_eq!(Pattern::from_frame(&frame, |&s| s == Alive).population(), 0);
+
+        let mut frame = Frame::new(4, 3);
+        Pattern::beehive().place(&mut frame, 0, 0, Orientation::Identity, Alive);
+        assert_eq!(Pattern::from_frame(&frame, |&s| s == Alive), Pattern::beehive());
+    }
+
+    #[test]
+    fn canonical() {
+        let glider = Pattern::glider();
+        let mut frame = Frame::new(9, 7);
+        glider.place(&mut frame, 5, 3, Orientation::Rotate270, Alive);
+        let moved = Pattern::from_frame(&frame, |&s| s == Alive);
+        assert_eq!((moved.cropped().width(), moved.cropped().height()), (3, 3));
+        assert_eq!(moved.canonical(), glider.canonical());
+        assert_eq!(moved.canonical_hash(), glider.canonical_hash());
+        for &o in &Orientation::all() {
+            assert_eq!(glider.oriented(o).canonical_hash(), glider.canonical_hash());
+        }
+
+        // a glider a generation later is a different phase, so a different
+        // object as far as hashes go
+        let next = Pattern::from_frame(&run(frame, 1), |&s| s == Alive);
+        assert!(next.canonical_hash() != glider.canonical_hash());
+        assert!(Pattern::lwss().canonical_hash() != glider.canonical_hash());
+        assert_eq!(Pattern::from_rows(&["...", "..."]).cropped(), Pattern::from_rows(&[]));
+    }
+
+    #[test]
+    fn equivalent() {
+        let glider = Pattern::glider();
+        assert!(glider.equivalent(&glider.oriented(Orientation::Transpose)));
+        assert!(!glider.equivalent(&Pattern::r_pentomino()));
+
+        // a blinker turns back into itself, whichever way it points
+        let mut frame = Frame::new(7, 5);
+        Pattern::blinker().place(&mut frame, 1, 1, Orientation::Identity, Alive);
+        let next = run(frame.clone(), 1);
+        assert!(next != frame && next.same_pattern(&frame));
+
+        // a glider comes back four generations later somewhere else, but is a
+        // different shape in between
+        let mut frame = Frame::new(10, 10);
+        glider.place(&mut frame, 1, 1, Orientation::Identity, Alive);
+        assert!(run(frame.clone(), 4).same_pattern(&frame));
+        assert!(!run(frame.clone(), 1).same_pattern(&frame));
+        assert!(Frame::<State>::new(3, 3).same_pattern(&Frame::new(5, 2)));
+        assert!(!Frame::<State>::new(3, 3).same_pattern(&frame));
+    }
+
+    #[test]
+    fn find_pattern() {
+        let mut frame = Frame::new(20, 12);
+        Pattern::glider().place(&mut frame, 2, 2, Orientation::Identity, Alive);
+        Pattern::glider().place(&mut frame, 9, 3, Orientation::Rotate90, Alive);
+        // wrapping around the bottom right corner
+        Pattern::glider().place(&mut frame, 18, 11, Orientation::FlipVertical, Alive);
+        Pattern::block().place(&mut frame, 14, 4, Orientation::Identity, Alive);
+
+        let alive = |s: &State| *s == Alive;
+        let mut gliders = frame.find_pattern(&Pattern::glider(), &Orientation::all(), alive);
+        gliders.sort_by_key(|&(x, y, _)| (y, x));
+        assert_eq!(gliders, vec![(2, 2, Orientation::Identity), (9, 3, Orientation::Rotate90),
+                                 (18, 11, Orientation::FlipVertical)]);
+        assert_eq!(frame.find_pattern(&Pattern::glider(), &[Orientation::Identity], alive),
+                   vec![(2, 2, Orientation::Identity)]);
+        // the block looks the same every way round, so it is found once
+        assert_eq!(frame.find_pattern(&Pattern::block(), &Orientation::all(), alive),
+                   vec![(14, 4, Orientation::Identity)]);
+    }
+
+    #[test]
+    fn gun_emits_gliders() {
+        let mut frame = Frame::new(80, 80);
+        Pattern::gosper_glider_gun().place(&mut frame, 1, 1, Orientation::Identity, Alive);
+        // gliders leave every 30 generations, those a half period apart being
+        // glide reflections of each other, so all of them are in a phase the
+        // glider pattern matches every other generation
+        let frame = run(frame, 121);
+        let gliders = frame.find_pattern(&Pattern::glider(), &Orientation::all(),
+                                         |s| *s == Alive);
+        assert_eq!(gliders.len(), 4);
+        assert!(gliders.iter().all(|&(x, y, _)| x > 20 && y > 8));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The parts of a step that are timed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// applying the rule to every cell
+    Rule,
+    /// replacing the old frame with the new one
+    Swap,
+    /// drawing the frame
+    Render,
+    /// reading and writing files, sockets and the like
+    Io,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [Phase::Rule, Phase::Swap, Phase::Render, Phase::Io];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Timings of the last few steps of a simulation, for comparing rules and
+/// backends without an external profiler. `Simulation::step_timed` times
+/// the rule and the swap, and frontends time rendering and io with `time`.
+///
+/// ```
+/// use simulation::{Frame, Simulation};
+/// use simulation::game_of_life;
+/// use simulation::perf::{Perf, Phase};
+///
+/// let mut perf = Perf::new(32);
+/// let mut sim = Simulation::new(Frame::new(64, 64), game_of_life::rule);
+/// for _ in 0..10 {
+///     sim.step_timed(&mut perf);
+///     perf.time(Phase::Render, || format!("{:?}", sim.frame().width()));
+/// }
+/// let report = perf.report();
+/// assert_eq!(report.steps, 10);
+/// println!("{}", report);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Perf {
+    /// how many of the latest samples of each phase are averaged
+    window: usize,
+    samples: [VecDeque<Duration>; 4],
+    /// the cells updated and time taken by the latest rule evaluations
+    cells: VecDeque<(usize, Duration)>,
+    steps: u64,
+}
+
+/// Averages over the latest steps timed by a `Perf`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PerfReport {
+    pub rule: Duration,
+    pub swap: Duration,
+    pub render: Duration,
+    pub io: Duration,
+    /// cells the rule was applied to per second of applying it
+    pub cells_per_second: f64,
+    /// how many steps were timed in all
+    pub steps: u64,
+}
+
+impl Perf {
+    /// Time steps, averaging over the latest window samples of each phase
+    ///
+    /// # Panics
+    ///
+    /// if window is 0
+    pub fn new(window: usize) -> Perf {
+        assert!(window > 0, "averages need at least one sample");
+        Perf {
+            window,
+            samples: Default::default(),
+            cells: VecDeque::new(),
+            steps: 0,
+        }
+    }
+
+    /// Run f, counting the time it takes towards phase
+    pub fn time<R, F>(&mut self, phase: Phase, f: F) -> R
+    where F: FnOnce() -> R {
+        let start = Instant::now();
+        let result = f();
+        self.record(phase, start.elapsed());
+        result
+    }
+
+    /// Count elapsed towards phase, for times measured elsewhere
+    pub fn record(&mut self, phase: Phase, elapsed: Duration) {
+        push(&mut self.samples[phase.index()], elapsed, self.window);
+    }
+
+    /// Count a step that applied the rule to cells cells in elapsed
+    pub fn record_step(&mut self, cells: usize, elapsed: Duration) {
+        self.record(Phase::Rule, elapsed);
+        push(&mut self.cells, (cells, elapsed), self.window);
+        self.steps += 1;
+    }
+
+    /// The average time of phase over its latest samples, or zero if it
+    /// hasn't been timed
+    pub fn average(&self, phase: Phase) -> Duration {
+        let samples = &self.samples[phase.index()];
+        if samples.is_empty() {
+            return Duration::ZERO;
+        }
+        samples.iter().sum::<Duration>() / samples.len() as u32
+    }
+
+    pub fn report(&self) -> PerfReport {
+        let (cells, elapsed) = self.cells.iter()
+            .fold((0, Duration::ZERO), |(c, e), &(cells, elapsed)| (c + cells, e + elapsed));
+        let seconds = elapsed.as_secs_f64();
+        PerfReport {
+            rule: self.average(Phase::Rule),
+            swap: self.average(Phase::Swap),
+            render: self.average(Phase::Render),
+            io: self.average(Phase::Io),
+            cells_per_second: if seconds > 0.0 { cells as f64 / seconds } else { 0.0 },
+            steps: self.steps,
+        }
+    }
+}
+
+/// Add sample to samples, dropping the oldest past window of them
+fn push<S>(samples: &mut VecDeque<S>, sample: S, window: usize) {
+    if samples.len() == window {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+impl fmt::Display for PerfReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rule {:?}, swap {:?}, render {:?}, io {:?}, {:.0} cells/s over {} steps",
+               self.rule, self.swap, self.render, self.io, self.cells_per_second, self.steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Perf, Phase};
+
+    use std::time::Duration;
+
+    #[test]
+    fn rolling_averages() {
+        let mut perf = Perf::new(2);
+        assert_eq!(perf.report().cells_per_second, 0.0);
+        perf.record_step(100, Duration::from_millis(10));
+        perf.record_step(100, Duration::from_millis(20));
+        perf.record_step(100, Duration::from_millis(30));
+        perf.record(Phase::Io, Duration::from_millis(4));
+
+        let report = perf.report();
+        assert_eq!(report.rule, Duration::from_millis(25));
+        assert_eq!(report.io, Duration::from_millis(4));
+        assert_eq!(report.render, Duration::ZERO);
+        assert_eq!(report.steps, 3);
+        assert!((report.cells_per_second - 4000.0).abs() < 1e-6);
+
+        assert_eq!(perf.time(Phase::Render, || 7), 7);
+        assert_eq!(perf.samples[Phase::Render.index()].len(), 1);
+    }
+}
@@ -0,0 +1,117 @@
+use super::{Frame, Square};
+use super::frame::cells;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use std::mem;
+
+/// Buffers of frames that are no longer needed, kept to hold the next frames
+/// instead of allocating new ones. Stepping every generation allocates a
+/// frame and drops the one before it, which a pool turns into reusing one
+/// buffer.
+///
+/// ```
+/// use simulation::{Frame, FramePool};
+/// use simulation::game_of_life::{self, State};
+///
+/// let mut pool = FramePool::new(2);
+/// let mut frame = Frame::<State>::new(64, 64);
+/// for _ in 0..100 {
+///     let next = frame.next_frame_in(&mut pool, game_of_life::rule);
+///     pool.recycle(std::mem::replace(&mut frame, next));
+/// }
+/// assert_eq!(pool.len(), 1);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FramePool<T> {
+    free: Vec<Vec<T>>,
+    limit: usize,
+}
+
+impl<T> FramePool<T> {
+    /// An empty pool that keeps at most limit buffers
+    pub fn new(limit: usize) -> FramePool<T> {
+        FramePool { free: vec![], limit }
+    }
+
+    /// How many buffers the pool holds
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    /// The bytes allocated for the buffers the pool holds
+    pub fn memory_usage(&self) -> usize {
+        self.free.iter().map(|b| b.capacity() * mem::size_of::<T>()).sum()
+    }
+
+    /// Keep the buffer of frame for a later frame, or drop it if the pool is
+    /// full
+    pub fn recycle(&mut self, frame: Frame<T>) {
+        if self.free.len() < self.limit {
+            let mut data = frame.into_vec();
+            data.clear();
+            self.free.push(data);
+        }
+    }
+
+    /// Like `Frame::new_from_fn`, but in a buffer from the pool if it has one
+    ///
+    /// # Panics
+    ///
+    /// if the frame is too large for its cells to fit in memory
+    pub fn take_from_fn<F>(&mut self, width: usize, height: usize, mut f: F) -> Frame<T>
+    where F: FnMut(usize, usize) -> T {
+        let cells = cells::<T>(width, height).unwrap_or_else(|e| panic!("{}", e));
+        let mut data = self.free.pop().unwrap_or_default();
+        data.extend((0..cells).map(|i| f(i % width, i / width)));
+        Frame::from_vec(width, height, data).expect("the buffer holds every cell")
+    }
+
+    /// A copy of frame in a buffer from the pool if it has one
+    pub fn take_clone(&mut self, frame: &Frame<T>) -> Frame<T>
+    where T: Clone {
+        let mut data = self.free.pop().unwrap_or_default();
+        data.extend_from_slice(frame.as_slice());
+        Frame::from_vec(frame.width(), frame.height(), data).expect("the buffer holds every cell")
+    }
+}
+
+impl<T> Frame<T> {
+    /// Like `next_frame`, but in a buffer from pool if it has one
+    pub fn next_frame_in<F>(&self, pool: &mut FramePool<T>, step: F) -> Frame<T>
+    where F: Fn(Square<T>) -> T {
+        pool.take_from_fn(self.width(), self.height(), |x, y| step(self.square(x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life::{self, State};
+    use super::super::patterns::{Orientation, Pattern};
+    use super::FramePool;
+
+    #[test]
+    fn reuses_buffers() {
+        let mut frame = Frame::new(10, 10);
+        Pattern::glider().place(&mut frame, 2, 2, Orientation::Identity, State::Alive);
+        let mut pool = FramePool::new(1);
+        pool.recycle(Frame::<State>::new(20, 20));
+        pool.recycle(Frame::<State>::new(20, 20));
+        assert_eq!(pool.len(), 1);
+        assert!(pool.memory_usage() >= 400);
+
+        let next = frame.next_frame_in(&mut pool, game_of_life::rule);
+        assert!(pool.is_empty());
+        assert_eq!(next, frame.next_frame(game_of_life::rule));
+        // the buffer of the larger frame is big enough without growing
+        assert!(next.into_vec().capacity() >= 400);
+
+        pool.recycle(frame.clone());
+        assert_eq!(pool.take_clone(&frame), frame);
+    }
+}
@@ -0,0 +1,178 @@
+use super::board;
+
+use numpy::{IntoPyArray, PyArray2, PyArray3, PyArrayMethods, PyReadonlyArray2,
+            PyUntypedArrayMethods};
+use numpy::ndarray::ArrayView2;
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+
+/// A frame of numbers, which is how Python sees the cells of every rule
+#[pyclass(module = "simulation")]
+pub struct Frame(super::Frame<f32>);
+
+impl Frame {
+    fn index(&self, (x, y): (usize, usize)) -> PyResult<(usize, usize)> {
+        if x >= self.0.width() || y >= self.0.height() {
+            return Err(PyIndexError::new_err(format!(
+                "({}, {}) is outside the {}x{} frame", x, y, self.0.width(), self.0.height())));
+        }
+        Ok((x, y))
+    }
+}
+
+#[pymethods]
+impl Frame {
+    #[new]
+    fn new(width: usize, height: usize) -> Frame {
+        Frame(super::Frame::new(width, height))
+    }
+
+    /// A frame holding a copy of a 2d float32 array indexed by row, then
+    /// column
+    #[staticmethod]
+    fn from_numpy(array: PyReadonlyArray2<f32>) -> Frame {
+        let (h, w) = (array.shape()[0], array.shape()[1]);
+        let data = array.as_array().iter().cloned().collect();
+        Frame(super::Frame::from_vec(w, h, data).unwrap())
+    }
+
+    /// A 2d array sharing the frame's memory, so writing to it changes the
+    /// frame. The array keeps the frame alive.
+    fn to_numpy(slf: Bound<'_, Self>) -> Bound<'_, PyArray2<f32>> {
+        let frame = slf.borrow();
+        let view = ArrayView2::from_shape((frame.0.height(), frame.0.width()),
+                                          frame.0.as_slice()).unwrap();
+        // frames never reallocate their data once created and the array holds
+        // a reference to the frame, so the memory outlives the array
+        unsafe { PyArray2::borrow_from_array_bound(&view, slf.clone().into_any()) }
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.0.width()
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.0.height()
+    }
+
+    fn __getitem__(&self, index: (usize, usize)) -> PyResult<f32> {
+        let (x, y) = self.index(index)?;
+        Ok(*self.0.get(x, y))
+    }
+
+    fn __setitem__(&mut self, index: (usize, usize), value: f32) -> PyResult<()> {
+        let (x, y) = self.index(index)?;
+        *self.0.get_mut(x, y) = value;
+        Ok(())
+    }
+}
+
+/// Runs one of the built in rules. Cells of discrete rules are their state,
+/// 0 being dead, and cells of continuous rules are their value.
+#[pyclass(module = "simulation")]
+pub struct Simulation(board::Board);
+
+#[pymethods]
+impl Simulation {
+    /// A simulation of the rule called rule starting from frame, which is
+    /// copied
+    #[new]
+    fn new(rule: &str, frame: &Frame) -> PyResult<Simulation> {
+        let mut board = board::Board::new(rule, frame.0.width(), frame.0.height())
+            .ok_or_else(|| PyValueError::new_err(format!(
+                "unknown rule {}, expected one of {}", rule, board::RULES.join(", "))))?;
+        for (x, y, &v) in frame.0.enumerate_squares() {
+            board.set(x, y, v);
+        }
+        Ok(Simulation(board))
+    }
+
+    #[getter]
+    fn rule(&self) -> &str {
+        self.0.rule()
+    }
+
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.0.generation()
+    }
+
+    /// Advance n generations. The GIL is released while stepping.
+    #[pyo3(signature = (n = 1))]
+    fn step(&mut self, py: Python<'_>, n: u64) {
+        py.allow_threads(|| self.0.step_n(n))
+    }
+
+    /// A copy of the current frame
+    #[getter]
+    fn frame(&self) -> Frame {
+        let data = self.0.values();
+        Frame(super::Frame::from_vec(self.0.width(), self.0.height(), data).unwrap())
+    }
+
+    /// The current frame as a 2d array. The array owns its memory, which is
+    /// handed over to numpy rather than copied.
+    fn values<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        self.0.values()
+            .into_pyarray_bound(py)
+            .reshape([self.0.height(), self.0.width()])
+            .unwrap()
+    }
+
+    /// The current frame drawn as an array of RGBA pixels by row, then column
+    fn rgba<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray3<u8>> {
+        let mut pixels = vec![0; self.0.width() * self.0.height() * 4];
+        self.0.write_rgba(&mut pixels);
+        pixels.into_pyarray_bound(py).reshape([self.0.height(), self.0.width(), 4]).unwrap()
+    }
+
+    /// Replace the current frame with a copy of frame, which has to be the
+    /// same size
+    #[setter]
+    fn set_frame(&mut self, frame: &Frame) -> PyResult<()> {
+        if (frame.0.width(), frame.0.height()) != (self.0.width(), self.0.height()) {
+            return Err(PyValueError::new_err(format!(
+                "a {}x{} frame can't replace a {}x{} one", frame.0.width(), frame.0.height(),
+                self.0.width(), self.0.height())));
+        }
+        for (x, y, &v) in frame.0.enumerate_squares() {
+            self.0.set(x, y, v);
+        }
+        Ok(())
+    }
+}
+
+/// The `simulation` Python module. Build it with maturin, or with `cargo
+/// rustc --lib --crate-type cdylib --release --features
+/// python,pyo3/extension-module` and rename the library to `simulation.so`.
+#[pymodule]
+fn simulation(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Frame>()?;
+    m.add_class::<Simulation>()?;
+    m.add("RULES", board::RULES.to_vec())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Frame, Simulation};
+
+    #[test]
+    fn simulation() {
+        let mut frame = Frame::new(5, 5);
+        for y in 1..4 {
+            frame.__setitem__((2, y), 1.).unwrap();
+        }
+        assert!(frame.__getitem__((5, 0)).is_err());
+        let mut sim = Simulation::new("life", &frame).unwrap();
+        sim.0.step();
+        let row = (0..5).map(|x| sim.frame().__getitem__((x, 2)).unwrap()).collect::<Vec<_>>();
+        assert_eq!(row, vec![0., 1., 1., 1., 0.]);
+        assert!(sim.set_frame(&Frame::new(4, 5)).is_err());
+        sim.set_frame(&Frame::new(5, 5)).unwrap();
+        assert_eq!(sim.0.values(), vec![0.; 25]);
+        assert!(Simulation::new("hashlife", &frame).is_err());
+    }
+}
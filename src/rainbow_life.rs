@@ -6,11 +6,13 @@ use rand::Rng;
 use std::collections::HashMap;
 
 /// Enum for a game of life that is multicolored with more complex rules
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum State {
     Red,
     Green,
     Blue,
+    #[default]
     Dead,
 }
 
@@ -31,18 +33,12 @@ where State: 'a {
     /// Return the number of nodes alive surrounding this number
     fn alive_count(&self) -> usize {
         let this = self.get(0, 0);
-//         println!("{:?}", self.within_ortholinear(1));
-        self.within_ortholinear(1)
-            .iter()
-            .filter(|e| { **e != State::Dead && **e != this.enemy() })
+        self.neighbors(1)
+            .filter(|&(_, _, e)| { *e != State::Dead && *e != this.enemy() })
             .count()
     }
 }
 
-impl Default for State {
-    fn default() -> State { State::Dead }
-}
-
 pub fn rule(curr: Square<State>) -> State {
     use self::State::Dead;
 
@@ -56,7 +52,8 @@ pub fn rule(curr: Square<State>) -> State {
     } else {
         match curr.alive_count() {
             3 => {
-                let neighbors = curr.within_ortholinear(1).iter().cloned()
+                let neighbors = curr.neighbors(1)
+                    .map(|(_, _, &e)| e)
                     .filter(|e| { *e != Dead })
                     .collect::<Vec<State>>();
                 // println!("{:?}", neighbors);
@@ -0,0 +1,139 @@
+use super::Frame;
+use super::patterns::Symmetry;
+
+use rand::Rng;
+use rand::distributions::{IndependentSample, Sample};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+impl<T> Frame<T> {
+    /// A width by height frame with every cell drawn from distribution, row
+    /// by row, so the same seed always gives the same frame
+    pub fn random<R, D>(width: usize, height: usize, rng: &mut R, distribution: &D) -> Frame<T>
+    where R: Rng, D: IndependentSample<T> {
+        let data = (0..width * height).map(|_| distribution.ind_sample(rng)).collect::<Vec<_>>();
+        Frame::from_vec(width, height, data).unwrap()
+    }
+}
+
+impl<T> Frame<T>
+where T: Clone {
+    /// A width by height frame drawn from distribution like `random`, with
+    /// every cell copied to the cells symmetry maps it to, e.g. to search for
+    /// oscillators that keep the symmetry
+    ///
+    /// # Panics
+    ///
+    /// if symmetry needs a square frame and width and height differ
+    pub fn random_symmetric<R, D>(width: usize, height: usize, rng: &mut R, distribution: &D,
+                                  symmetry: Symmetry) -> Frame<T>
+    where R: Rng, D: IndependentSample<T> {
+        assert!(width == height || !symmetry.needs_square(),
+                "a {}x{} frame can't have {:?} symmetry", width, height, symmetry);
+        let sector = Frame::random(width, height, rng, distribution);
+        // every cell takes the value of the first cell of its orbit
+        let data = (0..width * height).map(|i| {
+            let (x, y) = (i % width, i / width);
+            let first = symmetry.orientations().iter()
+                .map(|o| { let (x, y) = o.apply(x, y, width, height); y * width + x })
+                .min()
+                .unwrap();
+            sector.as_slice()[first].clone()
+        }).collect::<Vec<_>>();
+        Frame::from_vec(width, height, data).unwrap()
+    }
+}
+
+impl Frame<bool> {
+    /// A width by height frame where each cell is true with probability
+    /// density
+    pub fn random_bool<R>(width: usize, height: usize, rng: &mut R, density: f64) -> Frame<bool>
+    where R: Rng {
+        Frame::random(width, height, rng, &Density(density))
+    }
+
+    /// A width by height frame where each cell is true with probability
+    /// density, with symmetry like `random_symmetric`
+    pub fn random_bool_symmetric<R>(width: usize, height: usize, rng: &mut R, density: f64,
+                                    symmetry: Symmetry) -> Frame<bool>
+    where R: Rng {
+        Frame::random_symmetric(width, height, rng, &Density(density), symmetry)
+    }
+}
+
+/// True with a probability
+struct Density(f64);
+
+impl Sample<bool> for Density {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> bool {
+        self.ind_sample(rng)
+    }
+}
+
+impl IndependentSample<bool> for Density {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> bool {
+        rng.next_f64() < self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::patterns::Symmetry;
+
+    use rand::{SeedableRng, XorShiftRng};
+    use rand::distributions::Range;
+
+    #[test]
+    fn seeded() {
+        let rng = || XorShiftRng::from_seed([1, 2, 3, 4]);
+        let a = Frame::random(20, 10, &mut rng(), &Range::new(0u8, 4));
+        assert_eq!(a, Frame::random(20, 10, &mut rng(), &Range::new(0u8, 4)));
+        assert_eq!((a.width(), a.height()), (20, 10));
+        assert!(a.as_slice().iter().all(|&c| c < 4));
+        assert!((0..4).all(|s| a.as_slice().contains(&s)));
+    }
+
+    #[test]
+    fn density() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let frame = Frame::random_bool(100, 100, &mut rng, 0.3);
+        let alive = frame.as_slice().iter().filter(|&&c| c).count();
+        assert!(2700 < alive && alive < 3300, "{} alive", alive);
+        assert!(Frame::random_bool(5, 5, &mut rng, 0.).as_slice().iter().all(|&c| !c));
+        assert!(Frame::random_bool(5, 5, &mut rng, 1.).as_slice().iter().all(|&c| c));
+    }
+
+    #[test]
+    fn symmetric() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let symmetries = [Symmetry::C1, Symmetry::C2, Symmetry::C4, Symmetry::D4, Symmetry::D8];
+        for &symmetry in &symmetries {
+            for &(w, h) in &[(16, 16), (9, 9), (12, 7)] {
+                if w != h && symmetry.needs_square() {
+                    continue;
+                }
+                let frame = Frame::random_bool_symmetric(w, h, &mut rng, 0.5, symmetry);
+                for o in symmetry.orientations() {
+                    for (x, y, c) in frame.enumerate_squares() {
+                        let (i, j) = o.apply(x, y, w, h);
+                        assert_eq!(frame.get(i, j), c, "{:?} {}x{}", symmetry, w, h);
+                    }
+                }
+            }
+        }
+        // without symmetry the frame is as random as random_bool
+        let seeded = || XorShiftRng::from_seed([5, 6, 7, 8]);
+        let frame = Frame::random_bool_symmetric(8, 8, &mut seeded(), 0.5, Symmetry::C1);
+        assert_eq!(frame, Frame::random_bool(8, 8, &mut seeded(), 0.5));
+        assert!(Symmetry::D4.orientations().len() == 4 && !Symmetry::D4.needs_square());
+    }
+
+    #[test]
+    #[should_panic]
+    fn not_square() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        Frame::random_bool_symmetric(5, 4, &mut rng, 0.5, Symmetry::C4);
+    }
+}
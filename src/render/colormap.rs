@@ -0,0 +1,178 @@
+use super::{Color, ColorMap};
+use super::super::CellState;
+
+/// Colors cells in the default state one color and every other cell another
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Binary {
+    pub alive: Color,
+    pub dead: Color,
+}
+
+impl Default for Binary {
+    /// White on black
+    fn default() -> Binary {
+        Binary { alive: [255, 255, 255], dead: [0, 0, 0] }
+    }
+}
+
+impl<T: Default + PartialEq> ColorMap<T> for Binary {
+    fn color(&self, cell: &T) -> Color {
+        if *cell == T::default() { self.dead } else { self.alive }
+    }
+}
+
+/// Colors cells with the color their `CellState` gives them
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StateColors;
+
+impl<T: CellState> ColorMap<T> for StateColors {
+    fn color(&self, cell: &T) -> Color {
+        cell.color()
+    }
+}
+
+/// Gives each state of a `CellState` its own color by its number, cycling
+/// through the colors if there are more states than colors
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    colors: Vec<Color>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<Color>) -> Palette {
+        assert!(!colors.is_empty());
+        Palette { colors }
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+}
+
+impl Default for Palette {
+    /// Black for the default state followed by ten easily told apart colors
+    fn default() -> Palette {
+        Palette::new(vec![
+            [0, 0, 0],
+            [31, 119, 180], [255, 127, 14], [44, 160, 44], [214, 39, 40], [148, 103, 189],
+            [140, 86, 75], [227, 119, 194], [127, 127, 127], [188, 189, 34], [23, 190, 207],
+        ])
+    }
+}
+
+impl<T: CellState> ColorMap<T> for Palette {
+    fn color(&self, cell: &T) -> Color {
+        self.colors[cell.index() % self.colors.len()]
+    }
+}
+
+/// Interpolates between evenly spaced colors as a number goes from `min` to
+/// `max`. Numbers outside the range get the color at its nearest end and NaN
+/// gets the first color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<Color>,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Gradient {
+    /// A gradient through stops over `[0, 1]`
+    pub fn new(stops: Vec<Color>) -> Gradient {
+        assert!(!stops.is_empty());
+        Gradient { stops, min: 0.0, max: 1.0 }
+    }
+
+    /// Black to white
+    pub fn grayscale() -> Gradient {
+        Gradient::new(vec![[0, 0, 0], [255, 255, 255]])
+    }
+
+    /// The perceptually uniform viridis map, from dark purple to yellow
+    pub fn viridis() -> Gradient {
+        Gradient::new(vec![
+            [68, 1, 84], [71, 45, 123], [59, 82, 139], [44, 114, 142], [33, 145, 140],
+            [40, 174, 128], [94, 201, 98], [173, 220, 48], [253, 231, 37],
+        ])
+    }
+
+    /// The same colors spread over `[min, max]` instead
+    pub fn with_range(self, min: f64, max: f64) -> Gradient {
+        Gradient { min, max, ..self }
+    }
+
+    pub fn stops(&self) -> &[Color] {
+        &self.stops
+    }
+
+    /// The color of value
+    pub fn at(&self, value: f64) -> Color {
+        let t = if self.max > self.min {
+            ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        if t.is_nan() || self.stops.len() == 1 {
+            return self.stops[0];
+        }
+        let position = t * (self.stops.len() - 1) as f64;
+        let i = (position as usize).min(self.stops.len() - 2);
+        let (a, b, f) = (self.stops[i], self.stops[i + 1], position - i as f64);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+        [mix(a[0], b[0]), mix(a[1], b[1]), mix(a[2], b[2])]
+    }
+}
+
+impl ColorMap<f32> for Gradient {
+    fn color(&self, cell: &f32) -> Color {
+        self.at(*cell as f64)
+    }
+}
+
+impl ColorMap<f64> for Gradient {
+    fn color(&self, cell: &f64) -> Color {
+        self.at(*cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ColorMap;
+    use super::super::super::game_of_life::State;
+    use super::super::super::rainbow_life;
+    use super::{Binary, Gradient, Palette};
+
+    #[test]
+    fn binary() {
+        let colormap = Binary::default();
+        assert_eq!(colormap.color(&State::Alive), [255, 255, 255]);
+        assert_eq!(colormap.color(&State::Dead), [0, 0, 0]);
+        assert_eq!(colormap.color(&0.5f32), [255, 255, 255]);
+    }
+
+    #[test]
+    fn palette() {
+        let palette = Palette::default();
+        assert_eq!(palette.color(&rainbow_life::State::Dead), [0, 0, 0]);
+        assert_eq!(palette.color(&rainbow_life::State::Red), palette.colors()[1]);
+        assert!(palette.color(&rainbow_life::State::Green) !=
+                palette.color(&rainbow_life::State::Blue));
+        // wraps around
+        let palette = Palette::new(vec![[0, 0, 0], [1, 1, 1]]);
+        assert_eq!(palette.color(&3u8), [1, 1, 1]);
+    }
+
+    #[test]
+    fn gradient() {
+        let gray = Gradient::grayscale();
+        assert_eq!(gray.color(&0.0f32), [0, 0, 0]);
+        assert_eq!(gray.color(&0.5f32), [128, 128, 128]);
+        assert_eq!(gray.color(&2.0f32), [255, 255, 255]);
+        assert_eq!(gray.color(&f32::NAN), [0, 0, 0]);
+
+        let viridis = Gradient::viridis().with_range(-1.0, 1.0);
+        assert_eq!(viridis.color(&-1.0f64), [68, 1, 84]);
+        assert_eq!(viridis.color(&0.0f64), [33, 145, 140]);
+        assert_eq!(viridis.color(&1.0f64), [253, 231, 37]);
+    }
+}
@@ -0,0 +1,199 @@
+use super::super::{Simulation, Square};
+use super::{Color, ColorMap};
+
+use gif::{DisposalMethod, Encoder, Repeat, SetParameter};
+use gif::Frame as GifFrame;
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result, Write};
+
+/// Options for recording an animated gif
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GifOptions {
+    /// the delay between generations in hundredths of a second
+    pub delay: u16,
+    /// the side length of the block of pixels drawn for each cell
+    pub scale: usize,
+    /// whether the animation loops forever or plays once
+    pub repeat: bool,
+}
+
+impl Default for GifOptions {
+    fn default() -> GifOptions {
+        GifOptions { delay: 10, scale: 1, repeat: true }
+    }
+}
+
+/// A rectangle of cells as (left, top, right, bottom), exclusive of the right
+/// and bottom edges
+type Rect = (usize, usize, usize, usize);
+
+/// The smallest rectangle containing every cell that differs between a and b
+fn changed(a: &[u8], b: &[u8], width: usize) -> Option<Rect> {
+    a.iter().zip(b).enumerate()
+        .filter(|&(_, (x, y))| x != y)
+        .map(|(i, _)| (i % width, i / width))
+        .fold(None, |rect, (x, y)| {
+            let (l, t, r, b) = rect.unwrap_or((x, y, x + 1, y + 1));
+            Some((l.min(x), t.min(y), r.max(x + 1), b.max(y + 1)))
+        })
+}
+
+/// Record an animated gif of sim, starting with the current generation. The
+/// simulation is advanced by `generations` generations. All frames share a
+/// single palette, so the color map can produce at most 256 distinct colors.
+/// After the first frame only the region that changed is stored, and
+/// generations that don't change anything extend the previous frame instead.
+pub fn record<W, T, F, C>(writer: W, sim: &mut Simulation<T, F>, generations: usize,
+                          colormap: &C, options: &GifOptions) -> Result<()>
+where W: Write, T: Clone, F: Fn(Square<T>) -> T, C: ColorMap<T> + ?Sized {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("gif", generation = sim.generation(), generations).entered();
+    assert!(options.scale > 0);
+    let (w, h) = (sim.frame().width(), sim.frame().height());
+    let scale = options.scale;
+    let too_big = || Error::new(ErrorKind::InvalidInput, "frame is too large for a gif");
+    let pw = u16::try_from(w * scale).map_err(|_| too_big())?;
+    let ph = u16::try_from(h * scale).map_err(|_| too_big())?;
+
+    // render every generation as palette indices
+    let mut palette: Vec<Color> = vec![];
+    let mut frames = vec![];
+    for _ in 0..generations {
+        let mut indices = Vec::with_capacity(w * h);
+        for (_, _, cell) in sim.frame().enumerate_squares() {
+            let color = colormap.color(cell);
+            let i = match palette.iter().position(|c| *c == color) {
+                Some(i) => i,
+                None if palette.len() < 256 => {
+                    palette.push(color);
+                    palette.len() - 1
+                }
+                None => {
+                    return Err(Error::new(ErrorKind::InvalidData,
+                                          "more than 256 colors in a gif"));
+                }
+            };
+            indices.push(i as u8);
+        }
+        frames.push(indices);
+        sim.step();
+    }
+
+    // work out which region of each frame needs to be written and for how long
+    let mut parts: Vec<(usize, Rect, u16)> = vec![];
+    for (k, frame) in frames.iter().enumerate() {
+        let rect = if k == 0 { Some((0, 0, w, h)) } else { changed(&frames[k - 1], frame, w) };
+        match (rect, parts.last_mut()) {
+            (Some(rect), _) => parts.push((k, rect, options.delay)),
+            (None, Some(last)) => last.2 = last.2.saturating_add(options.delay),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    let flat = palette.iter().flat_map(|c| c.iter().cloned()).collect::<Vec<_>>();
+    let mut encoder = Encoder::new(writer, pw, ph, &flat)?;
+    if options.repeat {
+        encoder.set(Repeat::Infinite)?;
+    }
+    for (k, (l, t, r, b), delay) in parts {
+        let mut buffer = Vec::with_capacity((r - l) * (b - t) * scale * scale);
+        for py in t * scale..b * scale {
+            for px in l * scale..r * scale {
+                buffer.push(frames[k][(py / scale) * w + px / scale]);
+            }
+        }
+        let frame = GifFrame {
+            delay,
+            dispose: DisposalMethod::Keep,
+            left: (l * scale) as u16,
+            top: (t * scale) as u16,
+            width: ((r - l) * scale) as u16,
+            height: ((b - t) * scale) as u16,
+            buffer: Cow::Owned(buffer),
+            ..GifFrame::default()
+        };
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{Frame, Simulation};
+    use super::super::super::game_of_life;
+    use super::super::super::game_of_life::State;
+    use super::super::super::game_of_life::State::Alive;
+    use super::super::Color;
+    use super::{record, GifOptions};
+
+    use gif;
+
+    fn color(s: &State) -> Color {
+        match *s {
+            State::Alive => [255, 255, 255],
+            State::Dead => [0, 0, 0],
+        }
+    }
+
+    /// Decode a gif into (left, top, width, height, delay) for each frame
+    fn decode(buf: &[u8]) -> Vec<(u16, u16, u16, u16, u16)> {
+        let mut reader = gif::Decoder::new(buf).read_info().unwrap();
+        let mut frames = vec![];
+        while let Some(f) = reader.read_next_frame().unwrap() {
+            frames.push((f.left, f.top, f.width, f.height, f.delay));
+        }
+        frames
+    }
+
+    #[test]
+    fn blinker() {
+        let mut frame = Frame::new(6, 6);
+        *frame.get_mut(2, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        *frame.get_mut(2, 3) = Alive;
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+
+        let mut buf = vec![];
+        let options = GifOptions { delay: 5, scale: 2, repeat: true };
+        record(&mut buf, &mut sim, 3, &color, &options).unwrap();
+        assert_eq!(sim.generation(), 3);
+
+        let frames = decode(&buf);
+        assert_eq!(frames, vec![
+            (0, 0, 12, 12, 5),
+            // only the 3x3 block the blinker rotates in
+            (2, 2, 6, 6, 5),
+            (2, 2, 6, 6, 5),
+        ]);
+    }
+
+    #[test]
+    fn still_life_merges() {
+        let mut frame = Frame::new(4, 4);
+        *frame.get_mut(1, 1) = Alive;
+        *frame.get_mut(1, 2) = Alive;
+        *frame.get_mut(2, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+
+        let mut buf = vec![];
+        record(&mut buf, &mut sim, 4, &color, &GifOptions::default()).unwrap();
+        assert_eq!(decode(&buf), vec![(0, 0, 4, 4, 40)]);
+    }
+
+    #[test]
+    fn too_many_colors() {
+        let mut frame = Frame::<u32>::new(20, 20);
+        for x in 0..20 {
+            for y in 0..20 {
+                *frame.get_mut(x, y) = (x * 20 + y) as u32;
+            }
+        }
+        let mut sim = Simulation::new(frame, |sq| *sq.get(0, 0));
+        let colormap = |c: &u32| [*c as u8, (*c >> 8) as u8, 0];
+        let result = record(vec![], &mut sim, 1, &colormap, &GifOptions::default());
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use super::super::Frame;
+use super::{png, terminal, Color};
+use super::colormap::Gradient;
+use super::upscale::{self, Filter};
+
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+/// Cells that hold a single number
+pub trait Value: Copy {
+    fn value(self) -> f64;
+}
+
+macro_rules! value {
+    ($($t:ty),*) => {$(
+        impl Value for $t {
+            fn value(self) -> f64 {
+                self as f64
+            }
+        }
+    )*}
+}
+
+value!(u8, u16, u32, u64, i8, i16, i32, i64, usize, f32, f64);
+
+/// Draws numeric frames, such as sandpile heights or chemical
+/// concentrations, by running their values through a gradient. Unless fixed
+/// bounds are given the gradient stretches from the smallest to the largest
+/// finite value in each frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heatmap {
+    pub gradient: Gradient,
+    bounds: Option<(f64, f64)>,
+}
+
+impl Default for Heatmap {
+    /// Viridis, scaled to each frame
+    fn default() -> Heatmap {
+        Heatmap::new(Gradient::viridis())
+    }
+}
+
+impl Heatmap {
+    /// A heatmap through gradient, scaled to each frame
+    pub fn new(gradient: Gradient) -> Heatmap {
+        Heatmap { gradient, bounds: None }
+    }
+
+    /// Always map min to the start of the gradient and max to its end
+    pub fn with_bounds(self, min: f64, max: f64) -> Heatmap {
+        Heatmap { bounds: Some((min, max)), ..self }
+    }
+
+    /// The values mapped to either end of the gradient for frame
+    pub fn bounds<T: Value>(&self, frame: &Frame<T>) -> (f64, f64) {
+        if let Some(bounds) = self.bounds {
+            return bounds;
+        }
+        frame.enumerate_squares()
+            .map(|(_, _, v)| v.value())
+            .filter(|v| v.is_finite())
+            .fold(None, |bounds, v| match bounds {
+                None => Some((v, v)),
+                Some((min, max)) => Some((v.min(min), v.max(max))),
+            })
+            .unwrap_or((0.0, 0.0))
+    }
+
+    /// The coloring of frame's cells
+    pub fn colormap<T: Value>(&self, frame: &Frame<T>) -> impl Fn(&T) -> Color {
+        let (min, max) = self.bounds(frame);
+        let gradient = self.gradient.clone().with_range(min, max);
+        move |cell: &T| gradient.at(cell.value())
+    }
+
+    /// Write frame as a png where each cell is a scale by scale block of
+    /// pixels
+    pub fn write_png<W: Write, T: Value>(&self, writer: W, frame: &Frame<T>, scale: usize)
+                                         -> Result<()> {
+        png::write(writer, frame, &self.colormap(frame), scale)
+    }
+
+    /// Save frame as a png at path where each cell is a scale by scale block
+    /// of pixels
+    pub fn save_png<T: Value, P: AsRef<Path>>(&self, frame: &Frame<T>, path: P, scale: usize)
+                                              -> Result<()> {
+        png::save(frame, path, &self.colormap(frame), scale)
+    }
+
+    /// Write frame as a width by height png, filling in between the cells
+    /// with filter. The values are blended before they are colored, and the
+    /// gradient is scaled to frame rather than to the blended values.
+    pub fn write_png_upscaled<W: Write, T: Value>(&self, writer: W, frame: &Frame<T>,
+                                                  width: usize, height: usize, filter: Filter)
+                                                  -> Result<()> {
+        let upscaled = upscale::upscale(frame, width, height, filter);
+        let (min, max) = self.bounds(frame);
+        let gradient = self.gradient.clone().with_range(min, max);
+        png::write(writer, &upscaled, &|v: &f64| gradient.at(*v), 1)
+    }
+
+    /// Save frame as a width by height png at path, filling in between the
+    /// cells with filter
+    pub fn save_png_upscaled<T: Value, P: AsRef<Path>>(&self, frame: &Frame<T>, path: P,
+                                                       width: usize, height: usize,
+                                                       filter: Filter) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        self.write_png_upscaled(file, frame, width, height, filter)
+    }
+
+    /// Print frame in 24-bit color with two rows per line
+    pub fn print<W: Write, T: Value>(&self, writer: W, frame: &Frame<T>) -> Result<()> {
+        terminal::print_half_blocks(writer, frame, &self.colormap(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::colormap::Gradient;
+    use super::super::upscale::Filter;
+    use super::Heatmap;
+
+    use image;
+
+    #[test]
+    fn bounds() {
+        let mut frame = Frame::<u32>::new(3, 2);
+        *frame.get_mut(0, 0) = 4;
+        *frame.get_mut(2, 1) = 12;
+        assert_eq!(Heatmap::default().bounds(&frame), (0.0, 12.0));
+        assert_eq!(Heatmap::default().with_bounds(-1.0, 1.0).bounds(&frame), (-1.0, 1.0));
+
+        let mut frame = Frame::<f32>::new(2, 1).map(|_| 0.5);
+        *frame.get_mut(0, 0) = f32::INFINITY;
+        assert_eq!(Heatmap::default().bounds(&frame), (0.5, 0.5));
+    }
+
+    #[test]
+    fn png() {
+        let mut frame = Frame::<f32>::new(2, 1).map(|_| 2.0);
+        *frame.get_mut(1, 0) = 3.0;
+        let mut buf = vec![];
+        Heatmap::new(Gradient::grayscale()).write_png(&mut buf, &frame, 1).unwrap();
+        let img = image::load_from_memory_with_format(&buf, image::PNG).unwrap().to_rgb();
+        assert_eq!(img.get_pixel(0, 0).data, [0, 0, 0]);
+        assert_eq!(img.get_pixel(1, 0).data, [255, 255, 255]);
+
+        // with fixed bounds both are in the middle of the gradient
+        let mut buf = vec![];
+        Heatmap::new(Gradient::grayscale()).with_bounds(0.0, 5.0)
+            .write_png(&mut buf, &frame, 1).unwrap();
+        let img = image::load_from_memory_with_format(&buf, image::PNG).unwrap().to_rgb();
+        assert_eq!(img.get_pixel(0, 0).data, [102, 102, 102]);
+        assert_eq!(img.get_pixel(1, 0).data, [153, 153, 153]);
+    }
+
+    #[test]
+    fn upscaled_png() {
+        let frame = Frame::from_vec(2, 1, vec![0.0f64, 1.0]).unwrap();
+        let mut buf = vec![];
+        Heatmap::new(Gradient::grayscale())
+            .write_png_upscaled(&mut buf, &frame, 4, 2, Filter::Bilinear).unwrap();
+        let img = image::load_from_memory_with_format(&buf, image::PNG).unwrap().to_rgb();
+        assert_eq!(img.dimensions(), (4, 2));
+        let row = (0..4).map(|x| img.get_pixel(x, 1).data[0]).collect::<Vec<_>>();
+        assert_eq!(row, [64, 64, 191, 191]);
+    }
+
+    #[test]
+    fn terminal() {
+        let mut frame = Frame::<u32>::new(1, 2);
+        *frame.get_mut(0, 1) = 7;
+        let mut out = vec![];
+        Heatmap::default().print(&mut out, &frame).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "\x1b[38;2;68;1;84m\x1b[48;2;253;231;37m\u{2580}\x1b[0m\n");
+    }
+}
@@ -0,0 +1,64 @@
+use super::Frame;
+
+/// Built in ways of coloring cells
+pub mod colormap;
+/// Saving frames as png images
+pub mod png;
+/// Recording simulations as animated gifs
+pub mod gif;
+/// Streaming long runs into a video encoder
+pub mod video;
+/// Drawing frames as text in a terminal
+pub mod terminal;
+/// Coloring numeric frames by their values
+pub mod heatmap;
+/// Embedding frames in ratatui layouts
+#[cfg(feature = "ratatui")]
+pub mod widget;
+/// Converting frames to and from image's buffers
+pub mod raster;
+/// Drawing frames larger than they are with smooth filters
+pub mod upscale;
+/// Small previews of frames of any size
+pub mod thumbnail;
+/// Panning and zooming around frames too large to show whole
+pub mod viewport;
+
+/// An rgb color
+pub type Color = [u8; 3];
+
+/// Describes how a cell is displayed
+pub trait ColorMap<T> {
+    /// The color of cell
+    fn color(&self, cell: &T) -> Color;
+}
+
+impl<T, F> ColorMap<T> for F
+where F: Fn(&T) -> Color {
+    fn color(&self, cell: &T) -> Color {
+        self(cell)
+    }
+}
+
+/// Render frame into a row major rgb buffer where each cell is a scale by
+/// scale block of pixels
+pub fn rgb_buffer<T, C>(frame: &Frame<T>, colormap: &C, scale: usize) -> Vec<u8>
+where C: ColorMap<T> + ?Sized {
+    let mut buf = vec![];
+    rgb_buffer_into(frame, colormap, scale, &mut buf);
+    buf
+}
+
+/// Like `rgb_buffer`, but replacing the contents of buf, so that rendering
+/// every generation can reuse one buffer
+pub fn rgb_buffer_into<T, C>(frame: &Frame<T>, colormap: &C, scale: usize, buf: &mut Vec<u8>)
+where C: ColorMap<T> + ?Sized {
+    let (w, h) = (frame.width() * scale, frame.height() * scale);
+    buf.clear();
+    buf.reserve(w * h * 3);
+    for y in 0..h {
+        for x in 0..w {
+            buf.extend_from_slice(&colormap.color(frame.get(x / scale, y / scale)));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::super::Frame;
+use super::ColorMap;
+use super::raster;
+
+use image;
+
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+/// Write frame as a png where each cell is a scale by scale block of pixels
+pub fn write<W, T, C>(writer: W, frame: &Frame<T>, colormap: &C, scale: usize) -> Result<()>
+where W: Write, C: ColorMap<T> + ?Sized {
+    #[cfg(feature = "tracing")]
+    let _span = debug_span!("png", width = frame.width(), height = frame.height(), scale).entered();
+    let img = raster::to_image(frame, colormap, scale);
+    let (w, h) = img.dimensions();
+    image::png::PNGEncoder::new(writer).encode(&img, w, h, image::RGB(8))
+}
+
+/// Save frame as a png at path where each cell is a scale by scale block of
+/// pixels
+pub fn save<T, P, C>(frame: &Frame<T>, path: P, colormap: &C, scale: usize) -> Result<()>
+where P: AsRef<Path>, C: ColorMap<T> + ?Sized {
+    let file = BufWriter::new(File::create(path)?);
+    write(file, frame, colormap, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::game_of_life::State;
+    use super::super::Color;
+    use super::write;
+
+    use image;
+
+    fn color(s: &State) -> Color {
+        match *s {
+            State::Alive => [255, 255, 255],
+            State::Dead => [0, 0, 64],
+        }
+    }
+
+    #[test]
+    fn scaled() {
+        let mut frame = Frame::new(3, 2);
+        *frame.get_mut(2, 1) = State::Alive;
+
+        let mut buf = vec![];
+        write(&mut buf, &frame, &color, 4).unwrap();
+
+        let img = image::load_from_memory_with_format(&buf, image::PNG).unwrap().to_rgb();
+        assert_eq!(img.dimensions(), (12, 8));
+        assert_eq!(img.get_pixel(0, 0).data, [0, 0, 64]);
+        assert_eq!(img.get_pixel(7, 7).data, [0, 0, 64]);
+        assert_eq!(img.get_pixel(8, 4).data, [255, 255, 255]);
+        assert_eq!(img.get_pixel(11, 7).data, [255, 255, 255]);
+    }
+}
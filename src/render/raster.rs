@@ -0,0 +1,83 @@
+use super::super::Frame;
+use super::{rgb_buffer, Color, ColorMap};
+
+use image::{ImageBuffer, Rgb, RgbImage};
+
+/// Render frame into an image where each cell is a scale by scale block of
+/// pixels, for saving in any format image supports or running its filters
+pub fn to_image<T, C>(frame: &Frame<T>, colormap: &C, scale: usize) -> RgbImage
+where C: ColorMap<T> + ?Sized {
+    assert!(scale > 0);
+    let (w, h) = ((frame.width() * scale) as u32, (frame.height() * scale) as u32);
+    ImageBuffer::from_raw(w, h, rgb_buffer(frame, colormap, scale))
+        .expect("the buffer has a pixel for every cell")
+}
+
+/// Convert every pixel of img into a cell with mapper
+pub fn from_image<T, F>(img: &RgbImage, mapper: F) -> Frame<T>
+where T: Default + Clone, F: Fn(Color) -> T {
+    let (w, h) = img.dimensions();
+    let mut frame = Frame::new(w as usize, h as usize);
+    for (x, y, pixel) in img.enumerate_pixels() {
+        let Rgb { data } = *pixel;
+        *frame.get_mut(x as usize, y as usize) = mapper(data);
+    }
+    frame
+}
+
+/// The reverse of colormap over states, mapping each pixel to the state whose
+/// color is closest to it. Images that went through lossy formats or filters
+/// still load as the states they were drawn from.
+pub fn inverse<T, C>(states: &[T], colormap: &C) -> impl Fn(Color) -> T
+where T: Clone, C: ColorMap<T> + ?Sized {
+    assert!(!states.is_empty());
+    let palette = states.iter().map(|s| (colormap.color(s), s.clone())).collect::<Vec<_>>();
+    move |color| {
+        let distance = |c: &Color| {
+            c.iter().zip(&color)
+                .map(|(&a, &b)| (a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        };
+        palette.iter()
+            .min_by_key(|&(c, _)| distance(c))
+            .map(|(_, s)| s.clone())
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::rainbow_life::State;
+    use super::super::colormap::StateColors;
+    use super::{from_image, inverse, to_image};
+
+    use image::imageops;
+
+    #[test]
+    fn round_trip() {
+        let mut frame = Frame::new(3, 2);
+        *frame.get_mut(0, 0) = State::Red;
+        *frame.get_mut(2, 1) = State::Blue;
+        let states = [State::Dead, State::Red, State::Green, State::Blue];
+
+        let scaled = to_image(&frame, &StateColors, 2);
+        assert_eq!(scaled.dimensions(), (6, 4));
+        assert_eq!(scaled.get_pixel(1, 1).data, [255, 0, 0]);
+        assert_eq!(scaled.get_pixel(5, 2).data, [0, 0, 255]);
+        let img = to_image(&frame, &StateColors, 1);
+        assert_eq!(from_image(&img, inverse(&states, &StateColors)), frame);
+    }
+
+    #[test]
+    fn filtered() {
+        let mut frame = Frame::new(4, 4);
+        *frame.get_mut(1, 1) = State::Green;
+        // inverting the colors of a green cell leaves mostly red and blue
+        let mut img = to_image(&frame, &StateColors, 1);
+        imageops::invert(&mut img);
+        let loaded = from_image(&img, inverse(&[State::Dead, State::Green], &StateColors));
+        assert_eq!(*loaded.get(1, 1), State::Dead);
+        assert_eq!(*loaded.get(0, 0), State::Green);
+    }
+}
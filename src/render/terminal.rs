@@ -0,0 +1,201 @@
+use super::super::{CellState, Frame};
+use super::super::game_of_life;
+use super::{Color, ColorMap};
+
+use std::io::{Result, Write};
+
+/// Describes how a cell is drawn as a single character
+pub trait CellRenderer<T> {
+    /// The character for cell
+    fn render(&self, cell: &T) -> char;
+}
+
+impl<T, F> CellRenderer<T> for F
+where F: Fn(&T) -> char {
+    fn render(&self, cell: &T) -> char {
+        self(cell)
+    }
+}
+
+/// Draws Game of Life cells as `#` and `.`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LifeRenderer;
+
+impl CellRenderer<game_of_life::State> for LifeRenderer {
+    fn render(&self, cell: &game_of_life::State) -> char {
+        match *cell {
+            game_of_life::State::Alive => '#',
+            game_of_life::State::Dead => '.',
+        }
+    }
+}
+
+/// Draws cells as the character their `CellState` gives them
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StateChars;
+
+impl<T: CellState> CellRenderer<T> for StateChars {
+    fn render(&self, cell: &T) -> char {
+        cell.to_char()
+    }
+}
+
+/// Print frame with one line per row
+pub fn print<W, T, R>(writer: W, frame: &Frame<T>, renderer: &R) -> Result<()>
+where W: Write, R: CellRenderer<T> + ?Sized {
+    print_region(writer, frame, renderer, 0, 0, frame.width(), frame.height())
+}
+
+/// Print the width by height region of frame with its top left corner at
+/// (x, y), wrapping around the edges of the frame
+pub fn print_region<W, T, R>(mut writer: W, frame: &Frame<T>, renderer: &R,
+                             x: usize, y: usize, width: usize, height: usize) -> Result<()>
+where W: Write, R: CellRenderer<T> + ?Sized {
+    let (fw, fh) = (frame.width(), frame.height());
+    let mut line = String::with_capacity(width);
+    for j in 0..height {
+        line.clear();
+        for i in 0..width {
+            line.push(renderer.render(frame.get((x + i) % fw, (y + j) % fh)));
+        }
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Print frame in 24-bit color, drawing two rows per line with the upper half
+/// block character (its foreground is the upper cell and its background the
+/// lower one). Needs a terminal with truecolor support.
+pub fn print_half_blocks<W, T, C>(writer: W, frame: &Frame<T>, colormap: &C) -> Result<()>
+where W: Write, C: ColorMap<T> + ?Sized {
+    print_half_blocks_region(writer, frame, colormap, 0, 0, frame.width(), frame.height())
+}
+
+/// Print the width by height region of frame with its top left corner at
+/// (x, y) as half blocks, wrapping around the edges of the frame
+pub fn print_half_blocks_region<W, T, C>(mut writer: W, frame: &Frame<T>, colormap: &C,
+                                         x: usize, y: usize, width: usize, height: usize)
+                                         -> Result<()>
+where W: Write, C: ColorMap<T> + ?Sized {
+    let (fw, fh) = (frame.width(), frame.height());
+    let color = |i: usize, j: usize| colormap.color(frame.get((x + i) % fw, (y + j) % fh));
+    let mut line = String::new();
+    for j in (0..height).step_by(2) {
+        line.clear();
+        // only switch colors when they change from the previous character
+        let mut current: Option<(Color, Option<Color>)> = None;
+        for i in 0..width {
+            let top = color(i, j);
+            let bottom = if j + 1 < height { Some(color(i, j + 1)) } else { None };
+            if current != Some((top, bottom)) {
+                line.push_str(&format!("\x1b[38;2;{};{};{}m", top[0], top[1], top[2]));
+                match bottom {
+                    Some(b) => line.push_str(&format!("\x1b[48;2;{};{};{}m", b[0], b[1], b[2])),
+                    None => line.push_str("\x1b[49m"),
+                }
+                current = Some((top, bottom));
+            }
+            line.push('\u{2580}');
+        }
+        writeln!(writer, "{}\x1b[0m", line)?;
+    }
+    Ok(())
+}
+
+/// Print frame with each 2 by 4 block of cells packed into one braille
+/// character, showing a dot for each cell for which alive is true
+pub fn print_braille<W, T, F>(writer: W, frame: &Frame<T>, alive: F) -> Result<()>
+where W: Write, F: Fn(&T) -> bool {
+    print_braille_region(writer, frame, alive, 0, 0, frame.width(), frame.height())
+}
+
+/// Print the width by height region of frame with its top left corner at
+/// (x, y) as braille, wrapping around the edges of the frame
+pub fn print_braille_region<W, T, F>(mut writer: W, frame: &Frame<T>, alive: F,
+                                     x: usize, y: usize, width: usize, height: usize)
+                                     -> Result<()>
+where W: Write, F: Fn(&T) -> bool {
+    // the bit for the dot at (i, j) within a character
+    const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+    let (fw, fh) = (frame.width(), frame.height());
+    let mut line = String::new();
+    for j in (0..height).step_by(4) {
+        line.clear();
+        for i in (0..width).step_by(2) {
+            let mut bits = 0;
+            for (di, column) in DOTS.iter().enumerate() {
+                for (dj, &bit) in column.iter().enumerate() {
+                    let (ci, cj) = (i + di, j + dj);
+                    if ci < width && cj < height &&
+                       alive(frame.get((x + ci) % fw, (y + cj) % fh)) {
+                        bits |= bit;
+                    }
+                }
+            }
+            line.push(::std::char::from_u32(0x2800 + bits).unwrap());
+        }
+        writeln!(writer, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::game_of_life::State::Alive;
+    use super::super::super::game_of_life::State;
+    use super::super::Color;
+    use super::{print, print_braille, print_braille_region, print_half_blocks, print_region,
+                LifeRenderer};
+
+    #[test]
+    fn print_frame() {
+        let mut frame = Frame::new(4, 3);
+        *frame.get_mut(1, 0) = Alive;
+        *frame.get_mut(3, 2) = Alive;
+        let mut out = vec![];
+        print(&mut out, &frame, &LifeRenderer).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), ".#..\n....\n...#\n");
+    }
+
+    #[test]
+    fn region() {
+        let mut frame = Frame::<u8>::new(3, 3);
+        *frame.get_mut(0, 0) = 1;
+        *frame.get_mut(2, 2) = 2;
+        let renderer = |c: &u8| [' ', 'a', 'b'][*c as usize];
+        let mut out = vec![];
+        print_region(&mut out, &frame, &renderer, 2, 2, 2, 2).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "b \n a\n");
+    }
+
+    #[test]
+    fn half_blocks() {
+        let mut frame = Frame::new(2, 3);
+        *frame.get_mut(0, 0) = Alive;
+        let color = |s: &State| -> Color {
+            if *s == Alive { [255, 255, 255] } else { [0, 0, 0] }
+        };
+        let mut out = vec![];
+        print_half_blocks(&mut out, &frame, &color).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m\u{2580}\
+                    \x1b[38;2;0;0;0m\x1b[48;2;0;0;0m\u{2580}\x1b[0m\n\
+                    \x1b[38;2;0;0;0m\x1b[49m\u{2580}\u{2580}\x1b[0m\n");
+    }
+
+    #[test]
+    fn braille() {
+        let mut frame = Frame::new(3, 5);
+        *frame.get_mut(0, 0) = Alive;
+        *frame.get_mut(1, 3) = Alive;
+        *frame.get_mut(2, 4) = Alive;
+        let mut out = vec![];
+        print_braille(&mut out, &frame, |s| *s == Alive).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\u{2881}\u{2800}\n\u{2800}\u{2801}\n");
+
+        let mut out = vec![];
+        print_braille_region(&mut out, &frame, |s| *s == Alive, 2, 4, 2, 1).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\u{2801}\n");
+    }
+}
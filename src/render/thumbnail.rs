@@ -0,0 +1,78 @@
+use super::super::Frame;
+use super::ColorMap;
+
+use image::{ImageBuffer, RgbaImage};
+
+impl<T> Frame<T> {
+    /// A preview of the frame at most max_w by max_h pixels, whatever the
+    /// size of the frame, for showing many runs side by side. Square blocks
+    /// of cells are shrunk to a pixel of the average of their colors, so the
+    /// preview keeps the frame's proportions and sparse patterns still show
+    /// up faintly. Frames that already fit get a pixel per cell.
+    ///
+    /// # Panics
+    ///
+    /// if max_w or max_h is 0
+    pub fn thumbnail<C>(&self, max_w: usize, max_h: usize, colormap: &C) -> RgbaImage
+    where C: ColorMap<T> + ?Sized {
+        assert!(max_w > 0 && max_h > 0, "can't fit a frame in {}x{} pixels", max_w, max_h);
+        let block = self.width().div_ceil(max_w).max(self.height().div_ceil(max_h));
+        let pixels = self.downsample(block, block, |cells| {
+            let mut sum = [0; 3];
+            for cell in cells {
+                for (s, c) in sum.iter_mut().zip(colormap.color(cell)) {
+                    *s += c as usize;
+                }
+            }
+            sum.map(|s| ((s + cells.len() / 2) / cells.len()) as u8)
+        });
+        let mut buf = Vec::with_capacity(pixels.as_slice().len() * 4);
+        for &[r, g, b] in pixels.as_slice() {
+            buf.extend_from_slice(&[r, g, b, 255]);
+        }
+        ImageBuffer::from_raw(pixels.width() as u32, pixels.height() as u32, buf)
+            .expect("the buffer has a pixel for every block")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::super::game_of_life::State;
+    use super::super::super::game_of_life::State::Alive;
+    use super::super::Color;
+
+    fn color(s: &State) -> Color {
+        match *s {
+            State::Alive => [255, 255, 255],
+            State::Dead => [0, 0, 0],
+        }
+    }
+
+    #[test]
+    fn fits() {
+        let mut frame = Frame::<State>::new(100, 40);
+        for x in 0..50 {
+            for y in 0..40 {
+                *frame.get_mut(x, y) = Alive;
+            }
+        }
+        let thumbnail = frame.thumbnail(10, 10, &color);
+        assert_eq!(thumbnail.dimensions(), (10, 4));
+        assert_eq!(thumbnail.get_pixel(0, 0).data, [255, 255, 255, 255]);
+        assert_eq!(thumbnail.get_pixel(9, 3).data, [0, 0, 0, 255]);
+
+        // small frames aren't blown up
+        let small = Frame::<State>::new(3, 2).thumbnail(64, 64, &color);
+        assert_eq!(small.dimensions(), (3, 2));
+
+        // half alive blocks are grey
+        let mut stripes = Frame::<State>::new(4, 4);
+        for y in 0..4 {
+            *stripes.get_mut(0, y) = Alive;
+            *stripes.get_mut(2, y) = Alive;
+        }
+        let grey = stripes.thumbnail(2, 2, &color);
+        assert_eq!(grey.get_pixel(1, 1).data, [128, 128, 128, 255]);
+    }
+}
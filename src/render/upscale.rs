@@ -0,0 +1,140 @@
+use super::super::Frame;
+use super::{Color, ColorMap};
+use super::heatmap::Value;
+
+use image::{ImageBuffer, RgbImage};
+
+/// How values between the centres of cells are filled in when a frame is
+/// drawn larger than it is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Filter {
+    /// every pixel takes the value of the cell it falls in, which keeps the
+    /// cells as sharp blocks
+    #[default]
+    Nearest,
+    /// values blend linearly between the four nearest cells
+    Bilinear,
+    /// values follow a Catmull-Rom curve through the sixteen nearest cells,
+    /// which is smoother than bilinear but can overshoot a little at edges
+    Bicubic,
+}
+
+impl Filter {
+    /// The cells along one axis of size cells that the pixel p of pixels
+    /// draws from, with their weights. Frames wrap, so the cells past an
+    /// edge are those of the other side.
+    fn taps(self, p: usize, pixels: usize, size: usize) -> Vec<(usize, f64)> {
+        // the position of the pixel's centre, in cells from the first centre
+        let s = (p as f64 + 0.5) * size as f64 / pixels as f64 - 0.5;
+        let i = s.floor();
+        let f = s - i;
+        let cell = |d: isize| (i as isize + d).rem_euclid(size as isize) as usize;
+        match self {
+            Filter::Nearest => vec![(cell(f.round() as isize), 1.0)],
+            Filter::Bilinear => vec![(cell(0), 1.0 - f), (cell(1), f)],
+            Filter::Bicubic => {
+                let (f2, f3) = (f * f, f * f * f);
+                vec![
+                    (cell(-1), (-f3 + 2.0 * f2 - f) / 2.0),
+                    (cell(0), (3.0 * f3 - 5.0 * f2 + 2.0) / 2.0),
+                    (cell(1), (-3.0 * f3 + 4.0 * f2 + f) / 2.0),
+                    (cell(2), (f3 - f2) / 2.0),
+                ]
+            }
+        }
+    }
+}
+
+/// Resample the w by h grid get reads to width by height with filter
+fn resample<F>((w, h): (usize, usize), width: usize, height: usize, filter: Filter, get: F)
+               -> Vec<f64>
+where F: Fn(usize, usize) -> f64 {
+    assert!(width > 0 && height > 0, "can't draw a frame {}x{}", width, height);
+    assert!(w > 0 && h > 0, "can't upscale a {}x{} frame, which has no cells", w, h);
+    let columns = (0..width).map(|px| filter.taps(px, width, w)).collect::<Vec<_>>();
+    let mut values = Vec::with_capacity(width * height);
+    for py in 0..height {
+        let rows = filter.taps(py, height, h);
+        for taps in &columns {
+            values.push(rows.iter()
+                .map(|&(y, wy)| wy * taps.iter().map(|&(x, wx)| wx * get(x, y)).sum::<f64>())
+                .sum());
+        }
+    }
+    values
+}
+
+/// The values of frame drawn at width by height with filter, for coloring
+/// continuous frames, like chemical concentrations, with a heatmap at a
+/// size much larger than the frame without blocky cells
+///
+/// # Panics
+///
+/// if frame or the size drawn at has a side of 0
+pub fn upscale<T: Value>(frame: &Frame<T>, width: usize, height: usize, filter: Filter)
+                         -> Frame<f64> {
+    let get = |x, y| frame.get(x, y).value();
+    let values = resample((frame.width(), frame.height()), width, height, filter, get);
+    Frame::from_vec(width, height, values).unwrap()
+}
+
+/// Render frame into a width by height image, blending the colors of cells
+/// with filter. Heatmaps of numeric frames are better drawn from `upscale`,
+/// which blends the values before they are colored.
+pub fn to_image<T, C>(frame: &Frame<T>, colormap: &C, width: usize, height: usize,
+                      filter: Filter) -> RgbImage
+where C: ColorMap<T> + ?Sized {
+    let colors = frame.map(|c| colormap.color(c));
+    let size = (frame.width(), frame.height());
+    let [r, g, b]: [Vec<f64>; 3] = [0, 1, 2]
+        .map(|i| resample(size, width, height, filter, |x, y| colors.get(x, y)[i] as f64));
+    let channel = |v: f64| v.round().clamp(0.0, 255.0) as u8;
+    let mut buf = Vec::with_capacity(width * height * 3);
+    for ((r, g), b) in r.into_iter().zip(g).zip(b) {
+        let color: Color = [channel(r), channel(g), channel(b)];
+        buf.extend_from_slice(&color);
+    }
+    ImageBuffer::from_raw(width as u32, height as u32, buf)
+        .expect("the buffer has a pixel for every cell")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::{to_image, upscale, Filter};
+
+    fn frame() -> Frame<f32> {
+        Frame::from_vec(2, 2, vec![0.0, 4.0, 8.0, 4.0]).unwrap()
+    }
+
+    #[test]
+    fn filters() {
+        let nearest = upscale(&frame(), 4, 4, Filter::Nearest);
+        assert_eq!(nearest.as_slice()[..4], [0.0, 0.0, 4.0, 4.0]);
+        assert_eq!(nearest.as_slice()[12..], [8.0, 8.0, 4.0, 4.0]);
+
+        // pixel centres a quarter of a cell from the cell centres, with the
+        // frame wrapping around past the edges
+        let bilinear = upscale(&frame(), 4, 2, Filter::Bilinear);
+        assert_eq!(bilinear.as_slice(), &[1.0, 1.0, 3.0, 3.0, 7.0, 7.0, 5.0, 5.0]);
+
+        // at the same size every filter gives back the frame
+        for &filter in &[Filter::Nearest, Filter::Bilinear, Filter::Bicubic] {
+            assert_eq!(upscale(&frame(), 2, 2, filter), frame().map(|&v| v as f64));
+        }
+        // a constant frame stays constant
+        let flat = Frame::from_vec(3, 3, vec![2u8; 9]).unwrap();
+        let smooth = upscale(&flat, 10, 7, Filter::Bicubic);
+        assert!(smooth.as_slice().iter().all(|v| (v - 2.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn colors() {
+        let frame = Frame::from_vec(2, 1, vec![false, true]).unwrap();
+        let colormap = |&c: &bool| if c { [200, 100, 0] } else { [0, 0, 0] };
+        let img = to_image(&frame, &colormap, 4, 1, Filter::Bilinear);
+        assert_eq!(img.dimensions(), (4, 1));
+        let pixels = img.pixels().map(|p| p.data).collect::<Vec<_>>();
+        assert_eq!(pixels, [[50, 25, 0], [50, 25, 0], [150, 75, 0], [150, 75, 0]]);
+    }
+}
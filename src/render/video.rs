@@ -0,0 +1,159 @@
+use super::super::{Frame, FramePool, Simulation, Square};
+use super::{rgb_buffer_into, ColorMap};
+
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+/// Options for encoding a video with ffmpeg
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoOptions {
+    /// generations shown per second
+    pub fps: u32,
+    /// the side length of the block of pixels drawn for each cell
+    pub scale: usize,
+    /// the ffmpeg executable to run
+    pub ffmpeg: String,
+}
+
+impl Default for VideoOptions {
+    fn default() -> VideoOptions {
+        VideoOptions { fps: 30, scale: 1, ffmpeg: "ffmpeg".to_string() }
+    }
+}
+
+/// Streams rendered frames as raw rgb video into the stdin of a child process,
+/// normally ffmpeg. Frames are never buffered in memory, so this works for
+/// arbitrarily long runs.
+#[derive(Debug)]
+pub struct VideoSink {
+    child: Child,
+    width: usize,
+    height: usize,
+    scale: usize,
+    /// the last frame rendered, whose buffer the next one reuses
+    buf: Vec<u8>,
+}
+
+impl VideoSink {
+    /// Start ffmpeg writing a video to path for frames of the given size in
+    /// cells. The container and codec are picked by ffmpeg from the extension
+    /// of path (e.g. `.mp4` or `.webm`).
+    pub fn ffmpeg<P>(path: P, width: usize, height: usize, options: &VideoOptions)
+                     -> Result<VideoSink>
+    where P: AsRef<Path> {
+        let size = format!("{}x{}", width * options.scale, height * options.scale);
+        let mut command = Command::new(&options.ffmpeg);
+        command
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &size])
+            .args(["-r", &options.fps.to_string()])
+            .args(["-i", "-"])
+            // most codecs need even dimensions for yuv420p
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(path.as_ref());
+        VideoSink::spawn(command, width, height, options.scale)
+    }
+
+    /// Spawn command and stream raw rgb24 frames of the given size in cells
+    /// into its stdin
+    pub fn spawn(mut command: Command, width: usize, height: usize, scale: usize)
+                 -> Result<VideoSink> {
+        assert!(scale > 0);
+        let child = command.stdin(Stdio::piped()).spawn()?;
+        Ok(VideoSink { child, width, height, scale, buf: vec![] })
+    }
+
+    /// Render frame and send it to the encoder
+    pub fn write_frame<T, C>(&mut self, frame: &Frame<T>, colormap: &C) -> Result<()>
+    where C: ColorMap<T> + ?Sized {
+        #[cfg(feature = "tracing")]
+        let _span = trace_span!("video_frame").entered();
+        if frame.width() != self.width || frame.height() != self.height {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "frame size doesn't match the video"));
+        }
+        rgb_buffer_into(frame, colormap, self.scale, &mut self.buf);
+        self.child.stdin.as_mut().unwrap().write_all(&self.buf)
+    }
+
+    /// Write the current generation of sim and then advance it, `generations`
+    /// times
+    pub fn record<T, F, C>(&mut self, sim: &mut Simulation<T, F>, generations: usize,
+                           colormap: &C) -> Result<()>
+    where T: Clone, F: Fn(Square<T>) -> T, C: ColorMap<T> + ?Sized {
+        let mut pool = FramePool::new(1);
+        for _ in 0..generations {
+            self.write_frame(sim.frame(), colormap)?;
+            sim.step_in(&mut pool);
+        }
+        Ok(())
+    }
+
+    /// Close the stream and wait for the encoder to finish writing the video
+    pub fn finish(mut self) -> Result<()> {
+        drop(self.child.stdin.take());
+        let status = self.child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::other(format!("encoder exited with {}", status)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{Frame, Simulation};
+    use super::super::super::game_of_life;
+    use super::super::super::game_of_life::State;
+    use super::super::super::game_of_life::State::Alive;
+    use super::super::Color;
+    use super::VideoSink;
+
+    use std::env;
+    use std::fs;
+    use std::process::Command;
+
+    fn color(s: &State) -> Color {
+        match *s {
+            State::Alive => [255, 255, 255],
+            State::Dead => [0, 0, 0],
+        }
+    }
+
+    #[test]
+    fn raw_stream() {
+        let path = env::temp_dir().join("simulation_video_raw_stream");
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(format!("cat > {}", path.display()));
+
+        let mut frame = Frame::new(5, 4);
+        *frame.get_mut(2, 1) = Alive;
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+
+        let mut sink = VideoSink::spawn(command, 5, 4, 2).unwrap();
+        sink.record(&mut sim, 3, &color).unwrap();
+        assert!(sink.write_frame(&Frame::new(4, 4), &color).is_err());
+        sink.finish().unwrap();
+
+        let raw = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(raw.len(), 3 * 10 * 8 * 3);
+        // the live cell is the 2x2 block of pixels at (4, 2)
+        let pixel = |x: usize, y: usize| &raw[(y * 10 + x) * 3..(y * 10 + x) * 3 + 3];
+        assert_eq!(pixel(4, 2), [255, 255, 255]);
+        assert_eq!(pixel(5, 3), [255, 255, 255]);
+        assert_eq!(pixel(3, 2), [0, 0, 0]);
+    }
+
+    #[test]
+    fn failing_encoder() {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("cat > /dev/null; exit 1");
+        let sink = VideoSink::spawn(command, 1, 1, 1).unwrap();
+        assert!(sink.finish().is_err());
+    }
+}
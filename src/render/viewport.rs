@@ -0,0 +1,213 @@
+use super::super::Frame;
+
+/// Wrap a cell coordinate onto a frame side of length n
+fn wrap(v: f64, n: usize) -> usize {
+    (v.floor() as isize).rem_euclid(n as isize) as usize
+}
+
+/// A window onto a frame: which cell is in the middle of the screen, how many
+/// screen pixels (or characters) each cell takes up, and how big the screen
+/// is. Frames wrap around, so every screen pixel shows some cell.
+///
+/// When zoomed out (zoom below 1) each pixel covers a block of cells, and
+/// `project` reduces the block to a single value, e.g. whether any cell in it
+/// is alive. Renderers draw the projected frame at scale 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    /// the cell coordinates shown in the middle of the screen
+    pub center: (f64, f64),
+    /// screen pixels per cell
+    pub zoom: f64,
+    /// the width and height of the screen in pixels
+    pub screen: (usize, usize),
+}
+
+impl Viewport {
+    pub fn new(center: (f64, f64), zoom: f64, screen: (usize, usize)) -> Viewport {
+        assert!(zoom > 0.0);
+        Viewport { center, zoom, screen }
+    }
+
+    /// Show all of a width by height frame as large as fits on screen
+    pub fn fit(width: usize, height: usize, screen: (usize, usize)) -> Viewport {
+        let zoom = (screen.0 as f64 / width as f64).min(screen.1 as f64 / height as f64);
+        Viewport::new((width as f64 / 2.0, height as f64 / 2.0), zoom, screen)
+    }
+
+    /// The cell coordinates of the top left corner of the screen
+    pub fn origin(&self) -> (f64, f64) {
+        (self.center.0 - self.screen.0 as f64 / 2.0 / self.zoom,
+         self.center.1 - self.screen.1 as f64 / 2.0 / self.zoom)
+    }
+
+    /// The cell coordinates of the middle of screen pixel (px, py), not
+    /// wrapped onto any frame
+    pub fn to_cell(&self, px: usize, py: usize) -> (f64, f64) {
+        let (x0, y0) = self.origin();
+        (x0 + (px as f64 + 0.5) / self.zoom, y0 + (py as f64 + 0.5) / self.zoom)
+    }
+
+    /// The screen position of cell coordinates (x, y)
+    pub fn to_screen(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x0, y0) = self.origin();
+        ((x - x0) * self.zoom, (y - y0) * self.zoom)
+    }
+
+    /// The cell of frame under screen pixel (px, py)
+    pub fn cell_at<T>(&self, frame: &Frame<T>, px: usize, py: usize) -> (usize, usize) {
+        let (x, y) = self.to_cell(px, py);
+        (wrap(x, frame.width()), wrap(y, frame.height()))
+    }
+
+    /// Change the size of the screen, keeping the cell in the top left corner
+    /// where it is
+    pub fn resize(&mut self, screen: (usize, usize)) {
+        let (x0, y0) = self.origin();
+        self.screen = screen;
+        let (nx, ny) = self.origin();
+        self.pan(x0 - nx, y0 - ny);
+    }
+
+    /// Move the view by (dx, dy) cells
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.center = (self.center.0 + dx, self.center.1 + dy);
+    }
+
+    /// Multiply the zoom by factor, keeping the cell under screen pixel
+    /// (px, py) where it is
+    pub fn zoom_at(&mut self, factor: f64, px: usize, py: usize) {
+        assert!(factor > 0.0);
+        let (x, y) = self.to_cell(px, py);
+        self.zoom *= factor;
+        let (nx, ny) = self.to_cell(px, py);
+        self.pan(x - nx, y - ny);
+    }
+
+    /// The cells of one axis covered by screen pixel p, starting at cell
+    /// origin
+    fn covered(&self, origin: f64, p: usize, n: usize) -> (isize, usize) {
+        if self.zoom >= 1.0 {
+            ((origin + (p as f64 + 0.5) / self.zoom).floor() as isize, 1)
+        } else {
+            // neighbouring pixels split the cells between them without overlap
+            let start = (origin + p as f64 / self.zoom).floor();
+            let end = (origin + (p + 1) as f64 / self.zoom).floor();
+            (start as isize, ((end - start) as usize).clamp(1, n))
+        }
+    }
+
+    /// Build a screen sized frame where each pixel is reduce applied to the
+    /// cells it covers: exactly one when zoomed in, a block when zoomed out.
+    /// A frame without cells gives a screen of `U::default()`.
+    pub fn project<T, U, F>(&self, frame: &Frame<T>, reduce: F) -> Frame<U>
+    where U: Default + Clone, F: Fn(&[&T]) -> U {
+        let (w, h) = (frame.width(), frame.height());
+        let (x0, y0) = self.origin();
+        let mut screen = Frame::new(self.screen.0, self.screen.1);
+        if w == 0 || h == 0 {
+            return screen;
+        }
+        let mut block = vec![];
+        for py in 0..self.screen.1 {
+            let (cy, rows) = self.covered(y0, py, h);
+            for px in 0..self.screen.0 {
+                let (cx, cols) = self.covered(x0, px, w);
+                block.clear();
+                for j in 0..rows as isize {
+                    for i in 0..cols as isize {
+                        let x = (cx + i).rem_euclid(w as isize) as usize;
+                        let y = (cy + j).rem_euclid(h as isize) as usize;
+                        block.push(frame.get(x, y));
+                    }
+                }
+                *screen.get_mut(px, py) = reduce(&block);
+            }
+        }
+        screen
+    }
+}
+
+/// A reduction for `Viewport::project` that shows whether any cell in a block
+/// is alive
+pub fn any<T, F>(alive: F) -> impl Fn(&[&T]) -> bool
+where F: Fn(&T) -> bool {
+    move |cells| cells.iter().any(|c| alive(c))
+}
+
+/// A reduction for `Viewport::project` that shows the first cell in a block
+/// that isn't in the default state, or the default state if they all are
+pub fn first_set<T>(cells: &[&T]) -> T
+where T: Clone + Default + PartialEq {
+    let default = T::default();
+    cells.iter().find(|c| ***c != default).map_or(default, |c| (*c).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::{any, first_set, Viewport};
+
+    fn frame() -> Frame<u8> {
+        let mut frame = Frame::new(8, 6);
+        *frame.get_mut(1, 1) = 1;
+        *frame.get_mut(6, 4) = 2;
+        frame
+    }
+
+    #[test]
+    fn mapping() {
+        let view = Viewport::new((4.0, 3.0), 2.0, (16, 12));
+        assert_eq!(view.origin(), (0.0, 0.0));
+        assert_eq!(view.cell_at(&frame(), 3, 2), (1, 1));
+        assert_eq!(view.to_screen(1.0, 1.0), (2.0, 2.0));
+
+        let mut view = view;
+        view.pan(-1.0, 0.0);
+        // wraps around to the other side of the frame
+        assert_eq!(view.cell_at(&frame(), 0, 0), (7, 0));
+
+        let mut view = Viewport::new((4.0, 3.0), 1.0, (8, 6));
+        view.zoom_at(2.0, 6, 4);
+        assert_eq!(view.zoom, 2.0);
+        assert_eq!(view.cell_at(&frame(), 6, 4), (6, 4));
+
+        let mut view = Viewport::new((4.0, 3.0), 1.0, (8, 6));
+        view.resize((2, 2));
+        assert_eq!(view.origin(), (0.0, 0.0));
+        assert_eq!(view.center, (1.0, 1.0));
+    }
+
+    #[test]
+    fn zoomed_in() {
+        let view = Viewport::new((4.0, 3.0), 2.0, (16, 12));
+        let screen = view.project(&frame(), first_set);
+        assert_eq!(screen.width(), 16);
+        assert_eq!(*screen.get(2, 2), 1);
+        assert_eq!(*screen.get(3, 3), 1);
+        assert_eq!(*screen.get(4, 2), 0);
+        assert_eq!(*screen.get(13, 9), 2);
+    }
+
+    #[test]
+    fn zoomed_out() {
+        let view = Viewport::fit(8, 6, (4, 3));
+        assert_eq!(view.zoom, 0.5);
+        let screen = view.project(&frame(), any(|&c| c != 0));
+        assert_eq!(screen.width(), 4);
+        assert_eq!(screen.height(), 3);
+        let live = screen.enumerate_squares()
+            .filter(|&(_, _, &b)| b)
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<_>>();
+        assert_eq!(live, vec![(0, 0), (3, 2)]);
+
+        // zoomed out past the size of the frame the blocks are capped to it
+        let view = Viewport::new((4.0, 3.0), 0.01, (2, 2));
+        let screen = view.project(&frame(), |cells| cells.len());
+        assert_eq!(*screen.get(0, 0), 48);
+
+        // a frame without cells leaves the screen blank
+        let screen = view.project(&Frame::<u8>::new(0, 6), |cells| cells.len());
+        assert!(screen.as_slice().iter().all(|&n| n == 0));
+    }
+}
@@ -0,0 +1,165 @@
+use super::super::Frame;
+use super::ColorMap;
+use super::viewport::{self, Viewport};
+
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::Widget;
+
+/// How a `FrameWidget` packs cells into the characters of a terminal
+pub trait Glyphs<T> {
+    /// The columns and rows of cells each character shows
+    fn block(&self) -> (usize, usize);
+
+    /// Draw the block of screen whose top left cell is (x, y) into cell
+    fn draw(&self, screen: &Frame<T>, x: usize, y: usize, cell: &mut Cell);
+}
+
+/// Two cells per character in 24-bit color, the upper half block's
+/// foreground showing the upper cell and its background the lower one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfBlocks<C>(pub C);
+
+impl<T, C: ColorMap<T>> Glyphs<T> for HalfBlocks<C> {
+    fn block(&self) -> (usize, usize) {
+        (1, 2)
+    }
+
+    fn draw(&self, screen: &Frame<T>, x: usize, y: usize, cell: &mut Cell) {
+        let rgb = |[r, g, b]: [u8; 3]| Color::Rgb(r, g, b);
+        cell.set_char('\u{2580}')
+            .set_fg(rgb(self.0.color(screen.get(x, y))))
+            .set_bg(rgb(self.0.color(screen.get(x, y + 1))));
+    }
+}
+
+/// Eight cells per character as the dots of a braille pattern, showing a dot
+/// for each cell for which the function is true
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Braille<F>(pub F);
+
+impl<T, F: Fn(&T) -> bool> Glyphs<T> for Braille<F> {
+    fn block(&self) -> (usize, usize) {
+        (2, 4)
+    }
+
+    fn draw(&self, screen: &Frame<T>, x: usize, y: usize, cell: &mut Cell) {
+        // the bit for the dot at (i, j) within a character
+        const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+        let mut bits = 0;
+        for (i, column) in DOTS.iter().enumerate() {
+            for (j, &bit) in column.iter().enumerate() {
+                if (self.0)(screen.get(x + i, y + j)) {
+                    bits |= bit;
+                }
+            }
+        }
+        cell.set_char(::std::char::from_u32(0x2800 + bits).unwrap());
+    }
+}
+
+/// A ratatui widget that draws a frame, for dropping a simulation into the
+/// layout of a larger terminal app. The viewport is measured in cells rather
+/// than characters and takes its screen size from the area it is rendered
+/// into. Without one the whole frame is fit into the area.
+///
+/// Zoomed out, each cell of the screen shows the first cell in its block that
+/// isn't in the default state.
+pub struct FrameWidget<'a, T: 'a, G> {
+    frame: &'a Frame<T>,
+    glyphs: G,
+    view: Option<Viewport>,
+}
+
+impl<'a, T, G: Glyphs<T>> FrameWidget<'a, T, G> {
+    pub fn new(frame: &'a Frame<T>, glyphs: G) -> FrameWidget<'a, T, G> {
+        FrameWidget { frame, glyphs, view: None }
+    }
+
+    /// Show frame through view, keeping its center and zoom
+    pub fn viewport(mut self, view: Viewport) -> FrameWidget<'a, T, G> {
+        self.view = Some(view);
+        self
+    }
+}
+
+impl<'a, T, C: ColorMap<T>> FrameWidget<'a, T, HalfBlocks<C>> {
+    /// Draw frame as half blocks colored by colormap
+    pub fn half_blocks(frame: &'a Frame<T>, colormap: C) -> FrameWidget<'a, T, HalfBlocks<C>> {
+        FrameWidget::new(frame, HalfBlocks(colormap))
+    }
+}
+
+impl<'a, T, F: Fn(&T) -> bool> FrameWidget<'a, T, Braille<F>> {
+    /// Draw frame as braille, with a dot for each cell for which alive is true
+    pub fn braille(frame: &'a Frame<T>, alive: F) -> FrameWidget<'a, T, Braille<F>> {
+        FrameWidget::new(frame, Braille(alive))
+    }
+}
+
+impl<'a, T, G> Widget for FrameWidget<'a, T, G>
+where T: Clone + Default + PartialEq, G: Glyphs<T> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = area.intersection(buf.area);
+        if area.is_empty() {
+            return;
+        }
+        let (bw, bh) = self.glyphs.block();
+        let size = (area.width as usize * bw, area.height as usize * bh);
+        let view = match self.view {
+            Some(view) => Viewport { screen: size, ..view },
+            None => Viewport::fit(self.frame.width(), self.frame.height(), size),
+        };
+        let screen = view.project(self.frame, viewport::first_set);
+        for row in 0..area.height {
+            for column in 0..area.width {
+                let cell = &mut buf[(area.x + column, area.y + row)];
+                self.glyphs.draw(&screen, column as usize * bw, row as usize * bh, cell);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::Frame;
+    use super::super::viewport::Viewport;
+    use super::FrameWidget;
+
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::style::Color;
+    use ratatui::widgets::Widget;
+
+    fn frame() -> Frame<u8> {
+        let mut frame = Frame::new(4, 4);
+        *frame.get_mut(0, 0) = 1;
+        *frame.get_mut(3, 3) = 1;
+        frame
+    }
+
+    #[test]
+    fn braille() {
+        let frame = frame();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 2));
+        // two characters hold 4 by 4 dots, one for each cell
+        FrameWidget::braille(&frame, |&c: &u8| c == 1).render(Rect::new(1, 1, 2, 1), &mut buf);
+        assert_eq!(buf, Buffer::with_lines(["    ", " \u{2801}\u{2880} "]));
+    }
+
+    #[test]
+    fn half_blocks() {
+        let frame = frame();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 2, 1));
+        let colors = |&c: &u8| [c * 255, 0, 0];
+        // the top left 2 by 2 cells, one per half block
+        FrameWidget::half_blocks(&frame, colors)
+            .viewport(Viewport::new((1.0, 1.0), 1.0, (0, 0)))
+            .render(buf.area, &mut buf);
+        let cell = &buf[(0, 0)];
+        assert_eq!(cell.symbol(), "\u{2580}");
+        assert_eq!((cell.fg, cell.bg), (Color::Rgb(255, 0, 0), Color::Rgb(0, 0, 0)));
+        assert_eq!(buf[(1, 0)].fg, Color::Rgb(0, 0, 0));
+    }
+}
@@ -0,0 +1,293 @@
+use super::{Frame, Square, WithInverse};
+use super::game_of_life::State;
+
+use std::mem;
+use std::ops::BitXor;
+
+/// A second order automaton, which is reversible whatever rule it is built
+/// from. The next frame is the rule applied to the current frame xored with
+/// the previous one, so the previous frame can always be recovered by doing
+/// the same thing with the roles of the two swapped.
+#[derive(Debug)]
+pub struct SecondOrder<T, F> {
+    previous: Frame<T>,
+    current: Frame<T>,
+    rule: F,
+    generation: u64,
+}
+
+impl<T, F> SecondOrder<T, F>
+where T: Clone + BitXor<Output = T>, F: Fn(Square<T>) -> T {
+    /// Start at generation 0 from current, as if it had been preceded by
+    /// previous. Panics unless the two frames are the same size.
+    pub fn new(previous: Frame<T>, current: Frame<T>, rule: F) -> SecondOrder<T, F> {
+        assert!(previous.width() == current.width() && previous.height() == current.height(),
+                "the previous and current frames must be the same size");
+        SecondOrder { previous, current, rule, generation: 0 }
+    }
+
+    /// The frame that xoring the rule applied to from with with gives
+    fn advance(&self, from: &Frame<T>, with: &Frame<T>) -> Frame<T> {
+        from.next_frame(|sq| {
+            let (x, y) = sq.coordinate();
+            (self.rule)(sq) ^ with.get(x, y).clone()
+        })
+    }
+
+    /// Advance by one generation
+    pub fn step(&mut self) {
+        let next = self.advance(&self.current, &self.previous);
+        self.previous = mem::replace(&mut self.current, next);
+        self.generation += 1;
+    }
+
+    /// Go back by one generation, undoing a step, or return false and leave
+    /// the automaton alone at generation 0
+    pub fn step_back(&mut self) -> bool {
+        if self.generation == 0 {
+            return false;
+        }
+        let before = self.advance(&self.previous, &self.current);
+        self.current = mem::replace(&mut self.previous, before);
+        self.generation -= 1;
+        true
+    }
+}
+
+impl<T, F> SecondOrder<T, F> {
+    /// The current frame
+    pub fn frame(&self) -> &Frame<T> {
+        &self.current
+    }
+
+    /// The frame before the current one
+    pub fn previous(&self) -> &Frame<T> {
+        &self.previous
+    }
+
+    /// The number of generations that have passed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// A rule for the 2x2 blocks of a Margolus neighborhood, which are given and
+/// returned in the order top left, top right, bottom left, bottom right,
+/// together with the rule that undoes it
+pub trait BlockRule<T> {
+    /// The next states of the cells of block
+    fn next(&self, block: [T; 4]) -> [T; 4];
+
+    /// The states the cells of block had before a step of the rule
+    fn previous(&self, block: [T; 4]) -> [T; 4];
+}
+
+impl<T, F, G> BlockRule<T> for WithInverse<F, G>
+where F: Fn([T; 4]) -> [T; 4], G: Fn([T; 4]) -> [T; 4] {
+    fn next(&self, block: [T; 4]) -> [T; 4] {
+        (self.rule)(block)
+    }
+
+    fn previous(&self, block: [T; 4]) -> [T; 4] {
+        (self.inverse)(block)
+    }
+}
+
+/// The Critters rule, under which gliders travel and bounce off each other.
+/// Blocks with two live cells are left alone, and the others have every cell
+/// flipped, and are then turned half way around if three of them were alive.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Critters;
+
+/// Every cell of block alive if it was dead and dead if it was alive
+fn flip(block: [State; 4]) -> [State; 4] {
+    block.map(|c| c ^ State::Alive)
+}
+
+fn alive(block: &[State; 4]) -> usize {
+    block.iter().filter(|&&c| c == State::Alive).count()
+}
+
+impl BlockRule<State> for Critters {
+    fn next(&self, block: [State; 4]) -> [State; 4] {
+        match alive(&block) {
+            2 => block,
+            3 => {
+                let [a, b, c, d] = flip(block);
+                [d, c, b, a]
+            }
+            _ => flip(block),
+        }
+    }
+
+    fn previous(&self, block: [State; 4]) -> [State; 4] {
+        // flipping takes 3 live cells to 1, so those are the blocks to turn
+        match alive(&block) {
+            2 => block,
+            1 => {
+                let [a, b, c, d] = flip(block);
+                [d, c, b, a]
+            }
+            _ => flip(block),
+        }
+    }
+}
+
+/// A block automaton on the Margolus neighborhood, which is reversible
+/// whenever its rule is. The frame is split into 2x2 blocks that each step
+/// on their own, with the blocks moved down and right by one cell on odd
+/// generations, wrapping around the edges, so that information crosses
+/// between them.
+///
+/// ```
+/// use simulation::Frame;
+/// use simulation::game_of_life::State;
+/// use simulation::reversible::{Critters, Margolus};
+///
+/// let mut sim = Margolus::new(Frame::<State>::new(16, 16), Critters);
+/// sim.step();
+/// assert!(sim.step_back());
+/// assert!(!sim.step_back());
+/// ```
+#[derive(Debug)]
+pub struct Margolus<T, R> {
+    frame: Frame<T>,
+    rule: R,
+    generation: u64,
+}
+
+impl<T, R> Margolus<T, R>
+where T: Clone, R: BlockRule<T> {
+    /// Start at generation 0 from frame
+    ///
+    /// # Panics
+    ///
+    /// if the frame's width or height is odd, so that it can't be split into
+    /// blocks
+    pub fn new(frame: Frame<T>, rule: R) -> Margolus<T, R> {
+        assert!(frame.width().is_multiple_of(2) && frame.height().is_multiple_of(2),
+                "a {}x{} frame can't be split into 2x2 blocks", frame.width(), frame.height());
+        Margolus { frame, rule, generation: 0 }
+    }
+
+    /// Replace every block of the frame, starting offset cells down and
+    /// right of the corner, with what f gives for it
+    fn blocks<G>(&mut self, offset: usize, f: G)
+    where G: Fn(&R, [T; 4]) -> [T; 4] {
+        let (w, h) = (self.frame.width(), self.frame.height());
+        for y in (offset..h + offset).step_by(2) {
+            for x in (offset..w + offset).step_by(2) {
+                let (x0, y0, x1, y1) = (x % w, y % h, (x + 1) % w, (y + 1) % h);
+                let frame = &mut self.frame;
+                let block = [frame.get(x0, y0).clone(), frame.get(x1, y0).clone(),
+                             frame.get(x0, y1).clone(), frame.get(x1, y1).clone()];
+                let [a, b, c, d] = f(&self.rule, block);
+                *frame.get_mut(x0, y0) = a;
+                *frame.get_mut(x1, y0) = b;
+                *frame.get_mut(x0, y1) = c;
+                *frame.get_mut(x1, y1) = d;
+            }
+        }
+    }
+
+    /// Advance by one generation
+    pub fn step(&mut self) {
+        self.blocks((self.generation % 2) as usize, R::next);
+        self.generation += 1;
+    }
+
+    /// Go back by one generation, undoing a step, or return false and leave
+    /// the automaton alone at generation 0
+    pub fn step_back(&mut self) -> bool {
+        if self.generation == 0 {
+            return false;
+        }
+        self.generation -= 1;
+        self.blocks((self.generation % 2) as usize, R::previous);
+        true
+    }
+}
+
+impl<T, R> Margolus<T, R> {
+    /// The current frame
+    pub fn frame(&self) -> &Frame<T> {
+        &self.frame
+    }
+
+    /// The number of generations that have passed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::Alive;
+    use super::super::patterns::Pattern;
+    use super::super::WithInverse;
+    use super::{Critters, Margolus, SecondOrder};
+
+    #[test]
+    fn reverses() {
+        let mut start = Frame::new(12, 12);
+        for (x, y, c) in Pattern::glider().to_frame(Alive).enumerate_squares() {
+            *start.get_mut(x + 4, y + 4) = *c;
+        }
+        let mut sim = SecondOrder::new(Frame::new(12, 12), start.clone(), game_of_life::rule);
+        for _ in 0..25 {
+            sim.step();
+        }
+        assert_eq!(sim.generation(), 25);
+        assert!(*sim.frame() != start);
+        for _ in 0..25 {
+            assert!(sim.step_back());
+        }
+        assert_eq!(sim.generation(), 0);
+        assert_eq!(*sim.frame(), start);
+        assert_eq!(*sim.previous(), Frame::new(12, 12));
+        assert!(!sim.step_back());
+        assert_eq!(*sim.frame(), start);
+    }
+
+    #[test]
+    fn margolus() {
+        let mut start = Frame::new(16, 12);
+        for (x, y, c) in Pattern::glider().to_frame(Alive).enumerate_squares() {
+            *start.get_mut(x + 5, y + 3) = *c;
+        }
+        *start.get_mut(12, 9) = Alive;
+        let mut sim = Margolus::new(start.clone(), Critters);
+        for _ in 0..31 {
+            sim.step();
+        }
+        assert!(*sim.frame() != start);
+        for _ in 0..31 {
+            assert!(sim.step_back());
+        }
+        assert_eq!(sim.generation(), 0);
+        assert_eq!(*sim.frame(), start);
+        assert!(!sim.step_back());
+
+        // turning every block clockwise is undone by turning it back
+        let turn = WithInverse::new(|[a, b, c, d]: [u8; 4]| [c, a, d, b],
+                                    |[a, b, c, d]: [u8; 4]| [b, d, a, c]);
+        let mut frame = Frame::new(4, 4);
+        *frame.get_mut(0, 0) = 1;
+        let mut sim = Margolus::new(frame.clone(), turn);
+        sim.step();
+        assert_eq!(*sim.frame().get(1, 0), 1);
+        sim.step();
+        assert_eq!(*sim.frame().get(1, 3), 1);
+        sim.step_back();
+        sim.step_back();
+        assert_eq!(*sim.frame(), frame);
+    }
+
+    #[test]
+    #[should_panic(expected = "a 5x4 frame can't be split into 2x2 blocks")]
+    fn odd_size() {
+        Margolus::new(Frame::new(5, 4), Critters);
+    }
+}
@@ -0,0 +1,69 @@
+use super::Square;
+
+/// Gives the next state of a cell from its neighborhood. Every
+/// `Fn(Square<T>) -> T` is a rule, so functions like `game_of_life::rule` and
+/// closures can be used as they are, while rules with types of their own can
+/// also say more about themselves, like the rule that undoes them.
+pub trait Rule<T> {
+    /// The next state of the cell square is centered on
+    fn next(&self, square: Square<T>) -> T;
+}
+
+impl<T, F> Rule<T> for F
+where F: Fn(Square<T>) -> T {
+    fn next(&self, square: Square<T>) -> T {
+        self(square)
+    }
+}
+
+/// A rule that can be undone, so that a simulation stepped with it can be
+/// stepped back with `Simulation::step_back`
+pub trait Invertible<T>: Rule<T> {
+    /// The state the cell square is centered on had before a step of the
+    /// rule, square being its neighborhood after it
+    fn previous(&self, square: Square<T>) -> T;
+}
+
+/// A rule made of a function and the function that undoes it, for rules
+/// that are closures, whether they step cells or the blocks of
+/// `reversible::Margolus`, e.g.
+///
+#[cfg_attr(feature = "alloc", doc = "```")]
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+/// use simulation::{Frame, Simulation, Square, WithInverse};
+///
+/// // moving every cell right is undone by moving every cell left
+/// let right = WithInverse::new(|sq: Square<u8>| *sq.get(-1, 0),
+///                              |sq: Square<u8>| *sq.get(1, 0));
+/// let mut sim = Simulation::invertible(Frame::new(8, 8), right);
+/// sim.step();
+/// assert!(sim.step_back());
+/// assert!(!sim.step_back());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct WithInverse<F, G> {
+    pub(crate) rule: F,
+    pub(crate) inverse: G,
+}
+
+impl<F, G> WithInverse<F, G> {
+    /// The rule that steps with rule and steps back with inverse, which has
+    /// to undo it
+    pub fn new(rule: F, inverse: G) -> WithInverse<F, G> {
+        WithInverse { rule, inverse }
+    }
+}
+
+impl<T, F, G> Rule<T> for WithInverse<F, G>
+where F: Fn(Square<T>) -> T {
+    fn next(&self, square: Square<T>) -> T {
+        (self.rule)(square)
+    }
+}
+
+impl<T, F, G> Invertible<T> for WithInverse<F, G>
+where F: Fn(Square<T>) -> T, G: Fn(Square<T>) -> T {
+    fn previous(&self, square: Square<T>) -> T {
+        (self.inverse)(square)
+    }
+}
@@ -0,0 +1,231 @@
+use super::game_of_life::LifeLike;
+use super::generations::Generations;
+
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+/// The bits of birth counts, then survival counts
+const ALL: u32 = (1 << 18) - 1;
+
+/// A set of Life-like or Generations rules, all 2^18 of them unless some
+/// birth or survival counts are required or forbidden, for exploring rules
+/// automatically, e.g. running a sample of them through `ensemble`
+///
+/// ```
+/// use simulation::rule_space::RuleSpace;
+///
+/// // rules without B0, which flash the whole board, that keep blocks alive
+/// let space = RuleSpace::new().forbid_birth(0).require_survival(3);
+/// assert_eq!(space.len(), 1 << 16);
+/// assert!(space.life_like().all(|r| !r.birth()[0] && r.survival()[3]));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RuleSpace {
+    required: u32,
+    forbidden: u32,
+}
+
+impl RuleSpace {
+    /// Every rule
+    pub fn new() -> RuleSpace {
+        RuleSpace::default()
+    }
+
+    /// Only the rules where dead cells with n live neighbors are born
+    pub fn require_birth(self, n: usize) -> RuleSpace {
+        RuleSpace { required: self.required | bit(n), ..self }
+    }
+
+    /// Only the rules where dead cells with n live neighbors aren't born
+    pub fn forbid_birth(self, n: usize) -> RuleSpace {
+        RuleSpace { forbidden: self.forbidden | bit(n), ..self }
+    }
+
+    /// Only the rules where live cells with n live neighbors survive
+    pub fn require_survival(self, n: usize) -> RuleSpace {
+        RuleSpace { required: self.required | bit(n) << 9, ..self }
+    }
+
+    /// Only the rules where live cells with n live neighbors die
+    pub fn forbid_survival(self, n: usize) -> RuleSpace {
+        RuleSpace { forbidden: self.forbidden | bit(n) << 9, ..self }
+    }
+
+    /// The counts that can go either way
+    fn free(&self) -> u32 {
+        ALL & !(self.required | self.forbidden)
+    }
+
+    /// The number of rules, 0 if a count is both required and forbidden
+    pub fn len(&self) -> u64 {
+        if self.is_empty() { 0 } else { 1 << self.free().count_ones() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.required & self.forbidden != 0
+    }
+
+    /// The birth and survival counts of the ith rule, counting up from the
+    /// rule with none of the free counts
+    ///
+    /// # Panics
+    ///
+    /// if i isn't less than `len()`
+    pub fn counts(&self, i: u64) -> ([bool; 9], [bool; 9]) {
+        assert!(i < self.len(), "rule {} of a space of {}", i, self.len());
+        let (mut bits, mut i, mut free) = (self.required, i, self.free());
+        // spread the bits of i over the free counts, lowest first
+        while free != 0 {
+            let lowest = free & free.wrapping_neg();
+            if i & 1 == 1 {
+                bits |= lowest;
+            }
+            i >>= 1;
+            free &= !lowest;
+        }
+        split(bits)
+    }
+
+    /// Every Life-like rule in the space, in the order of `counts`
+    pub fn life_like(&self) -> impl Iterator<Item = LifeLike> + '_ {
+        (0..self.len()).map(move |i| {
+            let (birth, survival) = self.counts(i);
+            LifeLike::from_counts(birth, survival)
+        })
+    }
+
+    /// Every Generations rule with N states in the space, in the order of
+    /// `counts`
+    pub fn generations<const N: usize>(&self) -> impl Iterator<Item = Generations<N>> + '_ {
+        (0..self.len()).map(move |i| {
+            let (birth, survival) = self.counts(i);
+            Generations::from_counts(birth, survival)
+        })
+    }
+
+    /// The birth and survival counts of a rule of the space picked uniformly
+    /// at random
+    ///
+    /// # Panics
+    ///
+    /// if the space is empty
+    #[cfg(feature = "rand")]
+    pub fn sample_counts<R: Rng>(&self, rng: &mut R) -> ([bool; 9], [bool; 9]) {
+        assert!(!self.is_empty(), "sampling from an empty rule space");
+        split(self.required | rng.gen::<u32>() & self.free())
+    }
+
+    /// A Life-like rule of the space picked uniformly at random
+    #[cfg(feature = "rand")]
+    pub fn sample_life_like<R: Rng>(&self, rng: &mut R) -> LifeLike {
+        let (birth, survival) = self.sample_counts(rng);
+        LifeLike::from_counts(birth, survival)
+    }
+
+    /// A Generations rule with N states of the space picked uniformly at
+    /// random
+    #[cfg(feature = "rand")]
+    pub fn sample_generations<const N: usize, R: Rng>(&self, rng: &mut R) -> Generations<N> {
+        let (birth, survival) = self.sample_counts(rng);
+        Generations::from_counts(birth, survival)
+    }
+
+    /// counts with each free count flipped with probability rate, so the
+    /// result stays in the space
+    #[cfg(feature = "rand")]
+    pub fn mutate<R: Rng>(&self, counts: ([bool; 9], [bool; 9]), rate: f64, rng: &mut R)
+                          -> ([bool; 9], [bool; 9]) {
+        let mut bits = join(counts);
+        for n in (0..18).filter(|&n| self.free() & 1 << n != 0) {
+            if rng.gen::<f64>() < rate {
+                bits ^= 1 << n;
+            }
+        }
+        split(bits)
+    }
+
+    /// Counts taking each free count from a or b at random, and the others
+    /// as the space fixes them
+    #[cfg(feature = "rand")]
+    pub fn cross<R: Rng>(&self, a: ([bool; 9], [bool; 9]), b: ([bool; 9], [bool; 9]),
+                         rng: &mut R) -> ([bool; 9], [bool; 9]) {
+        let from_a = rng.gen::<u32>();
+        let bits = join(a) & from_a | join(b) & !from_a;
+        split(self.required | bits & self.free())
+    }
+}
+
+fn bit(n: usize) -> u32 {
+    assert!(n < 9, "a cell has at most 8 neighbors");
+    1 << n
+}
+
+/// The birth and survival counts of bits
+fn split(bits: u32) -> ([bool; 9], [bool; 9]) {
+    let mut counts = ([false; 9], [false; 9]);
+    for n in 0..9 {
+        counts.0[n] = bits & 1 << n != 0;
+        counts.1[n] = bits & 1 << (n + 9) != 0;
+    }
+    counts
+}
+
+/// The bits of birth and survival counts
+#[cfg(feature = "rand")]
+fn join((birth, survival): ([bool; 9], [bool; 9])) -> u32 {
+    (0..9).fold(0, |bits, n| bits | (birth[n] as u32) << n | (survival[n] as u32) << (n + 9))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::super::game_of_life::LifeLike;
+    use super::super::generations::Generations;
+    use super::RuleSpace;
+
+    use rand::{SeedableRng, XorShiftRng};
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn enumerates() {
+        assert_eq!(RuleSpace::new().len(), 1 << 18);
+        // Conway's Life with each of B0, B1 and B2 either way
+        let space = (4..9).fold(RuleSpace::new(), |s, n| s.forbid_birth(n)).require_birth(3);
+        let space = (0..9).fold(space, |s, n| {
+            if n == 2 || n == 3 { s.require_survival(n) } else { s.forbid_survival(n) }
+        });
+        let rules = space.life_like().map(|r| r.to_string()).collect::<Vec<_>>();
+        assert_eq!(rules, ["B3/S23", "B03/S23", "B13/S23", "B013/S23", "B23/S23", "B023/S23",
+                           "B123/S23", "B0123/S23"]);
+        assert_eq!(space.life_like().next(), Some(LifeLike::default()));
+        let brains = space.generations::<3>().collect::<HashSet<_>>();
+        assert_eq!(brains.len(), 8);
+        assert!(brains.contains(&"B23/S23/C3".parse::<Generations<3>>().unwrap()));
+
+        let contradiction = RuleSpace::new().require_birth(2).forbid_birth(2);
+        assert!(contradiction.is_empty());
+        assert_eq!(contradiction.life_like().count(), 0);
+    }
+
+    #[test]
+    fn samples() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let space = RuleSpace::new().forbid_birth(0).require_birth(3).forbid_survival(8);
+        let rules = (0..200).map(|_| space.sample_life_like(&mut rng)).collect::<HashSet<_>>();
+        assert!(rules.len() > 190);
+        assert!(rules.iter().all(|r| !r.birth()[0] && r.birth()[3] && !r.survival()[8]));
+        let rule = space.sample_generations::<4, _>(&mut rng);
+        assert!(rule.birth()[3]);
+
+        let conway = LifeLike::default();
+        let counts = (conway.birth(), conway.survival());
+        assert_eq!(space.mutate(counts, 0., &mut rng), counts);
+        // every free count flips, leaving B0 and S8 off
+        let (birth, survival) = space.mutate(counts, 1., &mut rng);
+        assert_eq!(LifeLike::from_counts(birth, survival).to_string(), "B12345678/S014567");
+        let other = space.sample_counts(&mut rng);
+        let (birth, survival) = space.cross(counts, other, &mut rng);
+        assert!((0..9).all(|n| birth[n] == counts.0[n] || birth[n] == other.0[n]));
+        assert!((0..9).all(|n| survival[n] == counts.1[n] || survival[n] == other.1[n]));
+    }
+}
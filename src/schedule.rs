@@ -0,0 +1,153 @@
+use super::{Frame, Rect, Square};
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// A rule in a schedule
+type Rule<'a, T> = Box<dyn Fn(Square<T>) -> T + 'a>;
+
+/// The rules used for one generation of a schedule: a rule for the whole
+/// frame, overridden inside regions by their own rules. Each cell is stepped
+/// by the rule of the first region holding it, or the rule for the whole
+/// frame if none do, and that rule sees the cell's whole neighborhood, so
+/// cells next to a border see the cells across it as they are.
+pub struct Phase<'a, T> {
+    rule: Rule<'a, T>,
+    regions: Vec<(Rect, Rule<'a, T>)>,
+}
+
+impl<'a, T> Phase<'a, T> {
+    /// Step every cell with rule
+    pub fn new<F>(rule: F) -> Phase<'a, T>
+    where F: Fn(Square<T>) -> T + 'a {
+        Phase { rule: Box::new(rule), regions: vec![] }
+    }
+
+    /// Step the cells in rect with rule instead, unless a region added
+    /// earlier holds them
+    pub fn region<F>(mut self, rect: Rect, rule: F) -> Phase<'a, T>
+    where F: Fn(Square<T>) -> T + 'a {
+        self.regions.push((rect, Box::new(rule)));
+        self
+    }
+
+    /// The next state of the cell square is centered on
+    pub fn rule(&self, square: Square<T>) -> T {
+        let (x, y) = square.coordinate();
+        let rule = self.regions.iter()
+            .find(|(rect, _)| rect.contains(x, y))
+            .map_or(&self.rule, |(_, rule)| rule);
+        rule(square)
+    }
+}
+
+/// Phases applied to generations in turn, starting over after the last, for
+/// hybrid rules like alternating between two rules every generation or Life
+/// running inside a border of Seeds, e.g.
+///
+/// ```
+/// use simulation::{Frame, Rect, Simulation};
+/// use simulation::game_of_life::{self, LifeLike, State};
+/// use simulation::schedule::{Phase, Schedule};
+///
+/// let seeds: LifeLike = "B2/S".parse().unwrap();
+/// let inside = Rect { x: 8, y: 8, width: 16, height: 16 };
+/// let schedule = Schedule::new()
+///     .then(Phase::new(|sq| seeds.rule(sq)).region(inside, game_of_life::rule));
+/// let mut sim = Simulation::new(Frame::<State>::new(32, 32), game_of_life::rule);
+/// sim.step_scheduled(&schedule);
+/// ```
+pub struct Schedule<'a, T> {
+    phases: Vec<Phase<'a, T>>,
+}
+
+impl<'a, T> Default for Schedule<'a, T> {
+    fn default() -> Schedule<'a, T> {
+        Schedule { phases: vec![] }
+    }
+}
+
+impl<'a, T> Schedule<'a, T> {
+    pub fn new() -> Schedule<'a, T> {
+        Schedule::default()
+    }
+
+    /// Use phase for the generation after the phases added so far
+    pub fn then(mut self, phase: Phase<'a, T>) -> Schedule<'a, T> {
+        self.phases.push(phase);
+        self
+    }
+
+    /// The phase used to step from generation to the one after it
+    ///
+    /// # Panics
+    ///
+    /// if the schedule has no phases
+    pub fn phase(&self, generation: u64) -> &Phase<'a, T> {
+        assert!(!self.phases.is_empty(), "schedules need at least one phase");
+        &self.phases[(generation % self.phases.len() as u64) as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.phases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phases.is_empty()
+    }
+}
+
+impl<T> Frame<T> {
+    /// The frame after this one, which is at generation, with the phase
+    /// schedule has for it
+    pub fn next_frame_scheduled(&self, schedule: &Schedule<T>, generation: u64) -> Frame<T> {
+        let phase = schedule.phase(generation);
+        self.next_frame(|sq| phase.rule(sq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Rect, Simulation, Square};
+    use super::super::game_of_life::{self, LifeLike, State};
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::{Phase, Schedule};
+
+    #[test]
+    fn alternating() {
+        // moving everything right one generation and down the next
+        let right = |sq: Square<u8>| *sq.get(-1, 0);
+        let down = |sq: Square<u8>| *sq.get(0, -1);
+        let schedule = Schedule::new().then(Phase::new(right)).then(Phase::new(down));
+        let mut frame = Frame::<u8>::new(4, 4);
+        *frame.get_mut(0, 0) = 1;
+        for generation in 0..4 {
+            frame = frame.next_frame_scheduled(&schedule, generation);
+        }
+        assert_eq!(*frame.get(2, 2), 1);
+        assert_eq!(schedule.len(), 2);
+    }
+
+    #[test]
+    fn regions() {
+        let seeds: LifeLike = "B2/S".parse().unwrap();
+        let inside = Rect { x: 0, y: 0, width: 5, height: 5 };
+        let schedule = Schedule::new()
+            .then(Phase::new(|sq| seeds.rule(sq)).region(inside, game_of_life::rule));
+
+        // a block is still inside the Life region but explodes under Seeds
+        let mut frame = Frame::<State>::new(12, 12);
+        for &(x, y) in &[(1, 1), (2, 1), (1, 2), (2, 2), (8, 8), (9, 8), (8, 9), (9, 9)] {
+            *frame.get_mut(x, y) = Alive;
+        }
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+        sim.step_scheduled(&schedule);
+        assert_eq!(sim.generation(), 1);
+        let next = sim.frame();
+        assert!([(1, 1), (2, 1), (1, 2), (2, 2)].iter().all(|&(x, y)| *next.get(x, y) == Alive));
+        assert!([(8, 8), (9, 8), (8, 9), (9, 9)].iter().all(|&(x, y)| *next.get(x, y) == Dead));
+        assert_eq!(*next.get(7, 8), Alive);
+    }
+}
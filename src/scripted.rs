@@ -0,0 +1,206 @@
+use super::Square;
+
+use rhai;
+use rhai::{Dynamic, Engine, Scope, AST};
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::result;
+
+/// How many operations a single call into a script may take, so a script that
+/// never returns fails instead of hanging the simulation
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// The ways loading or running a script can fail
+#[derive(Debug)]
+pub enum Error {
+    /// the script couldn't be read
+    Io(io::Error),
+    /// the script isn't valid Rhai
+    Parse(rhai::ParseError),
+    /// the script failed while running
+    Eval(Box<rhai::EvalAltResult>),
+    /// the script doesn't define a rule the way rules have to be defined
+    Invalid(String),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => write!(f, "{}", e),
+            Error::Parse(ref e) => write!(f, "{}", e),
+            Error::Eval(ref e) => write!(f, "{}", e),
+            Error::Invalid(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl From<rhai::ParseError> for Error {
+    fn from(e: rhai::ParseError) -> Error {
+        Error::Parse(e)
+    }
+}
+
+impl From<Box<rhai::EvalAltResult>> for Error {
+    fn from(e: Box<rhai::EvalAltResult>) -> Error {
+        Error::Eval(e)
+    }
+}
+
+/// A rule written in Rhai over cells with states 0 to 255, 0 being dead. The
+/// script defines `next(cell, neighbors)`, which gets the state of the cell
+/// and an array of the states around it row by row, and returns the next
+/// state as a number or as a bool for 1 or 0. Scripts that look further than
+/// the eight closest cells also define `radius()`, e.g.
+///
+/// ```rhai
+/// fn next(cell, neighbors) {
+///     let n = neighbors.filter(|c| c != 0).len();
+///     if cell != 0 { n == 2 || n == 3 } else { n == 3 }
+/// }
+/// ```
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    radius: isize,
+}
+
+/// Load the script at path
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Script> {
+    compile(&fs::read_to_string(path)?)
+}
+
+/// Compile a script from its source
+pub fn compile(source: &str) -> Result<Script> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let ast = engine.compile(source)?;
+
+    let defines = |name: &str, params: usize| {
+        ast.iter_functions().any(|f| f.name == name && f.params.len() == params)
+    };
+    if !defines("next", 2) {
+        return Err(Error::Invalid("the script has to define next(cell, neighbors)".to_string()));
+    }
+    let radius = if defines("radius", 0) {
+        engine.call_fn::<rhai::INT>(&mut Scope::new(), &ast, "radius", ())?
+    } else {
+        1
+    };
+    if !(1..=64).contains(&radius) {
+        return Err(Error::Invalid(format!("radius() returned {}, expected 1 to 64", radius)));
+    }
+    Ok(Script { engine, ast, radius: radius as isize })
+}
+
+impl Script {
+    /// How far from a cell the rule looks
+    pub fn radius(&self) -> isize {
+        self.radius
+    }
+
+    /// The next state of the center of curr, or the error the script failed
+    /// with
+    pub fn try_rule(&self, curr: Square<u8>) -> Result<u8> {
+        let r = self.radius;
+        let mut neighbors = rhai::Array::with_capacity(((2 * r + 1) * (2 * r + 1) - 1) as usize);
+        for j in -r..r + 1 {
+            for i in -r..r + 1 {
+                if i != 0 || j != 0 {
+                    neighbors.push(Dynamic::from_int(*curr.get(i, j) as rhai::INT));
+                }
+            }
+        }
+        let cell = *curr.get(0, 0) as rhai::INT;
+        let next = self.engine.call_fn::<Dynamic>(&mut Scope::new(), &self.ast, "next",
+                                                   (cell, neighbors))?;
+        if let Some(alive) = next.clone().try_cast::<bool>() {
+            return Ok(alive as u8);
+        }
+        match next.clone().try_cast::<rhai::INT>() {
+            Some(state) if (0..256).contains(&state) => Ok(state as u8),
+            _ => Err(Error::Invalid(format!("next returned {}, expected a state from 0 to 255 \
+                                             or a bool", next))),
+        }
+    }
+
+    /// The rule, to be used with `Frame::next_frame`
+    ///
+    /// # Panics
+    ///
+    /// if the script fails, use `try_rule` to handle that instead
+    pub fn rule(&self, curr: Square<u8>) -> u8 {
+        self.try_rule(curr).unwrap_or_else(|e| panic!("the rule script failed: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::compile;
+
+    const LIFE: &str = "
+        fn next(cell, neighbors) {
+            let n = neighbors.filter(|c| c != 0).len();
+            if cell != 0 { n == 2 || n == 3 } else { n == 3 }
+        }
+    ";
+
+    #[test]
+    fn life() {
+        let script = compile(LIFE).unwrap();
+        assert_eq!(script.radius(), 1);
+        let mut frame = Frame::<u8>::new(6, 6);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            *frame.get_mut(x, y) = 1;
+        }
+        let life = |&c: &u8| if c != 0 { Alive } else { Dead };
+        let mut expected = frame.map(life);
+        for _ in 0..8 {
+            frame = frame.next_frame(|sq| script.rule(sq));
+            expected = expected.next_frame(game_of_life::rule);
+        }
+        assert_eq!(frame.map(life), expected);
+    }
+
+    #[test]
+    fn radius() {
+        let script = compile("fn radius() { 2 } fn next(cell, neighbors) { neighbors.len() }")
+            .unwrap();
+        let frame = Frame::<u8>::new(5, 5);
+        assert_eq!(*frame.next_frame(|sq| script.rule(sq)).get(0, 0), 24);
+    }
+
+    #[test]
+    fn errors() {
+        let frame = Frame::<u8>::new(3, 3);
+        let message = |source| compile(source).err().unwrap().to_string();
+        assert_eq!(message("fn next(cell) { 0 }"),
+                   "the script has to define next(cell, neighbors)");
+        assert_eq!(message("fn radius() { 0 } fn next(c, n) { 0 }"),
+                   "radius() returned 0, expected 1 to 64");
+        assert!(compile("fn next(c, n) {").is_err());
+
+        let script = compile("fn next(c, n) { 256 }").unwrap();
+        assert_eq!(script.try_rule(frame.square(0, 0)).err().unwrap().to_string(),
+                   "next returned 256, expected a state from 0 to 255 or a bool");
+        let script = compile("fn next(c, n) { loop {} }").unwrap();
+        assert!(script.try_rule(frame.square(0, 0)).is_err());
+        assert!(super::load("/nonexistent/life.rhai").is_err());
+    }
+}
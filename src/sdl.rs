@@ -0,0 +1,174 @@
+use super::{Simulation, Square};
+use super::render::{rgb_buffer, ColorMap};
+
+use sdl2;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::video::FullscreenType;
+
+use std::fmt::Display;
+use std::io;
+use std::time::{Duration, Instant};
+
+fn sdl_error<E: Display>(e: E) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// An SDL2 window showing a simulation, synchronized to the display's refresh
+/// rate. The frame is drawn as a texture with one pixel per cell and scaled
+/// to fill the window, so it can be resized freely.
+///
+/// Keys: space pauses and resumes, `N` advances a single generation, `+` and
+/// `-` change the speed, `F` toggles fullscreen, `B` picks the next brush and
+/// escape closes the window.
+///
+/// With brushes set, clicking a cell paints it with the current brush, or
+/// clears it if it already holds that state, and dragging carries on doing the
+/// same. The right button clears cells.
+pub struct Window<T, F, C> {
+    sim: Simulation<T, F>,
+    colormap: C,
+    scale: usize,
+    paused: bool,
+    delay: Duration,
+    brushes: Vec<T>,
+    brush: usize,
+    /// the state being drawn by the current mouse drag
+    stroke: Option<T>,
+}
+
+impl<T, F, C> Window<T, F, C>
+where T: Clone + PartialEq + Default, F: Fn(Square<T>) -> T, C: ColorMap<T> {
+    /// A view of sim where each cell starts out as a scale by scale block of
+    /// pixels
+    pub fn new(sim: Simulation<T, F>, colormap: C, scale: usize) -> Window<T, F, C> {
+        assert!(scale > 0);
+        Window {
+            sim, colormap, scale,
+            paused: false, delay: Duration::from_millis(50),
+            brushes: vec![], brush: 0, stroke: None,
+        }
+    }
+
+    /// Let the mouse paint cells with these states
+    pub fn with_brushes(mut self, brushes: Vec<T>) -> Window<T, F, C> {
+        self.brushes = brushes;
+        self.brush = 0;
+        self
+    }
+
+    /// React to the mouse over cell (x, y). start is whether the button was
+    /// just pressed rather than dragged and erase whether it is the right
+    /// button.
+    pub fn handle_mouse(&mut self, x: usize, y: usize, start: bool, erase: bool) {
+        if x >= self.sim.frame().width() || y >= self.sim.frame().height() {
+            return;
+        }
+        if start {
+            let current = self.sim.frame().get(x, y);
+            self.stroke = match self.brushes.get(self.brush) {
+                _ if erase => Some(T::default()),
+                Some(brush) if brush == current => Some(T::default()),
+                Some(brush) => Some(brush.clone()),
+                None => None,
+            };
+        }
+        if let Some(ref state) = self.stroke {
+            *self.sim.frame_mut().get_mut(x, y) = state.clone();
+        }
+    }
+
+    /// The simulation being shown
+    pub fn simulation(&self) -> &Simulation<T, F> {
+        &self.sim
+    }
+
+    /// Take back the simulation
+    pub fn into_simulation(self) -> Simulation<T, F> {
+        self.sim
+    }
+
+    /// Open the window and run until it is closed
+    pub fn run(&mut self, title: &str) -> io::Result<()> {
+        let (w, h) = (self.sim.frame().width(), self.sim.frame().height());
+        let context = sdl2::init().map_err(sdl_error)?;
+        let video = context.video().map_err(sdl_error)?;
+        // keep cells as sharp squares when scaling
+        sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "0");
+        let window = video.window(title, (w * self.scale) as u32, (h * self.scale) as u32)
+            .position_centered()
+            .resizable()
+            .build()
+            .map_err(sdl_error)?;
+        let mut canvas = window.into_canvas().present_vsync().build().map_err(sdl_error)?;
+        let creator = canvas.texture_creator();
+        let mut texture = creator
+            .create_texture_streaming(PixelFormatEnum::RGB24, w as u32, h as u32)
+            .map_err(sdl_error)?;
+        let mut events = context.event_pump().map_err(sdl_error)?;
+
+        let mut last = Instant::now();
+        'running: loop {
+            // the window may have been resized, so map through its current size
+            let (ww, wh) = canvas.window().size();
+            let cell = |x: i32, y: i32| {
+                (x.max(0) as usize * w / ww.max(1) as usize,
+                 y.max(0) as usize * h / wh.max(1) as usize)
+            };
+            for event in events.poll_iter() {
+                match event {
+                    Event::MouseButtonDown { mouse_btn, x, y, .. }
+                    if mouse_btn == MouseButton::Left || mouse_btn == MouseButton::Right => {
+                        let (cx, cy) = cell(x, y);
+                        self.handle_mouse(cx, cy, true, mouse_btn == MouseButton::Right);
+                    }
+                    Event::MouseMotion { mousestate, x, y, .. }
+                    if mousestate.left() || mousestate.right() => {
+                        let (cx, cy) = cell(x, y);
+                        self.handle_mouse(cx, cy, false, mousestate.right());
+                    }
+                    Event::Quit { .. } |
+                    Event::KeyDown { keycode: Some(Keycode::ESCAPE), .. } => break 'running,
+                    Event::KeyDown { keycode: Some(key), .. } => match key {
+                        Keycode::SPACE => self.paused = !self.paused,
+                        Keycode::N => self.sim.step(),
+                        Keycode::B if !self.brushes.is_empty() => {
+                            self.brush = (self.brush + 1) % self.brushes.len();
+                        }
+                        Keycode::EQUALS | Keycode::PLUS | Keycode::KP_PLUS => self.delay /= 2,
+                        Keycode::MINUS | Keycode::KP_MINUS => {
+                            self.delay = (self.delay * 2).clamp(Duration::from_millis(1),
+                                                                Duration::from_secs(2));
+                        }
+                        Keycode::F => {
+                            let window = canvas.window_mut();
+                            let next = match window.fullscreen_state() {
+                                FullscreenType::Off => FullscreenType::Desktop,
+                                _ => FullscreenType::Off,
+                            };
+                            window.set_fullscreen(next).map_err(sdl_error)?;
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            if !self.paused && last.elapsed() >= self.delay {
+                self.sim.step();
+                last = Instant::now();
+            }
+
+            let title = format!("{} - generation {}{}", title, self.sim.generation(),
+                                if self.paused { " (paused)" } else { "" });
+            canvas.window_mut().set_title(&title).map_err(sdl_error)?;
+            let buf = rgb_buffer(self.sim.frame(), &self.colormap, 1);
+            texture.update(None, &buf, w * 3).map_err(sdl_error)?;
+            canvas.clear();
+            canvas.copy(&texture, None, None).map_err(sdl_error)?;
+            canvas.present();
+        }
+        Ok(())
+    }
+}
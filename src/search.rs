@@ -0,0 +1,144 @@
+use super::{Frame, Square};
+use super::census::Census;
+use super::ensemble;
+use super::patterns::Symmetry;
+
+use rand::{SeedableRng, XorShiftRng};
+
+use std::hash::Hash;
+
+/// Random soups run to see what they settle into, the way apgsearch does.
+/// Each soup is a size by size square of random cells in the middle of an
+/// empty board, run until the board repeats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoupSearch {
+    /// the side length of the soups
+    pub size: usize,
+    /// the side length of the board the soups are run on, which wraps, so
+    /// gliders that escape the ash come back around to it eventually
+    pub board: usize,
+    /// the chance of a soup cell being alive
+    pub density: f64,
+    pub symmetry: Symmetry,
+    /// soups still changing after this many generations are given up on and
+    /// counted as they are
+    pub max_generations: u64,
+}
+
+impl Default for SoupSearch {
+    /// Asymmetric 16 by 16 soups at half density on a 128 by 128 board
+    fn default() -> SoupSearch {
+        SoupSearch {
+            size: 16,
+            board: 128,
+            density: 0.5,
+            symmetry: Symmetry::C1,
+            max_generations: 10_000,
+        }
+    }
+}
+
+/// What a search turned up
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Haul {
+    /// the number of soups run
+    pub soups: u64,
+    /// how many of them repeated within the limit
+    pub settled: u64,
+    /// the objects the soups ended up as
+    pub census: Census,
+}
+
+impl Haul {
+    /// Add the soups of other to these
+    pub fn merge(&mut self, other: &Haul) {
+        self.soups += other.soups;
+        self.settled += other.settled;
+        self.census.merge(&other.census);
+    }
+}
+
+impl SoupSearch {
+    /// The board of seed, the same for the same seed every time
+    pub fn soup<T: Clone + Default>(&self, seed: u64, alive: T) -> Frame<T> {
+        assert!(self.size <= self.board, "a soup has to fit on the board");
+        // xorshift can't be seeded with all zeros, so fix half of the state
+        let mut rng = XorShiftRng::from_seed([seed as u32, (seed >> 32) as u32,
+                                              0x9e37_79b9, 0x243f_6a88]);
+        let soup = Frame::random_bool_symmetric(self.size, self.size, &mut rng, self.density,
+                                                self.symmetry);
+        let mut board = Frame::new(self.board, self.board);
+        let offset = (self.board - self.size) / 2;
+        for (x, y, &c) in soup.enumerate_squares() {
+            if c {
+                *board.get_mut(offset + x, offset + y) = alive.clone();
+            }
+        }
+        board
+    }
+
+    /// Run the soup of every seed as an ensemble on up to threads threads
+    /// and count the objects each ends up as. Cells are alive when they
+    /// aren't `T::default()`, and soups are made of alive cells.
+    pub fn search<T, R>(&self, seeds: &[u64], threads: usize, alive: T, rule: R) -> Haul
+    where T: Clone + Default + Eq + Hash + Send + Sync, R: Fn(Square<T>) -> T + Sync {
+        assert!(threads > 0, "a search needs at least one thread");
+        let dead = T::default();
+        let scenario = |seed| (self.soup(seed, alive.clone()), &rule);
+        let census = |frame: &Frame<T>| Census::take(frame, |c| *c != dead);
+        let (report, censuses) = ensemble::ensemble_with_previews(
+            seeds, threads, self.max_generations, scenario, census);
+        let mut haul = Haul {
+            soups: report.runs.len() as u64,
+            settled: report.runs.iter().filter(|o| o.settled.is_some()).count() as u64,
+            census: Census::default(),
+        };
+        for census in &censuses {
+            haul.census.merge(census);
+        }
+        haul
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::Alive;
+    use super::super::patterns::{Pattern, Symmetry};
+    use super::SoupSearch;
+
+    fn search() -> SoupSearch {
+        SoupSearch { size: 8, board: 32, max_generations: 1000, ..SoupSearch::default() }
+    }
+
+    #[test]
+    fn soups() {
+        let search = search();
+        let soup = search.soup(7, Alive);
+        assert_eq!((soup.width(), soup.height()), (32, 32));
+        assert_eq!(soup, search.soup(7, Alive));
+        assert!(soup != search.soup(8, Alive));
+        // everything alive is in the middle 8 by 8 square
+        assert!(soup.enumerate_squares().all(|(x, y, &c)| {
+            c != Alive || (12..20).contains(&x) && (12..20).contains(&y)
+        }));
+
+        let symmetric = SoupSearch { symmetry: Symmetry::D8, ..search };
+        let soup = symmetric.soup(7, Alive);
+        assert_eq!(soup.get(12, 13), soup.get(13, 12));
+        assert_eq!(soup.get(12, 13), soup.get(19, 18));
+    }
+
+    #[test]
+    fn haul() {
+        let seeds = (0..16).collect::<Vec<_>>();
+        let haul = search().search(&seeds, 4, Alive, game_of_life::rule);
+        assert_eq!(haul.soups, 16);
+        assert!(haul.settled > 0);
+        // the commonest objects of all turn up even in a handful of soups
+        assert!(haul.census.count(&Pattern::block()) > 0);
+        assert!(haul.census.count(&Pattern::blinker()) > 0);
+        // the haul is the same however the soups are split between threads
+        assert_eq!(search().search(&seeds, 1, Alive, game_of_life::rule), haul);
+    }
+}
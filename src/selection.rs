@@ -0,0 +1,129 @@
+use super::{Frame, Rect};
+use super::paint::Canvas;
+use super::patterns::{Orientation, Pattern};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// A rectangle of cells picked out of a frame to be copied or cut, e.g. by
+/// dragging across it in an editor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Selection {
+    pub rect: Rect,
+}
+
+impl Selection {
+    /// The cells between the corners from and to, which can be given in any
+    /// order and are both selected
+    pub fn new(from: (usize, usize), to: (usize, usize)) -> Selection {
+        let (x, y) = (from.0.min(to.0), from.1.min(to.1));
+        let (width, height) = (from.0.max(to.0) - x + 1, from.1.max(to.1) - y + 1);
+        Selection { rect: Rect { x, y, width, height } }
+    }
+
+    /// The selection cut down to the part that fits in a width by height
+    /// frame, or None if none of it does
+    pub fn clamp(&self, width: usize, height: usize) -> Option<Selection> {
+        let r = self.rect;
+        if r.x >= width || r.y >= height || r.area() == 0 {
+            return None;
+        }
+        let (width, height) = (r.width.min(width - r.x), r.height.min(height - r.y));
+        Some(Selection { rect: Rect { width, height, ..r } })
+    }
+
+    /// A copy of the selected cells of canvas, to be pasted later
+    ///
+    /// # Panics
+    ///
+    /// if the selection doesn't fit in the canvas
+    pub fn copy<T, C>(&self, canvas: &C) -> Frame<T>
+    where T: Clone, C: Canvas<T> + ?Sized {
+        let (w, h) = canvas.size();
+        let r = self.rect;
+        assert!(r.x + r.width <= w && r.y + r.height <= h,
+                "{:?} doesn't fit in a {}x{} frame", r, w, h);
+        let data = (0..r.area())
+            .map(|i| canvas.get(r.x + i % r.width, r.y + i / r.width).clone())
+            .collect::<Vec<_>>();
+        Frame::from_vec(r.width, r.height, data).unwrap()
+    }
+
+    /// The cells alive holds for in the selection, as a pattern the size of
+    /// the selection
+    pub fn copy_pattern<T, C, F>(&self, canvas: &C, alive: F) -> Pattern
+    where T: Clone, C: Canvas<T> + ?Sized, F: Fn(&T) -> bool {
+        Pattern::from_frame(&self.copy(canvas), alive)
+    }
+
+    /// Copy the selected cells of canvas and then set them all to fill
+    pub fn cut<T, C>(&self, canvas: &mut C, fill: T) -> Frame<T>
+    where T: Clone, C: Canvas<T> + ?Sized {
+        let copied = self.copy(canvas);
+        canvas.fill_rect(self.rect, fill);
+        copied
+    }
+}
+
+/// Copy every cell of clipboard onto canvas, in the given orientation, with
+/// its top left corner at (x, y). The clipboard wraps around the edges of the
+/// canvas.
+pub fn paste<T, C>(canvas: &mut C, clipboard: &Frame<T>, x: isize, y: isize,
+                   orientation: Orientation)
+where T: Clone, C: Canvas<T> + ?Sized {
+    let (w, h) = canvas.size();
+    let (cw, ch) = (clipboard.width(), clipboard.height());
+    for (i, j, c) in clipboard.enumerate_squares() {
+        let (i, j) = orientation.apply(i, j, cw, ch);
+        let x = (x + i as isize).rem_euclid(w as isize) as usize;
+        let y = (y + j as isize).rem_euclid(h as isize) as usize;
+        canvas.set(x, y, c.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Rect};
+    use super::super::edit::Editor;
+    use super::super::patterns::{Orientation, Pattern};
+    use super::{paste, Selection};
+
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[test]
+    fn select() {
+        let selection = Selection::new((3, 1), (1, 2));
+        assert_eq!(selection.rect, Rect { x: 1, y: 1, width: 3, height: 2 });
+        assert_eq!(selection.clamp(3, 5).unwrap().rect, Rect { x: 1, y: 1, width: 2, height: 2 });
+        assert_eq!(selection.clamp(1, 5), None);
+    }
+
+    #[test]
+    fn copy_cut_paste() {
+        let mut frame = Frame::from_vec(4, 3, (0..12).collect::<Vec<u8>>()).unwrap();
+        let selection = Selection::new((1, 1), (2, 2));
+        let copied = selection.copy(&frame);
+        assert_eq!(copied.as_slice(), &[5, 6, 9, 10]);
+        let even = selection.copy_pattern(&frame, |&c| c % 2 == 0);
+        assert_eq!(even, Pattern::from_rows(&[".O", ".O"]));
+
+        assert_eq!(selection.cut(&mut frame, 0), copied);
+        assert_eq!(frame.as_slice(), &[0, 1, 2, 3, 4, 0, 0, 7, 8, 0, 0, 11]);
+        paste(&mut frame, &copied, 3, -1, Orientation::Rotate90);
+        assert_eq!(frame.as_slice(), &[6, 1, 2, 10, 4, 0, 0, 7, 5, 0, 0, 9]);
+    }
+
+    #[test]
+    fn editor() {
+        // cutting and pasting in an editor is undone a stroke at a time
+        let mut editor = Editor::new(Frame::from_vec(3, 1, vec![1u8, 2, 3]).unwrap(), 100);
+        editor.begin_stroke();
+        let clipboard = Selection::new((0, 0), (0, 0)).cut(&mut editor, 0);
+        paste(&mut editor, &clipboard, 2, 0, Orientation::Identity);
+        editor.end_stroke();
+        assert_eq!(editor.frame().as_slice(), &[0, 2, 1]);
+        assert!(editor.undo());
+        assert_eq!(editor.frame().as_slice(), &[1, 2, 3]);
+    }
+}
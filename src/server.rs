@@ -0,0 +1,338 @@
+use super::{Frame, Simulation, Square};
+use super::io::ndjson::NdjsonSink;
+
+use tungstenite;
+use tungstenite::handshake::MidHandshake;
+use tungstenite::handshake::server::NoCallback;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{HandshakeError, Message, ServerHandshake, WebSocket};
+
+use std::fmt::Write;
+use std::io;
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a new connection gets to finish the WebSocket handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The most bytes that may be waiting to be sent to a client before it is
+/// dropped for not keeping up
+pub const MAX_BUFFERED: usize = 16 << 20;
+
+/// A WebSocket handshake waiting on the client, and when it started
+type Handshake = (MidHandshake<ServerHandshake<TcpStream, NoCallback>>, Instant);
+
+/// How far a round of a handshake got
+type Accepted = Result<WebSocket<TcpStream>,
+                       HandshakeError<ServerHandshake<TcpStream, NoCallback>>>;
+
+/// A number as json, which can't represent infinities or NaN
+fn number(v: f64) -> String {
+    if v.is_finite() { v.to_string() } else { "null".to_string() }
+}
+
+/// s as a json string
+fn string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => write!(json, "\\{}", c).unwrap(),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Whether e only means there is nothing to do on a non-blocking socket yet
+fn would_block(e: &tungstenite::Error) -> bool {
+    match *e {
+        tungstenite::Error::Io(ref e) => e.kind() == io::ErrorKind::WouldBlock,
+        _ => false,
+    }
+}
+
+/// Runs a simulation and streams it to WebSocket clients as json text
+/// messages.
+///
+/// A client first gets the whole frame, as in `NdjsonSink::write_data`, e.g.
+/// `{"generation":4,"width":3,"height":2,"data":[[0,1,0],[0,1,0]]}`, and
+/// after that only the cells that changed in each generation, e.g.
+/// `{"generation":5,"changes":[[1,0,0],[0,1,1]]}` with each change being
+/// `[x,y,value]`.
+///
+/// Clients control the simulation by sending `play`, `pause` or `step`, which
+/// advances a single generation. Changes to whether the simulation is paused
+/// are sent to every client as `{"paused":true}` or `{"paused":false}`, and
+/// anything else gets an `{"error":..}` back. Clients that more than
+/// `MAX_BUFFERED` bytes are waiting to be sent to are dropped.
+pub struct Server<T, F, V> {
+    sim: Simulation<T, F>,
+    value: V,
+    listener: TcpListener,
+    handshakes: Vec<Handshake>,
+    clients: Vec<WebSocket<TcpStream>>,
+    paused: bool,
+    delay: Duration,
+    last_step: Instant,
+}
+
+impl<T, F, V> Server<T, F, V>
+where T: Clone + PartialEq, F: Fn(Square<T>) -> T, V: Fn(&T) -> f64 {
+    /// Listen on addr, sending cells of sim to clients as the numbers value
+    /// maps them to. The simulation starts out playing, a generation every
+    /// 50ms.
+    pub fn bind<A: ToSocketAddrs>(addr: A, sim: Simulation<T, F>, value: V)
+                                  -> io::Result<Server<T, F, V>> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Server {
+            sim, value, listener,
+            handshakes: vec![],
+            clients: vec![],
+            paused: false,
+            delay: Duration::from_millis(50),
+            last_step: Instant::now(),
+        })
+    }
+
+    /// The address the server is listening on
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn simulation(&self) -> &Simulation<T, F> {
+        &self.sim
+    }
+
+    /// The number of clients connected
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Set the time between generations while playing
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Serve clients forever
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            self.poll()?;
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Do whatever is due without blocking: accept new clients, handle
+    /// commands and step if it is time to
+    pub fn poll(&mut self) -> io::Result<()> {
+        self.accept()?;
+
+        let was_paused = self.paused;
+        let mut step = false;
+        let mut i = 0;
+        while i < self.clients.len() {
+            match self.commands(i) {
+                Some(stepped) => {
+                    step |= stepped;
+                    i += 1;
+                }
+                None => {
+                    self.clients.swap_remove(i);
+                }
+            }
+        }
+        if self.paused != was_paused {
+            self.broadcast(format!("{{\"paused\":{}}}", self.paused));
+        }
+
+        if step || (!self.paused && self.last_step.elapsed() >= self.delay) {
+            let previous = self.sim.frame().clone();
+            self.sim.step();
+            self.last_step = Instant::now();
+            let delta = self.delta(&previous);
+            self.broadcast(delta);
+        }
+        for client in &mut self.clients {
+            // anything that doesn't fit in the socket yet goes out next time
+            let _ = client.flush();
+        }
+        Ok(())
+    }
+
+    /// Carry on with the handshakes of clients that connected earlier and
+    /// start those of new ones, without waiting on any of them
+    fn accept(&mut self) -> io::Result<()> {
+        for (handshake, started) in mem::take(&mut self.handshakes) {
+            // clients too slow to finish the handshake are dropped
+            if started.elapsed() <= HANDSHAKE_TIMEOUT {
+                self.handshake(handshake.handshake(), started)?;
+            }
+        }
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            stream.set_nonblocking(true)?;
+            let config = WebSocketConfig {
+                max_write_buffer_size: MAX_BUFFERED,
+                ..WebSocketConfig::default()
+            };
+            self.handshake(tungstenite::accept_with_config(stream, Some(config)), Instant::now())?;
+        }
+    }
+
+    /// Send the whole frame to clients that finished the handshake, and keep
+    /// the handshakes that are still waiting on the client
+    fn handshake(&mut self, result: Accepted, started: Instant) -> io::Result<()> {
+        let mut client = match result {
+            Ok(client) => client,
+            Err(HandshakeError::Interrupted(handshake)) => {
+                self.handshakes.push((handshake, started));
+                return Ok(());
+            }
+            // clients that fail the handshake are ignored
+            Err(HandshakeError::Failure(_)) => return Ok(()),
+        };
+        let mut sink = NdjsonSink::new(vec![]);
+        sink.write_data(self.sim.generation(), self.sim.frame(), &self.value)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        let frame = String::from_utf8(sink.into_inner()).unwrap();
+        match client.write(Message::Text(frame.trim_end().to_string())) {
+            Err(ref e) if !would_block(e) => {}
+            _ => self.clients.push(client),
+        }
+        Ok(())
+    }
+
+    /// Handle everything client i sent, returning whether it asked for a step,
+    /// or None if the client is gone or misbehaving and should be dropped
+    fn commands(&mut self, i: usize) -> Option<bool> {
+        let mut step = false;
+        loop {
+            let message = match self.clients[i].read() {
+                Ok(message) => message,
+                Err(ref e) if would_block(e) => return Some(step),
+                Err(_) => return None,
+            };
+            match message {
+                Message::Text(ref command) => match command.trim() {
+                    "play" => self.paused = false,
+                    "pause" => self.paused = true,
+                    "step" => step = true,
+                    command => {
+                        let error = format!("{{\"error\":{}}}",
+                                            string(&format!("unknown command {}", command)));
+                        match self.clients[i].write(Message::Text(error)) {
+                            Err(ref e) if !would_block(e) => return None,
+                            _ => {}
+                        }
+                    }
+                },
+                Message::Close(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    /// The cells that changed since previous
+    fn delta(&self, previous: &Frame<T>) -> String {
+        let mut delta = format!("{{\"generation\":{},\"changes\":[", self.sim.generation());
+        let changes = self.sim.frame().enumerate_squares()
+            .filter(|&(x, y, c)| c != previous.get(x, y));
+        for (i, (x, y, c)) in changes.enumerate() {
+            write!(delta, "{}[{},{},{}]", if i > 0 { "," } else { "" }, x, y,
+                   number((self.value)(c))).unwrap();
+        }
+        delta.push_str("]}");
+        delta
+    }
+
+    /// Queue message for every client, dropping clients that are gone or
+    /// have too much waiting to be sent already
+    fn broadcast(&mut self, message: String) {
+        self.clients.retain_mut(|client| {
+            match client.write(Message::Text(message.clone())) {
+                Ok(()) => true,
+                Err(ref e) => would_block(e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Simulation};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::Alive;
+    use super::{Server, HANDSHAKE_TIMEOUT};
+
+    use tungstenite;
+    use tungstenite::Message;
+
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn stream() {
+        let mut frame = Frame::new(5, 5);
+        for y in 1..4 {
+            *frame.get_mut(2, y) = Alive;
+        }
+        let sim = Simulation::new(frame, game_of_life::rule);
+        let mut server = Server::bind("127.0.0.1:0", sim, |c| (*c == Alive) as u8 as f64)
+            .unwrap();
+        server.set_paused(true);
+        server.set_delay(Duration::from_millis(0));
+        let addr = server.local_addr().unwrap();
+
+        // a client that never finishes its handshake doesn't hold up the rest
+        let stalled = TcpStream::connect(addr).unwrap();
+        let start = Instant::now();
+        server.poll().unwrap();
+        assert!(start.elapsed() < HANDSHAKE_TIMEOUT);
+
+        let client = thread::spawn(move || {
+            let stream = TcpStream::connect(addr).unwrap();
+            let (mut ws, _) = tungstenite::client(format!("ws://{}/", addr), stream).unwrap();
+            let mut messages = vec![ws.read().unwrap()];
+            for command in &["step", "da\"nce", "play"] {
+                ws.send(Message::Text(command.to_string())).unwrap();
+                messages.push(ws.read().unwrap());
+            }
+            ws.close(None).unwrap();
+            messages.into_iter().map(|m| m.into_text().unwrap()).collect::<Vec<_>>()
+        });
+        while !client.is_finished() {
+            server.poll().unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+        let messages = client.join().unwrap();
+
+        assert_eq!(messages[0], "{\"generation\":0,\"width\":5,\"height\":5,\"data\":\
+                                 [[0,0,0,0,0],[0,0,1,0,0],[0,0,1,0,0],[0,0,1,0,0],[0,0,0,0,0]]}");
+        assert_eq!(messages[1], "{\"generation\":1,\"changes\":[[2,1,0],[1,2,1],[3,2,1],[2,3,0]]}");
+        assert_eq!(messages[2], "{\"error\":\"unknown command da\\\"nce\"}");
+        assert_eq!(messages[3], "{\"paused\":false}");
+        assert!(!server.is_paused());
+        for _ in 0..10 {
+            server.poll().unwrap();
+        }
+        assert_eq!(server.clients(), 0);
+        drop(stalled);
+    }
+}
@@ -0,0 +1,688 @@
+use super::{Frame, FramePool, Invertible, Rule, Square};
+use super::agents::{Agent, Agents};
+use super::events::{Event, Subscribers};
+use super::history::History;
+use super::schedule::Schedule;
+#[cfg(feature = "std")]
+use super::io;
+#[cfg(feature = "std")]
+use super::io::replay::{self, Replay};
+#[cfg(feature = "std")]
+use super::io::save::{self, Setup};
+#[cfg(feature = "std")]
+use super::perf::{Perf, Phase};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fmt::Display;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{BufReader, BufWriter};
+use std::mem;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::str::FromStr;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// Drives a simulation, keeping track of the current frame, the rule used to
+/// advance it, and how many generations have passed. A simulation can also
+/// hold a pool to step in and a history of its frames, which count towards
+/// its memory usage and budget.
+#[derive(Debug)]
+pub struct Simulation<T, F> {
+    frame: Frame<T>,
+    rule: F,
+    generation: u64,
+    pool: Option<FramePool<T>>,
+    history: Option<History<T>>,
+    /// the bytes the simulation may hold before its history forgets frames
+    budget: Option<usize>,
+}
+
+/// A snapshot of a simulation that it can later be restored to
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Checkpoint<T> {
+    pub frame: Frame<T>,
+    pub generation: u64,
+}
+
+impl<T, F> Simulation<T, F>
+where F: Fn(Square<T>) -> T {
+    /// Start a simulation at generation 0 from frame
+    pub fn new(frame: Frame<T>, rule: F) -> Simulation<T, F> {
+        Simulation::start(frame, rule)
+    }
+}
+
+impl<T, F> Simulation<T, F>
+where F: Invertible<T> {
+    /// Start a simulation at generation 0 from frame with a rule that can be
+    /// undone, so that it can be stepped back as well as forward
+    pub fn invertible(frame: Frame<T>, rule: F) -> Simulation<T, F> {
+        Simulation::start(frame, rule)
+    }
+
+    /// Go back by one generation by undoing the rule, returning false and
+    /// leaving the simulation alone at generation 0, as there is nothing
+    /// before the start to go back to
+    pub fn step_back(&mut self) -> bool {
+        if self.generation == 0 {
+            return false;
+        }
+        let rule = &self.rule;
+        self.frame = self.frame.next_frame(|sq| rule.previous(sq));
+        self.generation -= 1;
+        true
+    }
+}
+
+impl<T, F> Simulation<T, F>
+where F: Rule<T> {
+
+    /// Advance the simulation by one generation, in a buffer from its pool
+    /// if it has one
+    pub fn step(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = trace_span!("step", generation = self.generation).entered();
+        match self.pool.take() {
+            Some(mut pool) => {
+                self.step_in(&mut pool);
+                self.pool = Some(pool);
+            }
+            None => {
+                let rule = &self.rule;
+                self.frame = self.frame.next_frame(|sq| rule.next(sq));
+                self.generation += 1;
+            }
+        }
+    }
+
+    /// Advance the simulation by one generation, taking the new frame's
+    /// buffer from pool and giving it the old one
+    pub fn step_in(&mut self, pool: &mut FramePool<T>) {
+        let rule = &self.rule;
+        let next = self.frame.next_frame_in(pool, |sq| rule.next(sq));
+        pool.recycle(mem::replace(&mut self.frame, next));
+        self.generation += 1;
+    }
+
+    /// Advance the simulation by n generations
+    pub fn step_n(&mut self, n: u64) {
+        #[cfg(feature = "tracing")]
+        let _span = debug_span!("step_n", generation = self.generation, n).entered();
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// Advance the simulation by one generation and then let agents take
+    /// their turns on the new frame
+    pub fn step_with<A>(&mut self, agents: &mut Agents<A>)
+    where A: Agent<Cell = T> {
+        self.step();
+        agents.tick(&mut self.frame);
+    }
+
+}
+
+impl<T, F> Simulation<T, F> {
+    /// Start a simulation at generation 0 from frame, whatever rule is
+    fn start(frame: Frame<T>, rule: F) -> Simulation<T, F> {
+        Simulation { frame, rule, generation: 0, pool: None, history: None, budget: None }
+    }
+
+    /// The current frame
+    pub fn frame(&self) -> &Frame<T> {
+        &self.frame
+    }
+
+    /// A mutable reference to the current frame, for editing it between steps
+    pub fn frame_mut(&mut self) -> &mut Frame<T> {
+        &mut self.frame
+    }
+
+    /// The number of generations that have passed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Advance the simulation by one generation with the phase schedule has
+    /// for the current generation instead of the simulation's rule
+    pub fn step_scheduled(&mut self, schedule: &Schedule<T>) {
+        self.frame = self.frame.next_frame_scheduled(schedule, self.generation);
+        self.generation += 1;
+    }
+
+    /// The bytes held by the current frame and the pool and history of the
+    /// simulation. Histories and pools passed to methods like `step_in` are
+    /// kept outside it and report their own.
+    pub fn memory_usage(&self) -> usize {
+        self.frame.memory_usage()
+            + self.pool.as_ref().map_or(0, FramePool::memory_usage)
+            + self.history.as_ref().map_or(0, History::memory_usage)
+    }
+
+    /// Step in buffers from pool, or allocate new frames with None
+    pub fn set_pool(&mut self, pool: Option<FramePool<T>>) {
+        self.pool = pool;
+    }
+
+    pub fn pool(&self) -> Option<&FramePool<T>> {
+        self.pool.as_ref()
+    }
+
+    pub fn history(&self) -> Option<&History<T>> {
+        self.history.as_ref()
+    }
+
+    /// Stop keeping a history, giving back the one kept so far
+    pub fn take_history(&mut self) -> Option<History<T>> {
+        self.history.take()
+    }
+}
+
+impl<T, F> Simulation<T, F>
+where T: Clone {
+    /// Take a snapshot of the current state of the simulation
+    pub fn checkpoint(&self) -> Checkpoint<T> {
+        Checkpoint {
+            frame: self.frame.clone(),
+            generation: self.generation,
+        }
+    }
+
+    /// Return to a previously taken snapshot
+    pub fn restore(&mut self, checkpoint: Checkpoint<T>) {
+        self.frame = checkpoint.frame;
+        self.generation = checkpoint.generation;
+    }
+}
+
+impl<T, F> Simulation<T, F>
+where T: Clone + PartialEq {
+    /// Keep a history of the simulation from the current frame on, with a
+    /// whole frame every interval generations, which `step_keeping_history`
+    /// adds to and `rewind_history` goes back through
+    ///
+    /// # Panics
+    ///
+    /// if interval is 0
+    pub fn keep_history(&mut self, interval: usize) {
+        self.history = Some(History::new(&self.frame, self.generation, interval));
+        self.fit_budget();
+    }
+
+    /// Forget the oldest frames of the simulation's history once the
+    /// simulation holds more than bytes, or never forget any with None. The
+    /// frame and pool are never given up, so the history gets whatever they
+    /// leave, though it always keeps the frames since its latest keyframe.
+    pub fn set_memory_budget(&mut self, bytes: Option<usize>) {
+        self.budget = bytes;
+        self.fit_budget();
+    }
+
+    /// Give the history what is left of the budget
+    fn fit_budget(&mut self) {
+        let others = self.frame.memory_usage()
+            + self.pool.as_ref().map_or(0, FramePool::memory_usage);
+        let budget = self.budget.map(|bytes| bytes.saturating_sub(others));
+        if let Some(ref mut history) = self.history {
+            history.set_budget(budget);
+        }
+    }
+
+    /// Advance the simulation by one generation, remembering the new frame
+    /// in its own history like `step_recording`, or just stepping if it
+    /// isn't keeping one
+    pub fn step_keeping_history(&mut self)
+    where F: Rule<T> {
+        match self.history.take() {
+            Some(mut history) => {
+                self.step_recording(&mut history);
+                self.history = Some(history);
+                self.fit_budget();
+            }
+            None => self.step(),
+        }
+    }
+
+    /// Like `rewind`, but through the simulation's own history
+    pub fn rewind_history(&mut self, generation: u64) -> bool {
+        match self.history.as_ref().and_then(|history| history.frame_at(generation)) {
+            Some(frame) => {
+                self.frame = frame;
+                self.generation = generation;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advance the simulation by one generation, remembering the new frame
+    /// in history. If the simulation was rewound, the frames history has
+    /// after the current one are forgotten first, so the new frame follows
+    /// the one it was stepped from.
+    ///
+    /// # Panics
+    ///
+    /// if the current generation is before the start of history, or after
+    /// its last frame, so that frames would be missing
+    pub fn step_recording(&mut self, history: &mut History<T>)
+    where F: Rule<T> {
+        history.truncate(self.generation);
+        assert!(self.generation == history.last_generation(),
+                "history ends at generation {} but the simulation is at {}",
+                history.last_generation(), self.generation);
+        self.step();
+        history.push(&self.frame);
+    }
+
+    /// Go back to the frame history has for generation, returning false and
+    /// leaving the simulation alone if it doesn't have one. Stepping on with
+    /// `step_recording` branches off from there, forgetting the frames after
+    /// it.
+    pub fn rewind(&mut self, history: &History<T>, generation: u64) -> bool {
+        match history.frame_at(generation) {
+            Some(frame) => {
+                self.frame = frame;
+                self.generation = generation;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T, F> Simulation<T, F>
+where T: Clone + Default + PartialEq, F: Rule<T> {
+    /// Advance the simulation by one generation, calling on_event with what
+    /// happened to each cell that changed as it is stepped
+    pub fn step_each_event<G>(&mut self, mut on_event: G)
+    where G: FnMut(Event<T>) {
+        let (frame, rule) = (&self.frame, &self.rule);
+        let next = Frame::new_from_fn(frame.width(), frame.height(), |x, y| {
+            let square = frame.square(x, y);
+            let next = rule.next(square);
+            if let Some(event) = Event::between(x, y, square.get(0, 0), &next) {
+                on_event(event);
+            }
+            next
+        });
+        self.frame = next;
+        self.generation += 1;
+    }
+
+    /// Advance the simulation by one generation, returning what happened to
+    /// each cell that changed
+    pub fn step_events(&mut self) -> Vec<Event<T>> {
+        let mut events = vec![];
+        self.step_each_event(|event| events.push(event));
+        events
+    }
+
+    /// Advance the simulation by one generation, delivering what happened to
+    /// each cell that changed to subscribers
+    pub fn step_notify(&mut self, subscribers: &mut Subscribers<T>) {
+        let generation = self.generation + 1;
+        self.step_each_event(|event| subscribers.notify(generation, &event));
+    }
+
+    /// Advance the simulation by one generation, emitting an event with the
+    /// new generation, its population of cells that aren't `T::default()` and
+    /// its activity, the fraction of cells that changed
+    #[cfg(feature = "tracing")]
+    pub fn step_traced(&mut self) {
+        let rule = &self.rule;
+        let next = self.frame.next_frame(|sq| rule.next(sq));
+        let prev = mem::replace(&mut self.frame, next);
+        self.generation += 1;
+        let dead = T::default();
+        let cells = self.frame.as_slice();
+        let population = cells.iter().filter(|&c| *c != dead).count();
+        let changed = cells.iter().zip(prev.as_slice()).filter(|(a, b)| a != b).count();
+        let activity = changed as f64 / cells.len().max(1) as f64;
+        debug!(generation = self.generation, population, activity, "stepped");
+    }
+
+    /// Grow the frame if live cells, those that aren't `T::default()`, are
+    /// within margin cells of an edge, so patterns that expand, like guns,
+    /// never wrap around onto themselves. A dimension that is too small for
+    /// the live cells plus margin on each side at least doubles, and the live
+    /// cells are moved to the middle of each dimension they were close to
+    /// the edges of. Returns how far the cells moved, or None if the frame
+    /// was left alone.
+    pub fn expand(&mut self, margin: usize) -> Option<(isize, isize)> {
+        let dead = T::default();
+        let rect = self.frame.bounding_box(|c| *c != dead)?;
+        let fit = |start: usize, len: usize, size: usize| {
+            if start >= margin && start + len + margin <= size {
+                return None;
+            }
+            let needed = len + 2 * margin;
+            let size = if needed > size { needed.max(2 * size) } else { size };
+            Some((size, ((size - len) / 2) as isize - start as isize))
+        };
+        let (w, h) = (self.frame.width(), self.frame.height());
+        let (x, y) = (fit(rect.x, rect.width, w), fit(rect.y, rect.height, h));
+        if x.is_none() && y.is_none() {
+            return None;
+        }
+        let ((w, dx), (h, dy)) = (x.unwrap_or((w, 0)), y.unwrap_or((h, 0)));
+
+        let mut frame = Frame::new(w, h);
+        let cropped = self.frame.crop(rect);
+        let (left, top) = ((rect.x as isize + dx) as usize, (rect.y as isize + dy) as usize);
+        for (x, y, c) in cropped.enumerate_squares() {
+            *frame.get_mut(left + x, top + y) = c.clone();
+        }
+        self.frame = frame;
+        Some((dx, dy))
+    }
+
+    /// Grow the frame with `expand` if it needs to and then advance the
+    /// simulation by one generation
+    pub fn step_expanding(&mut self, margin: usize) {
+        self.expand(margin);
+        self.step();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F> Simulation<T, F>
+where T: Clone, F: Rule<T> {
+    /// Advance the simulation by one generation, timing the rule and the
+    /// swap to the new frame in perf
+    pub fn step_timed(&mut self, perf: &mut Perf) {
+        let start = Instant::now();
+        let rule = &self.rule;
+        let next = self.frame.next_frame(|sq| rule.next(sq));
+        perf.record_step(next.as_slice().len(), start.elapsed());
+        perf.time(Phase::Swap, || drop(mem::replace(&mut self.frame, next)));
+        self.generation += 1;
+    }
+
+    /// Save the current frame and generation to the file at path along with
+    /// setup. Rules are arbitrary functions, so they are identified by name
+    /// (e.g. `B3/S23`), and random rules by the seed their rng was reseeded
+    /// with as well.
+    pub fn save<P: AsRef<Path>>(&self, path: P, setup: &Setup) -> io::Result<()>
+    where T: Display {
+        #[cfg(feature = "tracing")]
+        let _span = info_span!("save", path = %path.as_ref().display()).entered();
+        let file = BufWriter::new(File::create(path)?);
+        save::write(file, &self.checkpoint(), setup)
+    }
+
+    /// Load a simulation saved with save. rules is given the setup the
+    /// simulation was saved with and returns the rule to continue with, or
+    /// None if it isn't recognized.
+    pub fn load<P, G>(path: P, rules: G) -> io::Result<Simulation<T, F>>
+    where P: AsRef<Path>, G: FnOnce(&Setup) -> Option<F>, T: FromStr + Default {
+        #[cfg(feature = "tracing")]
+        let _span = info_span!("load", path = %path.as_ref().display()).entered();
+        let saved = save::read(BufReader::new(File::open(path)?))?;
+        let rule = rules(&saved.setup).ok_or_else(|| io::Error::Parse {
+            line: 3,
+            message: format!("unknown rule `{}`", saved.setup.rule),
+        })?;
+        let mut sim = Simulation::start(saved.checkpoint.frame.clone(), rule);
+        sim.restore(saved.checkpoint);
+        Ok(sim)
+    }
+
+    /// Set the cell at (x, y) to value, remembering the edit in replay so
+    /// that it is made again when the run is replayed
+    pub fn record(&mut self, replay: &mut Replay<T>, x: usize, y: usize, value: T) {
+        *self.frame.get_mut(x, y) = value.clone();
+        replay.record(self.generation, x, y, value);
+    }
+
+    /// Run a replay written with `io::replay::write` again, making each edit
+    /// once the simulation reaches the generation it was made at and stopping
+    /// after the last one. rules is given the replay, to pick the rule by its
+    /// name, seed and parameters, and returns None if it isn't recognized.
+    pub fn replay<P, G>(path: P, rules: G) -> io::Result<Simulation<T, F>>
+    where P: AsRef<Path>, G: FnOnce(&Replay<T>) -> Option<F>, T: FromStr + Default {
+        #[cfg(feature = "tracing")]
+        let _span = info_span!("replay", path = %path.as_ref().display()).entered();
+        let replay = replay::read(BufReader::new(File::open(path)?))?;
+        let rule = rules(&replay).ok_or_else(|| io::Error::Parse {
+            line: 2,
+            message: format!("unknown rule `{}`", replay.rule),
+        })?;
+        let mut sim = Simulation::start(replay.start.frame, rule);
+        sim.generation = replay.start.generation;
+        for edit in replay.edits {
+            while sim.generation < edit.generation {
+                sim.step();
+            }
+            *sim.frame.get_mut(edit.x, edit.y) = edit.value;
+        }
+        Ok(sim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Square, WithInverse};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::game_of_life::State::Alive;
+    use super::Simulation;
+
+    fn blinker() -> Frame<State> {
+        let mut frame = Frame::new(5, 5);
+        *frame.get_mut(2, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        *frame.get_mut(2, 3) = Alive;
+        frame
+    }
+
+    #[test]
+    fn step() {
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        sim.step();
+        assert_eq!(sim.generation(), 1);
+        assert!(*sim.frame() != blinker());
+        sim.step_n(3);
+        assert_eq!(sim.generation(), 4);
+        assert_eq!(*sim.frame(), blinker());
+    }
+
+    #[test]
+    fn step_in() {
+        use super::super::FramePool;
+
+        let mut pool = FramePool::new(1);
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        sim.step_in(&mut pool);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(sim.generation(), 1);
+        sim.step_in(&mut pool);
+        assert_eq!(*sim.frame(), blinker());
+    }
+
+    #[test]
+    fn checkpoint() {
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        let start = sim.checkpoint();
+        sim.step_n(5);
+        let later = sim.checkpoint();
+        sim.restore(start.clone());
+        assert_eq!(sim.checkpoint(), start);
+        sim.step_n(5);
+        assert_eq!(sim.checkpoint(), later);
+    }
+
+    #[test]
+    fn step_back() {
+        // moving every cell right is undone by moving every cell left
+        let right = |sq: Square<State>| *sq.get(-1, 0);
+        let left = |sq: Square<State>| *sq.get(1, 0);
+        let mut sim = Simulation::invertible(blinker(), WithInverse::new(right, left));
+        sim.step_n(3);
+        assert!(*sim.frame() != blinker());
+        for _ in 0..3 {
+            assert!(sim.step_back());
+        }
+        assert_eq!(sim.generation(), 0);
+        assert_eq!(*sim.frame(), blinker());
+
+        // there is nothing before the start
+        assert!(!sim.step_back());
+        assert_eq!(*sim.frame(), blinker());
+    }
+
+    #[test]
+    fn rewind() {
+        use super::super::history::History;
+
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        let mut history = History::new(sim.frame(), 0, 4);
+        for _ in 0..9 {
+            sim.step_recording(&mut history);
+        }
+        assert!(sim.rewind(&history, 4));
+        assert_eq!(sim.generation(), 4);
+        assert_eq!(*sim.frame(), blinker());
+        assert!(!sim.rewind(&history, 10));
+        assert_eq!(sim.generation(), 4);
+
+        // stepping after rewinding branches off from the rewound frame
+        *sim.frame_mut().get_mut(0, 0) = Alive;
+        sim.step_recording(&mut history);
+        assert_eq!(history.last_generation(), 5);
+        assert_eq!(history.frame_at(5).as_ref(), Some(sim.frame()));
+        assert_eq!(history.frame_at(6), None);
+    }
+
+    #[test]
+    fn owned_history() {
+        use super::super::FramePool;
+        use super::super::patterns::{Orientation, Pattern};
+
+        let mut frame = Frame::new(16, 16);
+        Pattern::glider().place(&mut frame, 2, 2, Orientation::Identity, Alive);
+        let mut sim = Simulation::new(frame, game_of_life::rule);
+        let bare = sim.memory_usage();
+        sim.set_pool(Some(FramePool::new(1)));
+        sim.keep_history(4);
+        for _ in 0..40 {
+            sim.step_keeping_history();
+        }
+        assert_eq!(sim.pool().map(FramePool::len), Some(1));
+        assert_eq!(sim.history().map(|h| h.last_generation()), Some(40));
+        let unbounded = sim.memory_usage();
+        assert!(unbounded > bare + sim.history().unwrap().memory_usage());
+
+        // the history gets what the frame and pool leave of the budget
+        sim.set_memory_budget(Some(5 * 256));
+        assert!(sim.memory_usage() < unbounded);
+        assert!(sim.history().unwrap().first_generation() > 0);
+        assert!(sim.rewind_history(40));
+        assert!(!sim.rewind_history(0));
+        let before = sim.memory_usage();
+        let history = sim.take_history().unwrap();
+        assert_eq!(sim.memory_usage(), before - history.memory_usage());
+    }
+
+    #[test]
+    fn expand() {
+        use super::super::patterns::Pattern;
+
+        // a glider in a frame it would wrap around in after a few steps
+        let mut sim = Simulation::new(Pattern::glider().to_frame(Alive), game_of_life::rule);
+        assert_eq!(sim.expand(1), Some((1, 1)));
+        assert_eq!((sim.frame().width(), sim.frame().height()), (6, 6));
+        assert_eq!(sim.expand(1), None);
+        for _ in 0..40 {
+            sim.step_expanding(2);
+        }
+        let population = sim.frame().as_slice().iter().filter(|&&c| c == Alive).count();
+        assert_eq!(population, 5);
+        assert!(sim.frame().width() >= 12 && sim.frame().width() == sim.frame().height());
+
+        // a blinker away from the edges is left alone
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        assert_eq!(sim.expand(1), None);
+        let mut empty = Simulation::new(Frame::<State>::new(3, 3), game_of_life::rule);
+        assert_eq!(empty.expand(5), None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn step_traced() {
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        sim.step_traced();
+        sim.step_traced();
+        assert_eq!(sim.generation(), 2);
+        assert_eq!(*sim.frame(), blinker());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn save_load() {
+        use super::super::io::save::Setup;
+        use std::env;
+        use std::fs;
+
+        let path = env::temp_dir().join("simulation_save_load");
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        sim.step_n(3);
+        sim.save(&path, &Setup::new("B3/S23")).unwrap();
+
+        let rules = |setup: &Setup| {
+            if setup.rule == "B3/S23" { Some(game_of_life::rule) } else { None }
+        };
+        let loaded = Simulation::load(&path, rules).unwrap();
+        assert_eq!(loaded.checkpoint(), sim.checkpoint());
+
+        sim.save(&path, &Setup::new("B36/S23")).unwrap();
+        assert!(Simulation::<State, _>::load(&path, rules).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn replay() {
+        use super::super::game_of_life::State::Dead;
+        use super::super::io::replay::{self, Replay};
+        use std::env;
+        use std::fs::{self, File};
+
+        let path = env::temp_dir().join("simulation_replay");
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        let mut recording = Replay::new(sim.checkpoint(), "B3/S23");
+        sim.step_n(3);
+        sim.record(&mut recording, 0, 0, Alive);
+        sim.record(&mut recording, 1, 0, Alive);
+        sim.step_n(2);
+        sim.record(&mut recording, 2, 2, Dead);
+        replay::write(File::create(&path).unwrap(), &recording).unwrap();
+
+        let rules = |r: &Replay<State>| {
+            if r.rule == "B3/S23" { Some(game_of_life::rule) } else { None }
+        };
+        let replayed = Simulation::replay(&path, rules).unwrap();
+        assert_eq!(replayed.checkpoint(), sim.checkpoint());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn checkpoint_serde() {
+        use super::Checkpoint;
+        use serde_json;
+
+        let mut sim = Simulation::new(blinker(), game_of_life::rule);
+        sim.step_n(3);
+        let json = serde_json::to_string(&sim.checkpoint()).unwrap();
+        let checkpoint: Checkpoint<State> = serde_json::from_str(&json).unwrap();
+        assert_eq!(checkpoint, sim.checkpoint());
+    }
+}
@@ -0,0 +1,221 @@
+use super::{Frame, Square};
+use super::convolve::Kernel;
+#[cfg(feature = "fft")]
+use super::fft::{CachedConvolver, FFT_RADIUS};
+
+/// Parameters and precomputed kernels for SmoothLife, a continuous
+/// generalization of Game of Life where each cell holds a value in [0, 1]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SmoothLife {
+    /// the interval of outer fillings that cause a dead cell to be born
+    pub birth: (f32, f32),
+    /// the interval of outer fillings that allow a living cell to survive
+    pub death: (f32, f32),
+    /// the width of the step around the edges of the birth/death intervals
+    pub alpha_n: f32,
+    /// the width of the step between being considered dead and alive
+    pub alpha_m: f32,
+    inner: Vec<(isize, isize, f32)>,
+    outer: Vec<(isize, isize, f32)>,
+    inner_area: f32,
+    outer_area: f32,
+    /// the kernels laid out for `Frame::convolve`, divided by their areas so
+    /// convolving gives fillings
+    inner_kernel: Kernel<f32>,
+    outer_kernel: Kernel<f32>,
+    /// the transforms of the kernels, for convolving with them when they are
+    /// large
+    #[cfg(feature = "fft")]
+    inner_transform: CachedConvolver,
+    #[cfg(feature = "fft")]
+    outer_transform: CachedConvolver,
+}
+
+/// Weight of a cell at distance d from the center of a disk of radius r. Cells
+/// straddling the edge get partial weight to avoid aliasing.
+fn antialias(d: f32, r: f32) -> f32 {
+    (r + 0.5 - d).clamp(0., 1.)
+}
+
+/// A smooth step that is 0 well below a and 1 well above it
+fn sigma1(x: f32, a: f32, alpha: f32) -> f32 {
+    1. / (1. + (-(x - a) * 4. / alpha).exp())
+}
+
+/// A smooth interval that is 1 between a and b and 0 outside of it
+fn sigma2(x: f32, a: f32, b: f32, alpha: f32) -> f32 {
+    sigma1(x, a, alpha) * (1. - sigma1(x, b, alpha))
+}
+
+/// Smoothly pick between x (when m is dead) and y (when m is alive)
+fn sigmam(x: f32, y: f32, m: f32, alpha: f32) -> f32 {
+    let t = sigma1(m, 0.5, alpha);
+    x * (1. - t) + y * t
+}
+
+impl SmoothLife {
+    /// Create SmoothLife with the given outer radius. The inner radius is a
+    /// third of the outer one and the transition parameters are the ones from
+    /// Rafler's original paper.
+    pub fn new(outer_radius: f32) -> SmoothLife {
+        let inner_radius = outer_radius / 3.;
+        let r = outer_radius.ceil() as isize;
+
+        let mut inner = vec![];
+        let mut outer = vec![];
+        for i in -r..r + 1 {
+            for j in -r..r + 1 {
+                let d = ((i * i + j * j) as f32).sqrt();
+                let w_in = antialias(d, inner_radius);
+                let w_out = antialias(d, outer_radius) * (1. - w_in);
+                if w_in > 0. {
+                    inner.push((i, j, w_in));
+                }
+                if w_out > 0. {
+                    outer.push((i, j, w_out));
+                }
+            }
+        }
+        let inner_area = inner.iter().map(|&(_, _, w)| w).sum();
+        let outer_area = outer.iter().map(|&(_, _, w)| w).sum();
+        let inner_kernel = Kernel::from_offsets(&inner).normalized();
+        let outer_kernel = Kernel::from_offsets(&outer).normalized();
+
+        SmoothLife {
+            birth: (0.278, 0.365),
+            death: (0.267, 0.445),
+            alpha_n: 0.028,
+            alpha_m: 0.147,
+            inner,
+            outer,
+            inner_area,
+            outer_area,
+            inner_kernel,
+            outer_kernel,
+            #[cfg(feature = "fft")]
+            inner_transform: CachedConvolver::new(),
+            #[cfg(feature = "fft")]
+            outer_transform: CachedConvolver::new(),
+        }
+    }
+
+    /// How many cells away the outer kernel reaches
+    pub fn radius(&self) -> usize {
+        self.outer_kernel.radius().0
+    }
+
+    /// The new value of a cell whose outer annulus has filling n and whose
+    /// inner disk has filling m
+    pub fn transition(&self, n: f32, m: f32) -> f32 {
+        let lo = sigmam(self.birth.0, self.death.0, m, self.alpha_m);
+        let hi = sigmam(self.birth.1, self.death.1, m, self.alpha_m);
+        sigma2(n, lo, hi, self.alpha_n)
+    }
+
+    /// The rule for SmoothLife, to be used with `Frame::next_frame`. This sums
+    /// both kernels for every cell, so `step` is much faster for stepping a
+    /// whole frame.
+    pub fn rule(&self, curr: Square<f32>) -> f32 {
+        let filling = |kernel: &[(isize, isize, f32)], area: f32| {
+            kernel.iter()
+                .map(|&(i, j, w)| { curr.get(i, j) * w })
+                .sum::<f32>() / area
+        };
+        let m = filling(&self.inner, self.inner_area);
+        let n = filling(&self.outer, self.outer_area);
+        self.transition(n, m)
+    }
+
+    /// The inner and outer fillings of every cell of frame at once, summed by
+    /// convolving the frame with the kernels. With the fft feature, kernels
+    /// with a radius of at least `FFT_RADIUS` are convolved through their
+    /// transforms, which are kept for as long as frames stay the same size.
+    pub fn fillings(&self, frame: &Frame<f32>) -> (Frame<f32>, Frame<f32>) {
+        #[cfg(feature = "fft")]
+        {
+            if self.radius() >= FFT_RADIUS {
+                return (self.inner_transform.convolve(&self.inner_kernel, frame),
+                        self.outer_transform.convolve(&self.outer_kernel, frame));
+            }
+        }
+        (frame.convolve(&self.inner_kernel), frame.convolve(&self.outer_kernel))
+    }
+
+    /// The frame after frame, the same as stepping it with `rule` up to
+    /// rounding
+    pub fn step(&self, frame: &Frame<f32>) -> Frame<f32> {
+        let (m, n) = self.fillings(frame);
+        let cells = m.as_slice().iter().zip(n.as_slice())
+            .map(|(&m, &n)| self.transition(n, m))
+            .collect();
+        Frame::from_vec(frame.width(), frame.height(), cells).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::SmoothLife;
+
+    use std::f32::consts::PI;
+
+    #[test]
+    fn kernel_area() {
+        let sl = SmoothLife::new(9.);
+        assert!((sl.inner_area - PI * 9.).abs() < 1.);
+        assert!((sl.outer_area - PI * (81. - 9.)).abs() < 3.);
+    }
+
+    #[test]
+    fn transition() {
+        let sl = SmoothLife::new(9.);
+        // birth when surrounded by the right amount of life
+        assert!(sl.transition(0.3, 0.) > 0.9);
+        // survival needs a little more
+        assert!(sl.transition(0.4, 1.) > 0.9);
+        // loneliness
+        assert!(sl.transition(0.05, 1.) < 0.1);
+        // overcrowding
+        assert!(sl.transition(0.9, 1.) < 0.1);
+        assert!(sl.transition(0.9, 0.) < 0.1);
+    }
+
+    #[test]
+    fn empty_stays_empty() {
+        let sl = SmoothLife::new(4.);
+        let frame = Frame::<f32>::new(12, 12);
+        let next = frame.next_frame(|sq| sl.rule(sq));
+        for (_, _, v) in next.enumerate_squares() {
+            assert!(*v < 1e-3);
+        }
+    }
+
+    #[test]
+    fn step() {
+        let sl = SmoothLife::new(4.);
+        let mut frame = Frame::<f32>::new(12, 14);
+        for (i, cell) in frame.as_mut_slice().iter_mut().enumerate() {
+            *cell = (i * 5 % 9) as f32 / 8.;
+        }
+        let fast = sl.step(&frame);
+        let slow = frame.next_frame(|sq| sl.rule(sq));
+        for (a, b) in fast.as_slice().iter().zip(slow.as_slice()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn full_dies() {
+        let sl = SmoothLife::new(4.);
+        let mut frame = Frame::<f32>::new(12, 12);
+        for x in 0..12 {
+            for y in 0..12 {
+                *frame.get_mut(x, y) = 1.;
+            }
+        }
+        let next = frame.next_frame(|sq| sl.rule(sq));
+        for (_, _, v) in next.enumerate_squares() {
+            assert!(*v < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+/// What a lookup that runs off one edge of a frame lands on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Edge {
+    /// the opposite edge
+    #[default]
+    Wrap,
+    /// the opposite edge, mirrored along the other axis
+    Flip,
+    /// a cell outside of the frame
+    Bounded,
+}
+
+/// How the edges of a frame are joined, one `Edge` for crossing the left and
+/// right edges and one for crossing the top and bottom
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Topology {
+    pub x: Edge,
+    pub y: Edge,
+}
+
+impl Topology {
+    /// Both axes wrap, what frames use unless told otherwise
+    pub fn torus() -> Topology {
+        Topology { x: Edge::Wrap, y: Edge::Wrap }
+    }
+
+    /// The left and right edges wrap and the top and bottom are bounded
+    pub fn cylinder() -> Topology {
+        Topology { x: Edge::Wrap, y: Edge::Bounded }
+    }
+
+    /// The left and right edges are joined with a half twist and the top and
+    /// bottom are bounded
+    pub fn mobius() -> Topology {
+        Topology { x: Edge::Flip, y: Edge::Bounded }
+    }
+
+    /// The left and right edges wrap and the top and bottom are joined with a
+    /// half twist
+    pub fn klein_bottle() -> Topology {
+        Topology { x: Edge::Wrap, y: Edge::Flip }
+    }
+
+    /// Both axes are joined with a half twist
+    pub fn projective_plane() -> Topology {
+        Topology { x: Edge::Flip, y: Edge::Flip }
+    }
+
+    /// Every edge is bounded
+    pub fn plane() -> Topology {
+        Topology { x: Edge::Bounded, y: Edge::Bounded }
+    }
+}
+
+/// Represents a single square in a frame, which wraps around at its edges
+/// unless it was made with another `Topology`. Cells are only ever looked at
+/// by reference, so they don't have to be `Clone`, and the square itself can
+/// be copied freely.
+#[derive(Debug, PartialEq)]
+pub struct Square<'a, T>
+where T: 'a {
+    cells: &'a [T],
+    width: usize,
+    height: usize,
+    point: (usize, usize),
+    topology: Topology,
+    /// what is past bounded edges
+    outside: Option<&'a T>,
+}
+
+// derived impls would needlessly require T: Clone
+impl<'a, T> Clone for Square<'a, T> {
+    fn clone(&self) -> Square<'a, T> {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Square<'a, T> {}
+
+/// Add x and y mod m
+fn add_modulo(x: usize, y: isize, m: usize) -> usize {
+    // y should not be greater than the modulo we are working with
+    assert!(y.unsigned_abs() < m);
+    let base = (x % m) + m;
+    let delta = if y.is_negative() { y + (m as isize) } else { y } as usize;
+    (base + delta) % m
+}
+
+impl<'a, T> Square<'a, T>
+where T: 'a {
+    /// The square centered at (x, y) of the width by height cells laid out
+    /// row by row
+    pub fn new(cells: &'a [T], width: usize, height: usize, x: usize, y: usize)
+               -> Square<'a, T> {
+        assert_eq!(cells.len(), width * height);
+        Square { cells, width, height, point: (x, y), topology: Topology::torus(), outside: None }
+    }
+
+    /// The square centered at (x, y) with edges joined according to
+    /// topology, where lookups past bounded edges give outside
+    pub fn with_topology(cells: &'a [T], width: usize, height: usize, x: usize, y: usize,
+                         topology: Topology, outside: &'a T) -> Square<'a, T> {
+        Square { topology, outside: Some(outside), ..Square::new(cells, width, height, x, y) }
+    }
+
+    /// Return a point relative to the square
+    pub fn get(&self, i: isize, j: isize) -> &'a T {
+        if self.topology == Topology::torus() {
+            let (x, y) = self.point;
+            let (x, y) = (add_modulo(x, i, self.width), add_modulo(y, j, self.height));
+            return &self.cells[y * self.width + x];
+        }
+        match self.locate(i, j) {
+            Some((x, y)) => &self.cells[y * self.width + x],
+            // only squares made by with_topology have bounded edges
+            None => self.outside.unwrap(),
+        }
+    }
+
+    /// Where the cell (i, j) away from the center is under the topology of
+    /// the square, or None if it is past a bounded edge
+    fn locate(&self, i: isize, j: isize) -> Option<(usize, usize)> {
+        let (w, h) = (self.width as isize, self.height as isize);
+        // offsets should not be greater than the size of the frame
+        assert!(i.abs() < w && j.abs() < h);
+        let (x, y) = (self.point.0 as isize + i, self.point.1 as isize + j);
+        let (x, y) = cross(self.topology.x, x, y, w, h)?;
+        let (y, x) = cross(self.topology.y, y, x, h, w)?;
+        Some((x as usize, y as usize))
+    }
+
+    /// The coordinate of the square
+    pub fn coordinate(&self) -> (usize, usize) {
+        self.point
+    }
+
+    /// How the edges of the frame the square is in are joined
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+}
+
+/// Bring a, which runs along an axis of length len, back onto the frame by
+/// crossing an edge, mirroring b along its axis of length other if the edge
+/// flips, or None if the edge is bounded
+fn cross(edge: Edge, a: isize, b: isize, len: isize, other: isize) -> Option<(isize, isize)> {
+    if 0 <= a && a < len {
+        return Some((a, b));
+    }
+    match edge {
+        Edge::Wrap => Some((a.rem_euclid(len), b)),
+        Edge::Flip => Some((a.rem_euclid(len), other - 1 - b)),
+        Edge::Bounded => None,
+    }
+}
+
+impl<'a, T> Square<'a, T>
+where T: 'a {
+    /// The cells at most r away from the center horizontally and vertically,
+    /// as their offset from the center and the cell, row by row. Does not
+    /// contain the center itself.
+    pub fn neighbors(&self, r: isize) -> Neighbors<'a, T> {
+        Neighbors {
+            square: *self,
+            r,
+            next: (-r, -r),
+        }
+    }
+
+    /// The Moore neighborhood of range r, the same cells as `neighbors`
+    pub fn within_moore(&self, r: isize) -> Neighbors<'a, T> {
+        self.neighbors(r)
+    }
+
+    /// The von Neumann neighborhood of range r, the diamond of cells at most
+    /// r steps away counting horizontal and vertical steps, row by row and
+    /// without the center
+    pub fn within_von_neumann(&self, r: isize) -> impl Iterator<Item = (isize, isize, &'a T)> {
+        self.neighbors(r).filter(move |&(i, j, _)| i.abs() + j.abs() <= r)
+    }
+
+    /// The cells whose centers are at most r from the center, row by row and
+    /// without the center
+    pub fn within_circle(&self, r: isize) -> impl Iterator<Item = (isize, isize, &'a T)> {
+        self.neighbors(r).filter(move |&(i, j, _)| i * i + j * j <= r * r)
+    }
+
+    /// How many cells of the Moore neighborhood of range r f holds for
+    pub fn count_moore<F>(&self, r: isize, f: F) -> usize
+    where F: Fn(&T) -> bool {
+        self.within_moore(r).filter(|&(_, _, c)| f(c)).count()
+    }
+
+    /// How many cells of the von Neumann neighborhood of range r f holds for
+    pub fn count_von_neumann<F>(&self, r: isize, f: F) -> usize
+    where F: Fn(&T) -> bool {
+        self.within_von_neumann(r).filter(|&(_, _, c)| f(c)).count()
+    }
+
+    /// How many cells within a circle of radius r f holds for
+    pub fn count_circle<F>(&self, r: isize, f: F) -> usize
+    where F: Fn(&T) -> bool {
+        self.within_circle(r).filter(|&(_, _, c)| f(c)).count()
+    }
+}
+
+/// An iterator over the cells around the center of a square, made by
+/// `Square::neighbors`
+#[derive(Debug)]
+pub struct Neighbors<'a, T>
+where T: 'a {
+    square: Square<'a, T>,
+    r: isize,
+    /// the offset of the next cell to yield
+    next: (isize, isize),
+}
+
+impl<'a, T> Iterator for Neighbors<'a, T>
+where T: 'a {
+    type Item = (isize, isize, &'a T);
+
+    fn next(&mut self) -> Option<(isize, isize, &'a T)> {
+        let r = self.r;
+        loop {
+            let (i, j) = self.next;
+            if j > r {
+                return None;
+            }
+            self.next = if i < r { (i + 1, j) } else { (-r, j + 1) };
+            if i != 0 || j != 0 {
+                return Some((i, j, self.square.get(i, j)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Edge, Square, Topology};
+
+    #[test]
+    fn wrap() {
+        let cells = [0, 1, 2, 3, 4, 5];
+        let square = Square::new(&cells, 3, 2, 0, 1);
+        assert_eq!(*square.get(0, 0), 3);
+        assert_eq!(*square.get(-1, 0), 5);
+        assert_eq!(*square.get(1, 1), 1);
+        assert_eq!(square.coordinate(), (0, 1));
+    }
+
+    #[test]
+    fn topology() {
+        let cells = [0, 1, 2, 3, 4, 5];
+        let outside = -1;
+        let at = |x, y, topology| Square::with_topology(&cells, 3, 2, x, y, topology, &outside);
+        assert_eq!(*at(0, 1, Topology::torus()).get(-1, 0), 5);
+        assert_eq!(*at(0, 1, Topology::mobius()).get(-1, 0), 2);
+        assert_eq!(*at(0, 1, Topology::mobius()).get(0, 1), -1);
+        assert_eq!(*at(0, 1, Topology::cylinder()).get(-1, -1), 2);
+        assert_eq!(*at(0, 1, Topology::klein_bottle()).get(0, 1), 2);
+        assert_eq!(*at(0, 1, Topology::klein_bottle()).get(-1, 1), 0);
+        assert_eq!(*at(0, 0, Topology::projective_plane()).get(-1, -1), 0);
+        assert_eq!(*at(0, 0, Topology::plane()).get(1, 1), 4);
+        assert_eq!(at(0, 0, Topology::plane()).neighbors(1).filter(|&(_, _, &c)| c == -1).count(),
+                   5);
+        let mixed = Topology { x: Edge::Bounded, y: Edge::Flip };
+        assert_eq!(*at(0, 1, mixed).get(0, 1), 2);
+        assert_eq!(*at(2, 1, mixed).get(1, 0), -1);
+    }
+
+    #[test]
+    fn neighbors() {
+        let cells = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        let square = Square::new(&cells, 4, 3, 0, 0);
+        let around = [(-1, -1, &11), (0, -1, &8), (1, -1, &9), (-1, 0, &3), (1, 0, &1),
+                      (-1, 1, &7), (0, 1, &4), (1, 1, &5)];
+        assert!(square.neighbors(1).eq(around.iter().cloned()));
+        assert_eq!(square.neighbors(0).count(), 0);
+        assert!(square.within_moore(1).eq(square.neighbors(1)));
+        assert_eq!(Square::new(&cells, 4, 3, 1, 1).neighbors(1).map(|(_, _, &c)| c).sum::<i32>(),
+                   5 * 9 - 5);
+    }
+
+    #[test]
+    fn shapes() {
+        let cells = [1u8; 49];
+        let square = Square::new(&cells, 7, 7, 3, 3);
+        assert_eq!(square.within_moore(2).count(), 24);
+        assert_eq!(square.within_von_neumann(2).count(), 12);
+        assert_eq!(square.within_circle(2).count(), 12);
+        assert_eq!(square.within_circle(3).count(), 28);
+        assert!(square.within_von_neumann(1)
+                    .map(|(i, j, _)| (i, j))
+                    .eq([(0, -1), (-1, 0), (1, 0), (0, 1)].iter().cloned()));
+
+        let mut cells = [0u8; 49];
+        cells[3 * 7 + 5] = 1;
+        cells[5 * 7 + 5] = 1;
+        let square = Square::new(&cells, 7, 7, 3, 3);
+        let alive = |&c: &u8| c == 1;
+        assert_eq!(square.count_moore(2, alive), 2);
+        assert_eq!(square.count_von_neumann(2, alive), 1);
+        assert_eq!(square.count_circle(2, alive), 1);
+        assert_eq!(square.count_circle(3, alive), 2);
+    }
+}
@@ -0,0 +1,432 @@
+use super::game_of_life;
+use super::generations::DecayState;
+#[cfg(feature = "alloc")]
+use super::two_color_life;
+#[cfg(feature = "std")]
+use super::{dla, rainbow_life};
+
+use std::convert::TryFrom;
+use std::mem;
+
+/// A type of cell that has a fixed number of states, each with a number, a
+/// character and a color, so that frames of it can be read, written, drawn
+/// and stored without being told how. The default state should be number 0.
+///
+/// Continuous cells like `f32` are numbered, written and drawn as the nearest
+/// of 256 levels from 0 to 1, but stored exactly.
+pub trait CellState: Sized {
+    /// How many states there are
+    const STATES: usize;
+
+    /// How many bytes of `to_bits` are needed to store a state exactly, e.g.
+    /// in the binary format or on the gpu, at most 8
+    const BYTES: usize = bytes_for(Self::STATES);
+
+    /// The number of the state, less than `STATES`
+    fn index(&self) -> usize;
+
+    /// The state numbered index, or None if there isn't one
+    fn from_index(index: usize) -> Option<Self>;
+
+    /// The character the state is written as
+    fn to_char(&self) -> char;
+
+    /// The state written as c, or None if there isn't one
+    fn from_char(c: char) -> Option<Self>;
+
+    /// The rgb color the state is drawn in
+    fn color(&self) -> [u8; 3];
+
+    /// The state stored exactly in the low `BYTES` bytes, which is its
+    /// number unless numbering it loses something
+    fn to_bits(&self) -> u64 {
+        self.index() as u64
+    }
+
+    /// The state stored by `to_bits`, or None if bits aren't one
+    fn from_bits(bits: u64) -> Option<Self> {
+        usize::try_from(bits).ok().and_then(Self::from_index)
+    }
+}
+
+/// The fewest bytes that can number states states
+const fn bytes_for(states: usize) -> usize {
+    let mut bytes = 1;
+    while bytes < 8 && (states - 1) as u64 >> (8 * bytes) != 0 {
+        bytes *= 2;
+    }
+    bytes
+}
+
+/// The character for state n of a type whose first two states are written
+/// as `.` and `O`, going on from `A` and skipping `O` and the surrogates
+fn letter(n: usize) -> Option<char> {
+    let mut c = 'A' as usize + n - 2;
+    if c >= 'O' as usize {
+        c += 1;
+    }
+    if c >= 0xD800 {
+        c += 0x800;
+    }
+    u32::try_from(c).ok().and_then(char::from_u32)
+}
+
+/// The state written as c by `letter`
+fn from_letter(c: char) -> Option<usize> {
+    Some(match c {
+        _ if c < 'A' => return None,
+        _ if c < 'O' => c as usize - 'A' as usize + 2,
+        'O' => return None,
+        _ if (c as u32) < 0xD800 => c as usize - 'A' as usize + 1,
+        _ => c as usize - 'A' as usize + 1 - 0x800,
+    })
+}
+
+impl CellState for bool {
+    const STATES: usize = 2;
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn from_index(index: usize) -> Option<bool> {
+        match index {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        if *self { 'O' } else { '.' }
+    }
+
+    fn from_char(c: char) -> Option<bool> {
+        match c {
+            '.' => Some(false),
+            'O' => Some(true),
+            _ => None,
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        if *self { [255, 255, 255] } else { [0, 0, 0] }
+    }
+}
+
+/// Written like the plaintext `.cells` format, with `.` for dead and `O` for
+/// alive
+impl CellState for game_of_life::State {
+    const STATES: usize = 2;
+
+    fn index(&self) -> usize {
+        (*self == game_of_life::State::Alive).index()
+    }
+
+    fn from_index(index: usize) -> Option<game_of_life::State> {
+        bool::from_index(index).map(|alive| {
+            if alive { game_of_life::State::Alive } else { game_of_life::State::Dead }
+        })
+    }
+
+    fn to_char(&self) -> char {
+        (*self == game_of_life::State::Alive).to_char()
+    }
+
+    fn from_char(c: char) -> Option<game_of_life::State> {
+        bool::from_char(c).and_then(|alive| game_of_life::State::from_index(alive.index()))
+    }
+
+    fn color(&self) -> [u8; 3] {
+        (*self == game_of_life::State::Alive).color()
+    }
+}
+
+/// Written as `.` for dead, `O` for alive and the characters from `A` for
+/// dying, skipping `O` and the surrogates, and drawn darker the further they
+/// have decayed
+impl<const N: usize> CellState for DecayState<N> {
+    const STATES: usize = N;
+
+    fn index(&self) -> usize {
+        if self.is_dead() { 0 } else { self.level() + 1 }
+    }
+
+    fn from_index(index: usize) -> Option<DecayState<N>> {
+        match index {
+            0 => Some(DecayState::DEAD),
+            1 => Some(DecayState::ALIVE),
+            _ => DecayState::dying(index - 1),
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self.index() {
+            0 => '.',
+            1 => 'O',
+            // N is small enough for every dying state to have a character
+            i => letter(i).expect("dying states have characters"),
+        }
+    }
+
+    fn from_char(c: char) -> Option<DecayState<N>> {
+        match c {
+            '.' => Some(DecayState::DEAD),
+            'O' => Some(DecayState::ALIVE),
+            _ => from_letter(c).and_then(DecayState::from_index),
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        // N is at least 2, so dead cells are at least at level 1
+        let dead = N - 1;
+        let v = (255 * ((dead - self.level()) as u64) / dead as u64) as u8;
+        [v, v, v]
+    }
+}
+
+/// Numbers written as `.` for 0, `O` for 1 and the characters from `A` for
+/// the rest, and drawn as shades of grey
+impl CellState for u8 {
+    const STATES: usize = 256;
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn from_index(index: usize) -> Option<u8> {
+        u8::try_from(index).ok()
+    }
+
+    fn to_char(&self) -> char {
+        match *self {
+            0 => '.',
+            1 => 'O',
+            n => letter(n as usize).expect("every number has a character"),
+        }
+    }
+
+    fn from_char(c: char) -> Option<u8> {
+        match c {
+            '.' => Some(0),
+            'O' => Some(1),
+            _ => from_letter(c).and_then(u8::from_index),
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        [*self, *self, *self]
+    }
+}
+
+macro_rules! continuous_state {
+    ($($t:ty, $bits:ty);*) => {$(
+        /// Values from 0 to 1, numbered as the nearest of 256 levels and
+        /// drawn as shades of grey, but stored exactly
+        impl CellState for $t {
+            const STATES: usize = 256;
+            const BYTES: usize = mem::size_of::<$t>();
+
+            fn index(&self) -> usize {
+                // NaN becomes 0
+                (self.clamp(0.0, 1.0) * 255.0 + 0.5) as usize
+            }
+
+            fn from_index(index: usize) -> Option<$t> {
+                u8::from_index(index).map(|i| i as $t / 255.0)
+            }
+
+            fn to_char(&self) -> char {
+                (self.index() as u8).to_char()
+            }
+
+            fn from_char(c: char) -> Option<$t> {
+                u8::from_char(c).and_then(|i| <$t>::from_index(i as usize))
+            }
+
+            fn color(&self) -> [u8; 3] {
+                (self.index() as u8).color()
+            }
+
+            fn to_bits(&self) -> u64 {
+                <$t>::to_bits(*self) as u64
+            }
+
+            fn from_bits(bits: u64) -> Option<$t> {
+                <$bits>::try_from(bits).ok().map(<$t>::from_bits)
+            }
+        }
+    )*}
+}
+
+continuous_state!(f32, u32; f64, u64);
+
+/// Written as `.` for dead and the characters from `O` on for the colors of
+/// live cells, and drawn blending from red to blue
+#[cfg(feature = "alloc")]
+impl CellState for two_color_life::State {
+    const STATES: usize = 257;
+
+    fn index(&self) -> usize {
+        match *self {
+            two_color_life::State::Dead => 0,
+            two_color_life::State::Alive(c) => c as usize + 1,
+        }
+    }
+
+    fn from_index(index: usize) -> Option<two_color_life::State> {
+        match index {
+            0 => Some(two_color_life::State::Dead),
+            i => u8::try_from(i - 1).ok().map(two_color_life::State::Alive),
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self.index() {
+            0 => '.',
+            1 => 'O',
+            i => letter(i).expect("every color has a character"),
+        }
+    }
+
+    fn from_char(c: char) -> Option<two_color_life::State> {
+        match c {
+            '.' => Some(two_color_life::State::Dead),
+            'O' => Some(two_color_life::State::Alive(0)),
+            _ => from_letter(c).and_then(two_color_life::State::from_index),
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        match *self {
+            two_color_life::State::Dead => [0, 0, 0],
+            two_color_life::State::Alive(c) => [255 - c, 64, c],
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl CellState for rainbow_life::State {
+    const STATES: usize = 4;
+
+    fn index(&self) -> usize {
+        match *self {
+            rainbow_life::State::Dead => 0,
+            rainbow_life::State::Red => 1,
+            rainbow_life::State::Green => 2,
+            rainbow_life::State::Blue => 3,
+        }
+    }
+
+    fn from_index(index: usize) -> Option<rainbow_life::State> {
+        match index {
+            0 => Some(rainbow_life::State::Dead),
+            1 => Some(rainbow_life::State::Red),
+            2 => Some(rainbow_life::State::Green),
+            3 => Some(rainbow_life::State::Blue),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        ['.', 'R', 'G', 'B'][self.index()]
+    }
+
+    fn from_char(c: char) -> Option<rainbow_life::State> {
+        ['.', 'R', 'G', 'B'].iter().position(|&s| s == c).and_then(Self::from_index)
+    }
+
+    fn color(&self) -> [u8; 3] {
+        [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]][self.index()]
+    }
+}
+
+#[cfg(feature = "std")]
+impl CellState for dla::State {
+    const STATES: usize = 2;
+
+    fn index(&self) -> usize {
+        (*self == dla::State::Cluster).index()
+    }
+
+    fn from_index(index: usize) -> Option<dla::State> {
+        bool::from_index(index).map(|c| if c { dla::State::Cluster } else { dla::State::Empty })
+    }
+
+    fn to_char(&self) -> char {
+        if *self == dla::State::Cluster { '#' } else { '.' }
+    }
+
+    fn from_char(c: char) -> Option<dla::State> {
+        match c {
+            '.' => Some(dla::State::Empty),
+            '#' => Some(dla::State::Cluster),
+            _ => None,
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        (*self == dla::State::Cluster).color()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CellState;
+    use super::super::game_of_life;
+    use super::super::generations::DecayState;
+
+    /// Every state survives being turned into a number and a character and
+    /// back, and the default state is number 0
+    fn round_trips<T: CellState + Default + PartialEq>() {
+        assert_eq!(T::default().index(), 0);
+        for i in 0..T::STATES {
+            let state = T::from_index(i).unwrap();
+            assert_eq!(state.index(), i);
+            assert!(T::from_char(state.to_char()) == Some(state));
+        }
+        assert!(T::from_index(T::STATES).is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        round_trips::<bool>();
+        round_trips::<game_of_life::State>();
+        round_trips::<DecayState<2>>();
+        round_trips::<DecayState<5>>();
+        round_trips::<DecayState<40>>();
+        assert_eq!(DecayState::<5>::dying(2).unwrap().to_char(), 'B');
+        // `O` is taken by live cells, so `N` is followed by `P`
+        assert_eq!(DecayState::<40>::dying(14).unwrap().to_char(), 'N');
+        assert_eq!(DecayState::<40>::dying(15).unwrap().to_char(), 'P');
+        assert_eq!(DecayState::<3>::dying(1).unwrap().color(), [127, 127, 127]);
+        assert!(game_of_life::State::from_char('x').is_none());
+
+        round_trips::<u8>();
+        assert_eq!((2u8.to_char(), 15u8.to_char(), 16u8.to_char()), ('A', 'N', 'P'));
+        assert_eq!((u8::BYTES, DecayState::<300>::BYTES), (1, 2));
+    }
+
+    #[test]
+    fn continuous() {
+        round_trips::<f32>();
+        round_trips::<f64>();
+        assert_eq!((0.5f32.index(), 2.0f32.index(), f32::NAN.index()), (128, 255, 0));
+        // stored exactly rather than as one of the levels
+        assert_eq!(<f32 as CellState>::from_bits(CellState::to_bits(&0.3f32)), Some(0.3));
+        assert_eq!(<f64 as CellState>::from_bits(CellState::to_bits(&0.3f64)), Some(0.3));
+        assert_eq!((f32::BYTES, f64::BYTES), (4, 8));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn round_trip_std() {
+        use super::super::{dla, rainbow_life, two_color_life};
+
+        round_trips::<rainbow_life::State>();
+        round_trips::<dla::State>();
+        round_trips::<two_color_life::State>();
+        assert_eq!(two_color_life::State::BYTES, 2);
+        assert_eq!(rainbow_life::State::Green.color(), [0, 255, 0]);
+    }
+}
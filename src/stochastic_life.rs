@@ -0,0 +1,123 @@
+use super::Square;
+use super::game_of_life::State;
+
+use rand::{Rng, SeedableRng};
+
+use std::cell::RefCell;
+
+/// Game of Life where births and survivals only happen with some probability.
+/// Stepping is reproducible as long as the rng is seeded the same way.
+#[derive(Debug)]
+pub struct StochasticLife<R> {
+    /// the probability that a cell Life would give birth to is born
+    pub birth: f64,
+    /// the probability that a cell Life would keep alive survives
+    pub survival: f64,
+    rng: RefCell<R>,
+}
+
+impl<R: Rng> StochasticLife<R> {
+    /// Create a stochastic Life with the given birth and survival probabilities
+    pub fn new(birth: f64, survival: f64, rng: R) -> StochasticLife<R> {
+        StochasticLife { birth, survival, rng: RefCell::new(rng) }
+    }
+
+    fn chance(&self, p: f64) -> bool {
+        self.rng.borrow_mut().gen::<f64>() < p
+    }
+
+    /// The rule for stochastic Life, to be used with `Frame::next_frame`
+    pub fn rule(&self, curr: Square<State>) -> State {
+        use self::State::Alive;
+        use self::State::Dead;
+
+        let (allowed, p) = if *curr.get(0, 0) == Alive {
+            (curr.alive_count() == 2 || curr.alive_count() == 3, self.survival)
+        } else {
+            (curr.alive_count() == 3, self.birth)
+        };
+        if allowed && self.chance(p) { Alive } else { Dead }
+    }
+}
+
+impl<R: Rng + SeedableRng<[u32; 4]>> StochasticLife<R> {
+    /// Reseed the rng with a seed drawn from it and return the seed, so that
+    /// a rule made from it later, e.g. when a save is loaded, carries on
+    /// drawing the same numbers as this one
+    pub fn checkpoint_seed(&self) -> [u32; 4] {
+        let mut rng = self.rng.borrow_mut();
+        // xorshift generators can't be seeded with all zeros
+        let mut seed = [0; 4];
+        while seed == [0; 4] {
+            seed = rng.gen();
+        }
+        rng.reseed(seed);
+        seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::game_of_life::State::Alive;
+    use super::StochasticLife;
+
+    use rand::{SeedableRng, XorShiftRng};
+
+    fn soup(seed: u32) -> Frame<State> {
+        let life = StochasticLife::new(0.5, 0.5, XorShiftRng::from_seed([seed, 2, 3, 4]));
+        let mut frame = Frame::<State>::new(16, 16);
+        for x in 0..16 {
+            for y in 0..16 {
+                *frame.get_mut(x, y) = if life.chance(0.5) { Alive } else { State::Dead };
+            }
+        }
+        frame
+    }
+
+    #[test]
+    fn certain_is_life() {
+        let life = StochasticLife::new(1., 1., XorShiftRng::from_seed([1, 2, 3, 4]));
+        let frame = soup(1);
+        assert_eq!(frame.next_frame(|sq| life.rule(sq)),
+                   frame.next_frame(game_of_life::rule));
+    }
+
+    #[test]
+    fn never_born() {
+        let life = StochasticLife::new(0., 1., XorShiftRng::from_seed([1, 2, 3, 4]));
+        let frame = soup(1);
+        let next = frame.next_frame(|sq| life.rule(sq));
+        for (x, y, s) in next.enumerate_squares() {
+            if *s == Alive {
+                assert_eq!(*frame.get(x, y), Alive);
+            }
+        }
+    }
+
+    #[test]
+    fn reproducible() {
+        let run = |seed| {
+            let life = StochasticLife::new(0.8, 0.8, XorShiftRng::from_seed([seed, 2, 3, 4]));
+            let mut frame = soup(7);
+            for _ in 0..2 {
+                frame = frame.next_frame(|sq| life.rule(sq));
+            }
+            frame
+        };
+        assert_eq!(run(1), run(1));
+        assert!(run(1) != run(2));
+    }
+
+    #[test]
+    fn checkpoint_seed() {
+        let life = StochasticLife::new(0.8, 0.8, XorShiftRng::from_seed([1, 2, 3, 4]));
+        let frame = soup(7).next_frame(|sq| life.rule(sq));
+        let seed = life.checkpoint_seed();
+        let resumed = StochasticLife::new(0.8, 0.8, XorShiftRng::from_seed(seed));
+        assert_eq!(frame.next_frame(|sq| life.rule(sq)),
+                   frame.next_frame(|sq| resumed.rule(sq)));
+    }
+}
@@ -0,0 +1,172 @@
+use super::Frame;
+
+use std::ops::{Add, Sub};
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Prefix sums of a frame, built once per generation, that give the sum of
+/// any rectangle in constant time. Rules over large neighborhoods, like
+/// Larger than Life, look up the count of live cells around the cell they are
+/// stepping instead of looping over every neighbor, e.g.
+///
+/// ```
+/// use simulation::Frame;
+/// use simulation::summed_area::SummedArea;
+///
+/// let frame = Frame::from_vec(3, 3, vec![1, 0, 1, 0, 1, 1, 0, 0, 0]).unwrap();
+/// let live = SummedArea::from_fn(&frame, |&c| c as u32);
+/// let next = frame.next_frame(|sq| {
+///     let (x, y) = sq.coordinate();
+///     (2 * live.box_sum(x, y, 1) > 9) as u8
+/// });
+/// assert_eq!(next.as_slice(), &[0; 9]);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SummedArea<T> {
+    /// the sum of the cells above and to the left of each cell, with an extra
+    /// row and column of zeros in front
+    sums: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> SummedArea<T>
+where T: Copy + Default + Add<Output = T> + Sub<Output = T> {
+    /// The sums of frame
+    pub fn new(frame: &Frame<T>) -> SummedArea<T> {
+        SummedArea::from_fn(frame, |&c| c)
+    }
+
+    /// The sums of f applied to every cell of frame
+    pub fn from_fn<U, F>(frame: &Frame<U>, f: F) -> SummedArea<T>
+    where F: Fn(&U) -> T {
+        let (width, height) = (frame.width(), frame.height());
+        let stride = width + 1;
+        let mut sums = vec![T::default(); stride * (height + 1)];
+        for (y, row) in frame.as_slice().chunks(width.max(1)).enumerate() {
+            let mut across = T::default();
+            for (x, cell) in row.iter().enumerate() {
+                across = across + f(cell);
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + across;
+            }
+        }
+        SummedArea { sums, width, height }
+    }
+
+    /// The width of the frame the sums are of
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the frame the sums are of
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The sum of the cells in [x0, x1) by [y0, y1), which doesn't wrap
+    fn sum_within(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> T {
+        let stride = self.width + 1;
+        let at = |x, y| self.sums[y * stride + x];
+        at(x1, y1) + at(x0, y0) - at(x0, y1) - at(x1, y0)
+    }
+
+    /// The sum of the w by h rectangle with its top left corner at (x, y),
+    /// wrapping around at the edges like `Square::get`
+    ///
+    /// # Panics
+    ///
+    /// if the rectangle is wider or taller than the frame
+    pub fn sum(&self, x: usize, y: usize, w: usize, h: usize) -> T {
+        assert!(w <= self.width && h <= self.height,
+                "a {}x{} rectangle doesn't fit in a {}x{} frame", w, h, self.width, self.height);
+        // split the rectangle where it wraps into at most two spans each way
+        let spans = |start: usize, len: usize, size: usize| {
+            let start = start % size;
+            if start + len <= size {
+                [(start, start + len), (0, 0)]
+            } else {
+                [(start, size), (0, start + len - size)]
+            }
+        };
+        let mut total = T::default();
+        for &(x0, x1) in &spans(x, w, self.width) {
+            for &(y0, y1) in &spans(y, h, self.height) {
+                if x0 < x1 && y0 < y1 {
+                    total = total + self.sum_within(x0, y0, x1, y1);
+                }
+            }
+        }
+        total
+    }
+
+    /// The sum of the box of cells at most r away from (x, y) horizontally
+    /// and vertically, including (x, y) itself
+    ///
+    /// # Panics
+    ///
+    /// if the box is wider or taller than the frame
+    pub fn box_sum(&self, x: usize, y: usize, r: usize) -> T {
+        let (w, h) = (self.width, self.height);
+        assert!(2 * r < w && 2 * r < h, "a box of radius {} doesn't fit in a {}x{} frame", r, w, h);
+        self.sum((x + w - r) % w, (y + h - r) % h, 2 * r + 1, 2 * r + 1)
+    }
+
+    /// The sum of every cell
+    pub fn total(&self) -> T {
+        self.sum_within(0, 0, self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life::State::{self, Alive, Dead};
+    use super::SummedArea;
+
+    fn soup() -> Frame<State> {
+        let cells = (0..13 * 11).map(|i| if i * 37 % 7 < 3 { Alive } else { Dead }).collect();
+        Frame::from_vec(13, 11, cells).unwrap()
+    }
+
+    #[test]
+    fn rectangles() {
+        let frame = Frame::from_vec(3, 2, vec![1, 2, 3, 4, 5, 6]).unwrap();
+        let sums = SummedArea::new(&frame);
+        assert_eq!((sums.width(), sums.height(), sums.total()), (3, 2, 21));
+        assert_eq!(sums.sum(1, 0, 2, 2), 2 + 3 + 5 + 6);
+        assert_eq!(sums.sum(2, 1, 2, 1), 6 + 4);
+        assert_eq!(sums.sum(2, 1, 2, 2), 6 + 4 + 3 + 1);
+        assert_eq!(sums.sum(1, 1, 0, 2), 0);
+        assert_eq!(sums.box_sum(0, 0, 0), 1);
+    }
+
+    #[test]
+    fn larger_than_life() {
+        // a majority vote over the 5x5 box around each cell
+        let frame = soup();
+        let live = SummedArea::from_fn(&frame, |&c| (c == Alive) as u32);
+        let fast = frame.next_frame(|sq| {
+            let (x, y) = sq.coordinate();
+            if live.box_sum(x, y, 2) > 12 { Alive } else { Dead }
+        });
+        let slow = frame.next_frame(|sq| {
+            let mut n = 0;
+            for i in -2..3 {
+                for j in -2..3 {
+                    n += (*sq.get(i, j) == Alive) as u32;
+                }
+            }
+            if n > 12 { Alive } else { Dead }
+        });
+        assert_eq!(fast, slow);
+        let alive = frame.as_slice().iter().filter(|&&c| c == Alive).count();
+        assert_eq!(live.total() as usize, alive);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_wide() {
+        SummedArea::new(&Frame::<u32>::new(4, 4)).box_sum(0, 0, 2);
+    }
+}
@@ -0,0 +1,104 @@
+use super::Frame;
+use super::parallel;
+
+/// Evenly spaced values of a rule parameter, from start to end inclusive
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Axis {
+    pub start: f64,
+    pub end: f64,
+    /// how many values there are, at least 1
+    pub steps: usize,
+}
+
+impl Axis {
+    pub fn new(start: f64, end: f64, steps: usize) -> Axis {
+        assert!(steps > 0, "an axis needs at least one value");
+        Axis { start, end, steps }
+    }
+
+    /// The ith value, start for an axis with a single value
+    pub fn value(&self, i: usize) -> f64 {
+        if self.steps == 1 {
+            return self.start;
+        }
+        self.start + (self.end - self.start) * i as f64 / (self.steps - 1) as f64
+    }
+
+    /// Every value in order
+    pub fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..self.steps).map(move |i| self.value(i))
+    }
+}
+
+/// Evaluate metric for every pair of a value of x and a value of y on up to
+/// threads threads. metric is expected to run a simulation with its rule
+/// parameters set to the pair and measure something about how it went, such
+/// as the final population. The results are laid out with x along the width
+/// and y along the height, so a phase diagram can be drawn with a
+/// `render::heatmap::Heatmap`.
+pub fn sweep<M>(x: Axis, y: Axis, threads: usize, metric: M) -> Frame<f64>
+where M: Fn(f64, f64) -> f64 + Sync {
+    sweep_with(x, y, threads, metric)
+}
+
+/// Like `sweep`, but for metrics giving more than a number, such as the
+/// value along with a `Frame::thumbnail` of how the run ended to show in
+/// its place
+pub fn sweep_with<M, R>(x: Axis, y: Axis, threads: usize, metric: M) -> Frame<R>
+where M: Fn(f64, f64) -> R + Sync, R: Send {
+    assert!(threads > 0, "a sweep needs at least one thread");
+    let results = parallel::map(x.steps * y.steps, threads, |i| {
+        metric(x.value(i % x.steps), y.value(i / x.steps))
+    });
+    Frame::from_vec(x.steps, y.steps, results).unwrap()
+}
+
+/// Evaluate metric for every value of x, like `sweep` with a single
+/// parameter
+pub fn sweep_1d<M>(x: Axis, threads: usize, metric: M) -> Vec<f64>
+where M: Fn(f64) -> f64 + Sync {
+    sweep(x, Axis::new(0., 0., 1), threads, |x, _| metric(x)).into_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::cyclic::Cyclic;
+    use super::{sweep, sweep_1d, sweep_with, Axis};
+
+    #[test]
+    fn axis() {
+        let axis = Axis::new(0., 1., 5);
+        assert_eq!(axis.values().collect::<Vec<_>>(), vec![0., 0.25, 0.5, 0.75, 1.]);
+        assert_eq!(Axis::new(3., 7., 1).values().collect::<Vec<_>>(), vec![3.]);
+    }
+
+    #[test]
+    fn grid() {
+        let products = sweep(Axis::new(1., 3., 3), Axis::new(0., 1., 2), 4, |x, y| x * y);
+        assert_eq!((products.width(), products.height()), (3, 2));
+        assert_eq!(products.as_slice(), &[0., 0., 0., 1., 2., 3.]);
+        assert_eq!(sweep_1d(Axis::new(0., 2., 3), 2, |x| x + 1.), vec![1., 2., 3.]);
+        let pairs = sweep_with(Axis::new(1., 2., 2), Axis::new(0., 1., 2), 2, |x, y| (x, y));
+        assert_eq!(pairs.as_slice(), &[(1., 0.), (2., 0.), (1., 1.), (2., 1.)]);
+    }
+
+    #[test]
+    fn phase_diagram() {
+        // how many cells a single seed of a cyclic automaton has advanced
+        // after a few generations, by threshold and range
+        let advanced = sweep(Axis::new(1., 3., 3), Axis::new(1., 2., 2), 3, |threshold, range| {
+            let cyclic = Cyclic::new(3, threshold as usize, range as isize);
+            let mut frame = Frame::<u8>::new(9, 9);
+            *frame.get_mut(4, 4) = 1;
+            for _ in 0..2 {
+                frame = frame.next_frame(|sq| cyclic.rule(sq));
+            }
+            frame.as_slice().iter().filter(|&&c| c != 0).count() as f64
+        });
+        // a single seed can only spread when one neighbor is enough
+        assert!(*advanced.get(0, 0) > 1. && *advanced.get(0, 1) > *advanced.get(0, 0));
+        assert_eq!(*advanced.get(1, 0), 1.);
+        assert_eq!(*advanced.get(2, 1), 1.);
+    }
+}
@@ -0,0 +1,176 @@
+use super::{Frame, Square};
+use super::{game_of_life, rainbow_life, two_color_life};
+use super::patterns::Orientation;
+
+use proptest::arbitrary::{any_with, Arbitrary};
+use proptest::collection;
+use proptest::prelude::*;
+use proptest::strategy::{BoxedStrategy, Just, Strategy};
+use proptest::test_runner::TestCaseError;
+
+use std::fmt::Debug;
+use std::ops::Range;
+
+/// The sizes arbitrary frames get, at least 3 so that rules looking at the
+/// eight closest cells don't wrap onto themselves
+const SIZES: Range<usize> = 3..24;
+
+/// Frames with a width in widths and a height in heights, their cells drawn
+/// from cells
+pub fn frames<S>(widths: Range<usize>, heights: Range<usize>, cells: S)
+                 -> BoxedStrategy<Frame<S::Value>>
+where S: Strategy + Clone + 'static, S::Value: Debug {
+    (widths, heights).prop_flat_map(move |(w, h)| {
+        collection::vec(cells.clone(), w * h)
+            .prop_map(move |data| Frame::from_vec(w, h, data).unwrap())
+    }).boxed()
+}
+
+/// Frames where each cell is alive with probability density, taking its
+/// state from live, and is `T::default()` otherwise
+pub fn soups<S>(widths: Range<usize>, heights: Range<usize>, density: f64, live: S)
+                -> BoxedStrategy<Frame<S::Value>>
+where S: Strategy + Clone + 'static, S::Value: Debug + Default {
+    let cells = (prop::bool::weighted(density), live)
+        .prop_map(|(alive, state)| if alive { state } else { Default::default() });
+    frames(widths, heights, cells)
+}
+
+/// Frames of 3 to 23 cells on each side
+impl<T> Arbitrary for Frame<T>
+where T: Arbitrary + Clone + 'static {
+    type Parameters = T::Parameters;
+    type Strategy = BoxedStrategy<Frame<T>>;
+
+    fn arbitrary_with(args: T::Parameters) -> BoxedStrategy<Frame<T>> {
+        frames(SIZES, SIZES, any_with::<T>(args).boxed())
+    }
+}
+
+impl Arbitrary for game_of_life::State {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<game_of_life::State>;
+
+    fn arbitrary_with(_: ()) -> BoxedStrategy<game_of_life::State> {
+        use super::game_of_life::State::*;
+        prop_oneof![Just(Dead), Just(Alive)].boxed()
+    }
+}
+
+impl Arbitrary for two_color_life::State {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<two_color_life::State>;
+
+    fn arbitrary_with(_: ()) -> BoxedStrategy<two_color_life::State> {
+        use super::two_color_life::State::*;
+        prop_oneof![Just(Dead), any::<u8>().prop_map(Alive)].boxed()
+    }
+}
+
+impl Arbitrary for rainbow_life::State {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<rainbow_life::State>;
+
+    fn arbitrary_with(_: ()) -> BoxedStrategy<rainbow_life::State> {
+        use super::rainbow_life::State::*;
+        prop_oneof![Just(Dead), Just(Red), Just(Green), Just(Blue)].boxed()
+    }
+}
+
+/// frame moved dx cells right and dy cells down, wrapping around
+pub fn translate<T: Clone>(frame: &Frame<T>, dx: usize, dy: usize) -> Frame<T> {
+    let (w, h) = (frame.width(), frame.height());
+    let data = (0..w * h)
+        .map(|i| frame.get((i % w + w - dx % w) % w, (i / w + h - dy % h) % h).clone())
+        .collect();
+    Frame::from_vec(w, h, data).unwrap()
+}
+
+/// frame rotated or reflected by orientation
+pub fn orient<T: Clone + Default>(frame: &Frame<T>, orientation: Orientation) -> Frame<T> {
+    let (w, h) = (frame.width(), frame.height());
+    let mut oriented = if orientation.swaps_axes() { Frame::new(h, w) } else { Frame::new(w, h) };
+    for (x, y, cell) in frame.enumerate_squares() {
+        let (x, y) = orientation.apply(x, y, w, h);
+        *oriented.get_mut(x, y) = cell.clone();
+    }
+    oriented
+}
+
+/// Check that stepping frame with rule twice gives the same frame
+pub fn check_deterministic<T, F>(frame: &Frame<T>, rule: F) -> Result<(), TestCaseError>
+where T: Clone + PartialEq + Debug, F: Fn(Square<T>) -> T {
+    prop_assert_eq!(frame.next_frame(&rule), frame.next_frame(&rule));
+    Ok(())
+}
+
+/// Check that moving frame and then stepping it is the same as stepping it
+/// and then moving it, which holds for rules that don't depend on where a
+/// cell is
+pub fn check_translation_invariant<T, F>(frame: &Frame<T>, rule: F, dx: usize, dy: usize)
+                                         -> Result<(), TestCaseError>
+where T: Clone + PartialEq + Debug, F: Fn(Square<T>) -> T {
+    prop_assert_eq!(translate(&frame.next_frame(&rule), dx, dy),
+                    translate(frame, dx, dy).next_frame(&rule));
+    Ok(())
+}
+
+/// Check that orienting frame and then stepping it is the same as stepping it
+/// and then orienting it, which holds for rules that treat every direction
+/// alike
+pub fn check_symmetric<T, F>(frame: &Frame<T>, rule: F, orientation: Orientation)
+                             -> Result<(), TestCaseError>
+where T: Clone + Default + PartialEq + Debug, F: Fn(Square<T>) -> T {
+    prop_assert_eq!(orient(&frame.next_frame(&rule), orientation),
+                    orient(frame, orientation).next_frame(&rule));
+    Ok(())
+}
+
+/// Any of the eight orientations
+pub fn orientations() -> BoxedStrategy<Orientation> {
+    proptest::sample::select(Orientation::all().to_vec()).boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Square};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::super::patterns::Orientation;
+    use super::{check_deterministic, check_symmetric, check_translation_invariant, orient,
+                orientations, soups, translate};
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn life_laws(frame in any::<Frame<State>>(), dx in 0..30usize, dy in 0..30usize,
+                     orientation in orientations()) {
+            check_deterministic(&frame, game_of_life::rule)?;
+            check_translation_invariant(&frame, game_of_life::rule, dx, dy)?;
+            check_symmetric(&frame, game_of_life::rule, orientation)?;
+        }
+
+        #[test]
+        fn soup_density(frame in soups(5..6, 4..5, 0.0, Just(Alive))) {
+            prop_assert_eq!((frame.width(), frame.height()), (5, 4));
+            prop_assert!(frame.enumerate_squares().all(|(_, _, c)| *c == Dead));
+        }
+    }
+
+    #[test]
+    fn broken_laws() {
+        let mut frame = Frame::<u8>::new(4, 3);
+        *frame.get_mut(1, 0) = 1;
+        let left = |sq: Square<u8>| *sq.get(1, 0);
+        assert!(check_translation_invariant(&frame, left, 1, 2).is_ok());
+        assert!(check_symmetric(&frame, left, Orientation::FlipHorizontal).is_err());
+        let position = |sq: Square<u8>| sq.coordinate().0 as u8;
+        assert!(check_translation_invariant(&frame, position, 1, 0).is_err());
+
+        assert_eq!(translate(&frame, 5, 1).as_slice(), &[0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0]);
+        let rotated = orient(&frame, Orientation::Rotate90);
+        assert_eq!((rotated.width(), rotated.height(), *rotated.get(2, 1)), (3, 4, 1));
+    }
+}
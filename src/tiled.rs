@@ -0,0 +1,216 @@
+use super::{Frame, Square};
+
+use std::mem;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+
+/// The rows of the frame a worker owns, with radius rows copied from the
+/// strips above and below so that rules can look past its edges
+struct Strip<T> {
+    front: Frame<T>,
+    back: Frame<T>,
+    rows: usize,
+}
+
+impl<T> Strip<T>
+where T: Clone {
+    /// Advance the rows this strip owns a generation. The ghost rows are left
+    /// stale until they are exchanged.
+    fn step<F>(&mut self, rule: &F, radius: usize)
+    where F: Fn(Square<T>) -> T {
+        for y in radius..radius + self.rows {
+            for x in 0..self.front.width() {
+                *self.back.get_mut(x, y) = rule(self.front.square(x, y));
+            }
+        }
+        mem::swap(&mut self.front, &mut self.back);
+    }
+
+    /// The cells of rows start..end of the strip
+    fn rows(&self, start: usize, end: usize) -> &[T] {
+        let width = self.front.width();
+        &self.front.as_slice()[start * width..end * width]
+    }
+
+    fn rows_mut(&mut self, start: usize, end: usize) -> &mut [T] {
+        let width = self.front.width();
+        &mut self.front.as_mut_slice()[start * width..end * width]
+    }
+}
+
+/// The first and last rows a strip owns, as its neighbors need them
+struct Edges<T> {
+    top: Vec<T>,
+    bottom: Vec<T>,
+}
+
+/// A simulation split into horizontal strips that are each stepped by their
+/// own thread. Workers only share the rows along the edges of their strips,
+/// which they exchange after every generation, so each thread keeps working
+/// on the same memory for as long as it runs.
+///
+/// Rules see a square of the strip rather than of the whole frame, so they
+/// must not look further than the radius the simulation was created with, and
+/// `Square::coordinate` is relative to the strip.
+pub struct TiledSimulation<T, F> {
+    strips: Vec<Strip<T>>,
+    rule: F,
+    radius: usize,
+    width: usize,
+    height: usize,
+    generation: u64,
+}
+
+impl<T, F> TiledSimulation<T, F>
+where T: Clone + Send + Sync, F: Fn(Square<T>) -> T + Sync {
+    /// Split frame between up to threads workers for a rule that looks at
+    /// most radius cells away. Every strip needs at least radius rows, so
+    /// short frames get fewer workers.
+    ///
+    /// # Panics
+    ///
+    /// if threads is 0, or the frame isn't wider than radius or is shorter
+    /// than radius
+    pub fn new(frame: Frame<T>, rule: F, radius: usize, threads: usize)
+               -> TiledSimulation<T, F> {
+        let (width, height) = (frame.width(), frame.height());
+        assert!(threads > 0, "a tiled simulation needs at least one thread");
+        assert!(radius < width && radius.max(1) <= height,
+                "a {}x{} frame is too small for a radius of {}", width, height, radius);
+        let count = threads.min(height / radius.max(1));
+        let cells = frame.as_slice();
+        let strips = (0..count).map(|i| {
+            let (start, end) = (height * i / count, height * (i + 1) / count);
+            let mut data = Vec::with_capacity((end - start + 2 * radius) * width);
+            for y in start + height - radius..end + height + radius {
+                let y = y % height;
+                data.extend_from_slice(&cells[y * width..(y + 1) * width]);
+            }
+            let front = Frame::from_vec(width, end - start + 2 * radius, data).unwrap();
+            Strip { back: front.clone(), front, rows: end - start }
+        }).collect();
+        TiledSimulation { strips, rule, radius, width, height, generation: 0 }
+    }
+
+    /// The number of worker threads stepping uses
+    pub fn threads(&self) -> usize {
+        self.strips.len()
+    }
+
+    /// The number of generations that have passed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The current frame, put back together from the strips
+    pub fn frame(&self) -> Frame<T> {
+        let mut data = Vec::with_capacity(self.width * self.height);
+        for strip in &self.strips {
+            data.extend_from_slice(strip.rows(self.radius, self.radius + strip.rows));
+        }
+        Frame::from_vec(self.width, self.height, data).unwrap()
+    }
+
+    /// Advance the simulation by one generation
+    pub fn step(&mut self) {
+        self.step_n(1)
+    }
+
+    /// Advance the simulation by n generations, starting the workers once and
+    /// synchronizing them with a barrier after each generation
+    pub fn step_n(&mut self, n: u64) {
+        if n == 0 {
+            return;
+        }
+        let (radius, count) = (self.radius, self.strips.len());
+        // edges are double buffered by generation, so a worker can publish
+        // its next edges while its neighbors still read the last ones
+        let slots = self.strips.iter().map(|strip| {
+            let edges = || Mutex::new(Edges {
+                top: strip.rows(radius, 2 * radius).to_vec(),
+                bottom: strip.rows(strip.rows, strip.rows + radius).to_vec(),
+            });
+            [edges(), edges()]
+        }).collect::<Vec<_>>();
+        let barrier = Barrier::new(count);
+        let (strips, rule) = (&mut self.strips, &self.rule);
+
+        thread::scope(|scope| {
+            for (i, strip) in strips.iter_mut().enumerate() {
+                let (slots, barrier) = (&slots, &barrier);
+                scope.spawn(move || for g in 0..n {
+                    let slot = (g % 2) as usize;
+                    strip.step(rule, radius);
+                    {
+                        let mut edges = slots[i][slot].lock().unwrap();
+                        edges.top.clone_from_slice(strip.rows(radius, 2 * radius));
+                        edges.bottom.clone_from_slice(
+                            strip.rows(strip.rows, strip.rows + radius));
+                    }
+                    barrier.wait();
+                    // with a single strip both neighbors are the same lock, so
+                    // they are taken one at a time
+                    let (above, below) = (&slots[(i + count - 1) % count], &slots[(i + 1) % count]);
+                    let end = strip.rows + 2 * radius;
+                    strip.rows_mut(0, radius).clone_from_slice(&above[slot].lock().unwrap().bottom);
+                    strip.rows_mut(end - radius, end)
+                        .clone_from_slice(&below[slot].lock().unwrap().top);
+                });
+            }
+        });
+        self.generation += n;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Frame;
+    use super::super::game_of_life;
+    use super::super::game_of_life::State::Alive;
+    use super::super::lenia::Lenia;
+    use super::TiledSimulation;
+
+    #[test]
+    fn life() {
+        let mut frame = Frame::new(20, 17);
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (10, 8), (10, 9), (10, 10)] {
+            *frame.get_mut(x, y) = Alive;
+        }
+        let mut expected = frame.clone();
+        for _ in 0..40 {
+            expected = expected.next_frame(game_of_life::rule);
+        }
+        for &threads in &[1, 2, 3, 5, 17, 64] {
+            let mut sim = TiledSimulation::new(frame.clone(), game_of_life::rule, 1, threads);
+            assert_eq!(sim.threads(), threads.min(17));
+            sim.step_n(25);
+            sim.step();
+            sim.step_n(14);
+            assert_eq!(sim.generation(), 40);
+            assert_eq!(sim.frame(), expected, "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn wide_radius() {
+        let lenia = Lenia::new(3, &[1.], 0.15, 0.015, 0.1);
+        let mut frame = Frame::new(16, 14);
+        for (x, y) in (4..10).flat_map(|x| (3..11).map(move |y| (x, y))) {
+            *frame.get_mut(x, y) = ((x * 7 + y * 3) % 10) as f32 / 10.;
+        }
+        let mut expected = frame.clone();
+        for _ in 0..10 {
+            expected = expected.next_frame(|s| lenia.rule(s));
+        }
+        let mut sim = TiledSimulation::new(frame, |s| lenia.rule(s), 3, 8);
+        assert_eq!(sim.threads(), 4);
+        sim.step_n(10);
+        assert_eq!(sim.frame(), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_short() {
+        TiledSimulation::new(Frame::<f32>::new(16, 2), |_| 0., 3, 2);
+    }
+}
@@ -0,0 +1,345 @@
+use super::{Simulation, Square};
+use super::render::terminal::CellRenderer;
+use super::render::viewport::{self, Viewport};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode,
+                       KeyEventKind, MouseButton, MouseEventKind};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::QueueableCommand;
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The delays between generations in milliseconds that the speed keys step
+/// through
+const DELAYS: [u64; 7] = [1000, 500, 200, 100, 50, 20, 0];
+
+/// An interactive terminal view of a simulation.
+///
+/// Keys: space pauses and resumes, `n` or `.` advances a single generation,
+/// `+` and `-` change the speed, the arrow keys pan, `[` and `]` zoom out and
+/// in, `b` picks the next brush and `q` or escape quits. Zoomed out, each
+/// character shows the first live cell in its block.
+///
+/// With brushes set, clicking a cell paints it with the current brush, or
+/// clears it if it already holds that state, and dragging carries on doing the
+/// same. The right button clears cells.
+pub struct Tui<T, F, R> {
+    sim: Simulation<T, F>,
+    renderer: R,
+    paused: bool,
+    speed: usize,
+    view: Viewport,
+    quit: bool,
+    brushes: Vec<T>,
+    brush: usize,
+    /// the state being drawn by the current mouse drag
+    stroke: Option<T>,
+}
+
+impl<T, F, R> Tui<T, F, R>
+where T: Clone + PartialEq + Default, F: Fn(Square<T>) -> T, R: CellRenderer<T> {
+    /// A view of sim that draws cells with renderer. Cells that aren't the
+    /// default state count towards the population.
+    pub fn new(sim: Simulation<T, F>, renderer: R) -> Tui<T, F, R> {
+        let (w, h) = (sim.frame().width(), sim.frame().height());
+        Tui {
+            sim, renderer,
+            paused: false, speed: 3, quit: false,
+            view: Viewport::new((w as f64 / 2.0, h as f64 / 2.0), 1.0, (w, h)),
+            brushes: vec![], brush: 0, stroke: None,
+        }
+    }
+
+    /// Let the mouse paint cells with these states
+    pub fn with_brushes(mut self, brushes: Vec<T>) -> Tui<T, F, R> {
+        self.brushes = brushes;
+        self.brush = 0;
+        self
+    }
+
+    /// The state the mouse currently paints with
+    pub fn brush(&self) -> Option<&T> {
+        self.brushes.get(self.brush)
+    }
+
+    /// The simulation being shown
+    pub fn simulation(&self) -> &Simulation<T, F> {
+        &self.sim
+    }
+
+    /// Take back the simulation
+    pub fn into_simulation(self) -> Simulation<T, F> {
+        self.sim
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The time between generations while running
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(DELAYS[self.speed])
+    }
+
+    /// The part of the frame being shown
+    pub fn viewport(&self) -> &Viewport {
+        &self.view
+    }
+
+    /// The cell shown in the top left corner
+    pub fn offset(&self) -> (usize, usize) {
+        self.view.cell_at(self.sim.frame(), 0, 0)
+    }
+
+    /// React to a key press
+    pub fn handle_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('n') | KeyCode::Char('.') => self.sim.step(),
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed = (self.speed + 1).min(DELAYS.len() - 1);
+            }
+            KeyCode::Char('-') => self.speed = self.speed.saturating_sub(1),
+            KeyCode::Left => self.pan(-1.0, 0.0),
+            KeyCode::Right => self.pan(1.0, 0.0),
+            KeyCode::Up => self.pan(0.0, -1.0),
+            KeyCode::Down => self.pan(0.0, 1.0),
+            KeyCode::Char('[') => {
+                let (cols, rows) = self.view.screen;
+                self.view.zoom_at(0.5, cols / 2, rows / 2);
+            }
+            KeyCode::Char(']') => {
+                let (cols, rows) = self.view.screen;
+                self.view.zoom_at(2.0, cols / 2, rows / 2);
+            }
+            KeyCode::Char('b') if !self.brushes.is_empty() => {
+                self.brush = (self.brush + 1) % self.brushes.len();
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            _ => {}
+        }
+    }
+
+    /// Move the view by (dx, dy) characters
+    fn pan(&mut self, dx: f64, dy: f64) {
+        let (w, h) = (self.sim.frame().width() as f64, self.sim.frame().height() as f64);
+        self.view.pan(dx / self.view.zoom, dy / self.view.zoom);
+        // keep the center on the frame so panning around never loses precision
+        self.view.center = (self.view.center.0.rem_euclid(w), self.view.center.1.rem_euclid(h));
+    }
+
+    /// React to the mouse at (col, row) of the view. start is whether the
+    /// button was just pressed rather than dragged and erase whether it is
+    /// the right button.
+    pub fn handle_mouse(&mut self, col: u16, row: u16, start: bool, erase: bool) {
+        if col as usize >= self.view.screen.0 || row as usize >= self.view.screen.1 {
+            return;
+        }
+        let (x, y) = self.view.cell_at(self.sim.frame(), col as usize, row as usize);
+        if start {
+            let current = self.sim.frame().get(x, y);
+            self.stroke = match self.brushes.get(self.brush) {
+                _ if erase => Some(T::default()),
+                Some(brush) if brush == current => Some(T::default()),
+                Some(brush) => Some(brush.clone()),
+                None => None,
+            };
+        }
+        if let Some(ref state) = self.stroke {
+            *self.sim.frame_mut().get_mut(x, y) = state.clone();
+        }
+    }
+
+    /// The text of the status bar
+    pub fn status(&self) -> String {
+        let population = self.sim.frame().enumerate_squares()
+            .filter(|&(_, _, c)| *c != T::default())
+            .count();
+        let mut status = format!("generation {}  population {}  {}  delay {}ms",
+                                 self.sim.generation(), population,
+                                 if self.paused { "paused" } else { "running" },
+                                 DELAYS[self.speed]);
+        if let Some(brush) = self.brush() {
+            status.push_str(&format!("  brush {}", self.renderer.render(brush)));
+        }
+        status
+    }
+
+    /// Draw the view and status bar onto a terminal of cols by rows
+    pub fn draw<W: Write>(&mut self, writer: &mut W, cols: u16, rows: u16) -> io::Result<()> {
+        // don't repeat the frame when it is smaller than the terminal
+        let (w, h) = (self.sim.frame().width() as f64, self.sim.frame().height() as f64);
+        let width = (cols as usize).min((w * self.view.zoom).ceil() as usize);
+        let height = (rows.saturating_sub(1) as usize).min((h * self.view.zoom).ceil() as usize);
+        self.view.resize((width, height));
+        let screen = self.view.project(self.sim.frame(), viewport::first_set);
+        let mut line = String::with_capacity(width);
+        for j in 0..height {
+            line.clear();
+            for i in 0..width {
+                line.push(self.renderer.render(screen.get(i, j)));
+            }
+            writer.queue(MoveTo(0, j as u16))?
+                .queue(Print(&line))?
+                .queue(Clear(ClearType::UntilNewLine))?;
+        }
+        let mut status = self.status();
+        status.truncate(cols as usize);
+        writer.queue(MoveTo(0, rows.saturating_sub(1)))?
+            .queue(SetAttribute(Attribute::Reverse))?
+            .queue(Print(status))?
+            .queue(SetAttribute(Attribute::Reset))?
+            .queue(Clear(ClearType::UntilNewLine))?;
+        writer.flush()
+    }
+
+    /// Take over the terminal until the user quits
+    pub fn run(&mut self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        stdout.queue(EnterAlternateScreen)?.queue(EnableMouseCapture)?.queue(Hide)?
+            .queue(Clear(ClearType::All))?;
+        let result = self.event_loop(&mut stdout);
+        // restore the terminal even if drawing failed
+        stdout.queue(Show)?.queue(DisableMouseCapture)?.queue(LeaveAlternateScreen)?.flush()?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn event_loop<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.quit = false;
+        let mut next = Instant::now() + self.delay();
+        while !self.quit {
+            let (cols, rows) = terminal::size()?;
+            self.draw(writer, cols, rows)?;
+
+            let timeout = if self.paused {
+                Duration::from_millis(250)
+            } else {
+                next.saturating_duration_since(Instant::now())
+            };
+            if event::poll(timeout)? {
+                match event::read()? {
+                    Event::Key(key) if key.kind != KeyEventKind::Release => {
+                        self.handle_key(key.code);
+                    }
+                    Event::Mouse(mouse) if mouse.row < rows.saturating_sub(1) => {
+                        let (col, row) = (mouse.column, mouse.row);
+                        match mouse.kind {
+                            MouseEventKind::Down(b) => {
+                                self.handle_mouse(col, row, true, b == MouseButton::Right);
+                            }
+                            MouseEventKind::Drag(b) => {
+                                self.handle_mouse(col, row, false, b == MouseButton::Right);
+                            }
+                            _ => {}
+                        }
+                    }
+                    Event::Resize(_, _) => writer.queue(Clear(ClearType::All)).map(|_| ())?,
+                    _ => {}
+                }
+            }
+            if !self.paused && Instant::now() >= next {
+                self.sim.step();
+                next = Instant::now() + self.delay();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Simulation, Square};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::game_of_life::State::{Alive, Dead};
+    use super::super::render::terminal::LifeRenderer;
+    use super::Tui;
+
+    use crossterm::event::KeyCode;
+
+    fn tui() -> Tui<State, fn(Square<State>) -> State, LifeRenderer> {
+        let mut frame = Frame::new(6, 5);
+        *frame.get_mut(2, 1) = Alive;
+        *frame.get_mut(2, 2) = Alive;
+        *frame.get_mut(2, 3) = Alive;
+        Tui::new(Simulation::new(frame, game_of_life::rule as fn(Square<State>) -> State),
+                 LifeRenderer)
+    }
+
+    #[test]
+    fn keys() {
+        let mut tui = tui();
+        tui.handle_key(KeyCode::Char(' '));
+        assert!(tui.paused());
+        tui.handle_key(KeyCode::Char('n'));
+        assert_eq!(tui.simulation().generation(), 1);
+
+        let delay = tui.delay();
+        tui.handle_key(KeyCode::Char('+'));
+        assert!(tui.delay() < delay);
+        for _ in 0..10 {
+            tui.handle_key(KeyCode::Char('-'));
+        }
+        assert_eq!(tui.delay().as_millis(), 1000);
+
+        tui.handle_key(KeyCode::Left);
+        tui.handle_key(KeyCode::Down);
+        assert_eq!(tui.offset(), (5, 1));
+    }
+
+    #[test]
+    fn draw() {
+        let mut tui = tui();
+        assert_eq!(tui.status(), "generation 0  population 3  running  delay 100ms");
+        let mut out = vec![];
+        tui.draw(&mut out, 80, 4).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        // only three rows fit above the status bar
+        assert!(out.contains("..#..."));
+        assert_eq!(out.matches("......").count(), 1);
+        assert!(out.contains("population 3"));
+
+        // zoomed out the blinker still shows up in a view half the size
+        tui.handle_key(KeyCode::Char('['));
+        assert_eq!(tui.viewport().zoom, 0.5);
+        let mut out = vec![];
+        tui.draw(&mut out, 80, 10).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(tui.viewport().screen, (3, 3));
+        assert_eq!(out.matches("#..").count(), 3);
+    }
+
+    #[test]
+    fn mouse() {
+        let mut tui = tui();
+        // nothing to paint with yet
+        tui.handle_mouse(0, 0, true, false);
+        assert_eq!(*tui.simulation().frame().get(0, 0), Dead);
+
+        let mut tui = tui.with_brushes(vec![Alive]);
+        assert!(tui.status().ends_with("brush #"));
+        tui.handle_mouse(0, 0, true, false);
+        tui.handle_mouse(1, 0, false, false);
+        tui.handle_mouse(9, 0, false, false);
+        assert_eq!(*tui.simulation().frame().get(0, 0), Alive);
+        assert_eq!(*tui.simulation().frame().get(1, 0), Alive);
+
+        // starting on a painted cell clears instead
+        tui.handle_mouse(2, 1, true, false);
+        tui.handle_mouse(0, 0, false, false);
+        assert_eq!(*tui.simulation().frame().get(2, 1), Dead);
+        assert_eq!(*tui.simulation().frame().get(0, 0), Dead);
+
+        // views that are panned paint the cell under the mouse
+        tui.handle_key(KeyCode::Right);
+        tui.handle_mouse(0, 0, true, false);
+        assert_eq!(*tui.simulation().frame().get(1, 0), Dead);
+        tui.handle_mouse(0, 4, true, true);
+        assert_eq!(*tui.simulation().frame().get(1, 4), Dead);
+    }
+}
@@ -1,24 +1,21 @@
 use super::Square;
 
 /// The state of a node in Rainbow Game of Life
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum State {
     /// an alive cell + it's color
     Alive(u8),
+    #[default]
     Dead,
 }
 
-impl Default for State {
-    fn default() -> State { State::Dead }
-}
-
 impl<'a> Square<'a, State>
 where State: 'a {
     /// Return the number of nodes alive surrounding this number
     fn alive_count(&self) -> usize {
-        self.within_ortholinear(1)
-            .iter()
-            .filter(|e| { **e != State::Dead })
+        self.neighbors(1)
+            .filter(|&(_, _, e)| { *e != State::Dead })
             .count()
     }
 }
@@ -37,8 +34,8 @@ pub fn rule(curr: Square<State>) -> State {
     } else {
         match curr.alive_count() {
             3 => {
-                let sum = curr.within_ortholinear(1).iter()
-                    .filter_map(|e| { match *e {
+                let sum = curr.neighbors(1)
+                    .filter_map(|(_, _, e)| { match *e {
                         Alive(c) => Some(c as u16),
                         _ => None,
                     }})
@@ -0,0 +1,86 @@
+use super::board;
+
+use wasm_bindgen;
+use wasm_bindgen::prelude::*;
+
+/// A board for JavaScript, e.g.
+///
+/// ```js
+/// const board = new Board("life", 64, 64);
+/// board.set(1, 0, 1); board.set(2, 1, 1);
+/// board.set(0, 2, 1); board.set(1, 2, 1); board.set(2, 2, 1);
+/// const pixels = new Uint8ClampedArray(64 * 64 * 4);
+/// board.step();
+/// board.draw(pixels);
+/// ctx.putImageData(new ImageData(pixels, 64, 64), 0, 0);
+/// ```
+///
+/// Build with `cargo rustc --lib --crate-type cdylib --release --features wasm
+/// --target wasm32-unknown-unknown` and generate the JavaScript glue with
+/// `wasm-bindgen --target web`.
+#[wasm_bindgen]
+pub struct Board(board::Board);
+
+#[wasm_bindgen]
+impl Board {
+    /// An empty board running the rule called rule, one of life, rainbow,
+    /// cyclic, smooth-life or lenia
+    #[wasm_bindgen(constructor)]
+    pub fn new(rule: &str, width: usize, height: usize) -> Result<Board, JsError> {
+        board::Board::new(rule, width, height)
+            .map(Board)
+            .ok_or_else(|| JsError::new(&format!("unknown rule {}", rule)))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rule(&self) -> String {
+        self.0.rule().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.0.width()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        self.0.height()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn generation(&self) -> f64 {
+        self.0.generation() as f64
+    }
+
+    /// The cell at (x, y), its state for discrete rules and its value for
+    /// continuous ones
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.0.get(x, y)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: f32) {
+        self.0.set(x, y, value)
+    }
+
+    /// Advance n generations, one if n isn't given
+    pub fn step(&mut self, n: Option<u32>) {
+        self.0.step_n(n.unwrap_or(1) as u64)
+    }
+
+    /// Every cell, row by row
+    pub fn values(&self) -> Vec<f32> {
+        self.0.values()
+    }
+
+    /// Draw the board into pixels as RGBA, for `ImageData`. pixels has to
+    /// hold 4 bytes per cell.
+    pub fn draw(&self, pixels: &mut [u8]) -> Result<(), JsError> {
+        let needed = self.0.width() * self.0.height() * 4;
+        if pixels.len() != needed {
+            return Err(JsError::new(&format!("the buffer has {} bytes, the board needs {}",
+                                             pixels.len(), needed)));
+        }
+        self.0.write_rgba(pixels);
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use super::{Frame, Simulation, Square};
+use super::render::ColorMap;
+
+use minifb;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode};
+
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Render frame into a buffer of 0RGB pixels, as minifb wants them, where each
+/// cell is a scale by scale block of pixels
+pub fn pixel_buffer<T, C>(frame: &Frame<T>, colormap: &C, scale: usize) -> Vec<u32>
+where C: ColorMap<T> + ?Sized {
+    let (w, h) = (frame.width() * scale, frame.height() * scale);
+    let mut buf = Vec::with_capacity(w * h);
+    for y in 0..frame.height() {
+        let row = (0..frame.width())
+            .map(|x| {
+                let [r, g, b] = colormap.color(frame.get(x, y));
+                (r as u32) << 16 | (g as u32) << 8 | b as u32
+            })
+            .flat_map(|p| ::std::iter::repeat_n(p, scale))
+            .collect::<Vec<_>>();
+        for _ in 0..scale {
+            buf.extend_from_slice(&row);
+        }
+    }
+    buf
+}
+
+/// A native window showing a simulation at interactive frame rates.
+///
+/// Keys: space pauses and resumes, `N` advances a single generation, `+` and
+/// `-` change the speed, `B` picks the next brush and escape closes the
+/// window.
+///
+/// With brushes set, clicking a cell paints it with the current brush, or
+/// clears it if it already holds that state, and dragging carries on doing the
+/// same. The right button clears cells.
+pub struct Window<T, F, C> {
+    sim: Simulation<T, F>,
+    colormap: C,
+    scale: usize,
+    paused: bool,
+    delay: Duration,
+    brushes: Vec<T>,
+    brush: usize,
+    /// the state being drawn by the current mouse drag
+    stroke: Option<T>,
+}
+
+impl<T, F, C> Window<T, F, C>
+where T: Clone + PartialEq + Default, F: Fn(Square<T>) -> T, C: ColorMap<T> {
+    /// A view of sim where each cell is a scale by scale block of pixels
+    pub fn new(sim: Simulation<T, F>, colormap: C, scale: usize) -> Window<T, F, C> {
+        assert!(scale > 0);
+        Window {
+            sim, colormap, scale,
+            paused: false, delay: Duration::from_millis(50),
+            brushes: vec![], brush: 0, stroke: None,
+        }
+    }
+
+    /// Let the mouse paint cells with these states
+    pub fn with_brushes(mut self, brushes: Vec<T>) -> Window<T, F, C> {
+        self.brushes = brushes;
+        self.brush = 0;
+        self
+    }
+
+    /// React to the mouse over cell (x, y). start is whether the button was
+    /// just pressed rather than dragged and erase whether it is the right
+    /// button.
+    pub fn handle_mouse(&mut self, x: usize, y: usize, start: bool, erase: bool) {
+        if x >= self.sim.frame().width() || y >= self.sim.frame().height() {
+            return;
+        }
+        if start {
+            let current = self.sim.frame().get(x, y);
+            self.stroke = match self.brushes.get(self.brush) {
+                _ if erase => Some(T::default()),
+                Some(brush) if brush == current => Some(T::default()),
+                Some(brush) => Some(brush.clone()),
+                None => None,
+            };
+        }
+        if let Some(ref state) = self.stroke {
+            *self.sim.frame_mut().get_mut(x, y) = state.clone();
+        }
+    }
+
+    /// The simulation being shown
+    pub fn simulation(&self) -> &Simulation<T, F> {
+        &self.sim
+    }
+
+    /// Take back the simulation
+    pub fn into_simulation(self) -> Simulation<T, F> {
+        self.sim
+    }
+
+    /// Open the window and run until it is closed
+    pub fn run(&mut self, title: &str) -> io::Result<()> {
+        let (w, h) = (self.sim.frame().width() * self.scale,
+                      self.sim.frame().height() * self.scale);
+        let mut window = minifb::Window::new(title, w, h, minifb::WindowOptions::default())
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        window.set_target_fps(60);
+
+        let mut last = Instant::now();
+        let mut held = false;
+        while window.is_open() && !window.is_key_down(Key::Escape) {
+            if window.is_key_pressed(Key::B, KeyRepeat::No) && !self.brushes.is_empty() {
+                self.brush = (self.brush + 1) % self.brushes.len();
+            }
+            let (left, right) = (window.get_mouse_down(MouseButton::Left),
+                                 window.get_mouse_down(MouseButton::Right));
+            match window.get_mouse_pos(MouseMode::Discard) {
+                Some((mx, my)) if left || right => {
+                    let (x, y) = (mx as usize / self.scale, my as usize / self.scale);
+                    self.handle_mouse(x, y, !held, right);
+                    held = true;
+                }
+                _ => held = left || right,
+            }
+
+            if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+                self.paused = !self.paused;
+            }
+            if window.is_key_pressed(Key::N, KeyRepeat::Yes) {
+                self.sim.step();
+            }
+            if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) ||
+               window.is_key_pressed(Key::NumPadPlus, KeyRepeat::Yes) {
+                self.delay /= 2;
+            }
+            if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) ||
+               window.is_key_pressed(Key::NumPadMinus, KeyRepeat::Yes) {
+                self.delay = (self.delay * 2).clamp(Duration::from_millis(1),
+                                                    Duration::from_secs(2));
+            }
+            if !self.paused && last.elapsed() >= self.delay {
+                self.sim.step();
+                last = Instant::now();
+            }
+
+            window.set_title(&format!("{} - generation {}{}", title, self.sim.generation(),
+                                      if self.paused { " (paused)" } else { "" }));
+            let buf = pixel_buffer(self.sim.frame(), &self.colormap, self.scale);
+            window.update_with_buffer(&buf, w, h)
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Frame, Simulation};
+    use super::super::game_of_life;
+    use super::super::game_of_life::State;
+    use super::super::render::Color;
+    use super::{pixel_buffer, Window};
+
+    #[test]
+    fn pixels() {
+        let mut frame = Frame::<u8>::new(2, 1);
+        *frame.get_mut(1, 0) = 1;
+        let colormap = |c: &u8| if *c == 1 { [0x12, 0x34, 0x56] } else { [0, 0, 0] };
+        assert_eq!(pixel_buffer(&frame, &colormap, 2),
+                   vec![0, 0, 0x123456, 0x123456, 0, 0, 0x123456, 0x123456]);
+    }
+
+    #[test]
+    fn mouse() {
+        let color = |s: &State| -> Color {
+            if *s == State::Alive { [255, 255, 255] } else { [0, 0, 0] }
+        };
+        let sim = Simulation::new(Frame::new(4, 4), game_of_life::rule);
+        let mut window = Window::new(sim, color, 2).with_brushes(vec![State::Alive]);
+        window.handle_mouse(1, 1, true, false);
+        window.handle_mouse(2, 1, false, false);
+        window.handle_mouse(7, 1, false, false);
+        assert_eq!(*window.simulation().frame().get(1, 1), State::Alive);
+        assert_eq!(*window.simulation().frame().get(2, 1), State::Alive);
+        window.handle_mouse(2, 1, true, true);
+        assert_eq!(*window.simulation().frame().get(2, 1), State::Dead);
+    }
+}